@@ -18,6 +18,8 @@ use matchmaker::{
 };
 use matchmaker_partial::{Apply, Set};
 
+use crate::remote::Reports;
+
 /// Sort function type accepted by `nucleo.sort_with` over `String` items.
 type StringSortFn = Arc<dyn Fn((u32, &String), (u32, &String)) -> bool + Send + Sync>;
 
@@ -79,10 +81,9 @@ pub enum MMAction {
     /// Run a command and display output in preview window (TODO)
     RunPreview(String),
 
-    // Unimplemented
-    /// History up (TODO)
+    /// [`matchmaker::Action::HistoryPrev`]
     HistoryUp,
-    /// History down (TODO)
+    /// [`matchmaker::Action::HistoryNext`]
     HistoryDown,
     /// [`matchmaker::Action::Execute`], confirm on error
     ExecuteOrConfirm(String),
@@ -96,6 +97,10 @@ pub enum MMAction {
     Transform(String),
     /// Execute command and parse output as configuration
     TransformConfig(String),
+
+    // remote
+    /// Answer pending requests for the picker state, such as from `--listen`.
+    Report,
 }
 
 pub struct ActionContext {
@@ -110,6 +115,7 @@ pub struct ActionContext {
     /// Current sort direction. `false` is ascending, `true` is descending.
     /// Tracked locally so `SortReverse(None)` can toggle it.
     pub sort_descending: bool,
+    pub reports: Reports,
     // pub output_template: Option<String>,
     // pub print_handle: AppendOnly<String>,
     // pub output_separator: String,
@@ -125,6 +131,7 @@ pub fn action_handler(
         ranges_fn,
         sort,
         sort_descending,
+        reports,
     }: &mut ActionContext,
 ) {
     match a {
@@ -139,10 +146,10 @@ pub fn action_handler(
 
         // history
         MMAction::HistoryUp => {
-            // todo
+            state.picker_ui.query.history_prev();
         }
         MMAction::HistoryDown => {
-            // todo
+            state.picker_ui.query.history_next();
         }
 
         MMAction::ReloadNext(x) => {
@@ -324,6 +331,7 @@ pub fn action_handler(
                 }
             }
         }
+        MMAction::Report => reports.answer(state),
         MMAction::TransformConfig(payload) => {
             let cmd = format_cli(state, &payload, None);
             if cmd.is_empty() {
@@ -401,6 +409,9 @@ impl MMAction {
             MMAction::Unbind(s) | MMAction::PopBind(s) => {
                 s.parse::<Trigger>()?;
             }
+            MMAction::Report => {
+                Err("Report is only sent by the picker itself".to_string())?;
+            }
             _ => {}
         }
         Ok(())
@@ -418,10 +429,10 @@ pub fn parse_bind_parts(s: &str) -> Result<(Trigger, Actions<MMAction>), StringE
 
     let actions = parts
         .iter()
-        .map(|p| Action::<MMAction>::from_str(p.trim()))
+        .map(|p| Actions::<MMAction>::parse_chain(p.trim()))
         .collect::<Result<Vec<_>, _>>()?;
 
-    Ok((trigger, Actions::from_iter(actions)))
+    Ok((trigger, Actions::from_iter(actions.into_iter().flatten())))
 }
 
 pub fn parse_push_bind_parts(s: &str) -> Result<(Trigger, Action<MMAction>), StringError> {
@@ -440,7 +451,7 @@ enum_from_str_display! {
     MMAction;
 
     units:
-    HistoryUp, HistoryDown, ReloadPrev, PopMode;
+    HistoryUp, HistoryDown, ReloadPrev, PopMode, Report;
 
 
     tuples:
@@ -466,6 +477,19 @@ macro_rules! enum_from_str_display {
         options: $($optional:ident),*;
         lossy: $( $lossy:ident ),* ;
     ) => {
+        impl $enum {
+            /// The names of the actions.
+            pub fn names() -> Vec<&'static str> {
+                vec![
+                    $( stringify!($unit), )*
+                    $( stringify!($tuple), )*
+                    $( stringify!($default), )*
+                    $( stringify!($optional), )*
+                    $( stringify!($lossy), )*
+                ]
+            }
+        }
+
         impl std::fmt::Display for $enum {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 use $enum::*;
@@ -652,9 +676,8 @@ fn apply_sort(
         });
 
     // state.picker_ui.worker.nucleo.reverse_items(true);
-    state.picker_ui.worker.nucleo.sort_with(Some(sort_fn));
     state.picker_ui.worker.nucleo.set_stability(u32::MAX);
-    state.picker_ui.worker.nucleo.resort();
+    state.picker_ui.worker.sort_with(sort_fn);
 }
 
 fn handle_sort(
@@ -665,9 +688,8 @@ fn handle_sort(
     sort_discriminant: &mut Option<SortMode>,
 ) {
     if *sort_discriminant == Some(mode) {
-        state.picker_ui.worker.nucleo.sort_with(None);
         state.picker_ui.worker.nucleo.set_stability(0);
-        state.picker_ui.worker.nucleo.resort();
+        state.picker_ui.worker.restore_sort();
         *sort_discriminant = None;
     } else {
         apply_sort(state, ranges_fn, n, mode);
@@ -726,6 +748,9 @@ mod tests {
 
         let (_trigger, action) = parse_push_bind_parts(&push_inner).unwrap();
         assert_eq!(action, Action::Semantic("enter_mm".into()));
+
+        let (_trigger, actions) = parse_bind_parts("ctrl-y = Select+Down, Accept").unwrap();
+        assert_eq!(actions.0, [Action::Select, Action::Down(1), Action::Accept]);
     }
 
     #[test]
@@ -0,0 +1,189 @@
+//! `mm --chain 'CMD::CMD::...'`: successive pickers, each reading the output of a command in
+//! which the previous selection is substituted.
+//!
+//! Each step runs `mm` again with the remaining arguments, so the steps share the same
+//! configuration. Cancelling a step (`esc`) returns to the previous one.
+
+use std::{ffi::OsString, process::Stdio};
+
+use cba::broc::shell_quote;
+use matchmaker::{MatchError, flow::Flow};
+use serde_json::json;
+
+pub const SEPARATOR: &str = "::";
+
+/// The selections of the steps so far.
+type Picks = Vec<Vec<String>>;
+
+/// Replace `{}` with the quoted selections, and `{N}` with the quoted N-th whitespace separated
+/// field of the first selection.
+pub fn substitute(template: &str, selections: &[String]) -> String {
+    let mut ret = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find('{') {
+        ret.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let inner = &after[..end];
+
+        if inner.is_empty() {
+            let quoted: Vec<String> = selections
+                .iter()
+                .map(|s| shell_quote(s).unwrap_or_default())
+                .collect();
+            ret.push_str(&quoted.join(" "));
+        } else if let Ok(n) = inner.parse::<usize>()
+            && n > 0
+        {
+            let field = selections
+                .first()
+                .and_then(|s| s.split_whitespace().nth(n - 1))
+                .unwrap_or_default();
+            ret.push_str(&shell_quote(field).unwrap_or_default());
+        } else {
+            // not a placeholder
+            ret.push('{');
+            ret.push_str(inner);
+            ret.push('}');
+        }
+        rest = &after[end + 1..];
+    }
+    ret.push_str(rest);
+    ret
+}
+
+/// Run the chain, returning the exit code.
+///
+/// `args` are passed to each step. Picks are exported to later steps as `MM_PICK_1`, `MM_PICK_2`,
+/// ... (newline-separated). The final selection is printed, or with `json`, an array of the
+/// selections of every step.
+pub async fn run(chain: &str, args: Vec<OsString>, json: bool) -> i32 {
+    let exe = std::env::current_exe().unwrap_or_else(|_| "mm".into());
+
+    let mut flow = Flow::<Picks>::new();
+    for template in chain.split(SEPARATOR).map(str::trim) {
+        let template = template.to_string();
+        let exe = exe.clone();
+        let args = args.clone();
+
+        // each step is a process of its own, which sets up the terminal itself
+        flow = flow.step(move |mut picks: Picks, _| {
+            let command = substitute(&template, picks.last().map_or(&[][..], Vec::as_slice));
+            let exe = exe.clone();
+            let args = args.clone();
+            async move {
+                let selection = pick(&exe, &args, &command, &picks).await?;
+                picks.push(selection);
+                Ok(picks)
+            }
+        });
+    }
+
+    match flow.run(Vec::new()).await {
+        Ok(picks) => {
+            if json {
+                println!("{}", json!(picks));
+            } else if let Some(last) = picks.last() {
+                for line in last {
+                    println!("{line}");
+                }
+            }
+            0
+        }
+        Err(MatchError::Abort(code)) => code,
+        Err(e) => {
+            cba::ebog!("{e}");
+            e.exit_code()
+        }
+    }
+}
+
+/// Run a picker over the output of `command`.
+async fn pick(
+    exe: &std::path::Path,
+    args: &[OsString],
+    command: &str,
+    picks: &Picks,
+) -> Result<Vec<String>, MatchError> {
+    let envs = picks
+        .iter()
+        .enumerate()
+        .map(|(i, p)| (format!("MM_PICK_{}", i + 1), p.join("\n")))
+        .collect::<Vec<_>>();
+
+    let io_error = |e: std::io::Error| MatchError::TUIError(e.to_string());
+
+    let mut source = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .envs(envs.iter().cloned())
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(io_error)?;
+    let source_out: Stdio = source
+        .stdout
+        .take()
+        .expect("stdout is piped")
+        .try_into()
+        .map_err(io_error)?;
+
+    let output = tokio::process::Command::new(exe)
+        .args(args)
+        .envs(envs)
+        .stdin(source_out)
+        .stdout(Stdio::piped())
+        .output()
+        .await
+        .map_err(io_error)?;
+    let _ = source.kill().await;
+
+    match output.status.code() {
+        Some(0) => Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect()),
+        // killed by a signal
+        code => Err(MatchError::Abort(code.unwrap_or(MatchError::EXIT_CANCEL))),
+    }
+}
+
+/// The arguments of this invocation without those of `--chain`.
+pub fn forwarded_args() -> Vec<OsString> {
+    let mut ret = Vec::new();
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--chain") => {
+                args.next();
+            }
+            Some(s) if s.starts_with("--chain=") || s == "--chain-json" => {}
+            _ => ret.push(arg),
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_substitute() {
+        let q = |s: &str| shell_quote(s).unwrap();
+        let selections = ["main abc".to_string(), "dev".to_string()];
+        assert_eq!(
+            substitute("git log {1} -- {}", &selections),
+            format!("git log {} -- {} {}", q("main"), q("main abc"), q("dev"))
+        );
+        assert_eq!(
+            substitute("awk '{print}' {2}", &selections),
+            format!("awk '{{print}}' {}", q("abc"))
+        );
+        assert_eq!(substitute("echo {", &selections), "echo {");
+    }
+}
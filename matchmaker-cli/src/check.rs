@@ -0,0 +1,304 @@
+//! `--check-config`: report problems with the config files and overrides without starting the
+//! picker.
+
+use std::{collections::HashSet, fmt::Display, path::Path, process::exit};
+
+use matchmaker::MatchError;
+use matchmaker_partial::{Apply, Set};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    clap::Cli,
+    config::{Config, PartialConfig},
+    parse::get_pairs,
+    paths::default_config_path,
+    set_override,
+    start::{apply_flags, finish_binds, resolve_override},
+};
+
+/// Stop looking for unknown keys in a file after this many.
+const MAX_UNKNOWN_KEYS: usize = 64;
+
+#[derive(Default)]
+struct Report {
+    /// The sources of the config, in the order they are applied.
+    order: Vec<String>,
+    /// Each problem, with where it was found.
+    problems: Vec<(String, String)>,
+    /// The config paths, used to tell where unknown keys are.
+    known: HashSet<String>,
+}
+
+/// Check the config files, overrides and command line, print a report and exit.
+pub fn run(cli: Cli, config_args: Vec<String>) -> ! {
+    let mut report = Report {
+        known: PartialConfig::paths().into_iter().collect(),
+        ..Default::default()
+    };
+    report.order.push("defaults".into());
+    // merged as in `start::enter`, to check the binds
+    let mut merged = Config::default();
+
+    let cfg_path = cli
+        .config
+        .clone()
+        .unwrap_or_else(|| default_config_path().to_path_buf());
+    if cli.config.is_some() || cfg_path.exists() {
+        report.order.push(format!("config: {}", cfg_path.display()));
+        if let Some(config) = report.check_file::<Config>(&cfg_path) {
+            if config.source.is_some() {
+                report.problem(
+                    cfg_path.display(),
+                    "'source' field is not supported in the main config",
+                );
+            }
+            merged = config;
+        }
+    }
+
+    for p in &cli.r#override {
+        let p = resolve_override(p.clone());
+        let Some(mut override_) = report.check_file::<PartialConfig>(&p) else {
+            report.order.push(format!("override: {}", p.display()));
+            continue;
+        };
+        if let Some(q) = override_.source.take() {
+            let source = p.parent().unwrap_or(Path::new("")).join(q);
+            report.order.push(format!("source: {}", source.display()));
+            if let Some(o) = report.check_file::<PartialConfig>(&source) {
+                if o.source.is_some() {
+                    report.problem(
+                        source.display(),
+                        "'source' field is ignored in a nested override",
+                    );
+                }
+                merged.apply(o);
+            }
+        }
+        report.order.push(format!("override: {}", p.display()));
+        merged.apply(override_);
+    }
+
+    let mut partial = PartialConfig::default();
+    if !config_args.is_empty() {
+        report
+            .order
+            .push(format!("command line: {}", config_args.join(" ")));
+        match get_pairs(config_args) {
+            Ok(pairs) => {
+                for (path, val) in pairs {
+                    if !path.is_empty() && (path[0] == "env" || path[0] == "envs") {
+                        report.problem(
+                            "command line",
+                            format!(
+                                "environment variables can't be overridden: {}",
+                                path.join(".")
+                            ),
+                        );
+                    } else if let Err(e) = set_override(&mut partial, &path, &val) {
+                        report.problem("command line", format!("{e:#}"));
+                    }
+                }
+            }
+            Err(e) => report.problem("command line", e),
+        }
+    }
+    report.order.push("flags".into());
+    merged.apply(partial);
+    apply_flags(&mut merged, &cli);
+
+    // the binds can only be checked once everything is merged
+    if report.problems.is_empty()
+        && let Err(e) = finish_binds(&mut merged, &cli.expect)
+    {
+        report.problem("binds", format!("{e:#}"));
+    }
+
+    report.print();
+    exit(if report.problems.is_empty() {
+        MatchError::EXIT_OK
+    } else {
+        MatchError::EXIT_ERROR
+    })
+}
+
+impl Report {
+    fn problem(&mut self, source: impl Display, problem: impl Display) {
+        self.problems
+            .push((source.to_string(), problem.to_string()));
+    }
+
+    /// Parse the file, reporting its unknown keys and invalid values.
+    fn check_file<T: DeserializeOwned>(&mut self, path: &Path) -> Option<T> {
+        let name = path.display().to_string();
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) => {
+                self.problem(name, e);
+                return None;
+            }
+        };
+        let mut table: toml::Table = match toml::from_str(&content) {
+            Ok(table) => table,
+            Err(e) => {
+                self.problem(name, e.to_string().trim_end());
+                return None;
+            }
+        };
+
+        // serde stops at the first unknown key: remove each one until none are left
+        let mut found_unknown = false;
+        for _ in 0..MAX_UNKNOWN_KEYS {
+            let Err(e) = toml::Value::Table(table.clone()).try_into::<T>() else {
+                break;
+            };
+            let Some(key) = unknown_field(e.message()).and_then(|k| self.locate(&table, k)) else {
+                break;
+            };
+            remove(&mut table, &key);
+            self.problem(&name, format!("unknown key `{}`", key.join(".")));
+            found_unknown = true;
+        }
+
+        // errors parsed from the original text show the line and key of the invalid value
+        let parsed = if found_unknown {
+            toml::Value::Table(table).try_into::<T>()
+        } else {
+            toml::from_str::<T>(&content)
+        };
+        parsed
+            .map_err(|e| self.problem(&name, e.to_string().trim_end()))
+            .ok()
+    }
+
+    /// Find where the key that serde rejected is, preferring keys within known tables.
+    fn locate(&self, table: &toml::Table, key: &str) -> Option<Vec<String>> {
+        self.find(table, key, &mut Vec::new(), true)
+            .or_else(|| self.find(table, key, &mut Vec::new(), false))
+    }
+
+    fn find(
+        &self,
+        table: &toml::Table,
+        key: &str,
+        prefix: &mut Vec<String>,
+        strict: bool,
+    ) -> Option<Vec<String>> {
+        for (k, v) in table {
+            prefix.push(k.clone());
+            let path = prefix.join(".");
+            if k == key && !self.known.contains(&path) {
+                return Some(prefix.clone());
+            }
+            if let toml::Value::Table(t) = v
+                && (!strict || self.has_children(&path))
+                && let Some(found) = self.find(t, key, prefix, strict)
+            {
+                return Some(found);
+            }
+            prefix.pop();
+        }
+        None
+    }
+
+    /// Whether the path is a section rather than a value or map.
+    fn has_children(&self, path: &str) -> bool {
+        let prefix = format!("{path}.");
+        self.known.iter().any(|p| p.starts_with(&prefix))
+    }
+
+    fn print(&self) {
+        println!("Merge order:");
+        for (i, source) in self.order.iter().enumerate() {
+            println!("  {}. {source}", i + 1);
+        }
+        println!();
+
+        if self.problems.is_empty() {
+            println!("No problems found.");
+            return;
+        }
+        println!("Problems:");
+        for (source, problem) in &self.problems {
+            let problem = problem.replace('\n', "\n    ");
+            println!("  {source}: {problem}");
+        }
+    }
+}
+
+/// The key in serde's "unknown field `key`" errors.
+fn unknown_field(message: &str) -> Option<&str> {
+    message.strip_prefix("unknown field `")?.split('`').next()
+}
+
+fn remove(table: &mut toml::Table, key: &[String]) {
+    let Some((last, parents)) = key.split_last() else {
+        return;
+    };
+    let mut table = table;
+    for k in parents {
+        match table.get_mut(k) {
+            Some(toml::Value::Table(t)) => table = t,
+            _ => return,
+        }
+    }
+    table.remove(last);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use matchmaker::{Action, binds::Trigger};
+
+    fn check(name: &str, content: &str) -> Vec<String> {
+        let dir = std::env::temp_dir().join(format!("mm-check-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("{name}.toml"));
+        std::fs::write(&path, content).unwrap();
+
+        let mut report = Report {
+            known: PartialConfig::paths().into_iter().collect(),
+            ..Default::default()
+        };
+        report.check_file::<Config>(&path);
+        std::fs::remove_file(path).unwrap();
+        report.problems.into_iter().map(|(_, p)| p).collect()
+    }
+
+    #[test]
+    fn test_unknown_keys() {
+        let problems = check(
+            "unknown",
+            "[results]\nfoo = 1\nbar = 2\n\n[nonexistent]\nx = 1\n",
+        );
+        assert_eq!(problems.len(), 3, "{problems:?}");
+        assert!(problems.contains(&"unknown key `results.foo`".to_string()));
+        assert!(problems.contains(&"unknown key `results.bar`".to_string()));
+        assert!(problems.contains(&"unknown key `nonexistent`".to_string()));
+    }
+
+    #[test]
+    fn test_invalid_value() {
+        let problems = check("invalid", "[results]\nkeep_right = 5\n");
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].contains("keep_right"), "{problems:?}");
+    }
+
+    #[test]
+    fn test_valid() {
+        assert!(check("valid", "[results]\nkeep_right = true\n").is_empty());
+    }
+
+    #[test]
+    fn test_finish_binds() {
+        let mut config = Config::default();
+        finish_binds(&mut config, &["ctrl-y".into()]).unwrap();
+        let trigger: Trigger = "ctrl-y".parse().unwrap();
+        assert_eq!(
+            config.binds[&trigger].0,
+            vec![Action::AcceptWith("ctrl-y".into())]
+        );
+
+        assert!(finish_binds(&mut Config::default(), &["ctrl-nonexistent".into()]).is_err());
+    }
+}
@@ -1,6 +1,9 @@
 use clap::{ArgAction, Parser};
+use matchmaker::{Preselect, config::FieldRanges};
 use std::{ffi::OsString, path::PathBuf};
 
+use crate::paths::sessions_path;
+
 pub static LIBRARY_FULL: &str = "matchmaker";
 pub static BINARY_SHORT: &str = "mm";
 
@@ -16,6 +19,10 @@ pub struct Cli {
     /// If piped, writes the current configuration to stdout.
     #[arg(long)]
     pub dump_config: bool,
+    /// Report unknown keys and invalid values in the config files and overrides, then exit,
+    /// with status 2 if there were any.
+    #[arg(long)]
+    pub check_config: bool,
     #[arg(short = 'F')]
     pub fullscreen: bool,
     #[arg(long)]
@@ -23,11 +30,94 @@ pub struct Cli {
     /// Print the last key pressed in the last `mm` run.
     #[arg(long)]
     pub last_key: bool,
+    /// Print the time taken by each phase of startup on exit.
+    #[arg(long)]
+    pub profile_startup: bool,
+
+    /// Working directory of the populating command, previews and executed commands.
+    #[arg(long, value_name = "DIR")]
+    pub cwd: Option<String>,
 
     /// Force the default command to run.
     #[arg(long)]
     pub no_read: bool,
 
+    /// Read input items delimited by NUL instead of newline.
+    #[arg(long)]
+    pub read0: bool,
+    /// Print output items delimited by NUL instead of newline.
+    #[arg(long)]
+    pub print0: bool,
+
+    /// Print the lines of stdin matching the query in score order, without starting the interface.
+    #[arg(long, short = 'f', value_name = "QUERY")]
+    pub filter: Option<String>,
+
+    /// Columns used for matching, such as `1,3..` or `-1`.
+    #[arg(long, value_name = "RANGES")]
+    pub nth: Option<FieldRanges>,
+    /// Columns which are displayed.
+    #[arg(long, value_name = "RANGES")]
+    pub with_nth: Option<FieldRanges>,
+
+    /// Reverse the order of the input.
+    #[arg(long)]
+    pub tac: bool,
+    /// Clip long lines on the left instead of the right.
+    #[arg(long)]
+    pub keep_right: bool,
+    /// Don't sort the results by score, only filter them.
+    #[arg(long)]
+    pub no_sort: bool,
+    /// Don't match the query against the items. Instead, the command is reloaded with `{q}`
+    /// substituted whenever the query changes.
+    #[arg(long)]
+    pub disabled: bool,
+
+    /// Static text displayed above the header lines read from the input.
+    #[arg(long, value_name = "TEXT")]
+    pub header: Option<String>,
+
+    /// Limit the number of selected items.
+    #[arg(long, value_name = "N")]
+    pub multi: Option<usize>,
+    /// Select the items with this text as they are read. Can be given multiple times.
+    #[arg(long, value_name = "TEXT")]
+    pub select: Vec<String>,
+    /// Comma-separated input indices of items to select as they are read.
+    #[arg(long, value_name = "N", value_delimiter = ',')]
+    pub select_index: Vec<u32>,
+    /// Continue from the query, selections and preview layout of the last picker started with
+    /// the same name.
+    #[arg(long, value_name = "NAME")]
+    pub session: Option<String>,
+
+    /// Run successive pickers over the output of `::`-separated commands, where `{}` and `{N}`
+    /// are replaced by the previous selection. `esc` returns to the previous picker.
+    #[arg(long, value_name = "CMDS")]
+    pub chain: Option<String>,
+    /// With `--chain`, print the selections of every step as a JSON array.
+    #[arg(long, requires = "chain")]
+    pub chain_json: bool,
+
+    /// Accept actions and state requests over HTTP on this port (0 for any), exported as `MM_PORT`.
+    #[cfg(feature = "listen")]
+    #[arg(long, value_name = "PORT")]
+    pub listen: Option<u16>,
+    /// Accept actions and state requests on a Unix socket at this path, exported as `MM_SOCKET`.
+    #[cfg(unix)]
+    #[arg(long, value_name = "PATH")]
+    pub control_socket: Option<PathBuf>,
+    /// Write a line of JSON to this file descriptor whenever the item or match counts change.
+    #[cfg(unix)]
+    #[arg(long, value_name = "FD")]
+    pub status_fd: Option<i32>,
+
+    /// Comma-separated keys which accept the selection.
+    /// The name of the key used to accept is printed on the first line of output (empty for other accepts).
+    #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+    pub expect: Vec<String>,
+
     /// args passed to the populating command.
     #[arg(last = true)]
     pub args: Vec<OsString>,
@@ -106,13 +196,40 @@ impl Cli {
             try_parse!("d", "-");
             try_parse!("override", "--");
             try_parse!("o", "-");
+            try_parse!("expect", "--");
+            try_parse!("cwd", "--");
+            try_parse!("header", "--");
+            try_parse!("multi", "--");
+            try_parse!("select", "--");
+            try_parse!("select-index", "--");
+            try_parse!("chain", "--");
+            try_parse!("nth", "--");
+            try_parse!("with-nth", "--");
+            #[cfg(feature = "listen")]
+            try_parse!("listen", "--");
+            #[cfg(unix)]
+            try_parse!("control-socket", "--");
+            #[cfg(unix)]
+            try_parse!("status-fd", "--");
+            try_parse!("session", "--");
+            try_parse!("filter", "--");
+            try_parse!("f", "-");
 
             // Flags
             if [
                 "--dump-config",
+                "--check-config",
                 "--test-keys",
                 "--last-key",
+                "--profile-startup",
                 "--no-read",
+                "--read0",
+                "--print0",
+                "--tac",
+                "--keep-right",
+                "--no-sort",
+                "--disabled",
+                "--chain-json",
                 "--help",
                 "-F",
             ]
@@ -155,4 +272,22 @@ impl Cli {
 
         (cli, rest)
     }
+
+    /// The items to select as they are read, from `--select` and `--select-index`.
+    pub fn preselect(&self) -> Vec<Preselect> {
+        let texts = self.select.iter().cloned().map(Preselect::Text);
+        let indices = self.select_index.iter().copied().map(Preselect::Index);
+        texts.chain(indices).collect()
+    }
+
+    /// The file storing the state of `--session`.
+    pub fn session_path(&self) -> anyhow::Result<Option<PathBuf>> {
+        let Some(name) = &self.session else {
+            return Ok(None);
+        };
+        if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") {
+            anyhow::bail!("Invalid session name: {name:?}");
+        }
+        Ok(Some(sessions_path()?.join(format!("{name}.json"))))
+    }
 }
@@ -0,0 +1,47 @@
+//! `mm completions <SHELL>`: print a completion script for the shell.
+
+use std::{io, process::exit, str::FromStr};
+
+use cba::ebog;
+use clap::{Arg, ArgAction, CommandFactory, builder::PossibleValuesParser};
+use clap_complete::Shell;
+use matchmaker::MatchError;
+use matchmaker_partial::Set;
+
+use crate::{
+    clap::{BINARY_SHORT, Cli},
+    config::PartialConfig,
+};
+
+/// Handle `mm completions <SHELL>`, exiting if it was invoked.
+pub fn dispatch() {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some("completions") {
+        return;
+    }
+
+    let Some(shell) = args
+        .next()
+        .and_then(|s| Shell::from_str(&s.to_lowercase()).ok())
+    else {
+        ebog!("Usage: {BINARY_SHORT} completions <bash|zsh|fish|powershell|elvish>");
+        exit(MatchError::EXIT_ERROR)
+    };
+
+    clap_complete::generate(shell, &mut command(), BINARY_SHORT, &mut io::stdout());
+    exit(0)
+}
+
+/// The clap command, with the config override paths offered as positional arguments.
+fn command() -> clap::Command {
+    // the overrides come before the trailing command arguments
+    Cli::command().mut_arg("args", |a| a.index(2)).arg(
+        Arg::new("overrides")
+            .index(1)
+            .value_name("PATH VALUE")
+            .action(ArgAction::Append)
+            .num_args(0..)
+            .value_parser(PossibleValuesParser::new(PartialConfig::paths()))
+            .help("Config overrides, as pairs of a path and a value"),
+    )
+}
@@ -61,6 +61,16 @@ pub struct Config {
     #[serde(default)]
     pub exit: ExitConfig,
 
+    // configure the query history
+    #[partial(attr)]
+    #[serde(default)]
+    pub history: HistoryConfig,
+
+    // configure selection limits
+    #[partial(attr)]
+    #[serde(default)]
+    pub selector: SelectorConfig,
+
     /// imports: only supported on overrides and with one nesting level
     #[serde(default)]
     #[partial(no_recurse)]
@@ -0,0 +1,257 @@
+//! `mm internal-docs <man|options|binds>`: generate reference documentation from the clap
+//! definitions, config paths and actions, for the bundled docs and packaging.
+
+use std::process::exit;
+
+use cba::ebog;
+use clap::{Arg, CommandFactory};
+use matchmaker::{Action, MatchError, message::Event};
+use matchmaker_partial::Set;
+
+use crate::{
+    action::MMAction,
+    clap::{BINARY_SHORT, Cli},
+    config::PartialConfig,
+};
+
+/// Handle `mm internal-docs <man|options|binds>`, exiting if it was invoked.
+pub fn dispatch() {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some("internal-docs") {
+        return;
+    }
+
+    let doc = match args.next().as_deref() {
+        Some("man") => man_page(),
+        Some("options") => options_reference(),
+        Some("binds") => binds_reference(),
+        _ => {
+            ebog!("Usage: {BINARY_SHORT} internal-docs <man|options|binds>");
+            exit(MatchError::EXIT_ERROR)
+        }
+    };
+    print!("{doc}");
+    exit(0)
+}
+
+/// The arguments shown in the documentation.
+fn arguments() -> Vec<Arg> {
+    Cli::command()
+        .get_arguments()
+        .filter(|a| !a.is_hide_set())
+        .cloned()
+        .collect()
+}
+
+/// How the argument is written, such as `-o, --override <PATH>`.
+fn usage(arg: &Arg) -> String {
+    let value = || {
+        let names = arg
+            .get_value_names()
+            .map(|names| names.iter().map(|n| format!("<{n}>")).collect::<Vec<_>>())
+            .unwrap_or_else(|| vec![format!("<{}>", arg.get_id().as_str().to_uppercase())]);
+        names.join(" ")
+    };
+
+    if arg.is_positional() {
+        let value = format!("{}...", value());
+        return if arg.is_last_set() {
+            format!("-- {value}")
+        } else {
+            value
+        };
+    }
+
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("-{short}"));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("--{long}"));
+    }
+    let mut usage = names.join(", ");
+    if arg.get_action().takes_values() {
+        usage.push(' ');
+        usage.push_str(&value());
+    }
+    usage
+}
+
+/// The help of the argument, followed by its possible values.
+fn help(arg: &Arg) -> String {
+    let mut help = arg.get_help().map(|h| h.to_string()).unwrap_or_default();
+    let values: Vec<_> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect();
+    if !values.is_empty() {
+        if !help.is_empty() {
+            help.push(' ');
+        }
+        help.push_str(&format!("[possible values: {}]", values.join(", ")));
+    }
+    help
+}
+
+/// Escape text for roff.
+fn roff(s: &str) -> String {
+    let s = s.replace('\\', "\\e").replace('-', "\\-");
+    if s.starts_with(['.', '\'']) {
+        format!("\\&{s}")
+    } else {
+        s
+    }
+}
+
+pub fn man_page() -> String {
+    let mut out = format!(
+        ".TH {} 1 \"\" \"{} {}\"\n",
+        BINARY_SHORT.to_uppercase(),
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION")
+    );
+    out.push_str(&format!(
+        ".SH NAME\n{} \\- {}\n",
+        BINARY_SHORT,
+        roff(env!("CARGO_PKG_DESCRIPTION"))
+    ));
+    out.push_str(&format!(
+        ".SH SYNOPSIS\n\\fB{BINARY_SHORT}\\fR [\\fIOPTIONS\\fR] [\\fIPATH VALUE\\fR]... [\\-\\- \\fICOMMAND\\fR...]\n"
+    ));
+    out.push_str(
+        ".SH DESCRIPTION\nAn interactive fuzzy finder over the lines of stdin, or the output of the default command.\n\
+         Configuration is read from the config file, and overridden by pairs of a config path and a value.\n",
+    );
+
+    out.push_str(".SH OPTIONS\n");
+    for arg in arguments() {
+        out.push_str(&format!(".TP\n\\fB{}\\fR\n", roff(&usage(&arg))));
+        let help = help(&arg);
+        if !help.is_empty() {
+            out.push_str(&format!("{}\n", roff(&help)));
+        }
+    }
+
+    out.push_str(&format!(
+        ".SH CONFIG PATHS\nSee \\fB{BINARY_SHORT} \\-\\-doc options\\fR for details.\n.PP\n.nf\n"
+    ));
+    for path in PartialConfig::paths() {
+        out.push_str(&format!("{}\n", roff(&path)));
+    }
+    out.push_str(".fi\n");
+
+    out.push_str(&format!(
+        ".SH COMMANDS\n.TP\n\\fB{BINARY_SHORT} completions\\fR \\fISHELL\\fR\nPrint a completion script for the shell.\n\
+         .TP\n\\fB{BINARY_SHORT} init\\fR \\fISHELL\\fR\nPrint key bindings for the shell.\n\
+         .TP\n\\fB{BINARY_SHORT} preview\\-server\\fR\nRun a daemon which caches the output of preview commands (Unix only).\n"
+    ));
+    out
+}
+
+pub fn options_reference() -> String {
+    let mut out = String::from("## Flags\n\n| Flag | Description |\n| ---- | ----------- |\n");
+    for arg in arguments() {
+        out.push_str(&format!(
+            "| `{}` | {} |\n",
+            usage(&arg),
+            help(&arg).replace('|', "\\|")
+        ));
+    }
+
+    out.push_str("\n## Config Paths\n\n");
+    for path in PartialConfig::paths() {
+        out.push_str(&format!("- `{path}`\n"));
+    }
+    out
+}
+
+pub fn binds_reference() -> String {
+    let list = |names: &[&str]| {
+        names
+            .iter()
+            .map(|n| format!("- `{n}`\n"))
+            .collect::<String>()
+    };
+
+    let mut out = String::from("## Actions\n\n");
+    out.push_str(&list(&Action::<MMAction>::names()));
+    out.push_str("\n### Command Line Actions\n\n");
+    out.push_str(&list(&MMAction::names()));
+
+    out.push_str("\n## Events\n\n");
+    let events: Vec<_> = Event::all().iter_names().map(|(name, _)| name).collect();
+    out.push_str(&list(&events));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_binds_doc_is_complete() {
+        let doc = include_str!("../assets/docs/binds.md");
+        let documented = |name: &str| {
+            doc.match_indices(name).any(|(i, _)| {
+                let before = doc[..i].chars().next_back();
+                let after = doc[i + name.len()..].chars().next();
+                !before.is_some_and(char::is_alphanumeric)
+                    && !after.is_some_and(char::is_alphanumeric)
+            })
+        };
+
+        let events: Vec<_> = Event::all().iter_names().map(|(name, _)| name).collect();
+        let missing: Vec<_> = Action::<MMAction>::names()
+            .into_iter()
+            .chain(MMAction::names())
+            .chain(events)
+            .filter(|name| !documented(name))
+            .collect();
+        assert!(missing.is_empty(), "Undocumented in binds.md: {missing:?}");
+    }
+
+    #[test]
+    fn test_options_doc_is_complete() {
+        let doc = include_str!("../assets/docs/options.md");
+        let documented = |name: &str| {
+            doc.match_indices(name).any(|(i, _)| {
+                let before = doc[..i].chars().next_back();
+                let after = doc[i + name.len()..].chars().next();
+                !before.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+                    && !after.is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
+            })
+        };
+
+        // Keys are documented by their last segment, and sections as `key.`.
+        let paths = PartialConfig::paths();
+        let keys = paths.iter().filter_map(|path| {
+            let key = path.rsplit('.').next()?;
+            (!documented(&format!("`{key}`")) && !documented(&format!("`{key}.`")))
+                .then(|| path.clone())
+        });
+        let flags = arguments().into_iter().filter_map(|arg| {
+            let flag = match (arg.get_long(), arg.get_short()) {
+                (Some(long), _) => format!("--{long}"),
+                (None, Some(short)) => format!("-{short}"),
+                (None, None) => return None,
+            };
+            (!documented(&flag)).then_some(flag)
+        });
+
+        let missing: Vec<_> = keys.chain(flags).collect();
+        assert!(
+            missing.is_empty(),
+            "Undocumented in options.md: {missing:?}"
+        );
+    }
+
+    #[test]
+    fn test_man_page() {
+        let page = man_page();
+        assert!(page.starts_with(".TH MM 1"));
+        assert!(page.contains("\\fB\\-o, \\-\\-override <PATH>\\fR"));
+        assert!(page.contains("[possible values: options, binds, template, other]"));
+    }
+}
@@ -1,14 +1,85 @@
+use cba::bait::ResultExt;
 use cba::broc::shell_quote;
 use cba::unwrap;
 use matchmaker::config_mm::ConfigPreprocessedData;
 use matchmaker::render::MMState;
 use std::borrow::Cow;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::TempPath;
 
 // support {1} -> first column
 const COLUMN_INDICES: bool = true;
 
 type ConfigMMState<'a, 'b> = MMState<'a, 'b, String, ConfigPreprocessedData>;
 
+/// The last files written for `{f}` and `{+f}`, with their contents. Each is removed when
+/// replaced by the next one written.
+static TEMP_FILES: Mutex<[Option<(String, TempPath)>; 2]> = Mutex::new([None, None]);
+
+/// Write the items to a file in the temporary directory, one per line, reusing the last one
+/// written if it has the same contents.
+///
+/// The file is created with a random name and readable only by the user.
+fn write_temp_file(items: &[String], multi: bool) -> Option<PathBuf> {
+    let mut content = items.join("\n");
+    content.push('\n');
+
+    let slot = &mut TEMP_FILES.lock().unwrap()[multi as usize];
+    if let Some((last, path)) = slot
+        && *last == content
+    {
+        return Some(path.to_path_buf());
+    }
+
+    let mut file = tempfile::Builder::new().prefix("mm-").tempfile()._elog()?;
+    file.write_all(content.as_bytes())._elog()?;
+    let path = file.into_temp_path();
+    let ret = path.to_path_buf();
+    *slot = Some((content, path));
+    Some(ret)
+}
+
+/// Remove the files written for `{f}` and `{+f}`.
+pub fn remove_temp_files() {
+    *TEMP_FILES.lock().unwrap() = [None, None];
+}
+
+/// [`format_cli`] for [`matchmaker::action::Action::Become`]: files written for `{f}` and `{+f}`
+/// can't be removed once `mm` is replaced, so the command removes them when it exits.
+pub fn format_become(
+    state: &ConfigMMState<'_, '_>,
+    template: &str,
+    repeat: Option<&dyn Fn(String)>,
+) -> String {
+    let cmd = format_cli(state, template, repeat);
+    if cmd.is_empty() || cfg!(windows) {
+        return cmd;
+    }
+
+    let files = TEMP_FILES.lock().unwrap();
+    let paths: Vec<_> = files
+        .iter()
+        .flatten()
+        .map(|(_, path)| path.to_path_buf())
+        .filter(|path| cmd.contains(&*path.to_string_lossy()))
+        .filter_map(|path| shell_quote(&path))
+        .collect();
+    if paths.is_empty() {
+        return cmd;
+    }
+    let vars: String = paths
+        .iter()
+        .enumerate()
+        .map(|(i, quoted)| format!("__mm_f{i}={quoted}; "))
+        .collect();
+    let removed: Vec<_> = (0..paths.len())
+        .map(|i| format!("\"$__mm_f{i}\""))
+        .collect();
+    format!("{vars}trap 'rm -f -- {}' EXIT\n{cmd}", removed.join(" "))
+}
+
 fn is_valid_key(s: &str) -> bool {
     let body = s.strip_prefix(&['=', '-', '_', '+'][..]).unwrap_or(s);
     if body.is_empty() || body == "!" || body == "#" {
@@ -71,6 +142,13 @@ pub fn format_cli(
     format_cli_inner(state, template, None)
 }
 
+/// Substitute `query` for `{q}` and `{=q}`, for commands run before the picker has started.
+pub fn format_query(template: &str, query: &str) -> String {
+    template
+        .replace("{=q}", query)
+        .replace("{q}", &shell_quote(query).unwrap_or_default())
+}
+
 fn format_cli_inner(
     state: &ConfigMMState<'_, '_>,
     template: &str,
@@ -168,7 +246,10 @@ fn any_need_current(template: &str) -> bool {
                     let key = &template[start..j];
 
                     // Check valid content and slice match for prefixes
-                    if is_valid_content(key) && !key.starts_with(['+', '-', '$']) {
+                    if is_valid_content(key)
+                        && !key.starts_with(['+', '-', '$'])
+                        && !["key", "cwd", "q"].contains(&key)
+                    {
                         return true;
                     }
                     continue 'outer;
@@ -230,6 +311,60 @@ fn process_key(
         };
     }
 
+    // the pressed key, unless shadowed by a column
+    let columns = &state.picker_ui.worker.columns;
+    if key == "key" && !columns.iter().any(|c| &*c.name == "key") {
+        let pressed = state.key().unwrap_or_default();
+        return if quote {
+            shell_quote(pressed)
+        } else {
+            Some(pressed.to_string())
+        };
+    }
+
+    // the working directory, likewise
+    if key == "cwd" && !columns.iter().any(|c| &*c.name == "cwd") {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        return if quote {
+            shell_quote(&cwd)
+        } else {
+            Some(cwd.to_string_lossy().into_owned())
+        };
+    }
+
+    // the query, likewise
+    if key == "q" && !columns.iter().any(|c| &*c.name == "q") {
+        let query = &state.picker_ui.query.input;
+        return if quote {
+            shell_quote(query)
+        } else {
+            Some(query.clone())
+        };
+    }
+
+    // an alias of the index, likewise
+    let key = if key == "n" && !columns.iter().any(|c| &*c.name == "n") {
+        "#"
+    } else {
+        key
+    };
+
+    // the items, written to a file, likewise
+    if key == "f" && !columns.iter().any(|c| &*c.name == "f") {
+        let items = if multi {
+            state.map_selected_to_vec(|_, item| item.clone())
+        } else {
+            let (_, item) = unwrap!(item_override.or_else(|| state.picker_ui.current_indexed()));
+            vec![item.clone()]
+        };
+        let path = write_temp_file(&items, multi)?;
+        return if quote {
+            shell_quote(&path)
+        } else {
+            Some(path.to_string_lossy().into_owned())
+        };
+    }
+
     // Handle ranges
     if key.contains("..") {
         return handle_range(key, state, quote, multi, item_override.map(|x| x.1));
@@ -445,18 +580,21 @@ mod tests {
                 ignore: true,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
             matchmaker::config::ColumnSetting {
                 name: "col2".to_string().into(),
                 ignore: true,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
             matchmaker::config::ColumnSetting {
                 name: "col3".to_string().into(),
                 ignore: true,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
         ];
         columns_config.split =
@@ -706,6 +844,136 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_format_cli_output() {
+        let (mut mm, injector, _guard) = setup_test_mm();
+        injector.push("a,b,c".to_string()).unwrap();
+        injector.push("1,2,3".to_string()).unwrap();
+        mm.worker.nucleo.tick(10);
+
+        let mut state_obj = State::new();
+        let Ok(mut tui) = matchmaker::tui::Tui::new(TerminalConfig::default()) else {
+            return;
+        };
+        let mut matcher = Matcher::new(NucleoConfig::DEFAULT);
+
+        let (mut ui, mut picker_ui, mut footer_ui, mut preview_ui) = UI::new(
+            mm.render_config,
+            &mut matcher,
+            mm.worker,
+            Selector::new(),
+            None,
+            &mut tui,
+            vec![],
+        );
+
+        let (idx1, _) = picker_ui.worker.get_nth_indexed(0).unwrap();
+        let (idx2, _) = picker_ui.worker.get_nth_indexed(1).unwrap();
+        picker_ui.selector.insert(idx1);
+        picker_ui.selector.insert(idx2);
+        picker_ui.query.set(Some("my query".to_string()), 8);
+
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+
+        {
+            let mut mm_state = state_obj.dispatcher(
+                &mut ui,
+                &mut picker_ui,
+                &mut footer_ui,
+                &mut preview_ui,
+                &event_tx,
+            );
+
+            let result = format_cli(&mut mm_state, "echo {q} {=q}", None);
+            assert_eq!(result, "echo 'my query' my query");
+
+            // the template is repeated for each accepted item
+            let lines = std::cell::RefCell::new(Vec::new());
+            let repeat = |s: String| lines.borrow_mut().push(s);
+            format_cli(&mut mm_state, "{#}: {=col2} ({=q})", Some(&repeat));
+            assert_eq!(
+                *lines.borrow(),
+                [
+                    format!("{idx1}: b (my query)"),
+                    format!("{idx2}: 2 (my query)")
+                ]
+            );
+
+            // unless it doesn't refer to them
+            lines.borrow_mut().clear();
+            format_cli(&mut mm_state, "{=q}", Some(&repeat));
+            assert_eq!(*lines.borrow(), ["my query"]);
+        }
+    }
+
+    #[test]
+    fn test_format_query() {
+        assert_eq!(
+            format_query("rg {q} | grep {=q}", "a b"),
+            "rg 'a b' | grep a b"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_format_cli_files() {
+        let (mut mm, injector, _guard) = setup_test_mm();
+        injector.push("a,b,c".to_string()).unwrap();
+        injector.push("1,2,3".to_string()).unwrap();
+        mm.worker.nucleo.tick(10);
+
+        let mut state_obj = State::new();
+        let Ok(mut tui) = matchmaker::tui::Tui::new(TerminalConfig::default()) else {
+            return;
+        };
+        let mut matcher = Matcher::new(NucleoConfig::DEFAULT);
+
+        let (mut ui, mut picker_ui, mut footer_ui, mut preview_ui) = UI::new(
+            mm.render_config,
+            &mut matcher,
+            mm.worker,
+            Selector::new(),
+            None,
+            &mut tui,
+            vec![],
+        );
+
+        let (idx1, _) = picker_ui.worker.get_nth_indexed(0).unwrap();
+        let (idx2, _) = picker_ui.worker.get_nth_indexed(1).unwrap();
+        picker_ui.selector.insert(idx1);
+        picker_ui.selector.insert(idx2);
+
+        let (event_tx, _event_rx) = mpsc::unbounded_channel();
+
+        {
+            let mut mm_state = state_obj.dispatcher(
+                &mut ui,
+                &mut picker_ui,
+                &mut footer_ui,
+                &mut preview_ui,
+                &event_tx,
+            );
+
+            assert_eq!(format_cli(&mut mm_state, "{n}", None), idx1.to_string());
+
+            let current = format_cli(&mut mm_state, "{=f}", None);
+            assert_eq!(std::fs::read_to_string(&current).unwrap(), "a,b,c\n");
+            // the same contents reuse the file
+            assert_eq!(format_cli(&mut mm_state, "{=f}", None), current);
+
+            let become_cmd = format_become(&mut mm_state, "cat {f}", None);
+            assert!(become_cmd.contains("trap 'rm -f -- \"$__mm_f0\"' EXIT\ncat "));
+
+            let selected = format_cli(&mut mm_state, "{-f}", None);
+            assert_eq!(
+                std::fs::read_to_string(&selected).unwrap(),
+                "a,b,c\n1,2,3\n"
+            );
+
+            remove_temp_files();
+            assert!(!std::path::Path::new(&selected).exists());
+        }
+    }
+
     // #[tokio::test]
     // async fn test_skip_empty() {
     //     use matchmaker::config_mm::ConfigMatchmaker;
@@ -0,0 +1,183 @@
+//! `preview.auto`: previewing items according to what they refer to when the preview layout has
+//! no command.
+//!
+//! The rules of `preview.rules` are tried first, followed by [`builtin_rules`]:
+//!
+//! | Target | Command                         |
+//! | ------ | ------------------------------- |
+//! | `file` | `bat`, `$PAGER` or `cat`        |
+//! | `dir`  | `ls -la`                        |
+//! | `url`  | `curl -sSI` (the headers)       |
+//! | `pid`  | `ps` with the process's details |
+
+use std::{path::Path, sync::OnceLock};
+
+use matchmaker::{
+    config::{PreviewRule, PreviewTarget, StringOrInt},
+    nucleo::ItemKind,
+};
+use regex::Regex;
+
+use crate::formatter::format_cli;
+
+type ConfigMMState<'a, 'b> =
+    matchmaker::render::MMState<'a, 'b, String, matchmaker::config_mm::ConfigPreprocessedData>;
+
+static RULES: OnceLock<Vec<(PreviewRule, Option<Regex>)>> = OnceLock::new();
+
+/// The rules tried after those of `preview.rules`.
+pub fn builtin_rules() -> Vec<PreviewRule> {
+    let rule = |target, command: &str| PreviewRule {
+        target,
+        command: command.to_string(),
+        ..Default::default()
+    };
+    vec![
+        rule(
+            PreviewTarget::File,
+            "if command -v bat >/dev/null 2>&1; then
+    bat --binary no-printing --color=always -- {}
+elif [ -n \"$PAGER\" ] && command -v \"${PAGER%% *}\" >/dev/null 2>&1; then
+    $PAGER -- {}
+else
+    cat -- {}
+fi",
+        ),
+        rule(PreviewTarget::Dir, "ls -la -- {}"),
+        rule(PreviewTarget::Url, "curl -sSI --max-time 5 -- {}"),
+        rule(
+            PreviewTarget::Pid,
+            "ps -o pid,ppid,user,%cpu,%mem,etime,command -p {}",
+        ),
+    ]
+}
+
+fn compile(rules: Vec<PreviewRule>) -> Result<Vec<(PreviewRule, Option<Regex>)>, regex::Error> {
+    rules
+        .into_iter()
+        .chain(builtin_rules())
+        .map(|rule| {
+            let regex = (!rule.pattern.is_empty())
+                .then(|| Regex::new(&rule.pattern))
+                .transpose()?;
+            Ok((rule, regex))
+        })
+        .collect()
+}
+
+/// Enable inference with the given rules, which are tried before the builtin ones.
+pub fn set_rules(rules: Vec<PreviewRule>) -> Result<(), regex::Error> {
+    let _ = RULES.set(compile(rules)?);
+    Ok(())
+}
+
+fn is_target(target: PreviewTarget, item: &str) -> bool {
+    match target {
+        PreviewTarget::Any => true,
+        PreviewTarget::File => ItemKind::of_path(Path::new(item)).contains(ItemKind::FILE),
+        PreviewTarget::Dir => ItemKind::of_path(Path::new(item)).contains(ItemKind::DIR),
+        PreviewTarget::Url => item.split_once("://").is_some_and(|(scheme, rest)| {
+            !scheme.is_empty()
+                && scheme.chars().all(|c| c.is_ascii_alphabetic())
+                && !rest.is_empty()
+        }),
+        PreviewTarget::Pid => !item.is_empty() && item.bytes().all(|b| b.is_ascii_digit()),
+    }
+}
+
+/// The command of the first rule matching the item, or the value of its column given by
+/// `column`.
+fn infer<'a>(
+    rules: &'a [(PreviewRule, Option<Regex>)],
+    item: &str,
+    column: impl Fn(&StringOrInt) -> Option<String>,
+) -> Option<&'a str> {
+    rules
+        .iter()
+        .find(|(rule, regex)| {
+            let value = match &rule.column {
+                Some(col) => column(col),
+                None => Some(item.to_string()),
+            };
+            value.is_some_and(|value| {
+                let value = value.trim();
+                regex.as_ref().is_none_or(|r| r.is_match(value)) && is_target(rule.target, value)
+            })
+        })
+        .map(|(rule, _)| rule.command.as_str())
+}
+
+/// [`format_cli`], inferring the command from the current item if the template is empty and
+/// inference is enabled.
+pub fn format_preview(
+    state: &ConfigMMState<'_, '_>,
+    template: &str,
+    repeat: Option<&dyn Fn(String)>,
+) -> String {
+    if template.is_empty()
+        && let Some(rules) = RULES.get()
+        && let Some(item) = state.current_raw()
+        && let Some(command) = infer(rules, item, |col| {
+            state
+                .picker_ui
+                .worker
+                .format_with(item, col)
+                .map(|v| v.into_owned())
+        })
+    {
+        return format_cli(state, command, repeat);
+    }
+    format_cli(state, template, repeat)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_infer() {
+        let rules = compile(vec![PreviewRule {
+            pattern: "^[0-9a-f]{7}$".into(),
+            command: "git show {}".into(),
+            ..Default::default()
+        }])
+        .unwrap();
+        let infer = |item| infer(&rules, item, |_| None);
+
+        assert_eq!(infer("abc1234"), Some("git show {}"));
+        assert_eq!(
+            infer("Cargo.toml"),
+            Some(builtin_rules()[0].command.as_str())
+        );
+        assert_eq!(infer("src"), Some("ls -la -- {}"));
+        assert_eq!(
+            infer("https://example.com"),
+            Some("curl -sSI --max-time 5 -- {}")
+        );
+        assert!(infer(" 1234 ").unwrap().starts_with("ps "));
+        assert_eq!(infer("no such thing"), None);
+    }
+
+    #[test]
+    fn test_infer_column() {
+        let rules = compile(vec![PreviewRule {
+            column: Some(StringOrInt::String("kind".into())),
+            pattern: "^commit$".into(),
+            command: "git show {2}".into(),
+            ..Default::default()
+        }])
+        .unwrap();
+        let kind = |value: &'static str| {
+            move |col: &StringOrInt| {
+                (*col == StringOrInt::String("kind".into())).then(|| value.to_string())
+            }
+        };
+
+        assert_eq!(
+            infer(&rules, "commit abc1234", kind("commit")),
+            Some("git show {2}")
+        );
+        // falls through to the builtin rules, which test the whole item
+        assert_eq!(infer(&rules, "src", kind("tree")), Some("ls -la -- {}"));
+    }
+}
@@ -0,0 +1,230 @@
+//! `mm init <SHELL>`: print key bindings for the shell, in the manner of fzf's key-bindings
+//! scripts.
+//!
+//! - `ctrl-t` inserts the selected files.
+//! - `ctrl-r` replaces the command line with a command from the history.
+//! - `alt-c` changes into the selected directory.
+//!
+//! The commands run by the widgets can be overridden with `MM_CTRL_T_COMMAND`,
+//! `MM_CTRL_R_COMMAND` and `MM_ALT_C_COMMAND`.
+
+use std::process::exit;
+
+use cba::ebog;
+use clap::CommandFactory;
+use matchmaker::MatchError;
+use matchmaker_partial::Set;
+
+use crate::{
+    clap::{BINARY_SHORT, Cli},
+    config::PartialConfig,
+};
+
+/// Handle `mm init <SHELL>`, exiting if it was invoked.
+pub fn dispatch() {
+    let mut args = std::env::args().skip(1);
+    if args.next().as_deref() != Some("init") {
+        return;
+    }
+
+    match args.next().as_deref().and_then(script) {
+        Some(script) => {
+            print!("{script}");
+            exit(0)
+        }
+        None => {
+            ebog!("Usage: {BINARY_SHORT} init <zsh|bash|fish>");
+            exit(MatchError::EXIT_ERROR)
+        }
+    }
+}
+
+/// `--name` for the field `name` of [`Cli`], so that renaming a flag breaks the build's tests
+/// rather than the scripts.
+fn flag(id: &str) -> String {
+    let command = Cli::command();
+    let arg = command
+        .get_arguments()
+        .find(|a| a.get_id() == id)
+        .unwrap_or_else(|| panic!("No such flag: {id}"));
+    format!("--{}", arg.get_long().expect("flags are long"))
+}
+
+/// A config override path, checked to exist.
+fn config_path(path: &str) -> &str {
+    assert!(
+        PartialConfig::paths().iter().any(|p| p == path),
+        "No such config path: {path}"
+    );
+    path
+}
+
+struct Commands {
+    /// Lists files itself.
+    file: String,
+    /// Reads the history, oldest first.
+    history: String,
+    /// Reads directories.
+    cd: String,
+}
+
+impl Commands {
+    fn new() -> Self {
+        let mm = BINARY_SHORT;
+        let no_preview = format!("{} false", config_path("preview.show"));
+        Self {
+            file: mm.to_string(),
+            // newest first, in order of recency rather than score
+            history: format!("{mm} {} {} {no_preview}", flag("tac"), flag("no_sort")),
+            cd: format!("{mm} {no_preview}"),
+        }
+    }
+}
+
+const FIND_DIRS: &str =
+    "find . -mindepth 1 \\( -name '.*' -prune \\) -o -type d -print 2>/dev/null | cut -c3-";
+
+fn script(shell: &str) -> Option<String> {
+    let Commands { file, history, cd } = Commands::new();
+    let script = match shell {
+        "zsh" => format!(
+            r#"# {BINARY_SHORT} key bindings for zsh, generated by `{BINARY_SHORT} init zsh`
+
+__mm_select() {{
+  setopt localoptions pipefail no_aliases 2> /dev/null
+  local item
+  eval "${{MM_CTRL_T_COMMAND:-{file}}}" < /dev/tty | while IFS= read -r item; do
+    echo -n -E "${{(q)item}} "
+  done
+}}
+
+mm-file-widget() {{
+  LBUFFER="${{LBUFFER}}$(__mm_select)"
+  local ret=$?
+  zle reset-prompt
+  return $ret
+}}
+zle -N mm-file-widget
+bindkey -M emacs '^T' mm-file-widget
+bindkey -M viins '^T' mm-file-widget
+
+mm-history-widget() {{
+  setopt localoptions pipefail no_aliases 2> /dev/null
+  local selected
+  selected="$(fc -ln 1 | eval "${{MM_CTRL_R_COMMAND:-{history}}}")"
+  local ret=$?
+  [[ -n "$selected" ]] && BUFFER="$selected" && CURSOR=$#BUFFER
+  zle reset-prompt
+  return $ret
+}}
+zle -N mm-history-widget
+bindkey -M emacs '^R' mm-history-widget
+bindkey -M viins '^R' mm-history-widget
+
+mm-cd-widget() {{
+  setopt localoptions pipefail no_aliases 2> /dev/null
+  local dir
+  dir="$({FIND_DIRS} | eval "${{MM_ALT_C_COMMAND:-{cd}}}")"
+  if [[ -z "$dir" ]]; then
+    zle redisplay
+    return 0
+  fi
+  zle push-line
+  BUFFER="builtin cd -- ${{(q)dir}}"
+  zle accept-line
+  local ret=$?
+  zle reset-prompt
+  return $ret
+}}
+zle -N mm-cd-widget
+bindkey -M emacs '\ec' mm-cd-widget
+bindkey -M viins '\ec' mm-cd-widget
+"#
+        ),
+        "bash" => format!(
+            r#"# {BINARY_SHORT} key bindings for bash, generated by `{BINARY_SHORT} init bash`
+
+__mm_select__() {{
+  local item
+  eval "${{MM_CTRL_T_COMMAND:-{file}}}" < /dev/tty | while IFS= read -r item; do
+    printf '%q ' "$item"
+  done
+}}
+
+__mm_file_widget__() {{
+  local selected
+  selected="$(__mm_select__)"
+  READLINE_LINE="${{READLINE_LINE:0:$READLINE_POINT}}$selected${{READLINE_LINE:$READLINE_POINT}}"
+  READLINE_POINT=$(( READLINE_POINT + ${{#selected}} ))
+}}
+
+__mm_history__() {{
+  local selected
+  selected="$(HISTTIMEFORMAT= builtin history | sed 's/^ *[0-9]*\*\{{0,1\}} *//' | eval "${{MM_CTRL_R_COMMAND:-{history}}}")"
+  if [[ -n "$selected" ]]; then
+    READLINE_LINE="$selected"
+    READLINE_POINT=${{#READLINE_LINE}}
+  fi
+}}
+
+__mm_cd__() {{
+  local dir
+  dir="$({FIND_DIRS} | eval "${{MM_ALT_C_COMMAND:-{cd}}}")" && [[ -n "$dir" ]] && builtin cd -- "$dir"
+}}
+
+bind -m emacs-standard -x '"\C-t": __mm_file_widget__'
+bind -m vi-insert -x '"\C-t": __mm_file_widget__'
+bind -m emacs-standard -x '"\C-r": __mm_history__'
+bind -m vi-insert -x '"\C-r": __mm_history__'
+bind -m emacs-standard -x '"\ec": __mm_cd__'
+bind -m vi-insert -x '"\ec": __mm_cd__'
+"#
+        ),
+        "fish" => format!(
+            r#"# {BINARY_SHORT} key bindings for fish, generated by `{BINARY_SHORT} init fish`
+
+function mm-file-widget -d "Insert the selected files"
+    set -q MM_CTRL_T_COMMAND; or set -l MM_CTRL_T_COMMAND "{file}"
+    set -l result (eval $MM_CTRL_T_COMMAND </dev/tty)
+    and commandline -it -- (string escape -- $result | string join ' ')' '
+    commandline -f repaint
+end
+
+function mm-history-widget -d "Replace the command line with a command from the history"
+    set -q MM_CTRL_R_COMMAND; or set -l MM_CTRL_R_COMMAND "{history}"
+    set -l result (builtin history --reverse | eval $MM_CTRL_R_COMMAND)
+    and commandline -r -- $result
+    commandline -f repaint
+end
+
+function mm-cd-widget -d "Change into the selected directory"
+    set -q MM_ALT_C_COMMAND; or set -l MM_ALT_C_COMMAND "{cd}"
+    set -l dir ({FIND_DIRS} | eval $MM_ALT_C_COMMAND)
+    and test -n "$dir"
+    and cd -- $dir
+    commandline -f repaint
+end
+
+bind \ct mm-file-widget
+bind \cr mm-history-widget
+bind \ec mm-cd-widget
+"#
+        ),
+        _ => return None,
+    };
+    Some(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scripts() {
+        for shell in ["zsh", "bash", "fish"] {
+            let script = script(shell).unwrap();
+            assert!(script.contains("--no-sort"));
+        }
+        assert!(script("tcsh").is_none());
+    }
+}
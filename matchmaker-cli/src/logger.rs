@@ -5,7 +5,7 @@ use cba::{
 };
 use std::{fs::OpenOptions, path::Path};
 
-pub fn init_logger([q, v]: [u8; 2], log_path: &Path) {
+pub fn init_logger([q, v]: [u8; 2], log_path: Option<&Path>) {
     bog::init_bogger(true, true);
     bog::init_filter((4 + v).saturating_sub(q));
 
@@ -51,18 +51,21 @@ pub fn init_logger([q, v]: [u8; 2], log_path: &Path) {
         }
     }
 
-    log_path.parent().map(cba::bs::create_dir);
+    if let Some(log_path) = log_path {
+        log_path.parent().map(cba::bs::create_dir);
+    }
 
-    if let Some(log_file) = OpenOptions::new()
-        .truncate(true)
-        .write(true)
-        .create(true)
-        .open(log_path)
-        .prefix(format!(
-            "Failed to open log file @ {}.",
-            log_path.to_string_lossy()
-        ))
-        ._wbog()
+    if let Some(log_path) = log_path
+        && let Some(log_file) = OpenOptions::new()
+            .truncate(true)
+            .write(true)
+            .create(true)
+            .open(log_path)
+            .prefix(format!(
+                "Failed to open log file @ {}.",
+                log_path.to_string_lossy()
+            ))
+            ._wbog()
     {
         builder.target(env_logger::Target::Pipe(Box::new(log_file)));
     }
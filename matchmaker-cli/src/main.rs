@@ -1,12 +1,23 @@
 mod action;
+mod chain;
+mod check;
 mod clap;
+mod completions;
 mod config;
 mod crokey;
+mod docs;
 pub mod formatter;
+mod infer;
+mod init;
 mod logger;
 mod parse;
 mod paths;
+mod persist;
+#[cfg(unix)]
+mod preview_server;
+mod profile;
 mod register;
+mod remote;
 mod start;
 mod utils;
 
@@ -36,12 +47,22 @@ static GLOBAL: MiMalloc = MiMalloc;
 
 #[tokio::main(flavor = "multi_thread")]
 async fn main() {
+    completions::dispatch();
+    docs::dispatch();
+    init::dispatch();
+    #[cfg(unix)]
+    preview_server::dispatch().await;
+
     let (cli, config_args) = Cli::get_partitioned_args();
+    if cli.profile_startup {
+        profile::enable();
+    }
 
-    init_logger(
-        [cli.quiet, cli.verbose],
-        &state_dir().join(format!("{BINARY_SHORT}.log")),
-    );
+    let log_path = log_path();
+    init_logger([cli.quiet, cli.verbose], log_path.as_deref().ok());
+    if let Err(e) = log_path {
+        log::warn!("{e}");
+    }
     log::debug!("{cli:?}, {config_args:?}");
 
     display_doc(&cli);
@@ -51,6 +72,10 @@ async fn main() {
         handle_download(download);
     }
 
+    if cli.check_config {
+        check::run(cli, config_args);
+    }
+
     // get config overrides
     let partial = if config_args.is_empty() {
         None
@@ -58,37 +83,52 @@ async fn main() {
         Some(get_partial(config_args).__ebog())
     };
 
+    if let Some(query) = &cli.filter {
+        #[cfg(unix)]
+        let status_file = cli.status_fd.map(|fd| remote::open_status_fd(fd).__ebog());
+        #[cfg(not(unix))]
+        let status_file = None;
+        filter_stdin(query, cli.read0, cli.print0, status_file);
+    }
+
+    if let Some(chain) = &cli.chain {
+        exit(chain::run(chain, chain::forwarded_args(), cli.chain_json).await);
+    }
+
     let no_read = cli.no_read;
+    let remote = remote::Remote::from_cli(&cli);
+    let preselect = cli.preselect();
+    let session = cli.session_path().__ebog();
     // get config
     let config = enter(cli, partial).__ebog();
+    profile::mark("config");
 
     // begin
-    match start(config, no_read).await {
+    let result = start(config, no_read, remote, preselect, session).await;
+    formatter::remove_temp_files();
+    profile::report();
+    match result {
         Ok(_) => {
             log::debug!("Execution Complete");
         }
-        Err(err) => match err {
-            MatchError::Abort(i) => {
-                exit(i);
-            }
-            MatchError::EventLoopClosed => {
-                exit(125);
-            }
-            MatchError::TUIError(e) => {
-                ebog!("TUI"; "{e}")
-            }
-            MatchError::NoMatch => {
-                let e = std::mem::take(&mut *CHUNK_ERROR.lock().unwrap());
-                if e.is_empty() {
+        Err(err) => {
+            match &err {
+                MatchError::TUIError(e) => {
+                    ebog!("TUI"; "{e}");
+                }
+                MatchError::NoMatch => {
+                    let e = std::mem::take(&mut *CHUNK_ERROR.lock().unwrap());
+                    if !e.is_empty() {
+                        ebog!("ChunkError"; "{e}");
+                        exit(MatchError::EXIT_ERROR);
+                    }
                     ebog!("NoMatch");
-                    exit(404);
-                } else {
-                    ebog!("ChunkError"; "{e}");
-                    exit(400);
                 }
+                MatchError::Become(_) => unreachable!(),
+                _ => {}
             }
-            _ => unreachable!(),
-        },
+            exit(err.exit_code());
+        }
     };
 }
 
@@ -105,22 +145,83 @@ fn get_partial(config_args: Vec<String>) -> anyhow::Result<PartialConfig> {
             continue;
         }
 
-        let parts = {
-            let mut parts = split_on_delimiter_with_doubled_escape(&val, ',');
-            let is_binds = path.len() == 1 && ["binds", "b"].contains(&path[0].as_ref());
-            try_split_kv(&mut parts, is_binds)?;
-            parts
-        };
-
-        _dbg!(&path; &parts);
-        partial
-            .set(path.as_slice(), &parts)
-            .prefix(format!("Invalid value for {}", path.join(".")))?;
+        set_override(&mut partial, &path, &val)?;
     }
 
     Ok(partial)
 }
 
+/// Set a single `path value` override given on the command line.
+fn set_override(partial: &mut PartialConfig, path: &[String], val: &str) -> anyhow::Result<()> {
+    let parts = {
+        let mut parts = split_on_delimiter_with_doubled_escape(val, ',');
+        let is_binds = path.len() == 1 && ["binds", "b"].contains(&path[0].as_ref());
+        try_split_kv(&mut parts, is_binds)?;
+        parts
+    };
+
+    _dbg!(&path; &parts);
+    partial
+        .set(path, &parts)
+        .prefix(format!("Invalid value for {}", path.join(".")))?;
+    Ok(())
+}
+
+/// With `status_file`, the number of lines read so far is reported while reading, followed by the
+/// number of matches.
+fn filter_stdin(
+    query: &str,
+    read0: bool,
+    print0: bool,
+    mut status_file: Option<std::fs::File>,
+) -> ! {
+    use std::{
+        io::{BufRead, Write},
+        time::Instant,
+    };
+
+    let mut report = |total: usize, matched: u32, reading: bool| {
+        if let Some(file) = &mut status_file {
+            let line = remote::status_json(total as u32, matched, 0, reading, false);
+            let _ = writeln!(file, "{line}");
+        }
+    };
+
+    let (input_separator, output_separator) = (
+        if read0 { b'\0' } else { b'\n' },
+        if print0 { '\0' } else { '\n' },
+    );
+    let mut last_report = Instant::now();
+    let mut lines: Vec<String> = Vec::new();
+    for mut line in std::io::stdin()
+        .lock()
+        .split(input_separator)
+        .map_while(Result::ok)
+    {
+        if !read0 && line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        lines.push(String::from_utf8_lossy(&line).into_owned());
+        if last_report.elapsed() >= remote::STATUS_INTERVAL {
+            report(lines.len(), 0, true);
+            last_report = Instant::now();
+        }
+    }
+    let total = lines.len();
+    let mut stdout = std::io::stdout().lock();
+    let matched = matchmaker::noninteractive::filter(lines, query, |line| {
+        write!(stdout, "{line}{output_separator}").is_err()
+    });
+    let _ = stdout.flush();
+    report(total, matched, false);
+
+    exit(if matched == 0 {
+        MatchError::EXIT_NO_MATCH
+    } else {
+        MatchError::EXIT_OK
+    })
+}
+
 fn display_doc(cli: &Cli) {
     use termimad::MadSkin;
     use termimad::crossterm::style::Color;
@@ -1,17 +1,38 @@
-use std::path::PathBuf;
+//! On-disk locations.
+//!
+//! Configuration lives in the config directory, and everything written by `mm` itself (logs,
+//! history, sockets, ...) in the state directory. Both can be overridden with `MM_CONFIG_DIR`
+//! and `MM_STATE_DIR`, and otherwise follow the XDG base directories where set, falling back to
+//! the platform defaults.
 
+use std::{ffi::OsString, path::PathBuf};
+
+use anyhow::Context;
 use cba::expr_as_path_fn;
 
-use crate::clap::LIBRARY_FULL;
+use crate::clap::{BINARY_SHORT, LIBRARY_FULL};
 
-fn config_dir_impl() -> Option<PathBuf> {
-    if let Some(env_val) = std::env::var_os("MATCHMAKER_CONFIG_DIR") {
-        let env_path = PathBuf::from(env_val);
-        if env_path.exists() {
-            return Some(env_path);
-        }
+/// An absolute path from the environment variable `name`. Relative paths are ignored, as
+/// required by the XDG specification.
+fn env_dir(var: &impl Fn(&str) -> Option<OsString>, name: &str) -> Option<PathBuf> {
+    var(name)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
+
+fn config_dir_with(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    if let Some(dir) = env_dir(&var, "MM_CONFIG_DIR") {
+        return Some(dir);
+    }
+    // accepted for compatibility, only where it exists
+    if let Some(dir) = env_dir(&var, "MATCHMAKER_CONFIG_DIR").filter(|dir| dir.exists()) {
+        return Some(dir);
+    }
+    if let Some(xdg) = env_dir(&var, "XDG_CONFIG_HOME") {
+        return Some(xdg.join(LIBRARY_FULL));
     }
 
+    // preferred where it already exists, i.e. on macOS
     if let Some(home) = dirs::home_dir() {
         let config = home.join(".config").join(LIBRARY_FULL);
         if config.exists() {
@@ -22,17 +43,53 @@ fn config_dir_impl() -> Option<PathBuf> {
     dirs::config_dir().map(|x| x.join(LIBRARY_FULL))
 }
 
-pub fn state_dir_impl() -> Option<PathBuf> {
-    dirs::state_dir()
-        .or_else(|| dirs::home_dir().map(|home| home.join(".local").join("state")))
-        .map(|x| x.join(LIBRARY_FULL))
+fn state_dir_with(var: impl Fn(&str) -> Option<OsString>) -> Option<PathBuf> {
+    if let Some(dir) = env_dir(&var, "MM_STATE_DIR") {
+        return Some(dir);
+    }
+    if let Some(xdg) = env_dir(&var, "XDG_STATE_HOME") {
+        return Some(xdg.join(LIBRARY_FULL));
+    }
+
+    let platform = if cfg!(windows) {
+        dirs::data_local_dir()
+    } else {
+        dirs::state_dir().or_else(|| dirs::home_dir().map(|home| home.join(".local").join("state")))
+    };
+    platform.map(|x| x.join(LIBRARY_FULL))
+}
+
+fn config_dir_impl() -> Option<PathBuf> {
+    config_dir_with(std::env::var_os)
 }
 
-expr_as_path_fn!(state_dir, state_dir_impl().unwrap_or_default());
-expr_as_path_fn!(
-    last_key_path,
-    state_dir_impl().unwrap_or_default().join("last_key")
-);
+/// The state directory, or an error if neither `MM_STATE_DIR`, `XDG_STATE_HOME` nor a platform
+/// default is available, rather than writing relative to the current directory.
+fn state_dir() -> anyhow::Result<PathBuf> {
+    state_dir_with(std::env::var_os)
+        .context("Could not determine the state directory, set MM_STATE_DIR")
+}
+
+pub fn last_key_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join("last_key"))
+}
+
+pub fn history_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join("history"))
+}
+
+pub fn sessions_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join("sessions"))
+}
+
+pub fn log_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join(format!("{BINARY_SHORT}.log")))
+}
+
+#[cfg(unix)]
+pub fn preview_socket_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join("preview.sock"))
+}
 
 #[cfg(debug_assertions)]
 expr_as_path_fn!(
@@ -53,3 +110,50 @@ expr_as_path_fn!(
     default_config_path,
     config_dir_impl().unwrap_or_default().join("config.toml")
 );
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> impl Fn(&str) -> Option<OsString> {
+        let pairs: Vec<(String, OsString)> = pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), OsString::from(v)))
+            .collect();
+        move |name| {
+            pairs
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.clone())
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_dir_overrides() {
+        let var = vars(&[
+            ("MM_STATE_DIR", "/tmp/mm-state"),
+            ("XDG_STATE_HOME", "/tmp/xdg-state"),
+            ("XDG_CONFIG_HOME", "/tmp/xdg-config"),
+        ]);
+        assert_eq!(state_dir_with(&var), Some(PathBuf::from("/tmp/mm-state")));
+        assert_eq!(
+            config_dir_with(&var),
+            Some(PathBuf::from("/tmp/xdg-config").join(LIBRARY_FULL))
+        );
+
+        // relative paths are ignored
+        let var = vars(&[("MM_CONFIG_DIR", "rel"), ("MATCHMAKER_CONFIG_DIR", "/")]);
+        assert_eq!(config_dir_with(&var), Some(PathBuf::from("/")));
+
+        // MATCHMAKER_CONFIG_DIR is only used where it exists
+        let var = vars(&[
+            ("MATCHMAKER_CONFIG_DIR", "/nonexistent/mm"),
+            ("XDG_CONFIG_HOME", "/tmp/xdg-config"),
+        ]);
+        assert_eq!(
+            config_dir_with(&var),
+            Some(PathBuf::from("/tmp/xdg-config").join(LIBRARY_FULL))
+        );
+    }
+}
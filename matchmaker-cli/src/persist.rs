@@ -0,0 +1,352 @@
+//! The format of state files written by `mm`, such as the history.
+//!
+//! State is stored as JSON, wrapped in an envelope recording the format version and the kind of
+//! state. Within a major version, changes only add fields, which older versions ignore and newer
+//! versions default, so files are shared freely between them. A file with a different major
+//! version is refused rather than misread, and one from a newer major version is never written
+//! over, as its state couldn't be carried back.
+
+use std::{collections::HashMap, fmt, path::Path, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de::DeserializeOwned};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::utils::write_atomic;
+
+/// The version written by this build.
+pub const STATE_VERSION: Version = Version { major: 1, minor: 0 };
+
+/// Serialized as `"major.minor"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+}
+
+impl Version {
+    pub fn is_compatible(&self) -> bool {
+        self.major == STATE_VERSION.major
+    }
+
+    fn check(self) -> Result<(), StateError> {
+        if self.major > STATE_VERSION.major {
+            Err(StateError::Newer { found: self })
+        } else if !self.is_compatible() {
+            Err(StateError::Incompatible { found: self })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (major, minor) = s.split_once('.').unwrap_or((s, "0"));
+        let parse = |n: &str| n.parse().map_err(|_| format!("Invalid version: {s}"));
+        Ok(Self {
+            major: parse(major)?,
+            minor: parse(minor)?,
+        })
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Envelope<T> {
+    version: Version,
+    kind: String,
+    data: T,
+}
+
+/// The part of the envelope which every major version has, read first as the rest may differ.
+#[derive(Debug, Deserialize)]
+struct Header {
+    version: Version,
+}
+
+/// The version of the state in `s`, or `None` if it isn't wrapped in an envelope.
+pub fn version(s: &str) -> Option<Version> {
+    serde_json::from_str::<Header>(s)
+        .ok()
+        .map(|header| header.version)
+}
+
+#[derive(Debug, Error)]
+pub enum StateError {
+    #[error("Unsupported version {found} (expected {}.x)", STATE_VERSION.major)]
+    Incompatible { found: Version },
+    #[error("Written by a newer version of mm (format {found}, expected {}.x)", STATE_VERSION.major)]
+    Newer { found: Version },
+    #[error("Expected {expected} state, found {found}")]
+    Kind { expected: String, found: String },
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Wrap `data` in an envelope of the current version.
+pub fn encode<T: Serialize>(kind: &str, data: &T) -> Result<String, StateError> {
+    Ok(serde_json::to_string(&Envelope {
+        version: STATE_VERSION,
+        kind: kind.to_string(),
+        data,
+    })?)
+}
+
+/// Unwrap state of the given kind, ignoring unknown fields.
+pub fn decode<T: DeserializeOwned>(kind: &str, s: &str) -> Result<T, StateError> {
+    // the version is checked before the data is interpreted
+    let value: Value = serde_json::from_str(s)?;
+    Header::deserialize(&value)?.version.check()?;
+    let envelope: Envelope<Value> = serde_json::from_value(value)?;
+    if envelope.kind != kind {
+        return Err(StateError::Kind {
+            expected: kind.to_string(),
+            found: envelope.kind,
+        });
+    }
+    Ok(serde_json::from_value(envelope.data)?)
+}
+
+/// Write `data` to `path`, unless the file there is from a newer major version.
+pub fn save<T: Serialize>(path: &Path, kind: &str, data: &T) -> anyhow::Result<()> {
+    if let Ok(existing) = std::fs::read_to_string(path)
+        && let Some(found) = version(&existing)
+        && found.major > STATE_VERSION.major
+    {
+        return Err(StateError::Newer { found }.into());
+    }
+    write_atomic(path, &encode(kind, data)?)?;
+    Ok(())
+}
+
+// ---------------- kinds ----------------
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryState {
+    /// Oldest first.
+    pub entries: Vec<String>,
+}
+
+impl HistoryState {
+    pub const KIND: &str = "history";
+
+    /// Also accepts the older format of one entry per line, which is anything not in an envelope.
+    pub fn parse(s: &str) -> Result<Self, StateError> {
+        if version(s).is_some() {
+            decode(Self::KIND, s)
+        } else {
+            Ok(Self {
+                entries: s.lines().map(String::from).collect(),
+            })
+        }
+    }
+}
+
+/// The state of a picker started with `--session`, which is a [`matchmaker::SavedSession`].
+pub const SESSION_KIND: &str = "session";
+
+/// Accepted items, keyed by their text.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FrecencyState {
+    pub entries: HashMap<String, Visits>,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Visits {
+    pub count: u32,
+    /// When the item was last accepted, in seconds since the unix epoch.
+    pub last: u64,
+}
+
+impl Visits {
+    /// The number of visits, weighted by how recent the last one is.
+    pub fn score(&self, now: u64) -> f64 {
+        let weight = match now.saturating_sub(self.last) {
+            ..3_600 => 4.0,
+            ..86_400 => 2.0,
+            ..604_800 => 0.5,
+            _ => 0.25,
+        };
+        self.count as f64 * weight
+    }
+}
+
+impl FrecencyState {
+    pub const KIND: &str = "frecency";
+
+    /// Record a visit to each item, then keep the `size` highest scoring entries.
+    pub fn record(&mut self, items: impl IntoIterator<Item = String>, now: u64, size: usize) {
+        for item in items {
+            let visits = self.entries.entry(item).or_default();
+            visits.count = visits.count.saturating_add(1);
+            visits.last = now;
+        }
+
+        if self.entries.len() > size {
+            let mut ranked: Vec<_> = self
+                .entries
+                .iter()
+                .map(|(item, visits)| (visits.score(now), visits.last, item.clone()))
+                .collect();
+            ranked.sort_by(|a, b| b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)));
+            for (.., item) in ranked.into_iter().skip(size) {
+                self.entries.remove(&item);
+            }
+        }
+    }
+
+    /// The current time, as recorded in [`Visits::last`].
+    pub fn now() -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+
+    pub fn scores(&self, now: u64) -> HashMap<String, f64> {
+        self.entries
+            .iter()
+            .map(|(item, visits)| (item.clone(), visits.score(now)))
+            .collect()
+    }
+
+    /// The amount added to the match score of an item with a frecency `score`.
+    ///
+    /// A matched character scores 16, so a single visit within the hour is worth about one more
+    /// matched character. The bonus grows logarithmically, so that frequent items don't bury
+    /// better matches.
+    pub fn bonus(score: f64) -> u32 {
+        (12.0 * score.ln_1p()).round() as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_skew() {
+        let s = encode(
+            HistoryState::KIND,
+            &HistoryState {
+                entries: vec!["a".into()],
+            },
+        )
+        .unwrap();
+        assert_eq!(HistoryState::parse(&s).unwrap().entries, ["a"]);
+
+        // newer minor versions may add fields
+        let newer =
+            r#"{"version":"1.3","kind":"history","data":{"entries":["b"],"pinned":[]},"extra":1}"#;
+        assert_eq!(HistoryState::parse(newer).unwrap().entries, ["b"]);
+
+        let incompatible = r#"{"version":"0.9","kind":"history","data":{"entries":"b"}}"#;
+        assert!(matches!(
+            HistoryState::parse(incompatible),
+            Err(StateError::Incompatible { .. })
+        ));
+        let newer = r#"{"version":"2.0","data":[]}"#;
+        assert!(matches!(
+            HistoryState::parse(newer),
+            Err(StateError::Newer { .. })
+        ));
+
+        assert_eq!(HistoryState::parse("x\ny\n").unwrap().entries, ["x", "y"]);
+        // only an envelope is read as json
+        assert_eq!(
+            HistoryState::parse("{print}\n{\"a\": 1}\n")
+                .unwrap()
+                .entries,
+            ["{print}", "{\"a\": 1}"]
+        );
+        assert_eq!(
+            HistoryState::parse("{\"a\": 1}").unwrap().entries,
+            ["{\"a\": 1}"]
+        );
+    }
+
+    #[test]
+    fn test_save_newer() {
+        let path = std::env::temp_dir().join(format!("mm-persist-{}", std::process::id()));
+        let newer = r#"{"version":"2.0","kind":"history","data":{}}"#;
+        std::fs::write(&path, newer).unwrap();
+
+        let state = HistoryState {
+            entries: vec!["a".into()],
+        };
+        assert!(save(&path, HistoryState::KIND, &state).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), newer);
+
+        std::fs::write(&path, "a\n").unwrap();
+        save(&path, HistoryState::KIND, &state).unwrap();
+        assert_eq!(
+            HistoryState::parse(&std::fs::read_to_string(&path).unwrap())
+                .unwrap()
+                .entries,
+            ["a"]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_session() {
+        let session = matchmaker::SavedSession {
+            query: "foo".into(),
+            selected: vec!["a".into()],
+            preview_layout: Some(1),
+            ..Default::default()
+        };
+        let s = encode(SESSION_KIND, &session).unwrap();
+        assert_eq!(
+            decode::<matchmaker::SavedSession>(SESSION_KIND, &s).unwrap(),
+            session
+        );
+
+        let partial = r#"{"version":"1.0","kind":"session","data":{"query":"bar"}}"#;
+        let session: matchmaker::SavedSession = decode(SESSION_KIND, partial).unwrap();
+        assert_eq!(session.query, "bar");
+        assert!(session.selected.is_empty());
+    }
+
+    #[test]
+    fn test_frecency() {
+        let day = 86_400;
+        let mut state = FrecencyState::default();
+        state.record(["a".to_string(), "b".to_string()], 0, 2);
+        state.record(["b".to_string()], 10 * day, 2);
+        state.record(["c".to_string()], 10 * day, 2);
+
+        let scores = state.scores(10 * day);
+        assert!(!scores.contains_key("a"));
+        assert!(scores["b"] > scores["c"]);
+        assert!(Visits { count: 1, last: 0 }.score(10) > Visits { count: 1, last: 0 }.score(day));
+
+        assert_eq!(FrecencyState::bonus(0.0), 0);
+        assert!(FrecencyState::bonus(scores["c"]) > FrecencyState::bonus(scores["b"]) / 2);
+        assert!(FrecencyState::bonus(1000.0) < 100);
+    }
+}
@@ -0,0 +1,354 @@
+//! `mm preview-server`: a daemon which runs preview commands on behalf of pickers and caches
+//! their output, so that expensive previews are computed once across invocations.
+//!
+//! Pickers opt in by setting `previewer.shell` to `["mm", "preview-client"]`, which forwards the
+//! command with its working directory and environment. When no server is running, the client
+//! starts one in the background and runs the command itself. The server exits after
+//! [`IDLE_TIMEOUT`] without a request. Only clients of the same user are served.
+
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    io::{self, Write},
+    os::unix::{
+        ffi::{OsStrExt, OsStringExt},
+        fs::{MetadataExt, PermissionsExt},
+        process::CommandExt,
+    },
+    path::{Path, PathBuf},
+    process::{Command, Stdio, exit},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::bail;
+use cba::{bait::ResultExt, bog::BogOkExt};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+};
+
+use crate::paths::preview_socket_path;
+
+/// Cached output older than this is recomputed.
+const TTL: Duration = Duration::from_secs(300);
+const CAPACITY: usize = 256;
+/// The server exits after this long without a request.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Handle `mm preview-server` and `mm preview-client <command>`, exiting if either was invoked.
+pub async fn dispatch() {
+    let mut args = std::env::args_os().skip(1);
+    match args.next().as_deref().and_then(OsStr::to_str) {
+        Some("preview-server") => {
+            serve(&preview_socket_path().__ebog()).await.__ebog();
+            exit(0)
+        }
+        Some("preview-client") => {
+            let command = args.next().unwrap_or_default();
+            exit(client(&preview_socket_path().__ebog(), command))
+        }
+        _ => {}
+    }
+}
+
+// ---------------- protocol ----------------
+
+/// NUL-separated: the working directory, the command, then `KEY=VALUE` pairs.
+struct Request {
+    cwd: PathBuf,
+    command: OsString,
+    env: Vec<(OsString, OsString)>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct Key {
+    cwd: PathBuf,
+    command: OsString,
+    env: Vec<(OsString, OsString)>,
+    // the modification time and size of each file named in the command
+    files: Vec<(PathBuf, SystemTime, u64)>,
+}
+
+impl Request {
+    fn encode(&self) -> Vec<u8> {
+        let mut ret = Vec::new();
+        ret.extend_from_slice(self.cwd.as_os_str().as_bytes());
+        ret.push(0);
+        ret.extend_from_slice(self.command.as_bytes());
+        for (k, v) in &self.env {
+            ret.push(0);
+            ret.extend_from_slice(k.as_bytes());
+            ret.push(b'=');
+            ret.extend_from_slice(v.as_bytes());
+        }
+        ret
+    }
+
+    fn decode(bytes: &[u8]) -> Option<Self> {
+        let mut fields = bytes.split(|b| *b == 0);
+        let cwd = PathBuf::from(OsString::from_vec(fields.next()?.to_vec()));
+        let command = OsString::from_vec(fields.next()?.to_vec());
+        let env = fields
+            .filter_map(|f| {
+                let eq = f.iter().position(|b| *b == b'=')?;
+                Some((
+                    OsString::from_vec(f[..eq].to_vec()),
+                    OsString::from_vec(f[eq + 1..].to_vec()),
+                ))
+            })
+            .collect();
+        Some(Self { cwd, command, env })
+    }
+
+    /// The environment is part of the key, as are the files named in the command, so that the
+    /// output is computed again when a previewed file changes. The variables describing the state
+    /// of the picker are left out unless the command uses them, as they change with every item.
+    fn key(&self) -> Key {
+        let command = self.command.to_string_lossy();
+        let mut env: Vec<_> = self
+            .env
+            .iter()
+            .filter(|(k, _)| !is_picker_state(k) || command.contains(&*k.to_string_lossy()))
+            .cloned()
+            .collect();
+        env.sort();
+        // an unterminated quote names no files
+        let files = shell_words::split(&command)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|word| self.cwd.join(word))
+            .filter_map(|path| {
+                let metadata = path.metadata().ok()?;
+                Some((path, metadata.modified().ok()?, metadata.len()))
+            })
+            .collect();
+        Key {
+            cwd: self.cwd.clone(),
+            command: self.command.clone(),
+            env,
+            files,
+        }
+    }
+}
+
+/// The variables set by pickers for commands, such as `MM_QUERY` and `FZF_QUERY`.
+fn is_picker_state(name: &OsStr) -> bool {
+    let name = name.as_bytes();
+    name.starts_with(b"MM_") || name.starts_with(b"FZF_")
+}
+
+// ---------------- server ----------------
+
+#[derive(Default)]
+struct Cache {
+    entries: HashMap<Key, (Instant, Arc<[u8]>)>,
+}
+
+impl Cache {
+    fn get(&mut self, key: &Key) -> Option<Arc<[u8]>> {
+        match self.entries.get(key) {
+            Some((created, output)) if created.elapsed() < TTL => Some(output.clone()),
+            Some(_) => {
+                self.entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    fn insert(&mut self, key: Key, output: Vec<u8>) {
+        if self.entries.len() >= CAPACITY
+            && let Some(oldest) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (created, _))| *created)
+                .map(|(k, _)| k.clone())
+        {
+            self.entries.remove(&oldest);
+        }
+        self.entries.insert(key, (Instant::now(), output.into()));
+    }
+}
+
+async fn serve(path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        if UnixStream::connect(path).await.is_ok() {
+            bail!("A preview server is already listening at {path:?}");
+        }
+        std::fs::remove_file(path)?;
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(path)?;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    let uid = std::fs::metadata(path)?.uid();
+    let cache = Arc::new(Mutex::new(Cache::default()));
+    log::info!("Preview server listening at {path:?}");
+
+    let idle = tokio::time::sleep(IDLE_TIMEOUT);
+    tokio::pin!(idle);
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                idle.as_mut().reset(tokio::time::Instant::now() + IDLE_TIMEOUT);
+                let Some((stream, _)) = accepted._elog() else {
+                    continue;
+                };
+                // commands are run with the client's environment, so only serve our own user
+                if stream.peer_cred().map(|cred| cred.uid()).ok() != Some(uid) {
+                    log::warn!("Refusing a preview client of another user");
+                    continue;
+                }
+                let cache = cache.clone();
+                tokio::spawn(async move {
+                    handle(stream, cache).await._elog();
+                });
+            }
+            _ = &mut idle => {
+                log::info!("Preview server idle for {IDLE_TIMEOUT:?}, exiting");
+                break;
+            }
+            _ = tokio::signal::ctrl_c() => break,
+        }
+    }
+
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+async fn handle(mut stream: UnixStream, cache: Arc<Mutex<Cache>>) -> io::Result<()> {
+    let mut buf = Vec::new();
+    stream.read_to_end(&mut buf).await?;
+    let Some(request) = Request::decode(&buf) else {
+        return Ok(());
+    };
+
+    let key = request.key();
+    let cached = cache.lock().unwrap().get(&key);
+    if let Some(output) = cached {
+        return stream.write_all(&output).await;
+    }
+
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(&request.command)
+        .current_dir(&request.cwd)
+        .env_clear()
+        .envs(request.env)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()?;
+
+    // forward output as it arrives, so the preview still streams on a miss
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    let mut output = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let n = stdout.read(&mut chunk).await?;
+        if n == 0 {
+            break;
+        }
+        output.extend_from_slice(&chunk[..n]);
+        // the picker has moved on, but the output can still be cached
+        let _ = stream.write_all(&chunk[..n]).await;
+    }
+
+    if child.wait().await?.success() {
+        cache.lock().unwrap().insert(key, output);
+    }
+    Ok(())
+}
+
+// ---------------- client ----------------
+
+fn client(path: &Path, command: OsString) -> i32 {
+    let request = Request {
+        cwd: std::env::current_dir().unwrap_or_default(),
+        command,
+        env: std::env::vars_os().collect(),
+    };
+
+    match std::os::unix::net::UnixStream::connect(path) {
+        Ok(mut stream) => {
+            let sent = stream
+                .write_all(&request.encode())
+                .and_then(|_| stream.shutdown(std::net::Shutdown::Write));
+            if sent.is_ok() && io::copy(&mut stream, &mut io::stdout().lock()).is_ok() {
+                return 0;
+            }
+        }
+        Err(e) => {
+            log::debug!("Preview server unavailable: {e}");
+            if matches!(
+                e.kind(),
+                io::ErrorKind::NotFound | io::ErrorKind::ConnectionRefused
+            ) {
+                start_server();
+            }
+        }
+    }
+
+    Command::new("sh")
+        .arg("-c")
+        .arg(&request.command)
+        .status()
+        .map_or(1, |s| s.code().unwrap_or(1))
+}
+
+/// Start a server in the background, to serve the following previews.
+fn start_server() {
+    let Some(exe) = std::env::current_exe()._elog() else {
+        return;
+    };
+    Command::new(exe)
+        .arg("preview-server")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        // outlive the picker's process group
+        .process_group(0)
+        .spawn()
+        ._elog();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key() {
+        let dir = std::env::temp_dir().join(format!("mm-preview-server-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("a file"), "content").unwrap();
+
+        let request = |command: &str, query: &str| Request {
+            cwd: dir.clone(),
+            command: command.into(),
+            env: vec![
+                ("HOME".into(), "/home".into()),
+                ("MM_QUERY".into(), query.into()),
+            ],
+        };
+
+        let key = request("cat 'a file'", "a").key();
+        assert_eq!(key.files.len(), 1);
+        assert_eq!(key.files[0].0, dir.join("a file"));
+        assert_eq!(
+            key.env,
+            vec![(OsString::from("HOME"), OsString::from("/home"))]
+        );
+        assert!(key == request("cat 'a file'", "b").key());
+
+        // the state is kept when the command uses it
+        let key = request("echo $MM_QUERY", "a").key();
+        assert_eq!(key.env.len(), 2);
+        assert!(key != request("echo $MM_QUERY", "b").key());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,89 @@
+//! `--profile-startup`: the time taken by each phase of startup, printed on exit.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+struct Profile {
+    start: Instant,
+    last: Instant,
+    phases: Vec<(&'static str, Duration)>,
+}
+
+static PROFILE: Mutex<Option<Profile>> = Mutex::new(None);
+
+/// Start timing. Phases are measured from here.
+pub fn enable() {
+    let now = Instant::now();
+    *PROFILE.lock().unwrap() = Some(Profile {
+        start: now,
+        last: now,
+        phases: Vec::new(),
+    });
+}
+
+/// Record the time since the previous phase ended as the time taken by `phase`.
+/// Only the first completion of a phase is recorded.
+pub fn mark(phase: &'static str) {
+    let mut profile = PROFILE.lock().unwrap();
+    let Some(profile) = profile.as_mut() else {
+        return;
+    };
+    if profile.phases.iter().any(|(p, _)| *p == phase) {
+        return;
+    }
+    let now = Instant::now();
+    profile.phases.push((phase, now - profile.last));
+    profile.last = now;
+}
+
+/// Print the phases to stderr.
+pub fn report() {
+    let Some(profile) = PROFILE.lock().unwrap().take() else {
+        return;
+    };
+    eprint!(
+        "{}",
+        format_report(&profile.phases, profile.last - profile.start)
+    );
+}
+
+fn format_report(phases: &[(&str, Duration)], total: Duration) -> String {
+    let width = phases
+        .iter()
+        .map(|(p, _)| p.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+    let line = |name: &str, d: Duration| {
+        format!("  {name:<width$}  {:>9.1}ms\n", d.as_secs_f64() * 1000.0)
+    };
+
+    let mut out = String::from("Startup profile:\n");
+    for (phase, d) in phases {
+        out.push_str(&line(phase, *d));
+    }
+    out.push_str(&line("total", total));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_report() {
+        let report = format_report(
+            &[
+                ("config", Duration::from_millis(12)),
+                ("first frame", Duration::from_micros(2500)),
+            ],
+            Duration::from_micros(14500),
+        );
+        assert_eq!(
+            report,
+            "Startup profile:\n  config            12.0ms\n  first frame        2.5ms\n  total             14.5ms\n"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use std::{process::Command};
+use std::process::{Command, Stdio};
 
 use cba::{
     broc::{CommandExt, tty_or_inherit},
@@ -120,6 +120,10 @@ impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
         });
         
         self.register_interrupt_handler(Interrupt::ExecuteSilent, move |state| {
+            // Copy
+            if state.discriminant_payload.is_some() {
+                return;
+            }
             let template = state.payload().clone();
             if !template.is_empty() {
                 let cmd = use_formatter(&formatter_, state, &template, None);
@@ -135,19 +139,22 @@ impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
                 );
                 vars.extend(extra);
                 
-                if let Some(mut _child) = Command::from_script(&cmd)
+                // output would be drawn over the picker
+                if let Some(mut child) = Command::from_script(&cmd)
                 .envs(vars)
-                .stdin(tty_or_inherit())
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
                 ._spawn()
                 {
-                    // match child.wait() {
-                    //     Ok(i) => {
-                    //         info!("Command [{cmd}] exited with {i}")
-                    //     }
-                    //     Err(e) => {
-                    //         info!("Failed to wait on command [{cmd}]: {e}")
-                    //     }
-                    // }
+                    std::thread::spawn(move || match child.wait() {
+                        Ok(i) => {
+                            info!("Command [{cmd}] exited with {i}")
+                        }
+                        Err(e) => {
+                            info!("Failed to wait on command [{cmd}]: {e}")
+                        }
+                    });
                 }
             };
         });
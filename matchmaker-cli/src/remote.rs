@@ -0,0 +1,641 @@
+//! Remote control of a running picker.
+//!
+//! With `--listen`, actions are POSTed to an HTTP endpoint and the picker state is read with a
+//! GET, as with fzf's `--listen`. With `--control-socket`, the same is done over a Unix socket,
+//! one line at a time. Actions are fed into the render loop through its [`RenderSender`], and
+//! state requests are answered from within it by [`MMAction::Report`].
+//!
+//! Changes to the item, match and selection counts are published by a [`StatusFeed`], written to
+//! `--status-fd` and to control socket connections which send `watch`.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use matchmaker::{Action, event::RenderSender, message::RenderCommand};
+use serde_json::{Value, json};
+use tokio::sync::{broadcast, oneshot};
+
+use crate::{
+    action::{MMAction, MMState},
+    clap::Cli,
+};
+
+/// How long a state request waits on the render loop.
+const REPORT_TIMEOUT: Duration = Duration::from_secs(2);
+/// Number of matches included in the state by default.
+pub const DEFAULT_LIMIT: usize = 100;
+/// Minimum time between status lines while items are being read or matched.
+pub const STATUS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How the picker can be controlled from outside the terminal.
+#[derive(Debug, Default, Clone)]
+pub struct Remote {
+    /// Port to accept HTTP requests on.
+    #[cfg(feature = "listen")]
+    pub listen: Option<u16>,
+    /// Path of a Unix socket to accept lines on.
+    #[cfg(unix)]
+    pub socket: Option<std::path::PathBuf>,
+    /// File descriptor to write status lines to.
+    #[cfg(unix)]
+    pub status_fd: Option<i32>,
+}
+
+impl Remote {
+    pub fn from_cli(#[allow(unused)] cli: &Cli) -> Self {
+        Self {
+            #[cfg(feature = "listen")]
+            listen: cli.listen,
+            #[cfg(unix)]
+            socket: cli.control_socket.clone(),
+            #[cfg(unix)]
+            status_fd: cli.status_fd,
+        }
+    }
+}
+
+/// Pending requests for the picker state, answered by [`MMAction::Report`].
+#[derive(Debug, Default, Clone)]
+pub struct Reports(Arc<Mutex<Vec<(usize, oneshot::Sender<String>)>>>);
+
+impl Reports {
+    /// Ask the render loop for its state as JSON, including up to `limit` matches.
+    pub async fn request(
+        &self,
+        render_tx: &RenderSender<MMAction>,
+        limit: usize,
+    ) -> Option<String> {
+        let (tx, rx) = oneshot::channel();
+        self.0.lock().unwrap().push((limit, tx));
+        render_tx
+            .send(RenderCommand::Action(Action::Custom(MMAction::Report)))
+            .ok()?;
+        tokio::time::timeout(REPORT_TIMEOUT, rx).await.ok()?.ok()
+    }
+
+    pub fn answer(&self, state: &MMState<'_, '_>) {
+        for (limit, tx) in self.0.lock().unwrap().drain(..) {
+            let _ = tx.send(state_json(state, limit).to_string());
+        }
+    }
+}
+
+/// The picker state, using the same keys as fzf.
+pub fn state_json(state: &MMState<'_, '_>, limit: usize) -> Value {
+    let item = |index: u32, text: &String| json!({ "index": index, "text": text });
+    let status = state.status();
+    let worker = &state.picker_ui.worker;
+
+    let matches: Vec<Value> = (0..status.matched_count.min(limit as u32))
+        .filter_map(|n| worker.get_nth_indexed(n))
+        .map(|(i, t)| item(i, t))
+        .collect();
+
+    json!({
+        "reading": worker.reading(),
+        "query": state.picker_ui.query.input,
+        "position": state.picker_ui.current_indexed().map(|_| state.picker_ui.results.index()),
+        "totalCount": status.item_count,
+        "matchCount": status.matched_count,
+        "current": state.picker_ui.current_indexed().map(|(i, t)| item(i, t)),
+        "matches": matches,
+        "selected": state.map_selections_to_vec(item),
+    })
+}
+
+/// Throttled status lines, published from the render loop on [`matchmaker::message::Event::StatusChange`]
+/// and [`matchmaker::message::Event::SelectionChange`].
+#[derive(Debug, Clone)]
+pub struct StatusFeed {
+    tx: broadcast::Sender<String>,
+    last: Arc<Mutex<LastStatus>>,
+}
+
+#[derive(Debug, Default)]
+struct LastStatus {
+    /// When a line was last sent
+    sent: Option<Instant>,
+    /// The latest line
+    line: String,
+    /// Whether the latest line is waiting for the end of the interval
+    pending: bool,
+}
+
+impl Default for StatusFeed {
+    fn default() -> Self {
+        Self {
+            tx: broadcast::channel(64).0,
+            last: Default::default(),
+        }
+    }
+}
+
+impl StatusFeed {
+    /// The latest status line (empty before the first), and a receiver for the following ones.
+    pub fn subscribe(&self) -> (String, broadcast::Receiver<String>) {
+        let last = self.last.lock().unwrap();
+        (last.line.clone(), self.tx.subscribe())
+    }
+
+    /// Send the status, or if one was sent within [`STATUS_INTERVAL`], send the latest status at
+    /// the end of the interval. Once reading and matching have finished, the status is sent
+    /// immediately.
+    pub fn publish(&self, state: &MMState<'_, '_>) {
+        let status = state.status();
+        let reading = state.picker_ui.worker.reading();
+        let line = status_json(
+            status.item_count,
+            status.matched_count,
+            state.picker_ui.selector.len(),
+            reading,
+            status.running,
+        )
+        .to_string();
+        self.publish_line(line, !reading && !status.running);
+    }
+
+    fn publish_line(&self, line: String, settled: bool) {
+        let mut last = self.last.lock().unwrap();
+        if last.line == line {
+            return;
+        }
+        last.line = line;
+
+        let wait = last.sent.map_or(Duration::ZERO, |t| {
+            STATUS_INTERVAL.saturating_sub(t.elapsed())
+        });
+        if settled || wait.is_zero() {
+            self.send(&mut last);
+        } else if !last.pending {
+            last.pending = true;
+            let feed = self.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(wait).await;
+                let mut last = feed.last.lock().unwrap();
+                if last.pending {
+                    feed.send(&mut last);
+                }
+            });
+        }
+    }
+
+    fn send(&self, last: &mut LastStatus) {
+        last.pending = false;
+        if self.tx.receiver_count() > 0 {
+            last.sent = Some(Instant::now());
+            let _ = self.tx.send(last.line.clone());
+        }
+    }
+}
+
+/// A status line, using the same keys as [`state_json`].
+pub fn status_json(
+    total: u32,
+    matched: u32,
+    selected: usize,
+    reading: bool,
+    running: bool,
+) -> Value {
+    json!({
+        "totalCount": total,
+        "matchCount": matched,
+        "selectedCount": selected,
+        "reading": reading,
+        "running": running,
+    })
+}
+
+/// Open `fd` for writing status lines.
+///
+/// The descriptor is reopened through `/dev/fd`, so that it is checked to be open and is not
+/// closed from under its owner.
+#[cfg(unix)]
+pub fn open_status_fd(fd: i32) -> std::io::Result<std::fs::File> {
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(format!("/dev/fd/{fd}"))
+}
+
+/// Write the lines received by `rx` to `file` until the picker exits.
+#[cfg(unix)]
+pub async fn write_status(file: std::fs::File, mut rx: broadcast::Receiver<String>) {
+    use cba::bait::ResultExt;
+    use tokio::io::AsyncWriteExt;
+
+    let mut file = tokio::fs::File::from_std(file);
+    loop {
+        let line = match rx.recv().await {
+            Ok(line) => line,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if file
+            .write_all(format!("{line}\n").as_bytes())
+            .await
+            ._elog()
+            .is_none()
+        {
+            break;
+        }
+        let _ = file.flush().await;
+    }
+}
+
+/// Parse one action per line, as in the output of `Transform`.
+pub fn parse_actions(s: &str) -> Result<Vec<Action<MMAction>>, String> {
+    s.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let action = Action::<MMAction>::from_str(line)
+                .map_err(|e| format!("Invalid action {line:?}: {e}"))?;
+            if let Action::Custom(a) = &action {
+                a.validate()
+                    .map_err(|e| format!("Invalid action {line:?}: {e}"))?;
+            }
+            Ok(action)
+        })
+        .collect()
+}
+
+/// Feed the actions in `s` to the render loop.
+pub fn send_actions(s: &str, render_tx: &RenderSender<MMAction>) -> Result<(), String> {
+    for action in parse_actions(s)? {
+        render_tx
+            .send(RenderCommand::Action(action))
+            .map_err(|_| "The picker has exited".to_string())?;
+    }
+    Ok(())
+}
+
+// ---------------- http ----------------
+
+#[cfg(feature = "listen")]
+pub mod http {
+    use std::io;
+
+    use cba::bait::ResultExt;
+    use matchmaker::event::RenderSender;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+        net::{TcpListener, TcpStream},
+    };
+
+    use super::{DEFAULT_LIMIT, Reports, send_actions};
+    use crate::action::MMAction;
+
+    /// Larger bodies are rejected.
+    const MAX_BODY: usize = 1 << 20;
+
+    /// Bind to the loopback interface. Port 0 picks a free port.
+    pub async fn bind(port: u16) -> io::Result<TcpListener> {
+        TcpListener::bind(("127.0.0.1", port)).await
+    }
+
+    /// Serve requests until the picker exits.
+    ///
+    /// If `MM_API_KEY` is set, requests must carry it in an `X-Api-Key` header. Requests from
+    /// browsers, i.e. with an `Origin` header or a `Host` other than the loopback interface, are
+    /// always rejected, so that web pages can't send actions.
+    pub async fn serve(listener: TcpListener, render_tx: RenderSender<MMAction>, reports: Reports) {
+        let api_key = std::env::var("MM_API_KEY").ok().filter(|k| !k.is_empty());
+
+        while !render_tx.is_closed() {
+            let Some((stream, _)) = listener.accept().await._elog() else {
+                continue;
+            };
+            let render_tx = render_tx.clone();
+            let reports = reports.clone();
+            let api_key = api_key.clone();
+            tokio::spawn(async move {
+                handle(stream, &render_tx, &reports, api_key.as_deref())
+                    .await
+                    ._elog();
+            });
+        }
+    }
+
+    async fn handle(
+        mut stream: TcpStream,
+        render_tx: &RenderSender<MMAction>,
+        reports: &Reports,
+        api_key: Option<&str>,
+    ) -> io::Result<()> {
+        let mut reader = BufReader::new(&mut stream);
+
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).await?;
+        let mut parts = request_line.split_whitespace();
+        let (method, target) = (
+            parts.next().unwrap_or_default().to_string(),
+            parts.next().unwrap_or_default().to_string(),
+        );
+
+        let mut content_length = 0;
+        let mut authorized = api_key.is_none();
+        let mut from_browser = false;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await? == 0 || line.trim().is_empty() {
+                break;
+            }
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.parse().unwrap_or(0);
+            } else if name.eq_ignore_ascii_case("x-api-key") {
+                authorized |= api_key.is_some_and(|key| constant_time_eq(key, value));
+            } else if name.eq_ignore_ascii_case("origin") {
+                from_browser = true;
+            } else if name.eq_ignore_ascii_case("host") {
+                from_browser |= !is_loopback_host(value);
+            }
+        }
+
+        let (status, content_type, body) = if from_browser {
+            (403, "text/plain", "Forbidden\n".to_string())
+        } else if !authorized {
+            (401, "text/plain", "Unauthorized\n".to_string())
+        } else if content_length > MAX_BODY {
+            (413, "text/plain", "Request body too large\n".to_string())
+        } else {
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).await?;
+            let body = String::from_utf8_lossy(&body);
+
+            let (path, query) = target.split_once('?').unwrap_or((&target, ""));
+            match (method.as_str(), path) {
+                ("GET", "/") => {
+                    let limit = query
+                        .split('&')
+                        .find_map(|kv| kv.strip_prefix("limit="))
+                        .and_then(|n| n.parse().ok())
+                        .unwrap_or(DEFAULT_LIMIT);
+                    match reports.request(render_tx, limit).await {
+                        Some(json) => (200, "application/json", json),
+                        None => (
+                            503,
+                            "text/plain",
+                            "The picker did not respond\n".to_string(),
+                        ),
+                    }
+                }
+                ("POST", "/") => match send_actions(&body, render_tx) {
+                    Ok(()) => (200, "text/plain", String::new()),
+                    Err(e) => (400, "text/plain", format!("{e}\n")),
+                },
+                _ => (404, "text/plain", "Not found\n".to_string()),
+            }
+        };
+
+        let reason = match status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            413 => "Payload Too Large",
+            _ => "Service Unavailable",
+        };
+        let response = format!(
+            "HTTP/1.1 {status} {reason}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    /// Whether a `Host` header names the loopback interface, guarding against DNS rebinding.
+    pub(super) fn is_loopback_host(host: &str) -> bool {
+        let name = if let Some(rest) = host.strip_prefix('[') {
+            rest.split_once(']').map_or(rest, |(name, _)| name)
+        } else {
+            host.rsplit_once(':').map_or(host, |(name, _)| name)
+        };
+        matches!(name, "127.0.0.1" | "localhost" | "::1")
+    }
+
+    /// Compare without returning early, so that the key can't be guessed from response times.
+    pub(super) fn constant_time_eq(a: &str, b: &str) -> bool {
+        a.len() == b.len()
+            && a.bytes()
+                .zip(b.bytes())
+                .fold(0, |acc, (x, y)| acc | (x ^ y))
+                == 0
+    }
+}
+
+// ---------------- socket ----------------
+
+#[cfg(unix)]
+pub mod socket {
+    use std::{
+        io,
+        os::unix::fs::FileTypeExt,
+        path::{Path, PathBuf},
+    };
+
+    use cba::bait::ResultExt;
+    use matchmaker::event::RenderSender;
+    use serde_json::json;
+    use tokio::{
+        io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+        net::{UnixListener, UnixStream},
+        sync::broadcast,
+    };
+
+    use super::{DEFAULT_LIMIT, Reports, StatusFeed, send_actions};
+    use crate::action::MMAction;
+
+    /// Removes the socket when dropped.
+    #[derive(Debug)]
+    pub struct SocketGuard(PathBuf);
+
+    impl Drop for SocketGuard {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// Bind to `path`, replacing a stale socket but not a live one, nor anything but a socket.
+    pub async fn bind(path: &Path) -> io::Result<(UnixListener, SocketGuard)> {
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} exists and is not a socket", path.display()),
+                ));
+            }
+            if UnixStream::connect(path).await.is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    "another picker is listening on this socket",
+                ));
+            }
+            std::fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            std::fs::create_dir_all(parent)?;
+        }
+        let listener = UnixListener::bind(path)?;
+        Ok((listener, SocketGuard(path.to_path_buf())))
+    }
+
+    /// Serve connections until the picker exits.
+    pub async fn serve(
+        listener: UnixListener,
+        render_tx: RenderSender<MMAction>,
+        reports: Reports,
+        feed: StatusFeed,
+    ) {
+        while !render_tx.is_closed() {
+            let Some((stream, _)) = listener.accept().await._elog() else {
+                continue;
+            };
+            let render_tx = render_tx.clone();
+            let reports = reports.clone();
+            let feed = feed.clone();
+            tokio::spawn(async move {
+                handle(stream, &render_tx, &reports, &feed).await._elog();
+            });
+        }
+    }
+
+    /// Each line is either an action, or `state` optionally followed by a limit on the number of
+    /// matches, or `watch`. State requests and invalid lines are replied to with a line of JSON.
+    /// After `watch`, status lines are also written to the connection as they are published.
+    async fn handle(
+        stream: UnixStream,
+        render_tx: &RenderSender<MMAction>,
+        reports: &Reports,
+        feed: &StatusFeed,
+    ) -> io::Result<()> {
+        let (reader, mut writer) = stream.into_split();
+        let mut lines = BufReader::new(reader).lines();
+        let mut watch = None;
+
+        loop {
+            let line = tokio::select! {
+                line = lines.next_line() => match line? {
+                    Some(line) => line,
+                    None => break,
+                },
+                Some(status) = next_status(&mut watch) => {
+                    writer.write_all(status.as_bytes()).await?;
+                    writer.write_all(b"\n").await?;
+                    continue;
+                }
+            };
+            let line = line.trim();
+            let reply = if line == "watch" {
+                let (latest, rx) = feed.subscribe();
+                watch = Some(rx);
+                if latest.is_empty() {
+                    continue;
+                }
+                latest
+            } else if let Some(rest) = line.strip_prefix("state")
+                && (rest.is_empty() || rest.starts_with(' '))
+            {
+                let limit = rest.trim().parse().unwrap_or(DEFAULT_LIMIT);
+                reports
+                    .request(render_tx, limit)
+                    .await
+                    .unwrap_or_else(|| json!({ "error": "The picker did not respond" }).to_string())
+            } else {
+                match send_actions(line, render_tx) {
+                    Ok(()) => continue,
+                    Err(e) => json!({ "error": e }).to_string(),
+                }
+            };
+            writer.write_all(reply.as_bytes()).await?;
+            writer.write_all(b"\n").await?;
+        }
+        Ok(())
+    }
+
+    /// The next status line, or never when not watching.
+    async fn next_status(watch: &mut Option<broadcast::Receiver<String>>) -> Option<String> {
+        let Some(rx) = watch else {
+            return std::future::pending().await;
+        };
+        loop {
+            match rx.recv().await {
+                Ok(line) => return Some(line),
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => {
+                    *watch = None;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_actions() {
+        let actions = parse_actions("up\n\n  Reload(ls -a)  \nPos(5)").unwrap();
+        assert_eq!(actions.len(), 3);
+        assert!(matches!(&actions[1], Action::Reload(cmd) if cmd == "ls -a"));
+
+        assert!(parse_actions("NotAnAction").is_err());
+        assert!(parse_actions("Bind(nokey)").is_err());
+        assert!(parse_actions("Report").is_err());
+        assert!(parse_actions("Bind(ctrl-a=Report)").is_err());
+    }
+
+    #[cfg(feature = "listen")]
+    #[test]
+    fn test_http_guards() {
+        assert!(http::is_loopback_host("127.0.0.1:8080"));
+        assert!(http::is_loopback_host("localhost"));
+        assert!(http::is_loopback_host("[::1]:8080"));
+        assert!(!http::is_loopback_host("attacker.example:8080"));
+
+        assert!(http::constant_time_eq("key", "key"));
+        assert!(!http::constant_time_eq("key", "kez"));
+        assert!(!http::constant_time_eq("key", "keys"));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_bind_keeps_regular_files() {
+        let path = std::env::temp_dir().join(format!("mm-bind-test-{}", std::process::id()));
+        std::fs::write(&path, "notes").unwrap();
+
+        assert!(socket::bind(&path).await.is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "notes");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_status_feed_sends_latest_line() {
+        let feed = StatusFeed::default();
+        let (_, mut rx) = feed.subscribe();
+
+        feed.publish_line("a".into(), false);
+        feed.publish_line("b".into(), false);
+        feed.publish_line("c".into(), false);
+        assert_eq!(rx.recv().await.unwrap(), "a");
+        // the throttled lines are replaced by the latest one at the end of the interval
+        assert_eq!(rx.recv().await.unwrap(), "c");
+
+        feed.publish_line("d".into(), false);
+        feed.publish_line("e".into(), true);
+        assert_eq!(rx.try_recv().unwrap(), "e");
+        // the pending line is superseded by the settled one
+        tokio::time::sleep(STATUS_INTERVAL * 2).await;
+        assert!(rx.try_recv().is_err());
+    }
+}
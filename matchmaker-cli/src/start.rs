@@ -2,7 +2,7 @@ use std::{
     collections::HashMap,
     env::set_current_dir,
     io::Read,
-    path::Path,
+    path::{Path, PathBuf},
     process::{Command, Stdio, exit},
     sync::{Arc, Mutex},
 };
@@ -12,33 +12,38 @@ use crate::{
     clap::Cli,
     config::PartialConfig,
     formatter::format_cli,
-    paths::{last_key_path, presets_path},
+    paths::{history_path, last_key_path, presets_path},
+    persist::{self, FrecencyState, HistoryState, SESSION_KIND, StateError},
     register::MMExt,
-    utils::{expand_tilde, guess_editor_cmd, guess_pager_cmd},
+    remote::{Remote, Reports, StatusFeed},
+    utils::{
+        backup_file, expand_tilde, guess_editor_cmd, guess_pager_cmd, relative_to, write_atomic,
+    },
 };
 use crate::{config::Config, paths::default_config_path};
 use cba::{
     _wbog,
     bait::{OptionExt, ResultExt, TransformExt},
-    bo::{
-        MapReaderError, load_type_or_default, map_chunks, map_reader_lines, read_to_chunks,
-        write_str,
-    },
+    bo::{MapReaderError, map_chunks, map_reader_lines, read_to_chunks},
     bog::BogOkExt,
     ebog, ibog, prints, wbog,
 };
 use cba::{bo::load_type, broc::CommandExt};
 use log::debug;
 use matchmaker::{
-    Action, Either, MatchError, Matchmaker, PickOptions, SSS,
-    binds::{BindMap, BindMapExt},
-    config::{CommandSetting, EnvValue, MatcherConfig, StartConfig},
+    Action, Either, MatchError, Matchmaker, PickOptions, Preselect, SSS,
+    binds::{BindMap, BindMapExt, Trigger},
+    config::{
+        CommandSetting, EnvValue, HistoryConfig, MatcherConfig, SortThreshold, StartConfig,
+        StringOrVec,
+    },
     config_mm::{ConfigInjector, ConfigPreprocessedData, OddEnds},
     event::{EventLoop, RenderSender},
     make_previewer,
-    message::Interrupt,
+    message::{Event, Interrupt},
     nucleo::{Column, Line, Span, Text, injector::Injector},
     preview::AppendOnly,
+    ui::{History, ToastLevel},
     use_formatter,
 };
 use matchmaker_partial::Apply;
@@ -57,7 +62,7 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
 
     if cli.dump_config && atty::is(atty::Stream::Stdout) {
         // if stdout: dump the default cfg with comments
-        write_str(cfg_path, crate::config::DEFAULT_CONFIG)?;
+        write_atomic(cfg_path, crate::config::DEFAULT_CONFIG)?;
         ibog!("Config written to {cfg_path:?}");
         exit(0)
     }
@@ -65,53 +70,33 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
     #[cfg(debug_assertions)]
     if cli.config.is_none() {
         #[cfg(target_os = "windows")]
-        write_str(cfg_path, include_str!("../assets/win.dev.toml")).unwrap();
+        write_atomic(cfg_path, include_str!("../assets/win.dev.toml")).unwrap();
 
         #[cfg(not(target_os = "windows"))]
-        write_str(cfg_path, include_str!("../assets/dev.toml")).unwrap();
+        write_atomic(cfg_path, include_str!("../assets/dev.toml")).unwrap();
     }
 
     let mut config: Config = if cli.config.is_some() {
         load_type(cfg_path, |s| toml::from_str(s))._ebog().or_exit()
     } else {
-        load_type_or_default(cfg_path, |s| toml::from_str(s))
+        load_config_or_recover(cfg_path)
     };
     // check config
     if config.source.is_some() {
         wbog!("'source' field is not supported in the main config.");
+        warn_config("'source' field is not supported in the main config.");
     }
 
     if config.render.status.template.is_empty() {
-        config.render.status.template = r#"\m/\t"#.to_string();
+        config.render.status.template = r#"\m/\t \o"#.to_string();
     }
 
     #[cfg(not(debug_assertions))]
     log::trace!("Initial cfg: {config:?}");
 
     // apply overrides
-    for mut p in cli.r#override {
-        if p.is_relative() && p.extension().is_none() {
-            let os = std::env::consts::OS;
-
-            let main_p = presets_path().join(&p).join("main.toml");
-            let main_os = presets_path().join(&p).join(format!("{}.main.toml", os));
-            let exact = presets_path().join(p.with_extension("toml"));
-            let mut os_name = std::ffi::OsString::from(format!("{}.", os));
-            os_name.push(exact.file_name().unwrap_or_default());
-            let exact_os = exact.with_file_name(os_name);
-
-            p = if main_p.exists() {
-                main_p
-            } else if main_os.exists() {
-                main_os
-            } else if exact.exists() {
-                exact
-            } else if exact_os.exists() {
-                exact_os
-            } else {
-                exact
-            }
-        }
+    for p in cli.r#override {
+        let p = resolve_override(p);
         // no recursion because tail bad
         let o: PartialConfig = load_type(&p, |s| toml::from_str(s))?;
 
@@ -125,6 +110,7 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
             let o: PartialConfig = load_type(source, |s| toml::from_str(s))?;
             if o.source.is_some() {
                 _wbog!("Ignoring 'source' field in nested override.");
+                warn_config("Ignoring 'source' field in nested override.");
             }
             #[cfg(not(debug_assertions))]
             log::trace!("source: {o:?}");
@@ -144,6 +130,7 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
         log::trace!("Applying cli overrides: {partial:?}");
         config.apply(partial); // resolve config.exit first
     }
+    apply_flags(&mut config, &cli);
 
     if !cli.args.is_empty() {
         if !atty::is(atty::Stream::Stdin) && !cli.no_read {
@@ -156,13 +143,14 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
 
     // dispatch subcommands
     if cli.last_key {
-        let path = config
-            .exit
-            .last_key_path
-            .as_deref()
-            .unwrap_or(last_key_path());
+        let path = match config.exit.last_key_path.clone() {
+            Some(path) => Ok(path),
+            None => last_key_path(),
+        };
 
-        let content = std::fs::read_to_string(path)._elog();
+        let content = path
+            .and_then(|path| std::fs::read_to_string(path).map_err(Into::into))
+            ._elog();
         if let Some(s) = content
             && let s = s.trim()
             && !s.is_empty()
@@ -174,10 +162,6 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
         }
     }
 
-    if cli.fullscreen {
-        config.tui.layout = None;
-    }
-
     if cli.dump_config {
         let contents = toml::to_string_pretty(&config).expect("failed to serialize to TOML");
 
@@ -187,10 +171,74 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
         exit(0);
     }
 
-    // check binds
+    finish_binds(&mut config, &cli.expect)?;
+
+    #[cfg(not(debug_assertions))]
+    debug!("Config computed: {config:?}");
+
+    Ok(config)
+}
+
+/// Apply the flags which set config values.
+pub fn apply_flags(config: &mut Config, cli: &Cli) {
+    if cli.read0 {
+        config.start.input_separator = Some('\0');
+    }
+    if cli.print0 {
+        config.start.output_separator = Some("\0".into());
+    }
+    if let Some(cwd) = &cli.cwd {
+        config.start.directory = EnvValue::new(cwd.clone());
+    }
+    if cli.tac {
+        config.matcher.worker.reverse = true;
+    }
+    if cli.keep_right {
+        config.render.results.keep_right = true;
+    }
+    if cli.no_sort {
+        config.matcher.worker.sort_threshold = SortThreshold::UNSORTED;
+    }
+    if cli.disabled {
+        config.matcher.worker.disabled = true;
+    }
+    if let Some(header) = &cli.header {
+        config.render.header.content = Some(StringOrVec::String(header.clone()));
+    }
+    if cli.multi.is_some() {
+        config.selector.max = cli.multi;
+    }
+    if cli.nth.is_some() {
+        config.columns.nth = cli.nth.clone();
+    }
+    if cli.with_nth.is_some() {
+        config.columns.with_nth = cli.with_nth.clone();
+    }
+    if cli.fullscreen {
+        config.tui.layout = None;
+    }
+}
+
+/// Add the default binds under the configured ones, along with the binds implied by `--expect`
+/// and `--disabled`, then check them.
+pub fn finish_binds(config: &mut Config, expect: &[String]) -> anyhow::Result<()> {
+    let binds = std::mem::take(&mut config.binds);
     config.binds = BindMap::default_binds()
         .with_extras()
-        .modify(|x| x.extend(config.binds));
+        .modify(|x| x.extend(binds));
+    for key in expect {
+        let trigger = key.parse::<Trigger>().map_err(anyhow::Error::msg)?;
+        config
+            .binds
+            .insert(trigger, Action::AcceptWith(key.clone()).into());
+    }
+    // search externally by reloading the command
+    if config.matcher.worker.disabled && !config.start.command.command.is_empty() {
+        config
+            .binds
+            .entry(Event::QueryChange.into())
+            .or_insert_with(|| Action::Reload(config.start.command.command.clone()).into());
+    }
     config.binds.check_cycles().map_err(anyhow::Error::msg)?;
     config.binds.retain(|_, actions| !actions.is_empty()); // enables disabling a bind via override
     // there is an additional step of resolve_semantics:
@@ -202,11 +250,7 @@ pub fn enter(cli: Cli, partial: Option<PartialConfig>) -> anyhow::Result<Config>
             }
         }
     }
-
-    #[cfg(not(debug_assertions))]
-    debug!("Config computed: {config:?}");
-
-    Ok(config)
+    Ok(())
 }
 
 /// Spawns a tokio task mapping f to reader segments.
@@ -250,10 +294,97 @@ pub fn map_reader<E: SSS + std::fmt::Display>(
 pub static COMMAND_ARGS: Mutex<Vec<std::ffi::OsString>> = Mutex::new(Vec::new());
 
 /// Holds the most recent chunk-read error so the top-level handler in
-/// `main` can distinguish a chunk-error exit (400) from a plain
-/// no-match exit (404). Drained via `mem::take` on the consumer side.
+/// `main` can distinguish a chunk-error exit (2) from a plain
+/// no-match exit (1). Drained via `mem::take` on the consumer side.
 pub static CHUNK_ERROR: Mutex<String> = Mutex::new(String::new());
 
+/// Config warnings are printed before the tui starts, where they are soon hidden,
+/// so they are also replayed as toasts once the picker is up.
+pub static CONFIG_WARNINGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn warn_config(message: impl Into<String>) {
+    CONFIG_WARNINGS.lock().unwrap().push(message.into());
+}
+
+/// Resolve an override given by name to a preset, preferring `<name>/main.toml`, then an
+/// OS-specific variant, then `<name>.toml`.
+pub fn resolve_override(p: PathBuf) -> PathBuf {
+    if !(p.is_relative() && p.extension().is_none()) {
+        return p;
+    }
+    let os = std::env::consts::OS;
+
+    let main_p = presets_path().join(&p).join("main.toml");
+    let main_os = presets_path().join(&p).join(format!("{}.main.toml", os));
+    let exact = presets_path().join(p.with_extension("toml"));
+    let mut os_name = std::ffi::OsString::from(format!("{}.", os));
+    os_name.push(exact.file_name().unwrap_or_default());
+    let exact_os = exact.with_file_name(os_name);
+
+    if main_p.exists() {
+        main_p
+    } else if main_os.exists() {
+        main_os
+    } else if exact.exists() {
+        exact
+    } else if exact_os.exists() {
+        exact_os
+    } else {
+        exact
+    }
+}
+
+/// Load the main config, continuing with the defaults if it can't be read.
+fn load_config_or_recover(path: &Path) -> Config {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Config::default(),
+        Err(e) => {
+            recover_file(path, e);
+            return Config::default();
+        }
+    };
+    toml::from_str(&content).unwrap_or_else(|e| {
+        recover_file(path, e);
+        Config::default()
+    })
+}
+
+/// Warn that `path` could not be read, keeping a copy in case it is later overwritten.
+fn recover_file(path: &Path, error: impl std::fmt::Display) {
+    let message = match backup_file(path) {
+        Ok(backup) => format!("Failed to read {path:?}, backed up to {backup:?}: {error}"),
+        Err(_) => format!("Failed to read {path:?}: {error}"),
+    };
+    wbog!("{message}");
+    warn_config(message);
+}
+
+/// Read a state file, backing it up if it can't be read. A file from a newer version of `mm` is
+/// left as it is, as [`persist::save`] won't write over it.
+fn read_state<T>(path: &Path, parse: impl FnOnce(&str) -> Result<T, StateError>) -> Option<T> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) => {
+            recover_file(path, e);
+            return None;
+        }
+    };
+    match parse(&content) {
+        Ok(state) => Some(state),
+        Err(e @ StateError::Newer { .. }) => {
+            let message = format!("Not using {path:?}: {e}");
+            wbog!("{message}");
+            warn_config(message);
+            None
+        }
+        Err(e) => {
+            recover_file(path, e);
+            None
+        }
+    }
+}
+
 pub fn process_envs(mut envs: HashMap<String, EnvValue>) -> HashMap<String, String> {
     let mut processed_envs = HashMap::new();
 
@@ -304,6 +435,7 @@ pub fn process_envs(mut envs: HashMap<String, EnvValue>) -> HashMap<String, Stri
                 processed_envs.insert(k.clone(), output.trim().to_string());
             } else {
                 _wbog!("Failed to execute env command for {}: {}", k, v.value);
+                warn_config(format!("Failed to execute env command for {k}"));
             }
         }
     }
@@ -311,11 +443,17 @@ pub fn process_envs(mut envs: HashMap<String, EnvValue>) -> HashMap<String, Stri
     processed_envs
 }
 
-const START_ERROR: Result<(), MatchError> = Err(MatchError::Abort(11));
+const START_ERROR: Result<(), MatchError> = Err(MatchError::Abort(MatchError::EXIT_ERROR));
 
-pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
+pub async fn start(
+    config: Config,
+    no_read: bool,
+    #[allow(unused)] remote: Remote,
+    preselect: Vec<Preselect>,
+    session: Option<PathBuf>,
+) -> Result<(), MatchError> {
     let Config {
-        render,
+        mut render,
         tui,
         previewer,
         matcher: MatcherConfig { matcher, worker },
@@ -335,8 +473,16 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
                 save_orphans,
                 skip_invalid_lines,
                 on_accept,
+                relative_output,
             },
         mut exit,
+        history:
+            HistoryConfig {
+                file: history_file,
+                size: history_size,
+                frecency,
+            },
+        selector,
         mut envs,
         source: _,
     } = config;
@@ -363,6 +509,12 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
     } else {
         command
     };
+    // the external search starts with the initial query
+    let command = if worker.disabled {
+        crate::formatter::format_query(&command, &render.query.initial)
+    } else {
+        command
+    };
 
     let initial_cmd = (!command.is_empty() && atty::is(atty::Stream::Stdin) || no_read)
         .then_some(command.clone())
@@ -375,8 +527,58 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
             EnvValue::new(initial_index.to_string()),
         );
     }
+    #[cfg(feature = "listen")]
+    let listener = if let Some(port) = remote.listen {
+        match crate::remote::http::bind(port).await {
+            Ok(listener) => {
+                if let Ok(addr) = listener.local_addr() {
+                    envs.insert(
+                        "MM_PORT".to_string(),
+                        EnvValue::new(addr.port().to_string()),
+                    );
+                }
+                Some(listener)
+            }
+            Err(e) => {
+                ebog!("Failed to listen on port {port}: {e}");
+                return START_ERROR;
+            }
+        }
+    } else {
+        None
+    };
+    #[cfg(unix)]
+    let (socket, _socket_guard) = match &remote.socket {
+        Some(path) => match crate::remote::socket::bind(path).await {
+            Ok((listener, guard)) => {
+                envs.insert(
+                    "MM_SOCKET".to_string(),
+                    EnvValue::new(path.to_string_lossy().into_owned()),
+                );
+                (Some(listener), Some(guard))
+            }
+            Err(e) => {
+                ebog!("Failed to listen on {}: {e}", path.display());
+                return START_ERROR;
+            }
+        },
+        None => (None, None),
+    };
+    #[cfg(unix)]
+    let status_file = match remote.status_fd {
+        Some(fd) => match crate::remote::open_status_fd(fd) {
+            Ok(file) => Some(file),
+            Err(e) => {
+                ebog!("Failed to open file descriptor {fd}: {e}");
+                return START_ERROR;
+            }
+        },
+        None => None,
+    };
+
     let envs = process_envs(envs);
 
+    let invoking_cwd = std::env::current_dir().unwrap_or_default();
     if !directory.value.is_empty() {
         let EnvValue { value, force, exec } = directory;
 
@@ -413,6 +615,16 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
         }
     }
 
+    if render.preview.auto {
+        if let Err(e) = crate::infer::set_rules(std::mem::take(&mut render.preview.rules)) {
+            ebog!("Invalid preview rule: {e}");
+            return START_ERROR;
+        }
+        if render.preview.layout.is_empty() {
+            render.preview.layout.push(Default::default());
+        }
+    }
+
     // ---------------------------------
 
     let abort_empty = exit.abort_empty;
@@ -422,8 +634,37 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
     let sort_descending = worker.reverse;
 
     if exit.last_key_path.is_none() {
-        exit.last_key_path = Some(last_key_path().into())
+        exit.last_key_path = last_key_path()._elog()
+    }
+
+    let history_file = history_file
+        .or_else(|| history_path()._elog())
+        .filter(|p| !p.as_os_str().is_empty());
+    let mut history = History::new(history_size);
+    if let Some(path) = &history_file
+        && path.exists()
+        && let Some(state) = read_state(path, HistoryState::parse)
+    {
+        history.extend(state.entries);
+    }
+
+    // stored next to the history
+    let frecency_file = history_file
+        .as_ref()
+        .filter(|_| frecency)
+        .map(|path| path.with_extension("frecency"));
+    let mut frecency_state = FrecencyState::default();
+    if let Some(path) = &frecency_file
+        && path.exists()
+        && let Some(state) = read_state(path, |s| persist::decode(FrecencyState::KIND, s))
+    {
+        frecency_state = state;
     }
+    let saved_session = session
+        .as_ref()
+        .filter(|path| path.exists())
+        .and_then(|path| read_state(path, |s| persist::decode(SESSION_KIND, s)));
+    crate::profile::mark("history");
 
     // set event loop mode
     let mode = if let Some(m) = mode {
@@ -442,9 +683,15 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
     };
     matchmaker::event::set_mode(&mode);
 
+    // the accepting key is reported on the first line of output
+    let expect = binds
+        .values()
+        .any(|actions| actions.iter().any(|a| matches!(a, Action::AcceptWith(_))));
+
     let event_loop = EventLoop::with_binds(binds)
         .with_tick_rate(render.ui.tick_rate)
         .with_mouse_events(render.ui.mouse_events);
+    crate::profile::mark("binds");
 
     // make matcher and matchmaker with matchmaker-and-matcher-maker
     let copy_trailing_newline = tui.copy_trailing_newline;
@@ -453,15 +700,25 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
         injector,
         OddEnds {
             hidden_columns,
+            column_styles,
             has_error,
             ranges_fn,
         },
     ) = Matchmaker::new_from_config(render, tui, worker, columns, exit, preprocess);
+    mm.config_selector(selector);
+    crate::profile::mark("worker");
 
     if has_error {
         return START_ERROR;
     }
 
+    if frecency_file.is_some() {
+        let scores = frecency_state.scores(FrecencyState::now());
+        mm.worker.set_score_fn(Some(Arc::new(move |item: &String, score| {
+            score + scores.get(item).map_or(0, |&s| FrecencyState::bonus(s))
+        })));
+    }
+
     // make previewer
     if !event_loop.original_binds().check_traces() {
         // maybe abort with error
@@ -471,10 +728,14 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
             as for<'a, 'b, 'c> fn(&'a MMState<'b, 'c>, &'a str, Option<&dyn Fn(String)>) -> String,
     );
     let binds_ptr = event_loop.get_binds_ptr();
+    let preview_formatter = Either::Right(
+        crate::infer::format_preview
+            as for<'a, 'b, 'c> fn(&'a MMState<'b, 'c>, &'a str, Option<&dyn Fn(String)>) -> String,
+    );
     let mut previewer = make_previewer(
         &mut mm,
         previewer,
-        cli_formatter.clone(),
+        preview_formatter,
         Box::new(move |config| matchmaker::binds::display_help(&binds_ptr.load(), config)),
     );
     previewer.connect_controller(event_loop.controller());
@@ -489,9 +750,25 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
         .matcher(matcher.0)
         .previewer(previewer)
         .hidden_columns(hidden_columns)
+        .column_styles(column_styles)
+        .preselect(preselect)
         .initializer(move |s| {
+            crate::profile::mark("tui");
             s.envs.extend(envs_);
+            s.picker_ui.query.history = history;
+            for warning in std::mem::take(&mut *CONFIG_WARNINGS.lock().unwrap()) {
+                s.notify(ToastLevel::Warn, warning);
+            }
+        });
+
+    if let Some(saved) = saved_session {
+        options = options.restore(saved);
+    }
+    if let Some(path) = session {
+        options = options.on_close(move |saved| {
+            persist::save(&path, SESSION_KIND, &saved)._elog();
         });
+    }
 
     let render_tx = options.render_tx();
     let push_fn = inject_line(
@@ -551,7 +828,10 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
         copy_trailing_newline,
         Some(render_tx.clone()),
     );
-    mm._register_become_handler(cli_formatter.clone());
+    mm._register_become_handler(Either::Right(
+        crate::formatter::format_become
+            as for<'a, 'b, 'c> fn(&'a MMState<'b, 'c>, &'a str, Option<&dyn Fn(String)>) -> String,
+    ));
 
     // reload handler
     let reload_formatter = cli_formatter.clone();
@@ -605,6 +885,57 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
         }
     });
 
+    // cwd handler
+    let cwd_formatter = cli_formatter.clone();
+    let initial_cwd = std::env::current_dir().unwrap_or_default();
+    mm.register_interrupt_handler(Interrupt::SetCwd, move |state| {
+        let path = if state.payload().is_empty() {
+            initial_cwd.clone()
+        } else {
+            let path = use_formatter(&cwd_formatter, state, state.payload(), None);
+            expand_tilde(path.into())
+        };
+
+        debug!("Switching to {path:?}");
+        if let Err(e) = set_current_dir(&path) {
+            state.notify(
+                ToastLevel::Error,
+                format!("Failed to switch to {path:?}: {e}"),
+            );
+        }
+    });
+
+    // remote control
+    let reports = Reports::default();
+    let status_feed = StatusFeed::default();
+    #[cfg(unix)]
+    if let Some(file) = status_file {
+        tokio::spawn(crate::remote::write_status(file, status_feed.subscribe().1));
+    }
+    #[cfg(feature = "listen")]
+    if let Some(listener) = listener {
+        tokio::spawn(crate::remote::http::serve(
+            listener,
+            render_tx.clone(),
+            reports.clone(),
+        ));
+    }
+    #[cfg(unix)]
+    if let Some(listener) = socket {
+        tokio::spawn(crate::remote::socket::serve(
+            listener,
+            render_tx.clone(),
+            reports.clone(),
+            status_feed.clone(),
+        ));
+    }
+    mm.register_event_handler(
+        Event::StatusChange | Event::SelectionChange | Event::Synced | Event::Resynced,
+        move |state, _| status_feed.publish(state),
+    );
+    // the first iteration of the render loop draws the first frame
+    mm.register_event_handler(Event::Start, |_, _| crate::profile::mark("first frame"));
+
     // debug!("{mm:?}");
 
     let mut action_context = ActionContext {
@@ -614,6 +945,7 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
         ranges_fn,
         sort: None,
         sort_descending,
+        reports,
     };
 
     let _output_separator = output_separator.clone();
@@ -621,9 +953,23 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
 
     options = options.ext_handler(move |x, y| action_handler(x, y, &mut action_context));
 
-    // TODO: accept logic is in render/mod.rs is todo!() - this closure is
-    // currently unreachable until the accept pipeline is restored.
     mm.output = Box::new(move |state: &mut MMState<'_, '_>| {
+        if let Some(path) = &history_file {
+            let query = state.picker_ui.query.input.clone();
+            let history = &mut state.picker_ui.query.history;
+            history.push(query);
+            let state = HistoryState {
+                entries: history.entries().cloned().collect(),
+            };
+            persist::save(path, HistoryState::KIND, &state)._elog();
+        }
+
+        if let Some(path) = &frecency_file {
+            let items = state.map_selected_to_vec(|_, x| x.clone());
+            frecency_state.record(items, FrecencyState::now(), history_size);
+            persist::save(path, FrecencyState::KIND, &frecency_state)._elog();
+        }
+
         if !on_accept.is_empty() {
             let cmd = format_cli(state, &on_accept, None);
             if cmd.is_empty() {
@@ -643,10 +989,22 @@ pub async fn start(config: Config, no_read: bool) -> Result<(), MatchError> {
             }
         };
 
+        if expect {
+            repeat(state.accept_key().unwrap_or_default().to_string());
+        }
+
         if let Some(template) = &output_template {
             format_cli(state, template, Some(&repeat));
         } else {
-            state.map_selected_to_vec(|_, x| repeat(x.as_str().to_string()));
+            let cwd = relative_output.then(|| std::env::current_dir().unwrap_or_default());
+            state.map_selected_to_vec(|_, x| match &cwd {
+                Some(cwd) => repeat(
+                    relative_to(&cwd.join(x), &invoking_cwd)
+                        .to_string_lossy()
+                        .into_owned(),
+                ),
+                None => repeat(x.as_str().to_string()),
+            });
         };
 
         vec![]
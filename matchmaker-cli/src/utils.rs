@@ -256,6 +256,64 @@ pub fn expand_tilde(path: PathBuf) -> PathBuf {
     path
 }
 
+/// Write to a temporary file beside `path` and rename it into place, so that `path` is never
+/// left partially written.
+pub fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(format!(".{}.tmp", std::process::id()));
+    let tmp = PathBuf::from(tmp);
+
+    std::fs::write(&tmp, content)?;
+    std::fs::rename(&tmp, path).inspect_err(|_| {
+        let _ = std::fs::remove_file(&tmp);
+    })
+}
+
+/// Copy `path` to `<path>.bak`, returning the backup's path.
+pub fn backup_file(path: &Path) -> std::io::Result<PathBuf> {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak");
+    let backup = PathBuf::from(backup);
+    std::fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// Express the absolute `path` relative to the absolute `base`, resolving `.` and `..` lexically.
+pub fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let normalize = |p: &Path| {
+        p.components().fold(Vec::new(), |mut acc, c| {
+            match c {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    if matches!(acc.last(), Some(Component::Normal(_))) {
+                        acc.pop();
+                    }
+                }
+                c => acc.push(c),
+            }
+            acc
+        })
+    };
+    let (path, base) = (normalize(path), normalize(base));
+
+    let common = path.iter().zip(&base).take_while(|(a, b)| a == b).count();
+    let mut ret: PathBuf = base[common..]
+        .iter()
+        .map(|_| Component::ParentDir)
+        .collect();
+    ret.extend(&path[common..]);
+
+    if ret.as_os_str().is_empty() {
+        ret.push(".");
+    }
+    ret
+}
+
 #[allow(unused)]
 pub fn guess_clip_cmd() -> Option<(String, String)> {
     #[cfg(target_os = "macos")]
@@ -358,4 +416,13 @@ mod tests {
             "url was {url}"
         );
     }
+
+    #[test]
+    fn relative_to_resolves_parents() {
+        let rel = |p: &str, b: &str| relative_to(Path::new(p), Path::new(b));
+        assert_eq!(rel("/a/b/c", "/a"), Path::new("b/c"));
+        assert_eq!(rel("/a/b", "/a/c/d"), Path::new("../../b"));
+        assert_eq!(rel("/a/c/../b/./x", "/a/b"), Path::new("x"));
+        assert_eq!(rel("/a", "/a"), Path::new("."));
+    }
 }
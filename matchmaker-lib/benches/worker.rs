@@ -0,0 +1,165 @@
+//! Throughput of the worker: injecting items, splitting them into columns, and matching them.
+//!
+//! Items are injected directly, through the preprocessors of the config (which parse ANSI
+//! escapes), and through a [`SegmentedInjector`].
+//!
+//! Run with `cargo bench -p matchmaker-lib`.
+
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use matchmaker::{
+    config::{ColumnsConfig, PreprocessConfig, Split},
+    config_mm::build_columns,
+    nucleo::{
+        Segmented, Worker,
+        injector::{Injector, SegmentedInjector, SplitterFn},
+    },
+    testing::fixtures,
+};
+use regex::Regex;
+use std::sync::Arc;
+
+const SIZES: [usize; 2] = [100_000, 1_000_000];
+
+fn wait<T: matchmaker::SSS, D>(worker: &mut Worker<T, D>) {
+    while worker.refresh().running {}
+}
+
+fn inject(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inject");
+    group.sample_size(10);
+    for n in SIZES {
+        let lines = fixtures::paths(n);
+        group.throughput(Throughput::Elements(n as u64));
+        group.bench_with_input(BenchmarkId::new("single_column", n), &lines, |b, lines| {
+            b.iter_batched(
+                || lines.clone(),
+                |lines| {
+                    let worker = Worker::new_single_column();
+                    worker.append(lines);
+                    worker
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        let lines = fixtures::ansi_lines(n);
+        group.bench_with_input(BenchmarkId::new("ansi", n), &lines, |b, lines| {
+            b.iter_batched(
+                || lines.clone(),
+                |lines| {
+                    let (columns, raw_preprocessor, text_preprocessor, _) =
+                        build_columns(&columns_config(), ansi());
+                    let worker = Worker::new(columns, 0, raw_preprocessor, text_preprocessor);
+                    worker.injector().extend(lines.into_iter()).unwrap();
+                    worker
+                },
+                BatchSize::LargeInput,
+            )
+        });
+
+        let lines = fixtures::columns(n);
+        group.bench_with_input(BenchmarkId::new("segmented", n), &lines, |b, lines| {
+            b.iter_batched(
+                || lines.clone(),
+                |lines| {
+                    let worker: Worker<Segmented<String>> =
+                        Worker::new_indexable(["mode", "size", "date", "path"], None);
+                    let splitter: SplitterFn<String> =
+                        Arc::new(|line: &String| whitespace_ranges(line));
+                    SegmentedInjector::new(worker.injector(), splitter)
+                        .extend(lines.into_iter())
+                        .unwrap();
+                    worker
+                },
+                BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+/// The byte ranges of the words of `line`, for a [`SegmentedInjector`].
+fn whitespace_ranges(line: &str) -> Box<[(u32, u32)]> {
+    line.split_whitespace()
+        .map(|word| {
+            let start = word.as_ptr() as usize - line.as_ptr() as usize;
+            (start as u32, (start + word.len()) as u32)
+        })
+        .collect()
+}
+
+fn ansi() -> PreprocessConfig {
+    PreprocessConfig {
+        ansi: true,
+        ..Default::default()
+    }
+}
+
+fn columns_config() -> ColumnsConfig {
+    ColumnsConfig {
+        split: Split::Delimiter(Regex::new(r"\s+").unwrap()),
+        ..Default::default()
+    }
+}
+
+fn split(c: &mut Criterion) {
+    let mut group = c.benchmark_group("split");
+    let n = 100_000;
+    group.throughput(Throughput::Elements(n as u64));
+
+    for (name, lines, preprocess) in [
+        (
+            "delimiter",
+            fixtures::columns(n),
+            PreprocessConfig::default(),
+        ),
+        ("delimiter_ansi", fixtures::ansi_lines(n), ansi()),
+    ] {
+        let (_, raw_preprocessor, text_preprocessor, _) =
+            build_columns(&columns_config(), preprocess);
+        group.bench_function(BenchmarkId::new("raw", name), |b| {
+            b.iter(|| {
+                lines
+                    .iter()
+                    .filter(|l| raw_preprocessor(*l).is_some())
+                    .count()
+            })
+        });
+        group.bench_function(BenchmarkId::new("text", name), |b| {
+            b.iter(|| {
+                lines
+                    .iter()
+                    .map(|l| text_preprocessor(l).1.len())
+                    .sum::<usize>()
+            })
+        });
+    }
+    group.finish();
+}
+
+fn results(c: &mut Criterion) {
+    let mut group = c.benchmark_group("results");
+    group.sample_size(10);
+    for n in SIZES {
+        let mut worker = Worker::new_single_column();
+        worker.append(fixtures::paths(n));
+        wait(&mut worker);
+
+        for query in ["wrk", "src/ui .rs$", "!target 'mod"] {
+            group.bench_with_input(BenchmarkId::new(query, n), &query, |b, query| {
+                b.iter(|| {
+                    // alternate with the empty query so that every iteration matches afresh
+                    worker.find("");
+                    wait(&mut worker);
+                    worker.find(query);
+                    wait(&mut worker);
+                    worker.matched_results().take(50).count()
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, inject, split, results);
+criterion_main!(benches);
@@ -19,7 +19,7 @@ async fn main() -> Result<()> {
             }
         }
         Err(err) => match err {
-            MatchError::Abort(1) => {
+            MatchError::Abort(MatchError::EXIT_CANCEL) => {
                 eprintln!("cancelled");
             }
             _ => {
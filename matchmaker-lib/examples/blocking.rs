@@ -0,0 +1,12 @@
+use matchmaker::{MatchResultExt, Matchmaker, Result};
+
+// No async runtime is needed to pick.
+fn main() -> Result<()> {
+    let picked = Matchmaker::builder()
+        .items(["item1", "item2", "item3"])
+        .pick_blocking()
+        .first()?;
+
+    println!("{picked}");
+    Ok(())
+}
@@ -0,0 +1,17 @@
+use matchmaker::{Matchmaker, Result};
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let picked = Matchmaker::builder()
+        .items(["apple\tred", "banana\tyellow", "cherry\tred"])
+        .columns("\t")
+        .multi(true)
+        .preview_cmd("echo {2}")
+        .pick()
+        .await?;
+
+    for item in picked {
+        println!("{item}");
+    }
+    Ok(())
+}
@@ -0,0 +1,35 @@
+use matchmaker::binds::BindMap;
+use matchmaker::flow::Flow;
+use matchmaker::nucleo::Worker;
+use matchmaker::{MatchResultExt, Matchmaker, PickOptions, Result, SharedTerminal};
+
+/// Pick one of `items`. Pressing `esc` returns to the previous picker.
+async fn pick(items: Vec<String>, terminal: SharedTerminal) -> Result<String> {
+    let worker = Worker::new_single_column();
+    worker.append(items);
+    Matchmaker::new_on_cloneable(worker)
+        .pick(PickOptions::with_binds(BindMap::default_binds()).terminal(terminal))
+        .await
+        .first()
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let (fruit, action) = Flow::new()
+        .step(|_: (), terminal| {
+            pick(
+                vec!["apple".into(), "banana".into(), "cherry".into()],
+                terminal,
+            )
+        })
+        .step(|fruit: String, terminal| async move {
+            let actions = ["eat", "bake", "juice"].map(|a| format!("{a} the {fruit}"));
+            let action = pick(actions.to_vec(), terminal).await?;
+            Ok((fruit, action))
+        })
+        .run(())
+        .await?;
+
+    println!("{fruit}: {action}");
+    Ok(())
+}
@@ -0,0 +1,20 @@
+use std::collections::BTreeMap;
+
+use matchmaker::action::NullActionExt;
+use matchmaker::{MatchResultExt, Matchmaker, PickOptions, Result};
+
+/// Pick an environment variable by name or value, previewing its value.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let vars: BTreeMap<String, String> = std::env::vars().collect();
+
+    let mut mm = Matchmaker::from_map(vars);
+    let previewer = mm.value_previewer();
+    let name = mm
+        .pick::<NullActionExt>(PickOptions::new().previewer(previewer))
+        .await
+        .first()?;
+
+    println!("{name}");
+    Ok(())
+}
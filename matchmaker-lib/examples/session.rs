@@ -0,0 +1,20 @@
+use matchmaker::binds::BindMap;
+use matchmaker::nucleo::Worker;
+use matchmaker::{Matchmaker, PickOptions, Result};
+
+// The picker is shown twice, the second time as it was left.
+#[tokio::main]
+async fn main() -> Result<()> {
+    let worker = Worker::new_single_column();
+    worker.append(["item1", "item2", "item3"].map(String::from));
+    let mut session = Matchmaker::new_on_cloneable(worker)
+        .into_session(|state| state.map_selected_to_vec(|_, item: &String| item.clone()));
+
+    for _ in 0..2 {
+        let picked = session
+            .resume(PickOptions::with_binds(BindMap::default_binds()))
+            .await?;
+        println!("{picked:?}");
+    }
+    Ok(())
+}
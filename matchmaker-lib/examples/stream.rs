@@ -0,0 +1,23 @@
+use futures::StreamExt;
+use matchmaker::binds::{BindMap, bindmap, key};
+use matchmaker::{Action, Matchmaker, PickOptions};
+
+// Items picked with enter are printed while the picker stays open, until esc is pressed.
+#[tokio::main]
+async fn main() {
+    let binds: BindMap = bindmap!(
+        key!(enter) => Action::AcceptNonExit,
+    );
+    let (mm, options) = Matchmaker::builder()
+        .items(["item1", "item2", "item3"])
+        .multi(true)
+        .binds(binds)
+        .build();
+
+    let mut picked = mm.pick_stream(options, |state| {
+        state.map_selected_to_vec(|_, item| item.clone())
+    });
+    while let Some(item) = picked.next().await {
+        eprintln!("picked {item}");
+    }
+}
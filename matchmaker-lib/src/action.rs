@@ -23,15 +23,34 @@ pub enum Action<A: ActionExt = NullActionExt> {
     CycleSelections,
     /// Clear all selections
     ClearSelections,
+    /// Switch between single and multi-select, clearing the selections
+    ToggleMulti,
     /// Accept current selection
     Accept,
-    /// Quit with code
+    /// Accept current selection, recording the given key name.
+    /// If empty, the key which triggered the action is used.
+    AcceptWith(String),
+    /// Yield the current selection to [`crate::Matchmaker::pick_stream`] and clear it, without
+    /// exiting.
+    AcceptNonExit,
+    /// Quit with code (default: [`crate::MatchError::EXIT_CANCEL`])
     Quit(i32),
 
     // Results
     /// Toggle wrap
     ToggleWrap,
     ToggleHeaderWrap,
+    /// Collapse the group of the current result, or expand it if collapsed.
+    /// See [`crate::nucleo::Worker::set_group_by`].
+    ToggleGroup,
+    /// Collapse every group
+    CollapseGroups,
+    /// Expand every group
+    ExpandGroups,
+    /// Show the children of the current node. See [`crate::nucleo::Worker::set_tree`].
+    ExpandNode,
+    /// Hide the children of the current node, or move to its parent if there are none.
+    CollapseNode,
 
     // Results Navigation
     /// Move selection index up
@@ -95,6 +114,9 @@ pub enum Action<A: ActionExt = NullActionExt> {
     PreviewScroll(i8),
     /// Jump between start, end, initial locations.
     PreviewJump,
+    /// Forward keys to the preview command until [`crate::config::PreviewerConfig::unfocus_key`]
+    /// is pressed. Requires [`crate::config::PreviewerConfig::pty`].
+    FocusPreview,
 
     /// Cycle columns
     NextColumn,
@@ -116,6 +138,9 @@ pub enum Action<A: ActionExt = NullActionExt> {
     /// The index is 0-based into the non-hidden columns.
     /// None acts on the active column.
     ShrinkColumn(Option<usize>),
+    /// Undo the column resizing from [`Action::ExpandColumn`], [`Action::ShrinkColumn`] and
+    /// dragging, restoring the configured widths.
+    ResetColumnWidths,
 
     // Programmable
     /// Execute command and continue
@@ -130,18 +155,27 @@ pub enum Action<A: ActionExt = NullActionExt> {
     Copy(String),
     /// Execute command asynchronously and copy its output to the clipboard
     CopyAsync(String),
+    /// Copy the n-th column of the selected items (or the current item), one per line, to the clipboard via OSC 52
+    CopyColumn(usize),
     /// Exit and become
     Become(String),
     /// Become without exiting the TUI
     BecomeSilent(String),
     /// Reload matcher/worker
     Reload(String),
+    /// Switch to the next source (see [`crate::Matchmaker::add_source`]), wrapping around
+    NextSource,
+    /// Switch to the n-th source, where 0 is the worker the picker was created with
+    Source(usize),
     /// Print via handler
     Print(String),
     /// Print key via handler
     PrintKey,
     /// Store a value in the state
     Store(String),
+    /// Change the working directory of commands and previews.
+    /// Relative paths are resolved against the current directory, and an empty path returns to the initial one.
+    SetCwd(String),
 
     // Edit (Input)
     /// Move cursor forward char
@@ -168,18 +202,31 @@ pub enum Action<A: ActionExt = NullActionExt> {
     InsertQuery(String),
     /// Set query cursor pos
     QueryPos(i32),
+    /// Replace the query with an older entry from the query history
+    HistoryPrev,
+    /// Replace the query with a newer entry from the query history
+    HistoryNext,
+    /// Switch between smart case and case-sensitive matching
+    ToggleCase,
+    /// Switch between fuzzy and exact matching of the query terms
+    ToggleExact,
 
     // Other/Experimental/Debugging
     /// Insert char into input
     Char(char),
     /// Force redraw
     Redraw,
+    /// Write the recent render states to a file (requires the `time-travel` feature)
+    DumpTrace,
     /// toggle first from [`crate::config::ExitConfig`]
     ToggleExitFirst(Option<bool>), // for everything else we have TransformConfig
     /// Custom action
     Custom(A),
     /// Activate the nth overlay
     Overlay(usize),
+    /// Move the focus to the next picker added with [`crate::PickOptions::pane`], or the next
+    /// pane of a [`crate::render::PickerPanes`]
+    FocusNext,
     /// Alias for a semantic trigger
     Semantic(String),
     /// A description of a binding, only used for help display.
@@ -281,6 +328,72 @@ impl<A: ActionExt> Default for Actions<A> {
     }
 }
 
+impl<A: ActionExt> Actions<A> {
+    /// Attach a description, which the help display shows in place of the actions.
+    pub fn with_desc(self, desc: impl Into<String>) -> Self {
+        let desc = desc.into();
+        if desc.is_empty() {
+            return self;
+        }
+        let mut ret = Vec::with_capacity(self.0.len() + 2);
+        ret.push(Action::Trace(desc));
+        ret.extend(self.0);
+        ret.push(Action::Trace(String::new()));
+        Actions(ret)
+    }
+
+    /// The description attached by [`Actions::with_desc`].
+    pub fn desc(&self) -> Option<&str> {
+        match self.0.as_slice() {
+            [Action::Trace(desc), inner @ .., Action::Trace(end)]
+                if !desc.is_empty()
+                    && !desc.starts_with("@@")
+                    && end.is_empty()
+                    && !inner.iter().any(|a| matches!(a, Action::Trace(_))) =>
+            {
+                Some(desc.as_str())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl<A: ActionExt + FromStr<Err = String>> Actions<A> {
+    /// Parse an action, or a chain of actions joined by `+`, such as `select+down`.
+    ///
+    /// A `+` within parentheses does not separate actions. If the parts are not all valid, the
+    /// string is parsed as a single action, such as `@a+b`.
+    pub fn parse_chain(s: &str) -> Result<Vec<Action<A>>, String> {
+        let mut parts = Vec::new();
+        let mut depth = 0usize;
+        let mut start = 0;
+        for (i, c) in s.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.saturating_sub(1),
+                '+' if depth == 0 => {
+                    parts.push(&s[start..i]);
+                    start = i + 1;
+                }
+                _ => {}
+            }
+        }
+        parts.push(&s[start..]);
+
+        match parts
+            .iter()
+            .map(|p| Action::from_str(p))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(actions) => Ok(actions),
+            Err(e) => match Action::from_str(s) {
+                Ok(action) if parts.len() > 1 => Ok(vec![action]),
+                _ => Err(e),
+            },
+        }
+    }
+}
+
 impl<A: ActionExt> From<Vec<Action<A>>> for Actions<A> {
     fn from(v: Vec<Action<A>>) -> Self {
         Actions(v)
@@ -339,7 +452,10 @@ impl<'de, A: ActionExt + FromStr<Err = String>> Deserialize<'de> for Actions<A>
     where
         D: serde::Deserializer<'de>,
     {
-        let helper = StringOrVec::deserialize(deserializer)?;
+        let (helper, desc) = match ActionsRepr::deserialize(deserializer)? {
+            ActionsRepr::Plain(helper) => (helper, String::new()),
+            ActionsRepr::Described(DescribedActions { action, desc }) => (action, desc),
+        };
         let strings = match helper {
             StringOrVec::String(s) => vec![s],
             StringOrVec::Vec(v) => v,
@@ -347,19 +463,50 @@ impl<'de, A: ActionExt + FromStr<Err = String>> Deserialize<'de> for Actions<A>
 
         let mut actions = Vec::new();
         for s in strings {
-            let action = Action::from_str(&s).map_err(serde::de::Error::custom)?;
-            actions.push(action);
+            actions.extend(Actions::parse_chain(&s).map_err(serde::de::Error::custom)?);
         }
 
-        Ok(Actions(actions))
+        Ok(Actions(actions).with_desc(desc))
     }
 }
 
+/// `{ action = ..., desc = "..." }`
+#[derive(Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+struct DescribedActions {
+    action: StringOrVec,
+    #[serde(default)]
+    desc: String,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ActionsRepr {
+    Plain(StringOrVec),
+    Described(DescribedActions),
+}
+
 impl<A: ActionExt + Display> Serialize for Actions<A> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
+        if let Some(desc) = self.desc() {
+            let strings: Vec<String> = self.0[1..self.0.len() - 1]
+                .iter()
+                .map(|a| a.to_string())
+                .collect();
+            let action = match <[String; 1]>::try_from(strings) {
+                Ok([s]) => StringOrVec::String(s),
+                Err(v) => StringOrVec::Vec(v),
+            };
+            return DescribedActions {
+                action,
+                desc: desc.to_string(),
+            }
+            .serialize(serializer);
+        }
+
         match self.0.len() {
             1 => serializer.serialize_str(&self.0[0].to_string()),
             _ => {
@@ -373,23 +520,25 @@ impl<A: ActionExt + Display> Serialize for Actions<A> {
 // ----- action serde
 enum_from_str_display!(
     units:
-    Select, Deselect, ToggleSelection = Toggle, CycleSelections = Cycle, ClearSelections = Clear, Accept,
+    Select, Deselect, ToggleSelection = Toggle, CycleSelections = Cycle, ClearSelections = Clear, ToggleMulti, Accept, AcceptNonExit,
 
     HalfPageDown, HalfPageUp,
 
-    ToggleWrap, TogglePreviewWrap, ToggleHeaderWrap, NextPreview, PrevPreview, PreviewJump,
+    ToggleWrap, TogglePreviewWrap, ToggleHeaderWrap, ToggleGroup, CollapseGroups, ExpandGroups, ExpandNode, CollapseNode, ToggleCase, ToggleExact, NextPreview, PrevPreview, PreviewJump, FocusPreview,
 
     PreviewHalfPageUp, PreviewHalfPageDown,
 
-    ForwardChar,BackwardChar, ForwardWord, BackwardWord, DeleteChar, DeleteWord, DeleteLineStart, DeleteLineEnd, ClearQuery = Cancel, Redraw, NextColumn, PrevColumn, HideColumn, UnhideColumn, PrintKey;
+    ForwardChar,BackwardChar, ForwardWord, BackwardWord, DeleteChar, DeleteWord, DeleteLineStart, DeleteLineEnd, ClearQuery = Cancel, Redraw, DumpTrace, NextColumn, PrevColumn, HideColumn, UnhideColumn, ResetColumnWidths, PrintKey, FocusNext,
+
+    HistoryPrev, HistoryNext, NextSource;
 
     tuples:
     Execute, ExecuteAsync, ExecuteThen, ExecuteSilent, Become, BecomeSilent, Preview,
     SetQuery, Pos, QueryPos, SwitchColumn, Store, InsertQuery,
-    CopyAsync, Copy;
+    CopyAsync, Copy, CopyColumn, Source;
 
     defaults:
-    (Up, 1), (Down, 1), (PreviewUp, 1), (PreviewDown, 1), (Quit, 1), (Overlay, 0), (Print, String::new()), (Help, String::new()), (Reload, String::new()), (PreviewScroll, 1), (PreviewHScroll, 1), (HScroll, 0), (VScroll, 0), (ExpandPreview, 1), (ShrinkPreview, 1);
+    (Up, 1), (Down, 1), (PreviewUp, 1), (PreviewDown, 1), (Quit, crate::MatchError::EXIT_CANCEL), (Overlay, 0), (Print, String::new()), (AcceptWith, String::new()), (Help, String::new()), (Reload, String::new()), (SetCwd, String::new()), (PreviewScroll, 1), (PreviewHScroll, 1), (HScroll, 0), (VScroll, 0), (ExpandPreview, 1), (ShrinkPreview, 1);
 
     options:
     SwitchPreview, SetPreview, ToggleColumn, ToggleExitFirst, ExpandColumn, ShrinkColumn
@@ -402,6 +551,18 @@ macro_rules! enum_from_str_display {
         defaults: $( $(#[$dattr:meta])* ($default:ident $(= $dalias:ident)?, $default_value:expr) ),*;
         options: $( $(#[$oattr:meta])* $optional:ident $(= $oalias:ident)? ),*
     ) => {
+        impl<A: ActionExt> Action<A> {
+            /// The names of the built-in actions, excluding aliases.
+            pub fn names() -> Vec<&'static str> {
+                let mut names = Vec::new();
+                $( $(#[$uattr])* names.push(stringify!($unit)); )*
+                $( $(#[$tattr])* names.push(stringify!($tuple)); )*
+                $( $(#[$dattr])* names.push(stringify!($default)); )*
+                $( $(#[$oattr])* names.push(stringify!($optional)); )*
+                names
+            }
+        }
+
         impl<A: ActionExt + Display> std::fmt::Display for Action<A> {
             fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
                 match self {
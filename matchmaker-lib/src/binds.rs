@@ -13,6 +13,7 @@ use serde::{
 };
 
 use crate::{
+    MatchError,
     action::{Action, ActionExt, Actions, NullActionExt},
     config::HelpDisplayConfig,
     message::Event,
@@ -38,8 +39,8 @@ impl<A: ActionExt> BindMap<A> {
     pub fn default_binds() -> Self {
         #[allow(unused_mut)]
         let mut ret = bindmap!(
-            key!(ctrl-c) => Action::Quit(1),
-            key!(esc) => Action::Quit(1),
+            key!(ctrl-c) => Action::Quit(MatchError::EXIT_CANCEL),
+            key!(esc) => Action::Quit(MatchError::EXIT_CANCEL),
             key!(up) => Action::Up(1),
             key!(down) => Action::Down(1),
             key!(enter) => Action::Accept,
@@ -103,7 +104,10 @@ impl<A: ActionExt> BindMap<A> {
             key!(tab) => [Action::ToggleSelection, Action::Down(1)],
             key!(shift-backtab) => [Action::ToggleSelection, Action::Up(1)],
             key!(ctrl-a) => Action::CycleSelections,
-            key!(ctrl-shift-a) => Action::ClearSelections
+            key!(ctrl-shift-a) => Action::ClearSelections,
+
+            key!(ctrl-p) => Action::HistoryPrev,
+            key!(ctrl-n) => Action::HistoryNext
 
             // not currently supported by crossterm
             // "shift+scrollup" = "PreviewUp"
@@ -179,7 +183,14 @@ impl<A: ActionExt> BindMap<A> {
 
                     // inner alias names replace outer alias display in chains
                     let already_traced = matches!(flat_actions.first(), Some(Action::Trace(_)));
-                    if !already_traced && !in_trace {
+                    if in_trace {
+                        // the enclosing description takes precedence
+                        resolved.extend(
+                            flat_actions
+                                .into_iter()
+                                .filter(|a| !matches!(a, Action::Trace(_))),
+                        );
+                    } else if !already_traced {
                         resolved.push(Action::Trace(format!("@@{alias}")));
                         resolved.extend(flat_actions);
                         resolved.push(Action::Trace(String::new()));
@@ -1310,6 +1321,73 @@ mod test {
         assert!(!help_hide_str.contains("@foo"));
     }
 
+    #[test]
+    fn test_bind_desc() {
+        let binds: BindMap<NullActionExt> = toml::from_str(
+            r#"
+            ctrl-o = { action = "Print(open)", desc = "Open" }
+            "@up" = { action = ["Up", "Up"], desc = "Skip" }
+            a = "@up"
+            "#,
+        )
+        .unwrap();
+
+        let ctrl_o = &binds[&Trigger::from_str("ctrl-o").unwrap()];
+        assert_eq!(ctrl_o.desc(), Some("Open"));
+        assert_eq!(
+            ctrl_o.0,
+            [
+                Action::Trace("Open".into()),
+                Action::Print("open".into()),
+                Action::Trace(String::new())
+            ]
+        );
+        assert!(binds.check_traces());
+
+        let resolved = binds.resolve_semantics(&[]);
+        assert_eq!(
+            resolved[&TriggerKind::Semantic("up".into())].desc(),
+            Some("Skip")
+        );
+        assert_eq!(
+            resolved[&TriggerKind::from_str("a").unwrap()].desc(),
+            Some("Skip")
+        );
+
+        let help = display_help(&resolved, &HelpDisplayConfig::default()).to_string();
+        assert!(help.contains("Open"));
+        assert!(!help.contains("Print(open)"));
+
+        // round trip
+        let serialized = toml::to_string(&binds).unwrap();
+        assert_eq!(
+            toml::from_str::<BindMap<NullActionExt>>(&serialized).unwrap(),
+            binds
+        );
+    }
+
+    #[test]
+    fn test_chained_actions() {
+        let binds: BindMap<NullActionExt> = toml::from_str(
+            r#"
+            ctrl-y = "select+down"
+            enter = ["execute(echo {} + 1)+accept"]
+            ctrl-a = "@a+b"
+            "#,
+        )
+        .unwrap();
+
+        let get = |key: &str| binds[&Trigger::from_str(key).unwrap()].0.clone();
+        assert_eq!(get("ctrl-y"), [Action::Select, Action::Down(1)]);
+        assert_eq!(
+            get("enter"),
+            [Action::Execute("echo {} + 1".into()), Action::Accept]
+        );
+        assert_eq!(get("ctrl-a"), [Action::Semantic("a+b".into())]);
+
+        assert!(toml::from_str::<BindMap<NullActionExt>>(r#"a = "select+nothing""#).is_err());
+    }
+
     #[test]
     fn test_display_help_sort_fn_last() {
         let binds: BindMap<NullActionExt> = bindmap!(
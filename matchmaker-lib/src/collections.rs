@@ -1,24 +1,255 @@
-use cba::define_collection_wrapper;
-
-define_collection_wrapper!(
-  /// A set of nucleo `u32` indices representing the items the user has selected.
-  ///
-  /// The index is the nucleo item index (the value stored in [`nucleo::Match::idx`])
-  /// and is stable for the lifetime of the worker's items. It is used as the row-cache
-  /// key in `ResultsUI` so that selected rows can be highlighted.
-  #[derive(Debug)]
-  Selector : indexmap::IndexSet<u32>
-);
+use std::ops::{Deref, DerefMut};
+
+use indexmap::IndexSet;
+
+use crate::{
+    SSS,
+    config::{SelectionMode, SelectionOrder, SelectorConfig},
+    nucleo::{EditMap, Worker},
+};
+
+/// A set of nucleo `u32` indices representing the items the user has selected.
+///
+/// The index is the nucleo item index (the value stored in [`nucleo::Match::idx`])
+/// and is stable until the worker is restarted or the item is edited. It is used as the row-cache
+/// key in `ResultsUI` so that selected rows can be highlighted.
+///
+/// [`Self::sel`] and [`Self::toggle`] respect the mode and limit given by [`SelectorConfig`];
+/// mutating the underlying set directly does not.
+///
+/// Changes to the selections are reported by [`crate::message::Event::SelectionChange`].
+#[derive(Debug, Default, Clone)]
+pub struct Selector {
+    set: IndexSet<u32>,
+    mode: SelectionMode,
+    max: Option<usize>,
+    evict: bool,
+    order: SelectionOrder,
+    /// Selected as they are read, see [`Self::preselect`].
+    preselect: Vec<Preselect>,
+    /// The number of items checked against `preselect`.
+    preselect_checked: u32,
+    /// Whether the selections changed since the last [`Self::take_changed`].
+    changed: bool,
+}
+
+/// An item to select once it is read. See [`Selector::preselect`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Preselect {
+    /// The nucleo index, i.e. the position in the input.
+    Index(u32),
+    /// Items where any column has exactly this text.
+    Text(String),
+    /// Items where the column at this index has exactly this text.
+    Column(usize, String),
+}
+
+impl Deref for Selector {
+    type Target = IndexSet<u32>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.set
+    }
+}
+
+impl DerefMut for Selector {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.changed = true;
+        &mut self.set
+    }
+}
 
 impl Selector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_config(config: &SelectorConfig) -> Self {
+        Self {
+            mode: config.mode,
+            max: config.max,
+            evict: config.evict,
+            order: config.order,
+            ..Self::default()
+        }
+    }
+
+    /// A selector without selections, with the same mode, limit and order.
+    pub fn empty_like(&self) -> Self {
+        Self {
+            mode: self.mode,
+            max: self.max,
+            evict: self.evict,
+            order: self.order,
+            ..Self::default()
+        }
+    }
+
+    pub fn mode(&self) -> SelectionMode {
+        self.mode
+    }
+
+    pub fn is_multi(&self) -> bool {
+        self.mode == SelectionMode::Multi
+    }
+
+    /// Switch modes, clearing the selection if the mode changes.
+    pub fn set_mode(&mut self, mode: SelectionMode) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.changed |= !self.set.is_empty();
+            self.set.clear();
+        }
+    }
+
+    /// The maximum number of selections, if limited.
+    pub fn max(&self) -> Option<usize> {
+        self.max
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.max.is_some_and(|max| self.set.len() >= max)
+    }
+
+    /// The selections, in the order given by [`SelectorConfig::order`].
+    pub fn ordered(&self) -> Vec<u32> {
+        let mut ret: Vec<u32> = self.set.iter().copied().collect();
+        if self.order == SelectionOrder::Input {
+            ret.sort_unstable();
+        }
+        ret
+    }
+
+    /// The position of a selection in [`Self::ordered`].
+    pub fn position(&self, idx: u32) -> Option<usize> {
+        match self.order {
+            SelectionOrder::Selected => self.set.get_index_of(&idx),
+            SelectionOrder::Input => self
+                .set
+                .contains(&idx)
+                .then(|| self.set.iter().filter(|&&i| i < idx).count()),
+        }
+    }
+
+    /// Select an index, returning whether it is selected afterwards.
+    ///
+    /// At the limit, the oldest selection is evicted if configured, otherwise the index is rejected.
+    /// In [`SelectionMode::Single`], nothing can be selected.
+    pub fn sel(&mut self, idx: u32) -> bool {
+        if !self.is_multi() {
+            return false;
+        }
+        if self.set.contains(&idx) {
+            return true;
+        }
+        if let Some(max) = self.max
+            && self.set.len() >= max
+        {
+            if !self.evict || max == 0 {
+                return false;
+            }
+            let excess = self.set.len() + 1 - max;
+            self.set.drain(..excess);
+        }
+        self.changed = true;
+        self.set.insert(idx)
+    }
+
+    /// Move the selections to the indices returned by [`crate::nucleo::Worker::edit`], dropping
+    /// those of removed items.
+    pub fn remap(&mut self, map: &EditMap) {
+        if !self.set.iter().any(|idx| map.contains_key(idx)) {
+            return;
+        }
+        self.changed = true;
+        self.set = self
+            .set
+            .iter()
+            .filter_map(|&idx| map.get(&idx).copied().unwrap_or(Some(idx)))
+            .collect();
+    }
+
+    /// Select items as they are read, e.g. to edit an existing selection. Applied by
+    /// [`Self::apply_preselect`], which the picker calls as items arrive.
+    pub fn preselect(&mut self, keys: impl IntoIterator<Item = Preselect>) {
+        self.preselect.extend(keys);
+    }
+
+    /// Select the items read since the last call which match [`Self::preselect`].
+    pub fn apply_preselect<T: SSS, D>(&mut self, worker: &Worker<T, D>) {
+        if self.preselect.is_empty() {
+            return;
+        }
+        let (_, count) = worker.counts();
+        for idx in self.preselect_checked..count {
+            let Some(item) = worker.get_by_idx(idx) else {
+                continue;
+            };
+            let matches = self.preselect.iter().any(|key| match key {
+                Preselect::Index(i) => *i == idx,
+                Preselect::Text(text) => (worker.raw_preprocessor)(item).is_some_and(|d| {
+                    worker
+                        .columns
+                        .iter()
+                        .any(|column| column.raw(item, &d) == text.as_str())
+                }),
+                Preselect::Column(col, text) => worker.columns.get(*col).is_some_and(|column| {
+                    (worker.raw_preprocessor)(item)
+                        .is_some_and(|d| column.raw(item, &d) == text.as_str())
+                }),
+            });
+            if matches {
+                self.sel(idx);
+            }
+        }
+        self.preselect_checked = count;
+    }
+
+    /// Check the items against [`Self::preselect`] again from the start, i.e. after the worker
+    /// is restarted.
+    pub fn reset_preselect(&mut self) {
+        self.preselect_checked = 0;
+    }
+
+    /// Toggle an index, returning whether it is selected afterwards.
+    pub fn toggle(&mut self, idx: u32) -> bool {
+        if self.set.shift_remove(&idx) {
+            self.changed = true;
+            false
+        } else {
+            self.sel(idx)
+        }
+    }
+
+    /// Select all of `indices`, or clear the selection if they are all selected already.
+    /// When limited, only the first indices up to the limit are selected, without evicting.
     pub fn cycle_all_bg(&mut self, indices: impl ExactSizeIterator<Item = u32>) {
-        let matched: indexmap::IndexSet<u32> = indices.collect();
-        if !matched.is_empty() && matched.is_subset(&self) {
-            self.clear();
+        if !self.is_multi() {
+            return;
+        }
+        let matched: IndexSet<u32> = indices.collect();
+        if !matched.is_empty() && matched.is_subset(&self.set) {
+            self.set.clear();
+            self.changed = true;
         } else {
-            self.extend(matched);
+            for idx in matched {
+                if self.is_full() {
+                    break;
+                }
+                self.changed |= self.set.insert(idx);
+            }
         }
     }
+
+    /// Mark the selections as changed, e.g. when they are swapped for another set.
+    pub(crate) fn set_changed(&mut self) {
+        self.changed = true;
+    }
+
+    /// Whether the selections changed since the last call.
+    pub(crate) fn take_changed(&mut self) -> bool {
+        std::mem::take(&mut self.changed)
+    }
 }
 
 #[derive(Debug, Clone, Default)]
@@ -211,3 +442,128 @@ impl HiddenColumns {
         Some(count)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selector_limit() {
+        let mut selector = Selector::from_config(&SelectorConfig {
+            max: Some(2),
+            ..Default::default()
+        });
+        assert!(selector.sel(1));
+        assert!(selector.sel(2));
+        assert!(!selector.sel(3));
+        assert!(!selector.toggle(1));
+        assert!(selector.toggle(3));
+        assert_eq!(selector.iter().copied().collect::<Vec<_>>(), [2, 3]);
+
+        selector.evict = true;
+        assert!(selector.sel(4));
+        assert_eq!(selector.iter().copied().collect::<Vec<_>>(), [3, 4]);
+
+        selector.clear();
+        selector.cycle_all_bg([5, 6, 7].into_iter());
+        assert_eq!(selector.len(), 2);
+    }
+
+    #[test]
+    fn test_selector_mode() {
+        let mut selector = Selector::from_config(&SelectorConfig {
+            mode: SelectionMode::Single,
+            ..Default::default()
+        });
+        assert!(!selector.sel(1));
+        assert!(!selector.toggle(1));
+        selector.cycle_all_bg([1, 2].into_iter());
+        assert!(selector.is_empty());
+
+        selector.set_mode(SelectionMode::Multi);
+        assert!(selector.sel(1));
+        selector.set_mode(SelectionMode::Multi);
+        assert_eq!(selector.len(), 1);
+        selector.set_mode(SelectionMode::Single);
+        assert!(selector.is_empty());
+    }
+
+    #[test]
+    fn test_selector_order() {
+        let mut selector = Selector::new();
+        selector.extend([3, 1, 2]);
+        assert_eq!(selector.ordered(), [3, 1, 2]);
+        assert_eq!(selector.position(1), Some(1));
+
+        selector.order = SelectionOrder::Input;
+        assert_eq!(selector.ordered(), [1, 2, 3]);
+        assert_eq!(selector.position(3), Some(2));
+        assert_eq!(selector.position(4), None);
+    }
+
+    #[test]
+    fn test_take_changed() {
+        let mut selector = Selector::new();
+        assert!(!selector.take_changed());
+
+        selector.sel(1);
+        assert!(selector.take_changed());
+        assert!(!selector.take_changed());
+
+        selector.toggle(2);
+        selector.toggle(2);
+        assert!(selector.take_changed());
+
+        selector.cycle_all_bg([1].into_iter());
+        assert!(selector.take_changed());
+        selector.cycle_all_bg(std::iter::empty());
+        assert!(!selector.take_changed());
+
+        selector.clear();
+        assert!(selector.take_changed());
+    }
+
+    #[test]
+    fn test_preselect() {
+        let mut worker = Worker::<String>::new_single_column();
+        let mut selector = Selector::new();
+        selector.preselect([Preselect::Index(0), Preselect::Text("c".into())]);
+
+        worker.append(["a", "b", "c"].map(String::from));
+        while worker.refresh().running {}
+        selector.apply_preselect(&worker);
+        assert_eq!(selector.ordered(), [0, 2]);
+
+        selector.clear();
+        selector.apply_preselect(&worker);
+        assert!(selector.is_empty());
+    }
+
+    #[test]
+    fn test_preselect_column() {
+        let mut worker = Worker::<String>::new_single_column();
+        let mut selector = Selector::new();
+        selector.preselect([
+            Preselect::Column(0, "b".into()),
+            Preselect::Column(1, "c".into()),
+        ]);
+
+        worker.append(["a", "b", "c"].map(String::from));
+        while worker.refresh().running {}
+        selector.apply_preselect(&worker);
+        assert_eq!(selector.ordered(), [1]);
+    }
+
+    #[test]
+    fn test_selector_remap() {
+        let mut selector = Selector::new();
+        selector.extend([3, 0, 1]);
+        selector.take_changed();
+        selector.remap(&EditMap::from([(2, None)]));
+        assert!(!selector.take_changed());
+
+        selector.remap(&EditMap::from([(1, None), (3, Some(4))]));
+        assert!(selector.take_changed());
+        assert_eq!(selector.iter().copied().collect::<Vec<_>>(), [4, 0]);
+    }
+}
@@ -10,7 +10,8 @@ pub use crate::config_types::*;
 pub use crate::utils::{Percentage, serde::StringOrVec};
 
 use crate::{
-    tui::IoStream,
+    binds::{KeyCombination, key},
+    tui::{BackendKind, IoStream},
     utils::serde::{escaped_opt_char, escaped_opt_string},
 };
 
@@ -44,6 +45,7 @@ pub struct MatcherConfig {
 #[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
 pub struct WorkerConfig {
     /// How "stable" the results are. Higher values prioritize the initial ordering.
+    /// `false` disables sorting, so that matches are only filtered.
     #[serde(alias = "sort")]
     pub sort_threshold: SortThreshold,
     /// TODO: Enable raw mode where non-matching items are also displayed in a dimmed color.
@@ -51,8 +53,14 @@ pub struct WorkerConfig {
     pub raw: bool,
     /// TODO: Track the current selection when the result list is updated.
     pub track: bool,
-    /// Reverse the order of the input
+    /// Reverse the order of the input, so that the last item read is shown first.
     pub reverse: bool,
+    /// How results with equal scores are ordered, in order of priority.
+    /// Items are always finally ordered by index.
+    pub tiebreak: Vec<Tiebreak>,
+    /// Don't match the query against the items, so that every item is shown in input order.
+    /// For use with an external search which reloads the items when the query changes.
+    pub disabled: bool,
 }
 
 /// (client-app responsibility). Configures how input is fed to to the worker(s).
@@ -75,6 +83,9 @@ pub struct StartConfig {
     pub output_template: Option<String>,
     /// Execution template for accepted items. Exclusive with output_template and output_separator.
     pub on_accept: String,
+    /// Print accepted items as paths relative to the directory mm was started from,
+    /// rather than the current working directory.
+    pub relative_output: bool,
 
     /// Default command to execute when stdin is not being read.
     #[partial(alias = "cmd", alias = "x")]
@@ -137,6 +148,46 @@ pub struct ExitConfig {
     pub last_key_path: Option<std::path::PathBuf>,
 }
 
+/// Limits on the selection.
+#[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
+pub struct SelectorConfig {
+    /// Whether multiple items can be selected.
+    pub mode: SelectionMode,
+    /// Maximum number of selected items. None for no limit.
+    pub max: Option<usize>,
+    /// At the limit, evict the oldest selection instead of rejecting the new one.
+    pub evict: bool,
+    /// The order selections are output in.
+    pub order: SelectionOrder,
+}
+
+/// Query history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
+pub struct HistoryConfig {
+    /// Accepted queries are appended here, and loaded on startup.
+    /// Set to an empty path to disable.
+    pub file: Option<std::path::PathBuf>,
+    /// Maximum number of entries kept.
+    pub size: usize,
+    /// (client-app responsibility) Rank previously accepted items ahead of results with the same
+    /// score, by how often and how recently they were accepted.
+    pub frecency: bool,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            file: None,
+            size: 1000,
+            frecency: false,
+        }
+    }
+}
+
 /// The ui config.
 #[derive(Default, Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
@@ -168,6 +219,8 @@ pub struct RenderConfig {
 #[serde(default, deny_unknown_fields)]
 pub struct TerminalConfig {
     pub stream: IoStream, // consumed
+    /// The library drawing to the terminal.
+    pub backend: BackendKind,
     pub restore_fullscreen: bool,
     pub redraw_on_resize: bool,
     // https://docs.rs/crossterm/latest/crossterm/event/struct.PushKeyboardEnhancementFlags.html
@@ -185,12 +238,15 @@ pub struct TerminalConfig {
     pub osc52: bool,
     /// Whether to drop the end of the output of the copy command if it is a new line
     pub copy_trailing_newline: bool,
+    /// Write each drawn frame with its timing to this asciicast v2 file.
+    pub record: Option<std::path::PathBuf>,
 }
 
 impl Default for TerminalConfig {
     fn default() -> Self {
         Self {
             stream: IoStream::default(),
+            backend: BackendKind::default(),
             restore_fullscreen: true,
             redraw_on_resize: bool::default(),
             sleep_ms: 100,
@@ -200,6 +256,7 @@ impl Default for TerminalConfig {
             clear_after_execute: true,
             osc52: true,
             copy_trailing_newline: false,
+            record: None,
         }
     }
 }
@@ -214,6 +271,10 @@ pub struct UiConfig {
     pub border: BorderSetting,
     pub tick_rate: u8, // separate from render, but best place ig
     pub mouse_events: bool,
+    #[partial(recurse)]
+    pub toast: ToastConfig,
+    #[partial(recurse)]
+    pub spinner: SpinnerConfig,
 }
 
 impl Default for UiConfig {
@@ -222,6 +283,77 @@ impl Default for UiConfig {
             border: Default::default(),
             tick_rate: 60,
             mouse_events: true,
+            toast: Default::default(),
+            spinner: Default::default(),
+        }
+    }
+}
+
+/// Animated while items are still being read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
+pub struct SpinnerConfig {
+    /// The frames of the animation, one per character. Empty to disable.
+    pub frames: String,
+    pub interval_ms: u64,
+    pub position: SpinnerPosition,
+    #[partial(recurse)]
+    pub style: StyleSetting,
+}
+
+impl Default for SpinnerConfig {
+    fn default() -> Self {
+        Self {
+            frames: "⠋⠙⠹⠸⠼⠴⠦⠧⠇⠏".into(),
+            interval_ms: 80,
+            position: SpinnerPosition::default(),
+            style: StyleSetting {
+                fg: Some(Color::Cyan),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// Notifications stacked over the bottom right corner of the results.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+#[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
+pub struct ToastConfig {
+    pub show: bool,
+    /// How long each toast is displayed.
+    pub duration_ms: u64,
+    /// The maximum number of toasts displayed at once. Older toasts are dropped first.
+    pub max: usize,
+    #[partial(recurse)]
+    pub info_style: StyleSetting,
+    #[partial(recurse)]
+    pub warn_style: StyleSetting,
+    #[partial(recurse)]
+    pub error_style: StyleSetting,
+}
+
+impl Default for ToastConfig {
+    fn default() -> Self {
+        Self {
+            show: true,
+            duration_ms: 3000,
+            max: 3,
+            info_style: StyleSetting {
+                modifier: Modifier::REVERSED,
+                ..Default::default()
+            },
+            warn_style: StyleSetting {
+                fg: Some(Color::Black),
+                bg: Some(Color::Yellow),
+                ..Default::default()
+            },
+            error_style: StyleSetting {
+                fg: Some(Color::White),
+                bg: Some(Color::Red),
+                ..Default::default()
+            },
         }
     }
 }
@@ -327,7 +459,8 @@ pub struct AutoscrollSettings {
     #[partial(alias = "a")]
     pub enabled: bool,
     /// Number of characters to show around the match.
-    #[partial(alias = "c")]
+    #[serde(alias = "hscroll_off")]
+    #[partial(alias = "c", alias = "hscroll_off")]
     pub context: usize,
     /// Whether to autoscroll to the end of the line.
     #[partial(alias = "e")]
@@ -348,6 +481,70 @@ impl Default for AutoscrollSettings {
     }
 }
 
+/// What the mouse buttons do to the result they click.
+///
+/// Binding a button in the keymap takes precedence over this.
+#[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ResultsMouseConfig {
+    #[serde(deserialize_with = "camelcase_normalized")]
+    pub left: RowClick,
+    #[serde(deserialize_with = "camelcase_normalized")]
+    pub right: RowClick,
+    #[serde(deserialize_with = "camelcase_normalized")]
+    pub middle: RowClick,
+    /// Select the results dragged over with the left button, when multi-select is enabled.
+    pub drag_select: bool,
+}
+
+impl Default for ResultsMouseConfig {
+    fn default() -> Self {
+        Self {
+            left: RowClick::Jump,
+            right: RowClick::Toggle,
+            middle: RowClick::Accept,
+            drag_select: true,
+        }
+    }
+}
+
+/// A vertical scrollbar drawn along the right edge of a pane.
+#[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScrollbarSetting {
+    pub show: bool,
+    /// Hide the scrollbar when all the content fits.
+    #[partial(alias = "a")]
+    pub auto_hide: bool,
+    /// Track style.
+    #[partial(recurse)]
+    pub style: StyleSetting,
+    #[serde(alias = "thumb")]
+    #[partial(recurse)]
+    pub thumb_style: StyleSetting,
+    pub thumb_symbol: String,
+    /// Empty to leave the track undrawn.
+    pub track_symbol: String,
+}
+
+impl Default for ScrollbarSetting {
+    fn default() -> Self {
+        Self {
+            show: false,
+            auto_hide: true,
+            style: StyleSetting {
+                modifier: Modifier::DIM,
+                ..Default::default()
+            },
+            thumb_style: StyleSetting::default(),
+            thumb_symbol: "┃".into(),
+            track_symbol: "│".into(),
+        }
+    }
+}
+
 #[partial(path, derive(Debug, Clone, PartialEq, Deserialize, Serialize))]
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(default, deny_unknown_fields)]
@@ -360,6 +557,9 @@ pub struct ResultsConfig {
     #[serde(deserialize_with = "deserialize_string_or_char_as_double_width")]
     pub multi_prefix: String,
     pub default_prefix: String,
+    /// The elements of the gutter to the left of each result, in order. Each element is padded
+    /// to a fixed width, so that results stay aligned.
+    pub gutter: Vec<GutterElement>,
 
     #[serde(alias = "prefix")]
     #[partial(recurse)]
@@ -410,6 +610,20 @@ pub struct ResultsConfig {
     pub scroll_padding: u16,
     #[partial(alias = "r")]
     pub reverse: Option<bool>,
+    #[partial(recurse, alias = "sb")]
+    pub scrollbar: ScrollbarSetting,
+    /// A position indicator drawn in the top right corner of the results, hidden when all the
+    /// rows fit. Empty to disable.
+    ///
+    /// Supports replacements:
+    /// - `\c` -> the row of the cursor
+    /// - `\t` -> total rows
+    /// - `\p` -> percentage of rows above the bottom of the results
+    ///
+    /// For example: `r#"[\c/\t]"#.to_string()`
+    pub indicator: String,
+    #[partial(recurse)]
+    pub indicator_style: StyleSetting,
 
     // wrap
     #[partial(alias = "w")]
@@ -420,12 +634,23 @@ pub struct ResultsConfig {
     // autoscroll
     #[partial(recurse, alias = "a")]
     pub autoscroll: AutoscrollSettings,
+    /// Clip long lines on the left instead of the right, unless they are autoscrolled to a match.
+    /// Useful for paths.
+    #[partial(alias = "k")]
+    pub keep_right: bool,
+
+    #[partial(recurse)]
+    pub mouse: ResultsMouseConfig,
 
     // ------------
     // experimental
     // ------------
     pub column_spacing: Count,
+    /// Drawn by [`GutterElement::Pointer`].
     pub current_prefix: String,
+    /// Show the position of each selection in [`SelectorConfig::order`] in place of
+    /// `multi_prefix`.
+    pub show_selection_order: bool,
     #[partial(alias = "acp")]
     pub active_column_min_percentage_hint: Option<Percentage>,
 
@@ -435,6 +660,8 @@ pub struct ResultsConfig {
     pub show_skipped: bool,
     /// Always false if max_height is set
     pub vscroll_current_only: bool,
+    /// Only scroll the current row horizontally, so that long rows can be inspected one at a time.
+    pub hscroll_current_only: bool,
 
     // lowpri: maybe space-around/space-between instead?
     #[partial(alias = "ra")]
@@ -450,6 +677,10 @@ pub struct ResultsConfig {
     #[partial(recurse)]
     pub separator_style: StyleSetting,
 
+    /// The style of the group headers (see [`crate::nucleo::Worker::set_group_by`]).
+    #[partial(recurse)]
+    pub group_header_style: StyleSetting,
+
     pub width_overrides: Vec<u16>,
 
     /// `[grow, shrink]` pixel deltas required before a column's preferred
@@ -466,6 +697,7 @@ impl Default for ResultsConfig {
 
             multi_prefix: "▌ ".to_string(),
             default_prefix: Default::default(),
+            gutter: vec![GutterElement::Marker],
             prefix_style: Default::default(),
             prefix_inactive_style: Default::default(),
             multi: true,
@@ -497,6 +729,12 @@ impl Default for ResultsConfig {
             scroll_wrap: false,
             scroll_padding: 2,
             reverse: None,
+            scrollbar: Default::default(),
+            indicator: String::new(),
+            indicator_style: StyleSetting {
+                modifier: Modifier::REVERSED,
+                ..Default::default()
+            },
 
             wrap: false,
             min_width: 2,
@@ -505,15 +743,23 @@ impl Default for ResultsConfig {
             max_height: 0,
 
             autoscroll: Default::default(),
+            keep_right: false,
+            mouse: Default::default(),
 
             column_spacing: Default::default(),
             current_prefix: Default::default(),
+            show_selection_order: false,
             right_align_last: false,
             stacked_columns: false,
             separator: Default::default(),
             separator_style: Default::default(),
+            group_header_style: StyleSetting {
+                modifier: Modifier::BOLD,
+                ..Default::default()
+            },
             show_skipped: true,
             vscroll_current_only: true,
+            hscroll_current_only: true,
 
             width_overrides: vec![],
             resize_col_thresholds: [4, 4],
@@ -537,6 +783,7 @@ pub struct StatusConfig {
     /// - `\r` -> cursor index
     /// - `\m` -> match count
     /// - `\t` -> total count
+    /// - `\p` -> selection count, as `count/max` if [`SelectorConfig::max`] is set
     /// - `\s` -> available whitespace / # appearances
     /// - `\S` -> Increment # appearances for `\s`
     ///
@@ -550,6 +797,14 @@ pub struct StatusConfig {
     pub row_connection: RowConnectionStyle,
 
     pub interactions: InteractionRegionSetting,
+
+    /// Briefly shown in place of the status when a key with no bind is pressed.
+    /// `\k` is replaced by the key. Empty to disable.
+    ///
+    /// For example: `r#"unbound: \k — press ? for help"#.to_string()`
+    pub unbound_hint: String,
+    /// How long transient messages (such as the [`Self::unbound_hint`]) are displayed.
+    pub transient_ms: u64,
 }
 impl Default for StatusConfig {
     fn default() -> Self {
@@ -565,6 +820,8 @@ impl Default for StatusConfig {
             row_connection: RowConnectionStyle::Full,
 
             interactions: Default::default(),
+            unbound_hint: String::new(),
+            transient_ms: 1500,
         }
     }
 }
@@ -606,8 +863,15 @@ pub struct DisplayConfig {
     /// Note: Incoming lines are partitioned into columns the same way regular lines are.
     #[partial(alias = "h")]
     pub header_lines: usize,
+    /// Style of the lines read from the input, applied over `style`.
+    #[partial(recurse)]
+    pub header_lines_style: StyleSetting,
 
     pub interactions: Vec<InteractionRegionSetting>,
+
+    /// Semantic triggers to display on a line of buttons after the content, labeled by the
+    /// descriptions of their binds. Clicking a button triggers it.
+    pub buttons: Vec<String>,
 }
 
 pub type InteractionRegionSetting = Vec<(u8, String)>;
@@ -625,8 +889,10 @@ impl Default for DisplayConfig {
             row_connection: Default::default(),
             content: None,
             header_lines: 0,
+            header_lines_style: Default::default(),
 
             interactions: Default::default(),
+            buttons: Default::default(),
         }
     }
 }
@@ -664,7 +930,14 @@ pub struct PreviewConfig {
     #[partial(alias = "c")]
     #[serde(alias = "cycle")]
     pub scroll_wrap: bool,
+    /// Wrap long lines at word boundaries, indenting the continuation to match the line.
     pub wrap: bool,
+    /// Keep the preview scrolled to the bottom as the command outputs more lines, like
+    /// `tail -f`. Scrolling up stops following, and scrolling back to the bottom resumes it.
+    pub follow: bool,
+    /// Rerun the command every this many milliseconds while the preview is visible, keeping the
+    /// scroll position. 0 to disable.
+    pub refresh_ms: u64,
     /// Whether to show the preview pane initially.
     /// Can either be a boolean or a number which the relevant dimension of the available ui area must exceed.
     pub show: ShowCondition,
@@ -678,6 +951,36 @@ pub struct PreviewConfig {
     /// If `None`, it defaults to the width of the preview border.
     /// If `0`, drag resizing is disabled.
     pub drag_width: Option<u16>,
+
+    #[partial(recurse, alias = "sb")]
+    pub scrollbar: ScrollbarSetting,
+
+    /// Prefix each line with its line number.
+    #[partial(alias = "n")]
+    pub line_numbers: bool,
+    #[partial(recurse)]
+    pub line_number_style: StyleSetting,
+    /// The style of the line given by [`PreviewInitialSetting::index`], counted from 1, whatever
+    /// the [`PreviewInitialSetting::offset`].
+    #[partial(recurse)]
+    pub target_style: StyleSetting,
+    /// A position indicator drawn in the top right corner of the preview. Empty to disable.
+    ///
+    /// Supports replacements:
+    /// - `\c` -> first displayed line
+    /// - `\t` -> total lines, including output which has not been displayed yet
+    /// - `\p` -> percentage of lines above the bottom of the preview
+    ///
+    /// For example: `r#"[\c/\t \p%]"#.to_string()`
+    pub indicator: String,
+    #[partial(recurse)]
+    pub indicator_style: StyleSetting,
+
+    /// (client-app responsibility) Infer the command of layouts which don't have one from the
+    /// current item, using [`PreviewConfig::rules`] followed by builtin rules.
+    pub auto: bool,
+    /// Rules for [`PreviewConfig::auto`]. The command of the first matching rule is used.
+    pub rules: Vec<PreviewRule>,
 }
 
 impl Default for PreviewConfig {
@@ -692,10 +995,29 @@ impl Default for PreviewConfig {
             layout: Default::default(),
             scroll_wrap: false,
             wrap: false,
+            follow: false,
+            refresh_ms: 0,
             show: Default::default(),
             reevaluate_show_on_resize: false,
             drag_width: None,
             trim_ends: false,
+            scrollbar: Default::default(),
+            line_numbers: false,
+            line_number_style: StyleSetting {
+                modifier: Modifier::DIM,
+                ..Default::default()
+            },
+            target_style: StyleSetting {
+                bg: Some(Color::DarkGray),
+                ..Default::default()
+            },
+            indicator: String::new(),
+            indicator_style: StyleSetting {
+                modifier: Modifier::REVERSED,
+                ..Default::default()
+            },
+            auto: false,
+            rules: Vec::new(),
         }
     }
 }
@@ -740,17 +1062,32 @@ pub struct PreviewInitialSetting {
 pub struct PreviewerConfig {
     pub try_lossy: bool,
     pub delay_clear: bool,
+    /// Display binary output as a hex dump of up to this many bytes. 0 to disable.
+    pub hexdump_bytes: usize,
 
     // todo
     pub cache: u8,
 
+    /// Wait this many milliseconds before running each command, and skip it if another was
+    /// requested meanwhile. See [`Self::delay_ms`], which makes this mostly redundant.
     pub debounce_ms: u64,
+    /// Run a command only once no other has been requested for this many milliseconds, so that
+    /// only the last of a burst is run. Unlike [`Self::debounce_ms`], the wait restarts with each
+    /// request. Both apply when set, one after the other.
+    pub delay_ms: u64,
+    /// Kill commands which run for longer than this many milliseconds. 0 to disable.
+    pub timeout_ms: u64,
     pub max_procs: usize,
     pub always_trigger: bool,
 
     pub help: HelpDisplayConfig,
     pub shell: Option<Vec<OsString>>,
     pub trim_commands: bool,
+    /// Run commands in a pseudo-terminal, so that keys can be forwarded to them with
+    /// [`crate::action::Action::FocusPreview`]. Requires the `pty` feature.
+    pub pty: bool,
+    /// The key which returns focus from the preview to the picker.
+    pub unfocus_key: KeyCombination,
 
     /// See [`StartConfig`]
     pub command_args: Vec<OsString>,
@@ -761,13 +1098,18 @@ impl Default for PreviewerConfig {
         Self {
             try_lossy: false,
             delay_clear: true,
+            hexdump_bytes: 4096,
             cache: 0,
             debounce_ms: 0,
+            delay_ms: 0,
+            timeout_ms: 0,
             max_procs: 4,
             always_trigger: true,
             help: Default::default(),
             shell: None,
             trim_commands: false,
+            pty: false,
+            unfocus_key: key!(ctrl - q),
 
             command_args: Default::default(),
         }
@@ -863,15 +1205,17 @@ pub struct BorderSetting {
 
 impl BorderSetting {
     pub fn as_block(&self) -> ratatui::widgets::Block<'_> {
+        self.as_titled_block(&self.title)
+    }
+
+    /// Same as [`BorderSetting::as_block`], but with `title` in place of the configured title.
+    pub fn as_titled_block<'a>(&'a self, title: &'a str) -> ratatui::widgets::Block<'a> {
         let mut ret = ratatui::widgets::Block::default()
             .padding(self.padding.0)
             .style(Style::default().bg(self.bg).add_modifier(self.modifier));
 
-        if !self.title.is_empty() {
-            let title = Span::styled(
-                &self.title,
-                Style::default().add_modifier(self.title_modifier),
-            );
+        if !title.is_empty() {
+            let title = Span::styled(title, Style::default().add_modifier(self.title_modifier));
 
             ret = ret.title(title)
         };
@@ -1014,6 +1358,11 @@ pub struct PreviewSetting {
     pub border: Option<BorderSetting>,
     #[serde(default, alias = "cmd", alias = "x")]
     pub command: String,
+    /// Extra environment variables for the command.
+    #[serde(alias = "env")]
+    pub envs: std::collections::HashMap<String, String>,
+    /// The directory to run the command in, as a template: i.e. `{=dir}`. Empty to inherit.
+    pub cwd: String,
 
     #[cfg(feature = "partial")]
     #[partial(unwrap)]
@@ -1066,6 +1415,10 @@ pub struct ColumnsConfig {
     pub default: StringOrInt,
     /// When autogenerating column names, start from 0 instead of 1.
     pub names_from_zero: bool,
+    /// Columns used for matching. All columns if unset.
+    pub nth: Option<FieldRanges>,
+    /// Columns which are displayed. All columns if unset.
+    pub with_nth: Option<FieldRanges>,
 }
 
 impl ColumnsConfig {
@@ -1082,6 +1435,8 @@ impl Default for ColumnsConfig {
             max_columns: 6,
             default: StringOrInt::Int(0),
             names_from_zero: false,
+            nth: None,
+            with_nth: None,
         }
     }
 }
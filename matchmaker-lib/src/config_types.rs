@@ -109,6 +109,30 @@ pub enum RowConnectionStyle {
     Full,
 }
 
+/// Where the loading spinner is drawn.
+#[derive(Debug, Default, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum SpinnerPosition {
+    /// The right end of the status line.
+    #[default]
+    Status,
+    /// The right end of the query bar.
+    Prompt,
+}
+
+/// What clicking a result with a mouse button does, see [`crate::config::ResultsMouseConfig`].
+#[derive(Debug, Default, Copy, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum RowClick {
+    /// Nothing.
+    None,
+    /// Move the cursor to the result.
+    #[default]
+    Jump,
+    /// Move the cursor to the result and toggle it in the selections.
+    Toggle,
+    /// Move the cursor to the result and accept.
+    Accept,
+}
+
 define_transparent_wrapper!(
     #[derive(Copy, Clone, serde::Serialize, serde::Deserialize)]
     #[serde(transparent)]
@@ -342,6 +366,132 @@ pub struct ColumnSetting {
     // not feature gated for config compatibility
     #[serde(default)]
     pub options: ColumnOptions,
+    #[serde(flatten)]
+    pub style: ColumnStyle,
+}
+
+/// Styling applied to every cell of a column, beneath the row and match styles.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColumnStyle {
+    #[serde(with = "cba::serde::empty_string_as_none")]
+    pub fg: Option<Color>,
+    #[serde(with = "cba::serde::empty_string_as_none")]
+    pub bg: Option<Color>,
+    pub modifier: Modifier,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub align: Option<ColumnAlign>,
+}
+
+impl ColumnStyle {
+    pub fn style(&self) -> Style {
+        StyleSetting {
+            fg: self.fg,
+            bg: self.bg,
+            modifier: self.modifier,
+        }
+        .into_style_no_submodifiers()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// Criteria for ordering results with equal scores, as in fzf's `--tiebreak`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tiebreak {
+    /// Prefer shorter text.
+    Length,
+    /// Prefer matches closer to the start of the text.
+    Begin,
+    /// Prefer matches closer to the end of the text.
+    End,
+    /// Prefer items which were read earlier.
+    Index,
+}
+
+/// An element of the gutter drawn to the left of each result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GutterElement {
+    /// `multi_prefix` on selected items, otherwise `default_prefix`.
+    Marker,
+    /// `current_prefix` on the current item, otherwise blank.
+    Pointer,
+    /// The position of the item among the matches, starting from 1.
+    Number,
+}
+
+/// Whether more than one item can be selected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionMode {
+    /// Only the current item is accepted; selection actions are ignored.
+    Single,
+    #[default]
+    Multi,
+}
+
+/// The order selections are output in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SelectionOrder {
+    /// The order the items were selected in.
+    #[default]
+    Selected,
+    /// The order the items were read in.
+    Input,
+}
+
+/// What the item of a [`PreviewRule`] refers to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PreviewTarget {
+    /// Anything.
+    #[default]
+    Any,
+    /// An existing file.
+    File,
+    /// An existing directory.
+    Dir,
+    /// A URL, i.e. `https://example.com`.
+    Url,
+    /// A process ID.
+    Pid,
+}
+
+/// A rule for inferring the preview command of an item, see [`crate::config::PreviewConfig::auto`].
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PreviewRule {
+    /// What the item must refer to.
+    #[serde(alias = "is")]
+    pub target: PreviewTarget,
+    /// A regex which the item must match. Empty to match anything.
+    pub pattern: String,
+    /// Test the value of this column instead of the whole item, by name or 0-based index.
+    #[serde(alias = "col")]
+    pub column: Option<StringOrInt>,
+    /// The preview command, as a template.
+    #[serde(alias = "cmd", alias = "x")]
+    pub command: String,
+}
+
+impl From<ColumnAlign> for ratatui::layout::Alignment {
+    fn from(a: ColumnAlign) -> Self {
+        match a {
+            ColumnAlign::Left => Self::Left,
+            ColumnAlign::Center => Self::Center,
+            ColumnAlign::Right => Self::Right,
+        }
+    }
 }
 
 #[derive(Default, Debug, Clone)]
@@ -472,6 +622,14 @@ impl<'de> Deserialize<'de> for ColumnSetting {
             #[serde(default)]
             options: ColumnOptions,
             name: ColumnName,
+            #[serde(default, with = "cba::serde::empty_string_as_none")]
+            fg: Option<Color>,
+            #[serde(default, with = "cba::serde::empty_string_as_none")]
+            bg: Option<Color>,
+            #[serde(default)]
+            modifier: Modifier,
+            #[serde(default)]
+            align: Option<ColumnAlign>,
         }
 
         #[derive(Deserialize)]
@@ -486,6 +644,7 @@ impl<'de> Deserialize<'de> for ColumnSetting {
                 ignore: true,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
                 name,
             }),
             Input::Obj(obj) => Ok(ColumnSetting {
@@ -493,6 +652,12 @@ impl<'de> Deserialize<'de> for ColumnSetting {
                 hidden: obj.hidden,
 
                 options: obj.options,
+                style: ColumnStyle {
+                    fg: obj.fg,
+                    bg: obj.bg,
+                    modifier: obj.modifier,
+                    align: obj.align,
+                },
                 name: obj.name,
             }),
         }
@@ -716,6 +881,126 @@ impl<'de> Deserialize<'de> for StringOrInt {
     }
 }
 
+// ----------------------------------------------------------------------------
+/// A set of column indices, written as comma-separated indices and inclusive ranges, i.e. `1,3..5,-2..`.
+///
+/// Negative indices count back from the last column, so `-1` is the last column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldRanges(Vec<(Option<isize>, Option<isize>)>);
+
+impl FieldRanges {
+    /// Whether the `index`-th of `len` columns is in the set.
+    /// Nonnegative indices in the set are offset by `offset`, i.e. 1 if columns are numbered from 1.
+    pub fn contains(&self, index: usize, len: usize, offset: usize) -> bool {
+        let resolve = |i: isize| {
+            if i < 0 {
+                len as isize + i
+            } else {
+                i - offset as isize
+            }
+        };
+        let index = index as isize;
+
+        self.0.iter().any(|&(start, end)| {
+            start.is_none_or(|s| resolve(s) <= index) && end.is_none_or(|e| index <= resolve(e))
+        })
+    }
+}
+
+impl std::str::FromStr for FieldRanges {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parse = |x: &str| -> Result<Option<isize>, String> {
+            let x = x.trim();
+            if x.is_empty() {
+                return Ok(None);
+            }
+            x.parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid column index '{x}'"))
+        };
+
+        let ranges = s
+            .split(',')
+            .map(|part| match part.split_once("..") {
+                Some((start, end)) => Ok((parse(start)?, parse(end)?)),
+                None => {
+                    let i = parse(part)?.ok_or_else(|| format!("Empty column range in '{s}'"))?;
+                    Ok((Some(i), Some(i)))
+                }
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(Self(ranges))
+    }
+}
+
+impl fmt::Display for FieldRanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (n, &(start, end)) in self.0.iter().enumerate() {
+            if n > 0 {
+                f.write_str(",")?;
+            }
+            match (start, end) {
+                (Some(s), Some(e)) if s == e => write!(f, "{s}")?,
+                _ => {
+                    if let Some(s) = start {
+                        write!(f, "{s}")?;
+                    }
+                    f.write_str("..")?;
+                    if let Some(e) = end {
+                        write!(f, "{e}")?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for FieldRanges {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for FieldRanges {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct Visitor;
+
+        impl<'de> de::Visitor<'de> for Visitor {
+            type Value = FieldRanges;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a column index or a string of column ranges")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: de::Error,
+            {
+                v.parse().map_err(E::custom)
+            }
+
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> {
+                Ok(FieldRanges(vec![(Some(v as isize), Some(v as isize))]))
+            }
+
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> {
+                Ok(FieldRanges(vec![(Some(v as isize), Some(v as isize))]))
+            }
+        }
+
+        deserializer.deserialize_any(Visitor)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -745,6 +1030,43 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn test_field_ranges() {
+        let f: FieldRanges = "1,3..4,-1".parse().unwrap();
+        let hits: Vec<_> = (0..6).filter(|&i| f.contains(i, 6, 1)).collect();
+        assert_eq!(hits, [0, 2, 3, 5]);
+        assert_eq!(f.to_string(), "1,3..4,-1");
+
+        let f: FieldRanges = "-2..".parse().unwrap();
+        assert!(!f.contains(3, 6, 1) && f.contains(4, 6, 1) && f.contains(5, 6, 1));
+
+        assert!("1,,2".parse::<FieldRanges>().is_err());
+    }
+
+    #[derive(Deserialize)]
+    struct TestColumns {
+        names: Vec<ColumnSetting>,
+    }
+
+    #[test]
+    fn test_column_style() {
+        let c: TestColumns = toml::from_str(
+            r#"names = ["cmd", { name = "pid", fg = "cyan", modifier = "BOLD", align = "right" }]"#,
+        )
+        .unwrap();
+
+        assert_eq!(c.names[0].style, ColumnStyle::default());
+        let style = c.names[1].style;
+        assert_eq!(style.fg, Some(Color::Cyan));
+        assert_eq!(style.bg, None);
+        assert_eq!(style.modifier, Modifier::BOLD);
+        assert_eq!(style.align, Some(ColumnAlign::Right));
+
+        let res: Result<TestColumns, _> =
+            toml::from_str(r#"names = [{ name = "pid", align = "top" }]"#);
+        assert!(res.is_err());
+    }
+
     #[derive(Deserialize, Serialize, PartialEq, Debug)]
     struct TestConfig {
         val: StringOrInt,
@@ -769,6 +1091,11 @@ define_transparent_wrapper!(
     SortThreshold: u32 = 0
 );
 
+impl SortThreshold {
+    /// Never sort by score, so matches stay in input order.
+    pub const UNSORTED: Self = Self(u32::MAX);
+}
+
 impl<'de> Deserialize<'de> for SortThreshold {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -805,7 +1132,11 @@ impl<'de> Deserialize<'de> for SortThreshold {
             where
                 E: serde::de::Error,
             {
-                Ok(SortThreshold(if v { 0 } else { u32::MAX }))
+                Ok(if v {
+                    SortThreshold(0)
+                } else {
+                    SortThreshold::UNSORTED
+                })
             }
         }
 
@@ -24,4 +24,25 @@ pub enum MatchError {
     NoMatch,
 }
 
+/// Exit codes, compatible with fzf.
+impl MatchError {
+    /// An item was accepted.
+    pub const EXIT_OK: i32 = 0;
+    /// Nothing matched.
+    pub const EXIT_NO_MATCH: i32 = 1;
+    pub const EXIT_ERROR: i32 = 2;
+    /// The user cancelled the picker (`esc`/`ctrl-c`).
+    pub const EXIT_CANCEL: i32 = 130;
+
+    /// The code a process exiting with this error should report.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            MatchError::Abort(code) => *code,
+            MatchError::Become(_) => Self::EXIT_OK,
+            MatchError::NoMatch => Self::EXIT_NO_MATCH,
+            MatchError::EventLoopClosed | MatchError::TUIError(_) => Self::EXIT_ERROR,
+        }
+    }
+}
+
 pub type Result<T> = std::result::Result<T, MatchError>;
@@ -1,4 +1,4 @@
-use crate::action::{Action, ActionExt, Actions, NullActionExt};
+use crate::action::{Action, ActionExt, Actions};
 use crate::binds::{BindMap, BindMapExt, ResolvedBindMap, SimpleMouseEvent, TriggerKind};
 use crate::message::{BindDirective, Event, RenderCommand};
 use anyhow::Result;
@@ -15,17 +15,58 @@ use log::{debug, error, info, warn};
 use ratatui::layout::Rect;
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
 use tokio::sync::mpsc;
 use tokio::time::{self};
 
-pub type RenderSender<A = NullActionExt> = mpsc::UnboundedSender<RenderCommand<A>>;
+mod channel;
+pub use channel::*;
+
 pub type EventSender = mpsc::UnboundedSender<Event>;
 pub type BindSender<A> = mpsc::UnboundedSender<BindDirective<A>>;
+/// A pty to forward keys to while it is focused, and the key which unfocuses it.
+#[cfg(feature = "pty")]
+pub(crate) type ForwardKeys = (crate::preview::PtyHandle, KeyCombination);
+
+/// Keeps at most one [`RenderCommand::Tick`] in flight.
+///
+/// Ticks fire on multiples of the tick interval, late ticks being skipped rather than bunched.
+/// A tick is only sent once the renderer has released the previous one, so a saturated render loop
+/// sees a single tick per batch instead of a backlog of stale ones.
+#[derive(Debug, Default)]
+pub struct TickGate {
+    pending: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl TickGate {
+    /// Returns true if a tick may be sent, counting it as dropped otherwise.
+    pub fn acquire(&self) -> bool {
+        if self.pending.swap(true, Ordering::AcqRel) {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Called by the renderer once the frame for a tick has been drawn.
+    pub fn release(&self) {
+        self.pending.store(false, Ordering::Release);
+    }
+
+    /// The number of ticks skipped because the previous one was still pending.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
 
 #[derive(Debug)]
 pub struct EventLoop<A: ActionExt> {
-    txs: Vec<mpsc::UnboundedSender<RenderCommand<A>>>,
+    txs: Vec<RenderSender<A>>,
     tick_interval: time::Duration,
+    ticks: Arc<TickGate>,
     paused: bool,
     skip_ticks: [bool; 2],
     dirty: bool,
@@ -46,8 +87,16 @@ pub struct EventLoop<A: ActionExt> {
 
     key_file: Option<PathBuf>,
     current_task: Option<tokio::task::JoinHandle<Result<()>>>,
+    /// Last time an unbound key was logged, and the number ignored since.
+    unbound_log: (Option<Instant>, usize),
+    /// Where keys are sent instead while the preview is focused, and the key which unfocuses it.
+    #[cfg(feature = "pty")]
+    pty: Arc<arc_swap::ArcSwapOption<ForwardKeys>>,
 }
 
+/// Minimum time between log entries for ignored keys.
+const UNBOUND_LOG_INTERVAL: time::Duration = time::Duration::from_secs(1);
+
 impl<A: ActionExt> Default for EventLoop<A> {
     fn default() -> Self {
         Self::new()
@@ -65,6 +114,7 @@ impl<A: ActionExt> EventLoop<A> {
         Self {
             txs: vec![],
             tick_interval: time::Duration::from_millis(200),
+            ticks: Arc::new(TickGate::default()),
             skip_ticks: [false; 2],
             paused: false,
             dirty: false,
@@ -80,6 +130,9 @@ impl<A: ActionExt> EventLoop<A> {
             mouse_events: false,
             key_file: None,
             current_task: None,
+            unbound_log: (None, 0),
+            #[cfg(feature = "pty")]
+            pty: Default::default(),
 
             bind_rx,
             bind_tx,
@@ -107,6 +160,11 @@ impl<A: ActionExt> EventLoop<A> {
         self
     }
 
+    /// The gate shared with the renderer, which should release it after each frame.
+    pub fn ticks(&self) -> Arc<TickGate> {
+        self.ticks.clone()
+    }
+
     pub fn get_binds_ptr(&self) -> Arc<ArcSwap<ResolvedBindMap<A>>> {
         self.binds.clone()
     }
@@ -122,7 +180,7 @@ impl<A: ActionExt> EventLoop<A> {
         &self.original_binds
     }
 
-    pub fn add_tx(&mut self, handler: mpsc::UnboundedSender<RenderCommand<A>>) -> &mut Self {
+    pub fn add_tx(&mut self, handler: RenderSender<A>) -> &mut Self {
         self.txs.push(handler);
         self
     }
@@ -136,6 +194,19 @@ impl<A: ActionExt> EventLoop<A> {
         self.txs.clear();
     }
 
+    /// While `pty` is focused, forward keys to it rather than processing them, except for
+    /// `unfocus_key` which returns focus to the picker.
+    #[cfg(feature = "pty")]
+    pub fn forward_keys(&mut self, pty: crate::preview::PtyHandle, unfocus_key: KeyCombination) {
+        self.pty.store(Some(Arc::new((pty, unfocus_key))));
+    }
+
+    /// Where [`EventLoop::forward_keys`] is stored, to change it once the loop is running.
+    #[cfg(feature = "pty")]
+    pub(crate) fn pty_slot(&self) -> Arc<arc_swap::ArcSwapOption<ForwardKeys>> {
+        self.pty.clone()
+    }
+
     pub fn controller(&self) -> EventSender {
         self.controller_tx.clone()
     }
@@ -294,10 +365,11 @@ impl<A: ActionExt> EventLoop<A> {
                 biased;
 
                 _ = interval.tick() => {
-                    if !self.skip_ticks.iter().all(|x| *x) || self.dirty {
-                        self.send(RenderCommand::Tick)
+                    if (!self.skip_ticks.iter().all(|x| *x) || self.dirty) && self.ticks.acquire() {
+                        self.send(RenderCommand::Tick);
+                        // a dropped tick leaves the loop dirty for the next one
+                        self.dirty = false;
                     }
-                    self.dirty = false;
                 }
 
                 // In case ctrl-c manifests as a signal instead of a key
@@ -343,6 +415,19 @@ impl<A: ActionExt> EventLoop<A> {
                             }
                             match event {
                                 CrosstermEvent::Key(k) => {
+                                    #[cfg(feature = "pty")]
+                                    if let Some((pty, unfocus_key)) = self.pty.load().as_deref()
+                                        && pty.is_focused()
+                                    {
+                                        if k.kind != crossterm::event::KeyEventKind::Release {
+                                            if KeyCombination::from(k).normalized() == *unfocus_key {
+                                                pty.unfocus();
+                                            } else {
+                                                pty.send_key(k);
+                                            }
+                                        }
+                                        continue;
+                                    }
                                     if let Some(key) = self.combiner.transform(k) {
                                         info!("{key:?}");
                                         let key = KeyCombination::normalized(key);
@@ -376,6 +461,9 @@ impl<A: ActionExt> EventLoop<A> {
                                             }
                                             if matched {
                                                 self.record_key(key.to_string());
+                                            } else {
+                                                self.log_unbound(&key.to_string());
+                                                self.send(RenderCommand::Unbound(key.to_string()));
                                             }
                                         }
                                     }
@@ -455,6 +543,9 @@ impl<A: ActionExt> EventLoop<A> {
         key: Option<String>,
     ) {
         let actions: Vec<Action<A>> = actions.into_iter().collect();
+        if let Some(k) = &key {
+            self.send(RenderCommand::Key(k.clone()));
+        }
 
         for action in actions {
             match action {
@@ -463,6 +554,9 @@ impl<A: ActionExt> EventLoop<A> {
                         self.send(Action::Print(k.clone()).into());
                     }
                 }
+                Action::AcceptWith(s) if s.is_empty() => {
+                    self.send(Action::AcceptWith(key.clone().unwrap_or_default()).into());
+                }
                 Action::Semantic(s) => {
                     if let Some(actions) = self.get_bind(TriggerKind::Semantic(s)) {
                         self.send_actions(actions.clone(), None);
@@ -475,6 +569,18 @@ impl<A: ActionExt> EventLoop<A> {
         }
     }
 
+    // a held key can produce a lot of these
+    fn log_unbound(&mut self, key: &str) {
+        let now = Instant::now();
+        let (last, count) = &mut self.unbound_log;
+        *count += 1;
+        if last.is_none_or(|t| now.duration_since(t) >= UNBOUND_LOG_INTERVAL) {
+            info!("Ignored unbound key: {key} ({count} since last report)");
+            *last = Some(now);
+            *count = 0;
+        }
+    }
+
     pub fn print_key(&self, key_combination: KeyCombination) -> String {
         self.fmt.to_string(key_combination)
     }
@@ -484,7 +590,7 @@ impl<A: ActionExt> EventLoop<A> {
     }
 }
 
-fn key_code_as_letter(key: KeyCombination) -> Option<char> {
+pub(crate) fn key_code_as_letter(key: KeyCombination) -> Option<char> {
     match key {
         KeyCombination {
             codes: crokey::OneToThree::One(crossterm::event::KeyCode::Char(l)),
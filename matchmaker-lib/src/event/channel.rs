@@ -0,0 +1,145 @@
+use std::collections::VecDeque;
+use std::mem::discriminant;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc::{
+    self,
+    error::{SendError, TrySendError},
+};
+
+use crate::action::{ActionExt, NullActionExt};
+use crate::message::RenderCommand;
+
+/// Capacity of the render channel created by [`crate::PickOptions`].
+pub const RENDER_CHANNEL_CAPACITY: usize = 1024;
+
+/// How a [`RenderCommand`] is handled when the render channel is full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// Replace a waiting command of the same kind, if any.
+    Coalesce,
+    /// Wait in line behind the channel.
+    Queue,
+}
+
+type Backlog<A> = Arc<Mutex<VecDeque<RenderCommand<A>>>>;
+
+/// Create a bounded render channel.
+///
+/// Commands sent while the channel is full go to a backlog which the receiver drains once the
+/// channel is empty, so order is preserved. Ticks, refreshes, redraws and resizes are coalesced in
+/// the backlog; everything else, in particular actions, is kept.
+pub fn render_channel<A: ActionExt>(capacity: usize) -> (RenderSender<A>, RenderReceiver<A>) {
+    let (tx, rx) = mpsc::channel(capacity);
+    let backlog = Backlog::default();
+    (
+        RenderSender {
+            tx,
+            backlog: backlog.clone(),
+        },
+        RenderReceiver { rx, backlog },
+    )
+}
+
+#[derive(Debug)]
+pub struct RenderSender<A: ActionExt = NullActionExt> {
+    tx: mpsc::Sender<RenderCommand<A>>,
+    backlog: Backlog<A>,
+}
+
+impl<A: ActionExt> Clone for RenderSender<A> {
+    fn clone(&self) -> Self {
+        Self {
+            tx: self.tx.clone(),
+            backlog: self.backlog.clone(),
+        }
+    }
+}
+
+impl<A: ActionExt> RenderSender<A> {
+    /// Send without blocking, only failing if the receiver has been dropped.
+    pub fn send(&self, cmd: RenderCommand<A>) -> Result<(), SendError<RenderCommand<A>>> {
+        let mut backlog = self.backlog.lock().unwrap();
+
+        // once anything is in the backlog, later commands must go behind it
+        let cmd = if backlog.is_empty() {
+            match self.tx.try_send(cmd) {
+                Ok(()) => return Ok(()),
+                Err(TrySendError::Closed(cmd)) => return Err(SendError(cmd)),
+                Err(TrySendError::Full(cmd)) => cmd,
+            }
+        } else if self.tx.is_closed() {
+            return Err(SendError(cmd));
+        } else {
+            cmd
+        };
+
+        if cmd.overflow() == Overflow::Coalesce
+            && let Some(waiting) = backlog
+                .iter_mut()
+                .find(|c| discriminant(*c) == discriminant(&cmd))
+        {
+            *waiting = cmd;
+        } else {
+            backlog.push_back(cmd);
+        }
+        Ok(())
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.tx.is_closed()
+    }
+}
+
+#[derive(Debug)]
+pub struct RenderReceiver<A: ActionExt = NullActionExt> {
+    rx: mpsc::Receiver<RenderCommand<A>>,
+    backlog: Backlog<A>,
+}
+
+impl<A: ActionExt> RenderReceiver<A> {
+    /// Like [`mpsc::Receiver::recv_many`], followed by the backlog if the channel was emptied.
+    /// The backlog is only filled while the channel is full, so this never waits on an empty
+    /// channel with a nonempty backlog.
+    pub async fn recv_many(&mut self, buffer: &mut Vec<RenderCommand<A>>, limit: usize) -> usize {
+        let n = self.rx.recv_many(buffer, limit).await;
+
+        let mut backlog = self.backlog.lock().unwrap();
+        if self.rx.is_empty() && !backlog.is_empty() {
+            let m = backlog.len();
+            buffer.extend(backlog.drain(..));
+            n + m
+        } else {
+            n
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::action::Action;
+
+    #[tokio::test]
+    async fn test_overflow_order() {
+        let (tx, mut rx) = render_channel::<NullActionExt>(1);
+        tx.send(RenderCommand::Tick).unwrap();
+        tx.send(RenderCommand::Action(Action::Up(1))).unwrap();
+        tx.send(RenderCommand::Tick).unwrap();
+        tx.send(RenderCommand::Action(Action::Down(1))).unwrap();
+        tx.send(RenderCommand::Tick).unwrap();
+
+        let mut buffer = Vec::new();
+        assert_eq!(rx.recv_many(&mut buffer, 256).await, 4);
+        let received: Vec<String> = buffer.iter().map(|c| format!("{c:?}")).collect();
+        assert_eq!(
+            received,
+            ["Tick", "Action(Up(1))", "Tick", "Action(Down(1))"]
+        );
+
+        // the backlog is drained, so commands go through the channel again
+        tx.send(RenderCommand::Refresh).unwrap();
+        buffer.clear();
+        assert_eq!(rx.recv_many(&mut buffer, 256).await, 1);
+    }
+}
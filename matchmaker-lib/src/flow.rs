@@ -0,0 +1,175 @@
+//! Chaining pickers into multi-step flows.
+//!
+//! A [`Flow`] runs a sequence of steps, each of which usually builds a [`crate::Matchmaker`] from
+//! the result of the previous step and picks from it. Quitting a picker with the flow's back code
+//! ([`crate::MatchError::EXIT_CANCEL`] by default, which `esc` is bound to) returns to the previous step with its input
+//! unchanged, so wizards such as "pick branch → pick commit → pick action" can be written as:
+//!
+//! ```no_run
+//! # use matchmaker::{Result, SharedTerminal, flow::Flow};
+//! # async fn pick_branch(_: (), _: SharedTerminal) -> Result<String> { todo!() }
+//! # async fn pick_commit(_: String, _: SharedTerminal) -> Result<(String, String)> { todo!() }
+//! # async fn run() -> Result<()> {
+//! let (branch, commit) = Flow::new()
+//!     .step(pick_branch)
+//!     .step(pick_commit)
+//!     .run(())
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Each step is also given a [`SharedTerminal`], which its picker should be started on with
+//! [`crate::PickOptions::terminal`]. The pickers then replace one another without leaving the
+//! screen, and keys typed while a step is busy go to the next picker. The terminal is restored
+//! when the flow ends.
+
+use std::{any::Any, future::Future};
+
+use futures::{FutureExt, future::LocalBoxFuture};
+
+use crate::{
+    MatchError, Result, SharedTerminal,
+    action::{ActionExt, NullActionExt},
+};
+
+type BoxedStep<A> =
+    Box<dyn FnMut(&dyn Any, SharedTerminal<A>) -> LocalBoxFuture<'static, Result<Box<dyn Any>>>>;
+
+/// A sequence of steps from `I` to `O`, built with [`Flow::step`].
+pub struct Flow<I, O = I, A: ActionExt = NullActionExt> {
+    steps: Vec<BoxedStep<A>>,
+    back_code: i32,
+    terminal: SharedTerminal<A>,
+    _marker: std::marker::PhantomData<fn(I) -> O>,
+}
+
+impl<I: Clone + 'static> Flow<I> {
+    pub fn new() -> Self {
+        Self::with_terminal(SharedTerminal::new())
+    }
+}
+
+impl<I: Clone + 'static, A: ActionExt> Flow<I, I, A> {
+    /// A flow whose pickers are drawn on `terminal`, which is needed for custom actions.
+    pub fn with_terminal(terminal: SharedTerminal<A>) -> Self {
+        Self {
+            steps: Vec::new(),
+            back_code: MatchError::EXIT_CANCEL,
+            terminal,
+            _marker: std::marker::PhantomData,
+        }
+    }
+}
+
+impl<I: Clone + 'static> Default for Flow<I> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<I: 'static, O: Clone + 'static, A: ActionExt> Flow<I, O, A> {
+    /// Append a step taking the output of the previous step, and the terminal to pick on.
+    ///
+    /// Steps may be run several times when navigating back, so they take their input by value
+    /// from a clone.
+    pub fn step<N, F, Fut>(mut self, mut f: F) -> Flow<I, N, A>
+    where
+        N: Clone + 'static,
+        F: FnMut(O, SharedTerminal<A>) -> Fut + 'static,
+        Fut: Future<Output = Result<N>> + 'static,
+    {
+        self.steps.push(Box::new(move |input: &dyn Any, terminal| {
+            let input = input
+                .downcast_ref::<O>()
+                .expect("flow steps are chained by type")
+                .clone();
+            f(input, terminal)
+                .map(|r| r.map(|n| Box::new(n) as Box<dyn Any>))
+                .boxed_local()
+        }));
+        Flow {
+            steps: self.steps,
+            back_code: self.back_code,
+            terminal: self.terminal,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// The exit code (of [`crate::action::Action::Quit`]) which returns to the previous step.
+    pub fn back_code(mut self, code: i32) -> Self {
+        self.back_code = code;
+        self
+    }
+
+    /// Run the steps in order, returning the output of the last one.
+    ///
+    /// Going back from the first step, and any other error, ends the flow with that error.
+    pub async fn run(mut self, input: I) -> Result<O> {
+        // inputs[i] is the input of step i
+        let mut inputs: Vec<Box<dyn Any>> = vec![Box::new(input)];
+
+        while inputs.len() <= self.steps.len() {
+            let i = inputs.len() - 1;
+            match (self.steps[i])(inputs[i].as_ref(), self.terminal.clone()).await {
+                Ok(output) => inputs.push(output),
+                Err(MatchError::Abort(code)) if code == self.back_code && i > 0 => {
+                    inputs.pop();
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let output = inputs.pop().expect("the flow has an input");
+        Ok(*output
+            .downcast::<O>()
+            .expect("flow steps are chained by type"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_back_navigation() {
+        // the second step goes back once, then succeeds
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let (c1, c2) = (calls.clone(), calls.clone());
+
+        let result = Flow::new()
+            .step(move |n: u32, _| {
+                c1.borrow_mut().push(format!("first {n}"));
+                async move { Ok(n + 1) }
+            })
+            .step(move |n: u32, _| {
+                let back = c2.borrow().len() < 2;
+                c2.borrow_mut().push(format!("second {n}"));
+                async move {
+                    if back {
+                        Err(MatchError::Abort(MatchError::EXIT_CANCEL))
+                    } else {
+                        Ok(n.to_string())
+                    }
+                }
+            })
+            .run(1)
+            .await
+            .unwrap();
+
+        assert_eq!(result, "2");
+        assert_eq!(
+            *calls.borrow(),
+            ["first 1", "second 2", "first 1", "second 2"]
+        );
+
+        // going back from the first step ends the flow
+        let result = Flow::new()
+            .step(|_: u32, _| async { Err::<u32, _>(MatchError::Abort(1)) })
+            .run(0)
+            .await;
+        assert!(matches!(result, Err(MatchError::Abort(1))));
+    }
+}
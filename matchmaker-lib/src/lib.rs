@@ -14,9 +14,10 @@ pub mod render;
 pub mod ui;
 // picker
 pub mod collections;
+pub mod flow;
 pub mod nucleo;
 pub mod preview;
-pub use collections::Selector;
+pub use collections::{Preselect, Selector};
 mod matchmaker;
 pub use matchmaker::*;
 pub mod tui;
@@ -29,3 +30,4 @@ pub use aliases::*;
 pub use errors::*;
 
 pub mod noninteractive;
+pub mod testing;
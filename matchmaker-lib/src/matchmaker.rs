@@ -1,24 +1,42 @@
-use std::fmt::{self, Debug, Formatter};
+use std::{
+    fmt::{self, Debug, Formatter},
+    sync::Arc,
+};
 
 use cba::bath::PathExt;
 use easy_ext::ext;
+use ratatui::layout::Rect;
 
 use crate::{
-    MatchError, Result, SSS, Selector,
+    MatchError, Preselect, Result, SSS, Selector,
     action::{Action, ActionExt, Actions, NullActionExt},
     binds::BindMap,
-    config::{ExitConfig, OverlayConfig, RenderConfig, TerminalConfig},
-    event::{EventLoop, RenderSender},
-    message::{Event, Interrupt},
+    config::{
+        ColumnStyle, ExitConfig, OverlayConfig, RenderConfig, SelectorConfig, StringOrInt,
+        TerminalConfig,
+    },
+    event::{EventLoop, RENDER_CHANNEL_CAPACITY, RenderReceiver, RenderSender, render_channel},
+    message::{AbortHandle, Event, Interrupt, Notifier},
     nucleo::Worker,
     preview::{Preview, previewer::Previewer},
-    render::{self, BoxedHandler, DynamicMethod, EventHandlers, InterruptHandlers, MMState},
-    tui,
-    ui::{Overlay, OverlayUI, UI},
+    render::{
+        self, BoxedHandler, DynamicHandlers, DynamicMethod, EventHandlers, InterruptHandlers,
+        MMState,
+    },
+    testing::Frames,
+    ui::{Overlay, OverlayUI, PickerUI, Source, SourceSet, UI},
 };
 
+mod builder;
+pub use builder::*;
 mod handlers;
 pub use handlers::*;
+mod map;
+pub use map::*;
+mod session;
+pub use session::*;
+mod stream;
+pub use stream::*;
 pub mod config_mm;
 /// A boxed closure that produces the `Vec<S>` result of a pick.
 ///
@@ -32,15 +50,17 @@ pub type AcceptHook<T, D, S> =
 /// 2. Instantiate this with Matchmaker::new(worker, accept_hook)
 /// 3. Register your handlers
 ///    3.5 Start and connect your previewer
-/// 4. Call mm.pick()
+/// 4. Call mm.pick(), or mm.pick_blocking() outside of async code
 pub struct Matchmaker<T: SSS, S, D = ()> {
     pub worker: Worker<T, D>,
     pub render_config: RenderConfig,
     pub tui_config: TerminalConfig,
     pub exit_config: ExitConfig,
+    pub selector_config: SelectorConfig,
     pub output: AcceptHook<T, D, S>,
     pub event_handlers: EventHandlers<T, D>,
     pub interrupt_handlers: InterruptHandlers<T, D>,
+    pub sources: SourceSet<T, D>,
 }
 
 // ----------- MAIN -----------------------
@@ -55,9 +75,11 @@ impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
             render_config: RenderConfig::default(),
             tui_config: TerminalConfig::default(),
             exit_config: ExitConfig::default(),
+            selector_config: SelectorConfig::default(),
             output: Box::new(accept_hook),
             event_handlers: EventHandlers::new(),
             interrupt_handlers: InterruptHandlers::new(),
+            sources: SourceSet::default(),
         }
     }
 
@@ -76,6 +98,11 @@ impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
         self.exit_config = exit;
         self
     }
+    /// Configure selection limits
+    pub fn config_selector(&mut self, selector: SelectorConfig) -> &mut Self {
+        self.selector_config = selector;
+        self
+    }
     /// Register a handler to listen on [`Event`]s
     pub fn register_event_handler<F>(&mut self, event: Event, handler: F)
     where
@@ -109,138 +136,76 @@ impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
         self.interrupt_handlers.set(variant, handler);
     }
 
+    /// Add a worker to switch to with [`Action::NextSource`] or [`Action::Source`], e.g. to pick
+    /// between files, branches and buffers in one picker.
+    pub fn add_source(&mut self, source: Source<T, D>) -> &mut Self {
+        self.sources.push(source);
+        self
+    }
+
     pub fn prepare(&mut self) {
         self.worker.find(&self.render_config.query.initial)
     }
 
     /// The main method of the Matchmaker. It starts listening for events and renders the TUI with ratatui. It successfully returns with all the selected items selected when the Accept action is received.
     pub async fn pick<A: ActionExt>(self, builder: PickOptions<'_, T, D, A>) -> Result<Vec<S>> {
-        let PickOptions {
-            previewer,
-            ext_handler,
-            ext_aliaser,
-            #[cfg(feature = "bracketed-paste")]
-            paste_handler,
-            overlay_config,
-            hidden_columns,
-            initializer,
-            ..
-        } = builder;
-
-        let mut event_loop = if let Some(e) = builder.event_loop {
-            e
-        } else if let Some(binds) = builder.binds {
-            EventLoop::with_binds(binds)
-                .with_tick_rate(self.render_config.ui.tick_rate)
-                .with_mouse_events(self.render_config.ui.mouse_events)
-        } else {
-            EventLoop::new()
-        };
-
-        let mut wait = false;
-        if let Some(path) = self.exit_config.last_key_path.clone()
-            && !path.is_empty()
-        {
-            event_loop.record_last_key(path);
-            wait = true;
-        }
-
-        let preview = match previewer {
-            Some(Either::Left(view)) => Some(view),
-            Some(Either::Right(mut previewer)) => {
-                let view = previewer.view();
-                previewer.connect_controller(event_loop.controller());
-
-                tokio::spawn(async move {
-                    let _ = previewer.run().await;
-                });
-
-                Some(view)
-            }
-            _ => None,
-        };
-
-        let (render_tx, render_rx) = builder
-            .channel
-            .unwrap_or_else(tokio::sync::mpsc::unbounded_channel);
-        event_loop.add_tx(render_tx.clone());
-
-        let mut tui =
-            tui::Tui::new(self.tui_config).map_err(|e| MatchError::TUIError(e.to_string()))?;
-        tui.enter()
-            .map_err(|e| MatchError::TUIError(e.to_string()))?;
-
-        // important to start after tui
-        let event_controller = event_loop.controller();
-        let event_controller_ = event_controller.clone();
-        let bind_controller = event_loop.bind_controller();
-        let event_loop_handle = tokio::spawn(async move {
-            let _ = event_loop.run().await;
-        });
-        log::debug!("event loop started");
-
-        let overlay_ui = if builder.overlays.is_empty() {
-            None
-        } else {
-            Some(OverlayUI::new(
-                builder.overlays.into_boxed_slice(),
-                overlay_config.unwrap_or_default(),
-            ))
-        };
-
-        let matcher = if let Some(matcher) = builder.matcher {
-            matcher
-        } else {
-            &mut nucleo::Matcher::new(nucleo::Config::DEFAULT)
+        let Matchmaker {
+            worker,
+            render_config,
+            tui_config,
+            exit_config,
+            selector_config,
+            output,
+            event_handlers,
+            interrupt_handlers,
+            sources,
+        } = self;
+
+        let mut output = Some(output);
+        let retained = Retained {
+            worker,
+            sources,
+            selector: Selector::from_config(&selector_config),
+            query: None,
+            position: None,
         };
-
-        let (ui, picker, footer, preview) = UI::new(
-            self.render_config,
-            matcher,
-            self.worker,
-            Selector::new(),
-            preview,
-            &mut tui,
-            hidden_columns,
-        );
-
-        // initial redraw to clear artifacts,
-        tui.redraw();
-
-        let ret = render::render_loop(
-            ui,
-            picker,
-            footer,
-            preview,
-            tui,
-            overlay_ui,
-            self.exit_config,
-            render_rx,
-            event_controller,
-            bind_controller,
-            self.output,
-            (self.event_handlers, self.interrupt_handlers),
-            ext_handler,
-            ext_aliaser,
-            initializer,
-            #[cfg(feature = "bracketed-paste")]
-            paste_handler,
+        run(
+            retained,
+            render_config,
+            tui_config,
+            exit_config,
+            &mut (event_handlers, interrupt_handlers),
+            &mut |state| output.take().map(|f| f(state)).unwrap_or_default(),
+            builder,
         )
-        .await;
-
-        log::trace!("render loop finished");
-
-        if wait && event_controller_.send(Event::Resume).is_ok() {
-            let _ = event_loop_handle.await;
-            log::debug!("event loop finished");
-        }
-
-        ret
+        .await
+        .0
     }
 
     pub async fn pick_default(self) -> Result<Vec<S>> {
         self.pick::<NullActionExt>(PickOptions::new()).await
     }
+
+    /// Run [`Matchmaker::pick`] to completion, blocking the current thread, for applications
+    /// which aren't async.
+    ///
+    /// The picker is polled on the current thread, while a runtime of its own drives its timers,
+    /// I/O and tasks on a background thread. This can also be called from within a tokio
+    /// runtime, though the thread is blocked until the picker closes.
+    pub fn pick_blocking<A: ActionExt>(self, options: PickOptions<'_, T, D, A>) -> Result<Vec<S>> {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .map_err(|e| MatchError::TUIError(e.to_string()))?;
+        let ret = {
+            let _guard = runtime.enter();
+            futures::executor::block_on(self.pick(options))
+        };
+        // the event loop and previewer may still be waiting on input
+        runtime.shutdown_background();
+        ret
+    }
 }
 
 impl<T: SSS + Clone, D: 'static> Matchmaker<T, T, D> {
@@ -276,6 +241,291 @@ impl<T> Result<T> {
     }
 }
 
+/// The parts of a picker which outlive an invocation, see [`Session`].
+pub(crate) struct Retained<T: SSS, D> {
+    pub(crate) worker: Worker<T, D>,
+    pub(crate) sources: SourceSet<T, D>,
+    pub(crate) selector: Selector,
+    /// The query and the position of the cursor in it.
+    pub(crate) query: Option<(String, u16)>,
+    pub(crate) position: Option<(u32, u16)>,
+}
+
+/// Run a picker to completion, returning the parts which can be reused.
+pub(crate) async fn run<T: SSS, S, D: 'static, A: ActionExt>(
+    retained: Retained<T, D>,
+    render_config: RenderConfig,
+    tui_config: TerminalConfig,
+    exit_config: ExitConfig,
+    handlers: &mut DynamicHandlers<T, D>,
+    output: &mut dyn FnMut(&mut MMState<'_, '_, T, D>) -> Vec<S>,
+    builder: PickOptions<'_, T, D, A>,
+) -> (Result<Vec<S>>, Retained<T, D>) {
+    let PickOptions {
+        previewer,
+        ext_handler,
+        ext_aliaser,
+        #[cfg(feature = "bracketed-paste")]
+        paste_handler,
+        overlay_config,
+        mut hidden_columns,
+        column_styles,
+        mut preselect,
+        panes,
+        initializer,
+        restore,
+        on_close,
+        ..
+    } = builder;
+
+    let headless = builder.headless.is_some();
+    let shared = builder.terminal.filter(|_| !headless);
+    let mut wait = false;
+    let held = match shared.as_ref().and_then(SharedTerminal::take) {
+        Some(held) => Ok(held),
+        None => {
+            let mut event_loop = if let Some(e) = builder.event_loop {
+                e
+            } else if let Some(binds) = builder.binds {
+                EventLoop::with_binds(binds)
+                    .with_tick_rate(render_config.ui.tick_rate)
+                    .with_mouse_events(render_config.ui.mouse_events)
+            } else {
+                EventLoop::new()
+            };
+
+            if let Some(path) = exit_config.last_key_path.clone()
+                && !path.is_empty()
+                && shared.is_none()
+            {
+                event_loop.record_last_key(path);
+                wait = true;
+            }
+
+            // a shared terminal may show a preview in a later picker
+            #[cfg(feature = "image")]
+            let query_images = previewer.is_some() || shared.is_some();
+            Held::start(
+                event_loop,
+                builder.channel,
+                tui_config,
+                builder.headless,
+                #[cfg(feature = "image")]
+                query_images,
+            )
+        }
+    };
+    let mut held = match held {
+        Ok(held) => held,
+        Err(e) => return (Err(MatchError::TUIError(e.to_string())), retained),
+    };
+    held.tui.shared = shared.is_some();
+
+    let preview = match previewer {
+        Some(Either::Left(view)) => Some(view),
+        Some(Either::Right(mut previewer)) => {
+            let view = previewer.view();
+            previewer.connect_controller(held.controller.clone());
+            previewer.connect_notifier(Notifier::new(held.render_tx.clone()));
+            #[cfg(feature = "pty")]
+            held.pty.store(Some(Arc::new((
+                previewer.pty(),
+                previewer.config.unfocus_key,
+            ))));
+
+            tokio::spawn(async move {
+                let _ = previewer.run().await;
+            });
+
+            Some(view)
+        }
+        _ => None,
+    };
+
+    let event_controller = held.controller.clone();
+    let bind_controller = held.bind_controller.clone();
+    let ticks = held.ticks.clone();
+    let binds = held.binds.load_full();
+
+    let overlay_ui = if builder.overlays.is_empty() {
+        None
+    } else {
+        Some(OverlayUI::new(
+            builder.overlays.into_boxed_slice(),
+            overlay_config.unwrap_or_default(),
+        ))
+    };
+
+    let matcher = if let Some(matcher) = builder.matcher {
+        matcher
+    } else {
+        &mut nucleo::Matcher::new(nucleo::Config::DEFAULT)
+    };
+
+    let Retained {
+        worker,
+        sources,
+        mut selector,
+        mut query,
+        mut position,
+    } = retained;
+    let mut preview_layout = None;
+    if let Some(session) = restore {
+        query = Some((session.query, session.query_cursor));
+        position = Some(session.position);
+        preselect.extend(
+            session
+                .selected
+                .into_iter()
+                .map(|text| Preselect::Column(0, text)),
+        );
+        hidden_columns.extend(session.hidden_columns);
+        preview_layout = session.preview_layout;
+    }
+    selector.preselect(preselect);
+    let mut pane_matchers: Vec<_> = panes
+        .iter()
+        .map(|_| nucleo::Matcher::new(nucleo::Config::DEFAULT))
+        .collect();
+    let pane_selectors: Vec<_> = panes.iter().map(|_| selector.empty_like()).collect();
+    let (ui, mut picker, mut footer, mut preview) = UI::new(
+        render_config,
+        matcher,
+        worker,
+        selector,
+        preview,
+        &mut held.tui,
+        hidden_columns,
+    );
+    picker.sources = sources;
+    picker.results.set_column_styles(column_styles);
+    picker.header.set_buttons(&*binds);
+    footer.set_buttons(&*binds);
+    let mut panes: Vec<_> = panes
+        .into_iter()
+        .zip(pane_matchers.iter_mut())
+        .zip(pane_selectors)
+        .map(|((worker, matcher), selector)| {
+            let mut pane = PickerUI::new(
+                picker.results.config.clone(),
+                picker.status.status_config.clone(),
+                picker.query.config.clone(),
+                picker.header.config.clone(),
+                matcher,
+                worker,
+                selector,
+            );
+            pane.header.set_buttons(&*binds);
+            pane
+        })
+        .collect();
+    let mut focus = 0;
+    if let Some((query, cursor)) = query {
+        picker.query.set(query, cursor);
+    }
+    if let Some(position) = position {
+        picker.results.set_position(position);
+    }
+    if let Some(p) = preview.as_mut()
+        && let Some(idx) = preview_layout
+    {
+        p.set_layout(idx);
+    }
+    #[cfg(feature = "image")]
+    if let Some(p) = preview.as_mut()
+        && let Some(picker) = held.image_picker.clone()
+    {
+        p.set_image_picker(picker);
+    }
+
+    // initial redraw to clear artifacts,
+    held.tui.redraw();
+
+    let ret = render::render_loop(
+        ui,
+        &mut picker,
+        &mut panes,
+        &mut focus,
+        footer,
+        &mut preview,
+        &mut held.tui,
+        overlay_ui,
+        exit_config,
+        &mut held.render_rx,
+        event_controller,
+        bind_controller,
+        ticks.clone(),
+        output,
+        handlers,
+        ext_handler,
+        ext_aliaser,
+        initializer,
+        #[cfg(feature = "bracketed-paste")]
+        paste_handler,
+    )
+    .await;
+
+    log::trace!("render loop finished");
+    let controller = held.controller.clone();
+    let event_loop_handle = match &shared {
+        Some(terminal) => {
+            terminal.put(held);
+            None
+        }
+        None => {
+            let handle = held.handle.take();
+            drop(held);
+            handle
+        }
+    };
+    // the main picker is retained
+    render::focus_pane(&mut picker, &mut panes, focus, 0);
+    log::debug!("Dropped {} ticks", ticks.dropped());
+
+    if wait
+        && let Some(handle) = event_loop_handle
+        && controller.send(Event::Resume).is_ok()
+    {
+        let _ = handle.await;
+        log::debug!("event loop finished");
+    }
+
+    if let Some(handler) = on_close {
+        handler(SavedSession {
+            query: picker.query.input.clone(),
+            query_cursor: picker.query.cursor(),
+            position: picker.results.position(),
+            selected: picker
+                .selector
+                .iter()
+                .filter_map(|&idx| {
+                    let item = picker.worker.get_by_idx(idx)?;
+                    let text = picker.worker.format_with(item, &StringOrInt::Int(0))?;
+                    Some(text.into_owned())
+                })
+                .collect(),
+            preview_layout: preview.as_ref().map(|p| p.layout_index()),
+            hidden_columns: picker
+                .results
+                .hidden_cols()
+                .mask()
+                .iter()
+                .enumerate()
+                .filter_map(|(i, &hidden)| hidden.then_some(i))
+                .collect(),
+        });
+    }
+
+    let retained = Retained {
+        query: Some((picker.query.input.clone(), picker.query.cursor())),
+        position: Some(picker.results.position()),
+        worker: picker.worker,
+        sources: picker.sources,
+        selector: picker.selector,
+    };
+    (ret, retained)
+}
+
 // --------- BUILDER -------------
 
 /// Returns what should be pushed to input
@@ -308,13 +558,18 @@ pub struct PickOptions<'a, T: SSS, D, A: ActionExt = NullActionExt> {
     previewer: Option<Either<Preview, Previewer>>,
 
     hidden_columns: Vec<usize>,
+    column_styles: Vec<ColumnStyle>,
+    preselect: Vec<Preselect>,
+    panes: Vec<Worker<T, D>>,
 
     // Initializing code, i.e. to setup state.
     initializer: Option<Initializer<T, D>>,
-    pub channel: Option<(
-        RenderSender<A>,
-        tokio::sync::mpsc::UnboundedReceiver<crate::message::RenderCommand<A>>,
-    )>,
+    restore: Option<SavedSession>,
+    on_close: Option<SessionSaver>,
+    terminal: Option<SharedTerminal<A>>,
+    // Set by a TestHarness to draw into its buffers instead of the terminal.
+    pub(crate) headless: Option<(Rect, Arc<Frames>)>,
+    pub channel: Option<(RenderSender<A>, RenderReceiver<A>)>,
 }
 
 impl<'a, T: SSS, D, A: ActionExt> PickOptions<'a, T, D, A> {
@@ -333,7 +588,14 @@ impl<'a, T: SSS, D, A: ActionExt> PickOptions<'a, T, D, A> {
             overlays: Vec::new(),
             channel: None,
             hidden_columns: vec![],
+            column_styles: vec![],
+            preselect: vec![],
+            panes: vec![],
             initializer: None,
+            restore: None,
+            on_close: None,
+            terminal: None,
+            headless: None,
         }
     }
 
@@ -384,6 +646,29 @@ impl<'a, T: SSS, D, A: ActionExt> PickOptions<'a, T, D, A> {
         self
     }
 
+    /// Items to select once they are read. See [`Selector::preselect`].
+    pub fn preselect(mut self, keys: impl IntoIterator<Item = Preselect>) -> Self {
+        self.preselect.extend(keys);
+        self
+    }
+
+    /// Show another picker over the items of `worker` beside the main one, e.g. to pick a source
+    /// and a destination. Keys go to the focused picker, which [`Action::FocusNext`] moves
+    /// through the pickers in turn.
+    ///
+    /// The accept hook is called with the focused picker as [`MMState::picker_ui`] and the
+    /// others as [`MMState::panes`].
+    pub fn pane(mut self, worker: Worker<T, D>) -> Self {
+        self.panes.push(worker);
+        self
+    }
+
+    /// Set the cell style of each column, in column order.
+    pub fn column_styles(mut self, column_styles: Vec<ColumnStyle>) -> Self {
+        self.column_styles = column_styles;
+        self
+    }
+
     pub fn ext_handler<F>(mut self, handler: F) -> Self
     where
         F: FnMut(A, &mut MMState<'_, '_, T, D>) + Send + Sync + 'static,
@@ -408,6 +693,29 @@ impl<'a, T: SSS, D, A: ActionExt> PickOptions<'a, T, D, A> {
         self
     }
 
+    /// Continue from a [`SavedSession`], with its query, position, selections, preview layout
+    /// and hidden columns.
+    pub fn restore(mut self, session: SavedSession) -> Self {
+        self.restore = Some(session);
+        self
+    }
+
+    /// Called with the state of the picker when it is closed, to [`Self::restore`] it later.
+    pub fn on_close<F>(mut self, handler: F) -> Self
+    where
+        F: FnOnce(SavedSession) + Send + Sync + 'static,
+    {
+        self.on_close = Some(Box::new(handler));
+        self
+    }
+
+    /// Draw on a terminal shared with other pickers, which is left on the screen when this one
+    /// closes. Once the terminal is started, the binds and event loop given here are not used.
+    pub fn terminal(mut self, terminal: SharedTerminal<A>) -> Self {
+        self.terminal = Some(terminal);
+        self
+    }
+
     #[cfg(feature = "bracketed-paste")]
     pub fn paste_handler<F>(mut self, handler: F) -> Self
     where
@@ -434,12 +742,17 @@ impl<'a, T: SSS, D, A: ActionExt> PickOptions<'a, T, D, A> {
         if let Some((s, _)) = &self.channel {
             s.clone()
         } else {
-            let channel = tokio::sync::mpsc::unbounded_channel();
+            let channel = render_channel(RENDER_CHANNEL_CAPACITY);
             let ret = channel.0.clone();
             self.channel = Some(channel);
             ret
         }
     }
+
+    /// A handle to close the picker from another task, e.g. when the host application shuts down.
+    pub fn abort_handle(&mut self) -> AbortHandle {
+        AbortHandle::new(self.render_tx())
+    }
 }
 
 impl<'a, T: SSS, D, A: ActionExt> Default for PickOptions<'a, T, D, A> {
@@ -456,9 +769,11 @@ impl<T: SSS, S, D> Debug for Matchmaker<T, S, D> {
             .field("render_config", &self.render_config)
             .field("tui_config", &self.tui_config)
             .field("exit_config", &self.exit_config)
+            .field("selector_config", &self.selector_config)
             .field("accept_hook", &"<accept_hook>")
             .field("event_handlers", &self.event_handlers)
             .field("interrupt_handlers", &self.interrupt_handlers)
+            .field("sources", &self.sources.len())
             .finish()
     }
 }
@@ -0,0 +1,207 @@
+use std::{borrow::Cow, sync::Arc};
+
+use regex::Regex;
+
+use crate::{
+    Either, MatchResultExt, Matchmaker, PickOptions, Result,
+    binds::{BindMap, display_help},
+    config::{
+        ColumnsConfig, ExitConfig, PreprocessConfig, PreviewSetting, PreviewerConfig, RenderConfig,
+        SelectionMode, SelectorConfig, Split, TerminalConfig, WorkerConfig,
+    },
+    config_mm::{ConfigMatchmaker, ConfigPreprocessedData, OddEnds},
+    event::EventLoop,
+    make_previewer,
+    render::MMState,
+};
+
+impl ConfigMatchmaker {
+    /// A builder for the common case of picking from a list of strings.
+    ///
+    /// ```no_run
+    /// # async fn run() -> matchmaker::Result<()> {
+    /// use matchmaker::Matchmaker;
+    ///
+    /// let picked = Matchmaker::builder()
+    ///     .items(["src/main.rs", "src/lib.rs"])
+    ///     .multi(true)
+    ///     .preview_cmd("bat {}")
+    ///     .pick()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> MatchmakerBuilder {
+        MatchmakerBuilder::default()
+    }
+}
+
+/// Assembles the worker, selector, binds and previewer of a [`ConfigMatchmaker`] over lines of
+/// text, which are returned as they were given when accepted.
+///
+/// Created with [`Matchmaker::builder`]. Options without a dedicated method can be set on the
+/// config structs, as in [`Matchmaker::new_from_config`].
+#[derive(Default)]
+pub struct MatchmakerBuilder {
+    items: Vec<String>,
+    pub render_config: RenderConfig,
+    pub tui_config: TerminalConfig,
+    pub worker_config: WorkerConfig,
+    pub columns_config: ColumnsConfig,
+    pub exit_config: ExitConfig,
+    pub preprocess_config: PreprocessConfig,
+    pub selector_config: SelectorConfig,
+    pub previewer_config: PreviewerConfig,
+    binds: BindMap,
+}
+
+impl MatchmakerBuilder {
+    /// Add items to pick from.
+    pub fn items<I>(mut self, items: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<String>,
+    {
+        self.items.extend(items.into_iter().map(Into::into));
+        self
+    }
+
+    /// Split items into columns on `delimiter`, which is matched literally.
+    ///
+    /// The columns are named from 1, and the first is matched against by default.
+    pub fn columns(mut self, delimiter: &str) -> Self {
+        let regex = Regex::new(&regex::escape(delimiter)).expect("escaped regex is valid");
+        self.columns_config.split = Split::Delimiter(regex);
+        self
+    }
+
+    /// Whether several items can be selected.
+    pub fn multi(mut self, multi: bool) -> Self {
+        self.selector_config.mode = if multi {
+            SelectionMode::Multi
+        } else {
+            SelectionMode::Single
+        };
+        self
+    }
+
+    /// Preview the current item with the output of a command.
+    ///
+    /// `{}` is replaced by the quoted item, and `{name}` by the column called `name`.
+    pub fn preview_cmd(mut self, command: impl Into<String>) -> Self {
+        self.render_config.preview.layout = vec![PreviewSetting {
+            command: command.into(),
+            ..Default::default()
+        }];
+        self
+    }
+
+    /// The initial query.
+    pub fn query(mut self, query: impl Into<String>) -> Self {
+        self.render_config.query.initial = query.into();
+        self
+    }
+
+    /// Add binds, overriding the defaults for the same triggers.
+    pub fn binds(mut self, binds: BindMap) -> Self {
+        self.binds.extend(binds);
+        self
+    }
+
+    /// Build the [`Matchmaker`], and the [`PickOptions`] to pass to [`Matchmaker::pick`].
+    pub fn build(
+        self,
+    ) -> (
+        ConfigMatchmaker,
+        PickOptions<'static, String, ConfigPreprocessedData>,
+    ) {
+        let has_preview = !self.render_config.preview.layout.is_empty();
+
+        let mut binds = BindMap::default_binds();
+        binds.extend(self.binds);
+        let event_loop = EventLoop::with_binds(binds)
+            .with_tick_rate(self.render_config.ui.tick_rate)
+            .with_mouse_events(self.render_config.ui.mouse_events);
+
+        let (
+            mut mm,
+            _,
+            OddEnds {
+                hidden_columns,
+                column_styles,
+                ..
+            },
+        ) = Matchmaker::new_from_config(
+            self.render_config,
+            self.tui_config,
+            self.worker_config,
+            self.columns_config,
+            self.exit_config,
+            self.preprocess_config,
+        );
+        mm.config_selector(self.selector_config);
+        mm.output = Box::new(
+            |state: &mut MMState<'_, '_, String, ConfigPreprocessedData>| {
+                state.map_selected_to_vec(|_, item| item.clone())
+            },
+        );
+        mm.worker.append(self.items);
+
+        let mut options = PickOptions::new()
+            .hidden_columns(hidden_columns)
+            .column_styles(column_styles);
+
+        if has_preview {
+            let formatter = Either::Left(Arc::new(
+                mm.worker
+                    .default_format_fn::<true>(|item: &String| Cow::Borrowed(item.as_str())),
+            ));
+            let binds_ptr = event_loop.get_binds_ptr();
+            let previewer = make_previewer(
+                &mut mm,
+                self.previewer_config,
+                formatter,
+                Box::new(move |config| display_help(&binds_ptr.load(), config)),
+            );
+            options = options.previewer(previewer);
+        }
+
+        (mm, options.event_loop(event_loop))
+    }
+
+    /// Build and run the picker, returning the accepted items.
+    pub async fn pick(self) -> Result<Vec<String>> {
+        let (mm, options) = self.build();
+        mm.pick(options).await
+    }
+
+    /// Build and run the picker without an async runtime. See [`Matchmaker::pick_blocking`].
+    pub fn pick_blocking(self) -> Result<Vec<String>> {
+        let (mm, options) = self.build();
+        mm.pick_blocking(options)
+    }
+
+    /// Build and run the picker, returning the first accepted item.
+    pub async fn pick_one(self) -> Result<String> {
+        self.pick().await.first()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build() {
+        let (mm, _) = Matchmaker::builder()
+            .items(["a\t1", "b\t2"])
+            .columns("\t")
+            .multi(false)
+            .preview_cmd("echo {2}")
+            .build();
+
+        assert_eq!(mm.selector_config.mode, SelectionMode::Single);
+        assert_eq!(&*mm.worker.columns[1].name, "2");
+        assert_eq!(mm.render_config.preview.layout[0].command, "echo {2}");
+    }
+}
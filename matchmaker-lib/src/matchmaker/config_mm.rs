@@ -1,12 +1,15 @@
 use crate::{
     AcceptHook, Matchmaker,
     config::{
-        ColumnsConfig, ExitConfig, PreprocessConfig, RenderConfig, StringOrInt, TerminalConfig,
-        WorkerConfig,
+        ColumnStyle, ColumnsConfig, ExitConfig, PreprocessConfig, RenderConfig, StringOrInt,
+        TerminalConfig, WorkerConfig,
     },
     nucleo::{Column, Worker, injector::WorkerInjector},
     render::{EventHandlers, InterruptHandlers, MMState},
-    utils::text::{self, sanitize_string},
+    utils::{
+        hyperlink::strip_hyperlinks,
+        text::{self, sanitize_string},
+    },
 };
 
 use ansi_to_tui::IntoText;
@@ -15,6 +18,7 @@ use std::{borrow::Cow, sync::Arc};
 
 pub struct OddEnds {
     pub hidden_columns: Vec<usize>,
+    pub column_styles: Vec<ColumnStyle>,
     pub has_error: bool,
     /// Factory producing per-column range lookups. Given a column index `n`,
     /// returns a closure that maps an input `&String` to the `(start, end)`
@@ -53,17 +57,23 @@ impl ConfigMatchmaker {
         let mut has_error = false;
 
         let cc = columns_config;
-        let hidden_columns = cc
-            .names
-            .iter()
-            .enumerate()
-            .filter_map(|(i, x)| x.hidden.then_some(i))
-            .collect();
+        let column_styles = cc.names.iter().map(|x| x.style).collect();
 
         // Build columns (also builds and truncates column_names internally).
         let (columns, raw_preprocessor, text_preprocessor, ranges_fn) =
             build_columns(&cc, preprocess_config);
 
+        let offset = !cc.names_from_zero as usize;
+        let hidden_columns = (0..columns.len())
+            .filter(|&i| {
+                cc.names.get(i).is_some_and(|x| x.hidden)
+                    || cc
+                        .with_nth
+                        .as_ref()
+                        .is_some_and(|f| !f.contains(i, columns.len(), offset))
+            })
+            .collect();
+
         // Resolve default column from the names attached to the built columns.
         let default_index = default_column(&cc, &columns);
 
@@ -71,6 +81,8 @@ impl ConfigMatchmaker {
 
         worker.reverse_items(worker_config.reverse);
         worker.set_stability(*worker_config.sort_threshold);
+        worker.set_tiebreak(&worker_config.tiebreak);
+        worker.disable_matching(worker_config.disabled);
         for (i, c) in cc.names.iter().enumerate() {
             worker.set_column_options(i, c.options)
         }
@@ -95,14 +107,17 @@ impl ConfigMatchmaker {
             render_config,
             tui_config,
             exit_config,
+            selector_config: Default::default(),
             output: accept_hook,
             event_handlers,
             interrupt_handlers,
+            sources: Default::default(),
         };
         new.prepare();
 
         let misc = OddEnds {
             hidden_columns,
+            column_styles,
             has_error,
             ranges_fn,
         };
@@ -375,7 +390,7 @@ pub fn build_columns(
             };
 
             let (plain, ranges) = if ansi {
-                let plain = s.as_bytes().into_text().ok()?.to_string();
+                let plain = strip_hyperlinks(&s).into_text().ok()?.to_string();
                 let ranges = split_fn(&plain);
                 (plain, ranges)
             } else {
@@ -412,7 +427,8 @@ pub fn build_columns(
             };
 
             if ansi {
-                match s.as_bytes().into_text() {
+                // hyperlinks are only kept clickable in the preview, so they are shown as text
+                match strip_hyperlinks(&s).into_text() {
                     Ok(mut text) => {
                         text::scrub_text_styles(&mut text);
                         let plain = text.to_string();
@@ -451,8 +467,7 @@ pub fn build_columns(
         move |item: &String| {
             let s: &str = if trim { item.trim() } else { item.as_str() };
             if ansi {
-                let plain = s
-                    .as_bytes()
+                let plain = strip_hyperlinks(s)
                     .into_text()
                     .map(|text| text.to_string())
                     .unwrap_or_else(|_| s.to_string());
@@ -524,6 +539,26 @@ pub fn build_columns(
         })
         .collect();
 
+    // Restrict matching to the columns given by `nth`, unless that would leave none.
+    let columns = match &cc.nth {
+        Some(nth) if (0..col_count).any(|i| nth.contains(i, col_count, offset)) => columns
+            .into_iter()
+            .enumerate()
+            .map(|(i, c)| {
+                if nth.contains(i, col_count, offset) {
+                    c
+                } else {
+                    c.without_filtering()
+                }
+            })
+            .collect(),
+        Some(nth) => {
+            cba::wbog!("columns.nth ({nth}) matches no columns, matching on all columns.");
+            columns
+        }
+        None => columns,
+    };
+
     (columns, raw_preprocessor, text_preprocessor, ranges_fn)
 }
 
@@ -763,18 +798,21 @@ mod tests {
                 ignore: false,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
             ColumnSetting {
                 name: "b".to_string().into(),
                 ignore: false,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
             ColumnSetting {
                 name: "c".to_string().into(),
                 ignore: false,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
         ];
         let (_columns, _raw_pp, text_pp, _) = build_columns(&cc, options);
@@ -829,18 +867,21 @@ mod tests {
                 ignore: false,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
             ColumnSetting {
                 name: "b".to_string().into(),
                 ignore: false,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
             ColumnSetting {
                 name: "c".to_string().into(),
                 ignore: false,
                 hidden: false,
                 options: Default::default(),
+                style: Default::default(),
             },
         ];
         cc
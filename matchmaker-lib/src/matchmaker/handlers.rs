@@ -1,14 +1,24 @@
 use std::{
+    cell::RefCell,
     env,
     fs::OpenOptions,
     io::{self, Write},
+    path::PathBuf,
     process::{Command, Stdio},
-    sync::Arc,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
 };
 
-use cba::{_info, bait::ResultExt, broc::CommandExt, define_either, env_vars};
+use cba::{
+    _info,
+    bait::ResultExt,
+    broc::{CommandExt, EnvVars},
+    define_either, env_vars,
+};
 use log::{debug, info, warn};
-use ratatui::text::Text;
+use ratatui::{layout::Rect, text::Text};
 use tokio::io::AsyncReadExt;
 
 use crate::{
@@ -132,6 +142,10 @@ impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
 
         let formatter_2 = formatter.clone();
         self.register_interrupt_handler(Interrupt::ExecuteSilent, move |state| {
+            // handled by the copy handler
+            if state.discriminant_payload.is_some() {
+                return;
+            }
             let template = state.payload();
             if !template.is_empty() {
                 let cmd = use_formatter(&formatter_2, state, template, None);
@@ -147,19 +161,22 @@ impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
                 );
                 vars.extend(extra);
 
-                if let Some(mut _child) = Command::from_script(&cmd)
+                // output would be drawn over the picker
+                if let Some(mut child) = Command::from_script(&cmd)
                     .envs(vars)
-                    .stdin(maybe_tty())
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
                     ._spawn()
                 {
-                    // match child.wait() {
-                    //     Ok(i) => {
-                    //         info!("Command [{cmd}] exited with {i}")
-                    //     }
-                    //     Err(e) => {
-                    //         info!("Failed to wait on command [{cmd}]: {e}")
-                    //     }
-                    // }
+                    std::thread::spawn(move || match child.wait() {
+                        Ok(i) => {
+                            info!("Command [{cmd}] exited with {i}")
+                        }
+                        Err(e) => {
+                            info!("Failed to wait on command [{cmd}]: {e}")
+                        }
+                    });
                 }
             };
         });
@@ -448,28 +465,48 @@ pub fn make_previewer<T: SSS, S, D: 'static>(
 
     let help_config = previewer_config.help.clone();
 
+    // the last command sent, so that selection changes only rerun commands which include them
+    let last_cmd = RefCell::new(String::new());
+
     // preview handler
     // important that PreviewSet events don't accidentally trigger this!
-    mm.register_event_handler(Event::CursorChange | Event::PreviewChange | Event::Synced, move |state, _| {
+    mm.register_event_handler(Event::CursorChange | Event::PreviewChange | Event::Synced | Event::SelectionChange, move |state, _| {
             // don't clobber previewset events
             if state.contains(Event::PreviewSet) {
                 // code logic-wise, recieve PreviewSet::None semantically => will recieve PreviewMessage::Unset => we should skip anyways (events is immutable), altho semantically such a state should actually trigger a new preview tho it would be niche
                 return;
             }
+            let selection_only = !state.events.intersects(Event::CursorChange | Event::PreviewChange | Event::Synced);
+            if selection_only && !state.preview_visible() {
+                return;
+            }
+
+            if state.preview_visible() {
+                let title = preview_title(&formatter, state);
+                if let Some(p) = state.preview_ui {
+                    p.set_title(title);
+                }
+            }
 
             if state.preview_visible() &&
             let m = state.preview_payload().clone() &&
             let cmd = use_formatter(&formatter, state, &m, None) &&
             !cmd.is_empty()
             {
+                if selection_only && *last_cmd.borrow() == cmd {
+                    return;
+                }
+                last_cmd.replace(cmd.clone());
+
                 let mut envs = state.make_env_vars();
                 let extra = env_vars!(
                     "COLUMNS" => state.previewer_area().map_or("0".to_string(), |r| r.width.to_string()),
                     "LINES" => state.previewer_area().map_or("0".to_string(), |r| r.height.to_string()),
                 );
                 envs.extend(extra);
+                let cwd = preview_context(&formatter, state, &mut envs);
 
-                let msg = PreviewMessage::Run(cmd.clone(), envs);
+                let msg = PreviewMessage::Run(cmd.clone(), envs, cwd);
                 if preview_tx.send(msg.clone()).is_err() {
                     warn!("Failed to send to preview: {}", msg)
                 }
@@ -520,7 +557,8 @@ pub fn make_previewer<T: SSS, S, D: 'static>(
                             "LINES" => state.previewer_area().map_or("0".to_string(), |r| r.height.to_string()),
                         );
                         envs.extend(extra);
-                        PreviewMessage::Run(cmd, envs)
+                        let cwd = preview_context(&formatter_clone, state, &mut envs);
+                        PreviewMessage::Run(cmd, envs, cwd)
                     }
                 }
             };
@@ -534,6 +572,106 @@ pub fn make_previewer<T: SSS, S, D: 'static>(
     previewer
 }
 
+impl Previewer {
+    /// A previewer which displays the text returned by `f` for the active result and the area
+    /// of the preview, instead of the output of a command.
+    ///
+    /// `f` is called on a blocking thread whenever the active result changes, so it may take its
+    /// time, such as to query a database: its text is discarded if the cursor moved meanwhile.
+    /// Connect the previewer with [`PickOptions::previewer`](crate::PickOptions::previewer).
+    pub fn from_fn<T: SSS + Clone, S, D: 'static>(
+        mm: &mut Matchmaker<T, S, D>,
+        config: PreviewerConfig,
+        f: impl Fn(&T, Rect) -> Text<'static> + Send + Sync + 'static,
+    ) -> Self {
+        let (previewer, tx) = Previewer::new(config);
+        let f = Arc::new(f);
+        // incremented on each change, so that stale text is not displayed
+        let generation = Arc::new(AtomicU64::new(0));
+
+        mm.register_event_handler(
+            Event::CursorChange | Event::PreviewChange | Event::Synced,
+            move |state, _| {
+                let current = generation.fetch_add(1, Ordering::AcqRel) + 1;
+
+                let (true, Some(item), Some(area)) = (
+                    state.preview_visible(),
+                    state.current_raw().cloned(),
+                    state.previewer_area(),
+                ) else {
+                    let _ = tx.send(PreviewMessage::Unset);
+                    return;
+                };
+                if let Some(p) = state.preview_ui {
+                    p.set_target(None);
+                    p.jump = Default::default();
+                }
+
+                let (f, tx, generation) = (f.clone(), tx.clone(), generation.clone());
+                tokio::task::spawn_blocking(move || {
+                    let text = f(&item, area);
+                    if generation.load(Ordering::Acquire) == current {
+                        let _ = tx.send(PreviewMessage::Set(text));
+                    }
+                });
+            },
+        );
+
+        previewer
+    }
+}
+
+/// Expand the placeholders in the border title of the active preview layout, without quoting
+/// them. `{total}` is the number of items.
+/// None if the title has no placeholders.
+fn preview_title<T: SSS, D: 'static>(
+    formatter: &AttachmentFormatter<T, D>,
+    state: &MMState<'_, '_, T, D>,
+) -> Option<String> {
+    let template = &state.preview_ui.as_ref()?.border().title;
+    if !template.contains('{') {
+        return None;
+    }
+    let template = template.replace("{total}", &state.status().item_count.to_string());
+
+    // titles aren't passed to a shell: {key} -> {=key} and {+key} -> {-key}
+    let mut raw = String::with_capacity(template.len() + 4);
+    let mut escaped = false;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        raw.push(c);
+        if c == '{' && !escaped {
+            match chars.peek() {
+                Some('+') => {
+                    chars.next();
+                    raw.push('-');
+                }
+                Some('{' | '=' | '-' | '_') | None => {}
+                Some(_) => raw.push('='),
+            }
+        }
+        escaped = c == '\\';
+    }
+
+    Some(use_formatter(formatter, state, &raw, None))
+}
+
+/// Add the environment variables of the active preview layout, and return its working directory.
+fn preview_context<T: SSS, D: 'static>(
+    formatter: &AttachmentFormatter<T, D>,
+    state: &MMState<'_, '_, T, D>,
+    envs: &mut EnvVars,
+) -> Option<PathBuf> {
+    let setting = state.preview_ui.as_ref()?.setting()?;
+    envs.extend(setting.envs.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    if setting.cwd.is_empty() {
+        return None;
+    }
+    let cwd = use_formatter(formatter, state, &setting.cwd, None);
+    (!cwd.is_empty()).then(|| cwd.into())
+}
+
 // ----------------------------
 
 fn maybe_tty() -> Stdio {
@@ -0,0 +1,87 @@
+use std::{borrow::Cow, collections::BTreeMap, fmt::Display, sync::Arc};
+
+use ratatui::text::Text;
+
+use crate::{
+    Matchmaker, SSS,
+    config::PreviewerConfig,
+    message::Event,
+    nucleo::{Column, Worker},
+    preview::previewer::{PreviewMessage, Previewer},
+};
+
+/// An entry of a map picker: the key, and the [`Display`] representation of its value.
+pub type MapEntry<K> = (K, String);
+
+impl<K: Clone + Display + SSS> Matchmaker<MapEntry<K>, K> {
+    /// A picker over the entries of a map, returning the selected keys.
+    ///
+    /// The `key` and `value` columns display the entries by their [`Display`] representations.
+    /// Both are matched against, the keys by default.
+    pub fn from_map<V: Display>(map: BTreeMap<K, V>) -> Self {
+        let worker = Worker::new(
+            [
+                Column::new("key", |(k, _): &MapEntry<K>, _: &()| {
+                    Text::from(k.to_string())
+                })
+                .with_raw(|(k, _): &MapEntry<K>, _: &()| Cow::Owned(k.to_string())),
+                Column::new("value", |(_, v): &MapEntry<K>, _: &()| {
+                    Text::from(v.as_str())
+                })
+                .with_raw(|(_, v): &MapEntry<K>, _: &()| Cow::Borrowed(v.as_str())),
+            ],
+            0,
+            Arc::new(|_: &MapEntry<K>| Some(())),
+            Arc::new(|_: &MapEntry<K>| ()),
+        );
+        worker.append(map.into_iter().map(|(k, v)| (k, v.to_string())));
+
+        Self::new(worker, |state| {
+            state.map_selected_to_vec(|_, (k, _)| k.clone())
+        })
+    }
+
+    /// Show the value of the current entry in the preview.
+    ///
+    /// The returned previewer is passed to [`crate::PickOptions::previewer`]. A default preview
+    /// layout is added if none is configured.
+    pub fn value_previewer(&mut self) -> Previewer {
+        if self.render_config.preview.layout.is_empty() {
+            self.render_config.preview.layout.push(Default::default());
+        }
+
+        let (previewer, tx) = Previewer::new(PreviewerConfig::default());
+        self.register_event_handler(Event::CursorChange | Event::Synced, move |state, _| {
+            let value = state
+                .current_raw()
+                .map(|(_, v)| v.clone())
+                .unwrap_or_default();
+            let _ = tx.send(PreviewMessage::Set(Text::from(value)));
+        });
+        previewer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::StringOrInt;
+
+    #[test]
+    fn test_from_map() {
+        let map = BTreeMap::from([(1, 'a'), (2, 'b')]);
+        let mm = Matchmaker::from_map(map);
+
+        let names: Vec<_> = mm.worker.columns.iter().map(|c| c.name.clone()).collect();
+        assert_eq!(names, [Arc::from("key"), Arc::from("value")]);
+
+        let entry = (2, "b".to_string());
+        let format = |col: &str| {
+            mm.worker
+                .format_with(&entry, &StringOrInt::String(col.into()))
+                .map(Cow::into_owned)
+        };
+        assert_eq!(format("key").as_deref(), Some("2"));
+        assert_eq!(format("value").as_deref(), Some("b"));
+    }
+}
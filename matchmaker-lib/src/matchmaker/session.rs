@@ -0,0 +1,255 @@
+use std::sync::{Arc, Mutex};
+
+use arc_swap::ArcSwap;
+use ratatui::{backend::CrosstermBackend, layout::Rect};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::{
+    Matchmaker, PickOptions, Result, SSS, Selector,
+    action::{ActionExt, NullActionExt},
+    binds::ResolvedBindMap,
+    config::{ExitConfig, RenderConfig, TerminalConfig},
+    event::{
+        BindSender, EventLoop, EventSender, RENDER_CHANNEL_CAPACITY, RenderReceiver, RenderSender,
+        TickGate, render_channel,
+    },
+    nucleo::Worker,
+    render::{DynamicHandlers, MMState},
+    testing::Frames,
+    tui::{AnyBackend, Tui},
+};
+
+use super::{Retained, run};
+
+/// The accept hook of a [`Session`], which is called on every accept.
+pub type SessionAcceptHook<T, D, S> =
+    Box<dyn FnMut(&mut MMState<'_, '_, T, D>) -> Vec<S> + Send + Sync + 'static>;
+
+/// The state of a picker when it was closed, which can be stored to continue where the user left
+/// off in a later process. Obtained with [`PickOptions::on_close`] and applied with
+/// [`PickOptions::restore`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedSession {
+    pub query: String,
+    /// The position of the cursor in the query.
+    pub query_cursor: u16,
+    /// The scroll position and the cursor of the results.
+    pub position: (u32, u16),
+    /// The text of the first column of the selected items, as items are identified by their
+    /// index only within one process.
+    pub selected: Vec<String>,
+    /// The index of the preview layout, as in [`crate::Action::SwitchPreview`].
+    pub preview_layout: Option<u8>,
+    pub hidden_columns: Vec<usize>,
+}
+
+/// Called with the [`SavedSession`] of a picker when it is closed.
+pub type SessionSaver = Box<dyn FnOnce(SavedSession) + Send + Sync + 'static>;
+
+/// A picker which can be shown repeatedly. The items, selections and handlers are kept between
+/// invocations, as well as the query and cursor. To also keep it on the screen in between, pass
+/// each invocation the same [`SharedTerminal`].
+///
+/// Created with [`Matchmaker::into_session`].
+pub struct Session<T: SSS, S, D = ()> {
+    /// Only taken while the picker is shown.
+    retained: Option<Retained<T, D>>,
+    pub render_config: RenderConfig,
+    pub tui_config: TerminalConfig,
+    pub exit_config: ExitConfig,
+    handlers: DynamicHandlers<T, D>,
+    output: SessionAcceptHook<T, D, S>,
+}
+
+impl<T: SSS, S, D: 'static> Matchmaker<T, S, D> {
+    /// Turn the matchmaker into a [`Session`], which can be picked from repeatedly.
+    ///
+    /// `accept_hook` replaces the accept hook given to [`Matchmaker::new`].
+    pub fn into_session<F>(self, accept_hook: F) -> Session<T, S, D>
+    where
+        F: FnMut(&mut MMState<'_, '_, T, D>) -> Vec<S> + Send + Sync + 'static,
+    {
+        Session {
+            retained: Some(Retained {
+                selector: Selector::from_config(&self.selector_config),
+                worker: self.worker,
+                sources: self.sources,
+                query: None,
+                position: None,
+            }),
+            render_config: self.render_config,
+            tui_config: self.tui_config,
+            exit_config: self.exit_config,
+            handlers: (self.event_handlers, self.interrupt_handlers),
+            output: Box::new(accept_hook),
+        }
+    }
+}
+
+impl<T: SSS, S, D: 'static> Session<T, S, D> {
+    /// Show the picker, with the query, cursor and selections it was last closed with.
+    ///
+    /// # Panics
+    /// If a previous call was cancelled before it returned.
+    pub async fn resume<A: ActionExt>(
+        &mut self,
+        options: PickOptions<'_, T, D, A>,
+    ) -> Result<Vec<S>> {
+        let retained = self.retained.take().expect("picker was closed uncleanly");
+        let (ret, retained) = run(
+            retained,
+            self.render_config.clone(),
+            self.tui_config.clone(),
+            self.exit_config.clone(),
+            &mut self.handlers,
+            &mut *self.output,
+            options,
+        )
+        .await;
+        self.retained = Some(retained);
+        ret
+    }
+
+    /// The worker, to add or restart items between invocations.
+    pub fn worker(&self) -> &Worker<T, D> {
+        &self.retained().worker
+    }
+
+    /// Start the next invocation afresh, with no selections and the initial query.
+    pub fn reset(&mut self) {
+        let retained = self.retained.as_mut().expect("picker was closed uncleanly");
+        retained.selector.clear();
+        retained.query = None;
+        retained.position = None;
+    }
+
+    fn retained(&self) -> &Retained<T, D> {
+        self.retained.as_ref().expect("picker was closed uncleanly")
+    }
+}
+
+/// A terminal and event loop which outlive a picker, so that pickers shown one after another,
+/// such as the steps of a [`crate::flow::Flow`], stay on the screen and receive the keys typed in
+/// between. Given to each picker with [`PickOptions::terminal`].
+///
+/// The first picker enters the terminal and starts the event loop with its [`TerminalConfig`]
+/// and binds, which the later pickers share. The terminal is restored once the last clone is
+/// dropped. It should only be used by one picker at a time.
+pub struct SharedTerminal<A: ActionExt = NullActionExt>(Arc<Mutex<Option<Held<A>>>>);
+
+impl<A: ActionExt> SharedTerminal<A> {
+    pub fn new() -> Self {
+        Self(Arc::new(Mutex::new(None)))
+    }
+
+    /// The parts left by the previous picker, if any.
+    pub(crate) fn take(&self) -> Option<Held<A>> {
+        self.0.lock().unwrap().take()
+    }
+
+    pub(crate) fn put(&self, held: Held<A>) {
+        // the next picker may release the tick the last one exited with
+        held.ticks.release();
+        #[cfg(feature = "pty")]
+        held.pty.store(None);
+        *self.0.lock().unwrap() = Some(held);
+    }
+}
+
+impl<A: ActionExt> Default for SharedTerminal<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A: ActionExt> Clone for SharedTerminal<A> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// The terminal and the ends of the event loop which a picker runs on.
+pub(crate) struct Held<A: ActionExt> {
+    pub(crate) tui: Tui,
+    pub(crate) render_tx: RenderSender<A>,
+    pub(crate) render_rx: RenderReceiver<A>,
+    pub(crate) controller: EventSender,
+    pub(crate) bind_controller: BindSender<A>,
+    pub(crate) ticks: Arc<TickGate>,
+    pub(crate) binds: Arc<ArcSwap<ResolvedBindMap<A>>>,
+    #[cfg(feature = "pty")]
+    pub(crate) pty: Arc<arc_swap::ArcSwapOption<crate::event::ForwardKeys>>,
+    #[cfg(feature = "image")]
+    pub(crate) image_picker: Option<ratatui_image::picker::Picker>,
+    pub(crate) handle: Option<JoinHandle<()>>,
+    // a headless picker only handles the commands it is sent, but the event loop is kept for
+    // its channels
+    _event_loop: Option<EventLoop<A>>,
+}
+
+impl<A: ActionExt> Held<A> {
+    /// Enter the terminal, or draw into `frames` when headless, and start the event loop.
+    pub(crate) fn start(
+        mut event_loop: EventLoop<A>,
+        channel: Option<(RenderSender<A>, RenderReceiver<A>)>,
+        tui_config: TerminalConfig,
+        headless: Option<(Rect, Arc<Frames>)>,
+        #[cfg(feature = "image")] query_images: bool,
+    ) -> anyhow::Result<Self> {
+        let (render_tx, render_rx) =
+            channel.unwrap_or_else(|| render_channel(RENDER_CHANNEL_CAPACITY));
+        event_loop.add_tx(render_tx.clone());
+
+        let is_headless = headless.is_some();
+        let tui = match headless {
+            Some((area, frames)) => Tui::headless(
+                AnyBackend::Crossterm(CrosstermBackend::new(Box::new(std::io::sink()))),
+                tui_config,
+                area,
+                frames,
+            ),
+            None => {
+                let mut tui = Tui::new(tui_config)?;
+                tui.enter()?;
+                tui
+            }
+        };
+        // the terminal replies to the query on stdin, so it is sent before the event loop reads it
+        #[cfg(feature = "image")]
+        let image_picker = (query_images && !is_headless).then(crate::ui::query_picker);
+
+        let mut held = Self {
+            tui,
+            render_tx,
+            render_rx,
+            controller: event_loop.controller(),
+            bind_controller: event_loop.bind_controller(),
+            ticks: event_loop.ticks(),
+            binds: event_loop.get_binds_ptr(),
+            #[cfg(feature = "pty")]
+            pty: event_loop.pty_slot(),
+            #[cfg(feature = "image")]
+            image_picker,
+            handle: None,
+            _event_loop: None,
+        };
+        // important to start after tui
+        if is_headless {
+            held._event_loop = Some(event_loop);
+        } else {
+            held.handle = Some(tokio::spawn(async move {
+                let _ = event_loop.run().await;
+            }));
+            log::debug!("event loop started");
+        }
+        Ok(held)
+    }
+}
+
+impl<A: ActionExt> Drop for Held<A> {
+    fn drop(&mut self) {
+        self.tui.shared = false;
+    }
+}
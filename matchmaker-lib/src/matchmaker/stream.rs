@@ -0,0 +1,93 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures::{Future, Stream};
+use tokio::sync::mpsc::{UnboundedReceiver, unbounded_channel};
+
+use crate::{
+    MatchError, Matchmaker, PickOptions, Result, SSS, action::ActionExt, message::Interrupt,
+    render::MMState,
+};
+
+impl<T: SSS, S: 'static, D: 'static> Matchmaker<T, S, D> {
+    /// Like [`Matchmaker::pick`], but the picker stays open when [`crate::Action::AcceptNonExit`]
+    /// is triggered, yielding the items returned by `on_accept` instead.
+    ///
+    /// The items of the final accept follow, after which the stream ends. If the picker exited
+    /// with an error, it is available from [`PickStream::error`].
+    pub fn pick_stream<'a, A: ActionExt, F>(
+        mut self,
+        options: PickOptions<'a, T, D, A>,
+        mut on_accept: F,
+    ) -> PickStream<'a, S>
+    where
+        F: FnMut(&mut MMState<'_, '_, T, D>) -> Vec<S> + 'static,
+    {
+        let (tx, rx) = unbounded_channel();
+        self.register_interrupt_handler(Interrupt::AcceptNonExit, move |state| {
+            for item in on_accept(state) {
+                let _ = tx.send(item);
+            }
+        });
+
+        PickStream {
+            pick: Some(Box::pin(self.pick(options))),
+            rx,
+            last: Vec::new().into_iter(),
+            error: None,
+        }
+    }
+}
+
+/// The items accepted while a picker is open. Returned by [`Matchmaker::pick_stream`].
+///
+/// The picker only runs while the stream is polled.
+pub struct PickStream<'a, S> {
+    pick: Option<Pin<Box<dyn Future<Output = Result<Vec<S>>> + 'a>>>,
+    rx: UnboundedReceiver<S>,
+    /// The items of the final accept.
+    last: std::vec::IntoIter<S>,
+    error: Option<MatchError>,
+}
+
+impl<S> PickStream<'_, S> {
+    /// The error the picker exited with, once the stream has ended.
+    pub fn error(&self) -> Option<&MatchError> {
+        self.error.as_ref()
+    }
+}
+
+// The items are never pinned.
+impl<S> Unpin for PickStream<'_, S> {}
+
+impl<S> Stream for PickStream<'_, S> {
+    type Item = S;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<S>> {
+        let this = self.get_mut();
+        if let Poll::Ready(Some(item)) = this.rx.poll_recv(cx) {
+            return Poll::Ready(Some(item));
+        }
+
+        if let Some(pick) = &mut this.pick {
+            match pick.as_mut().poll(cx) {
+                Poll::Ready(ret) => {
+                    this.pick = None;
+                    match ret {
+                        Ok(items) => this.last = items.into_iter(),
+                        Err(e) => this.error = Some(e),
+                    }
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+
+        // the picker has exited: what it yielded before the final accept comes first
+        if let Ok(item) = this.rx.try_recv() {
+            return Poll::Ready(Some(item));
+        }
+        Poll::Ready(this.last.next())
+    }
+}
@@ -6,8 +6,10 @@ use crate::{
     Actions,
     action::{Action, ActionExt},
     binds::Trigger,
-    ui::HeaderTable,
+    event::{Overflow, RenderSender},
+    ui::{HeaderTable, ToastLevel},
 };
+use std::sync::Arc;
 
 bitflags! {
     #[derive(bitflags_derive::FlagsDisplay, bitflags_derive::FlagsFromStr, Debug, PartialEq, Eq, Hash, Clone, Copy, Default, PartialOrd, Ord)]
@@ -51,6 +53,11 @@ bitflags! {
         // requires connecting [`EventLoop`] to [`Previewer`]
         const PreviewStarted = 1 << 15;
         const PreviewFinished = 1 << 16;
+
+        /// Item or match counts changed
+        const StatusChange = 1 << 17;
+        /// Items selected or deselected
+        const SelectionChange = 1 << 18;
     }
 }
 // ---------------------------------------------------------------------
@@ -67,6 +74,8 @@ pub enum Interrupt {
     BecomeSilent,
     Print,
     Reload,
+    SetCwd,
+    AcceptNonExit,
     Custom,
 }
 
@@ -81,6 +90,15 @@ pub enum RenderCommand<A: ActionExt> {
     #[cfg(feature = "bracketed-paste")]
     Paste(String),
     HeaderTable(HeaderTable),
+    /// The composition text of an input method, shown underlined at the query cursor.
+    /// Committed text is sent as input, after an empty preedit ends the composition.
+    Preedit(String),
+    /// A key was pressed which maps to no bind.
+    Unbound(String),
+    /// A key was pressed which maps to the actions following this command.
+    Key(String),
+    /// Display a toast.
+    Notify(ToastLevel, String),
     Ack,
     Tick,
     Refresh,
@@ -97,7 +115,70 @@ impl<A: ActionExt> From<Action<A>> for RenderCommand<A> {
 
 impl<A: ActionExt> RenderCommand<A> {
     pub fn quit() -> Self {
-        RenderCommand::Action(Action::Quit(1))
+        RenderCommand::Action(Action::Quit(crate::MatchError::EXIT_CANCEL))
+    }
+
+    /// Only repeatable commands are coalesced, actions are never dropped.
+    pub fn overflow(&self) -> Overflow {
+        match self {
+            RenderCommand::Tick
+            | RenderCommand::Refresh
+            | RenderCommand::Redraw
+            | RenderCommand::Resize(_) => Overflow::Coalesce,
+            _ => Overflow::Queue,
+        }
+    }
+}
+
+/// Sends toasts to the render loop without naming its [`ActionExt`].
+#[derive(Clone)]
+pub struct Notifier(Arc<dyn Fn(ToastLevel, String) + Send + Sync>);
+
+impl Notifier {
+    pub fn new<A: ActionExt>(render_tx: RenderSender<A>) -> Self {
+        Self(Arc::new(move |level, message| {
+            let _ = render_tx.send(RenderCommand::Notify(level, message));
+        }))
+    }
+
+    pub fn notify(&self, level: ToastLevel, message: impl Into<String>) {
+        (self.0)(level, message.into())
+    }
+}
+
+impl std::fmt::Debug for Notifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Notifier")
+    }
+}
+
+/// Closes the picker from another task, which then returns [`crate::MatchError::Abort`].
+///
+/// Created with [`crate::PickOptions::abort_handle`].
+#[derive(Clone)]
+pub struct AbortHandle(Arc<dyn Fn(i32) + Send + Sync>);
+
+impl AbortHandle {
+    pub fn new<A: ActionExt>(render_tx: RenderSender<A>) -> Self {
+        Self(Arc::new(move |code| {
+            let _ = render_tx.send(RenderCommand::Action(Action::Quit(code)));
+        }))
+    }
+
+    /// Abort with [`crate::MatchError::EXIT_CANCEL`].
+    pub fn abort(&self) {
+        self.abort_with(crate::MatchError::EXIT_CANCEL)
+    }
+
+    /// Abort with the exit code. Does nothing if the picker has already closed.
+    pub fn abort_with(&self, code: i32) {
+        (self.0)(code)
+    }
+}
+
+impl std::fmt::Debug for AbortHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("AbortHandle")
     }
 }
 
@@ -2,7 +2,7 @@ use std::time::{Duration, Instant};
 
 use crate::{
     SSS,
-    nucleo::{Render, Worker, new_snapshot},
+    nucleo::{Render, Worker},
 };
 
 /// Map f on matches without starting the interface.
@@ -16,24 +16,51 @@ pub fn get_matches<T: SSS + Render>(
 
     let total = worker.append(items);
     worker.find(query);
+    wait(&mut worker, total, Some(timeout));
 
-    let start = Instant::now();
-    loop {
-        let (_, status) = new_snapshot(&mut worker.nucleo);
-
-        if status.item_count == total && !status.running {
+    for t in worker.matched_results() {
+        if f(t) {
             break;
         }
+    }
+}
+
+/// Match all of `items` against `query`, then call f on each match in score order until it returns true.
+/// Returns the number of matches.
+///
+/// Unlike [`get_matches`], this waits for the matcher to finish, however long it takes.
+pub fn filter<T: SSS + Render>(
+    items: impl IntoIterator<Item = T>,
+    query: &str,
+    mut f: impl FnMut(&T) -> bool,
+) -> u32 {
+    let mut worker = Worker::new_single_column();
+
+    let total = worker.append(items);
+    worker.find(query);
+    wait(&mut worker, total, None);
 
-        if start.elapsed() >= timeout {
+    let (matched, _) = worker.counts();
+    for t in worker.matched_results() {
+        if f(t) {
             break;
         }
-        // new_snapshot already waits
     }
+    matched
+}
 
-    for t in worker.matched_results() {
-        if f(t) {
+fn wait<T: SSS>(worker: &mut Worker<T>, total: u32, timeout: Option<Duration>) {
+    let start = Instant::now();
+    loop {
+        let status = worker.refresh();
+
+        if status.item_count == total && !status.running {
+            break;
+        }
+
+        if timeout.is_some_and(|t| start.elapsed() >= t) {
             break;
         }
+        // refresh already waits
     }
 }
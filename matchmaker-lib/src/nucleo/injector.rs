@@ -6,8 +6,11 @@ use std::sync::{
     atomic::{AtomicU32, Ordering},
 };
 
-use super::Segmented;
+use futures::{Stream, StreamExt};
+use tokio::sync::mpsc::Receiver;
+
 use super::worker::{Column, Worker, WorkerError};
+use super::{ItemKind, Kinded, Segmented};
 use crate::{SSS, nucleo::SegmentableItem};
 
 pub trait Injector {
@@ -62,6 +65,7 @@ pub struct WorkerInjector<T, D = ()> {
     pub(super) raw_preprocessor: Arc<dyn Fn(&T) -> Option<D> + Send + Sync>,
     pub(super) version: u32,
     pub(super) picker_version: Arc<AtomicU32>,
+    pub(super) token: Arc<()>,
 }
 
 impl<T: SSS, D> Injector for WorkerInjector<T, D> {
@@ -115,17 +119,44 @@ impl<T: SSS, D> Injector for WorkerInjector<T, D> {
     }
 }
 
+/// The most items [`WorkerInjector::feed_stream`] pushes at once.
+const STREAM_BATCH: usize = 1024;
+
+impl<T: SSS, D> WorkerInjector<T, D> {
+    /// Push the items of a stream as they arrive, until it ends.
+    ///
+    /// The items which are ready are pushed together, and the stream is only polled once they
+    /// have been pushed. Fails once the injector is shut down, i.e. by [`Worker::edit`].
+    pub async fn feed_stream(&self, stream: impl Stream<Item = T>) -> Result<(), WorkerError> {
+        let mut batches = std::pin::pin!(stream.ready_chunks(STREAM_BATCH));
+        while let Some(batch) = batches.next().await {
+            self.extend(batch.into_iter())?;
+            // a stream which is always ready would otherwise starve the runtime
+            tokio::task::yield_now().await;
+        }
+        Ok(())
+    }
+
+    /// Push the items sent on a channel, until all senders are dropped.
+    ///
+    /// See [`Self::feed_stream`].
+    pub async fn feed_receiver(&self, mut rx: Receiver<T>) -> Result<(), WorkerError> {
+        self.feed_stream(futures::stream::poll_fn(|cx| rx.poll_recv(cx)))
+            .await
+    }
+}
+
 pub(crate) fn push_impl<T, D>(
     injector: &nucleo::Injector<T>,
     columns: &[Column<T, D>],
     item: T,
     d: &D,
-) {
+) -> u32 {
     injector.push(item, |item, dst| {
         for (column, text) in columns.iter().filter(|column| column.filter).zip(dst) {
             *text = column.raw(item, d).into()
         }
-    });
+    })
 }
 
 pub(super) fn extend_impl<T, D, I>(
@@ -180,6 +211,39 @@ impl<T: SegmentableItem, I: Injector<InputItem = Segmented<T>>> Injector
     }
 }
 
+pub type ClassifierFn<T> = std::sync::Arc<dyn Fn(&T) -> ItemKind + Send + Sync>;
+
+/// Tags each item with its [`ItemKind`] as it is injected.
+pub struct KindedInjector<T, I: Injector<InputItem = Kinded<T>>> {
+    injector: I,
+    classifier: ClassifierFn<T>,
+}
+
+impl<T, I: Injector<InputItem = Kinded<T>>> Injector for KindedInjector<T, I> {
+    type InputItem = T;
+    type Inner = I;
+    type Context = ClassifierFn<T>;
+
+    fn new(injector: Self::Inner, data: Self::Context) -> Self {
+        Self {
+            injector,
+            classifier: data,
+        }
+    }
+
+    fn wrap(
+        &self,
+        item: Self::InputItem,
+    ) -> Result<<Self::Inner as Injector>::InputItem, WorkerError> {
+        let kind = (self.classifier)(&item);
+        Ok(Kinded::new(item, kind))
+    }
+
+    fn inner(&self) -> &Self::Inner {
+        &self.injector
+    }
+}
+
 // ----------- CLONE ----------------------------
 impl<T, D> Clone for WorkerInjector<T, D> {
     fn clone(&self) -> Self {
@@ -189,6 +253,7 @@ impl<T, D> Clone for WorkerInjector<T, D> {
             raw_preprocessor: Arc::clone(&self.raw_preprocessor),
             version: self.version,
             picker_version: Arc::clone(&self.picker_version),
+            token: Arc::clone(&self.token),
         }
     }
 }
@@ -203,3 +268,66 @@ impl<T: SegmentableItem, I: Injector<InputItem = Segmented<T>> + Clone> Clone
         }
     }
 }
+
+impl<T, I: Injector<InputItem = Kinded<T>> + Clone> Clone for KindedInjector<T, I> {
+    fn clone(&self) -> Self {
+        Self {
+            injector: self.injector.clone(),
+            classifier: Arc::clone(&self.classifier),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nucleo::Render;
+
+    #[tokio::test]
+    async fn test_feed_receiver() {
+        let worker = Worker::<String>::new_single_column();
+        let injector = worker.injector();
+        let (tx, rx) = tokio::sync::mpsc::channel(2);
+        tokio::spawn(async move {
+            for i in 0..10 {
+                tx.send(i.to_string()).await.unwrap();
+            }
+        });
+        injector.feed_receiver(rx).await.unwrap();
+        assert_eq!(injector.inner.injected_items(), 10);
+
+        worker.version.fetch_add(1, Ordering::Relaxed);
+        let stream = futures::stream::iter(["a".to_string()]);
+        assert!(injector.feed_stream(stream).await.is_err());
+    }
+
+    #[test]
+    fn test_kinded_injector() {
+        let mut worker = Worker::<Kinded<String>>::new_single_column();
+        let classifier: ClassifierFn<String> = Arc::new(|item: &String| {
+            if item.ends_with('/') {
+                ItemKind::DIR
+            } else {
+                ItemKind::FILE | ItemKind::EXECUTABLE
+            }
+        });
+        let injector = KindedInjector::new(worker.injector(), classifier);
+        for item in ["run", "src/"] {
+            injector.push(item.to_string()).unwrap();
+        }
+
+        worker.set_score_fn(Some(Arc::new(|item: &Kinded<String>, score| {
+            score + 1000 * item.kind.contains(ItemKind::DIR) as u32
+        })));
+        while worker.refresh().running {}
+        let results: Vec<_> = worker.matched_results().map(|k| k.kind).collect();
+        assert_eq!(
+            results,
+            [ItemKind::DIR, ItemKind::FILE | ItemKind::EXECUTABLE]
+        );
+
+        let text = worker.get_by_idx(0).unwrap().as_text();
+        assert_eq!(text.to_string(), "run*");
+        assert_eq!(text.style, ItemKind::EXECUTABLE.style());
+    }
+}
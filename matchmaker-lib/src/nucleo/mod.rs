@@ -1,6 +1,7 @@
 pub mod injector;
 pub mod query;
 pub mod render_item;
+mod tree;
 pub mod variants;
 mod worker;
 
@@ -11,9 +12,13 @@ use std::{
 };
 
 use crate::SSS;
+pub use tree::DepthFn;
 pub use variants::*;
 pub use worker::*;
 
+/// Derive [`Columns`] and [`ColumnIndexable`] for a struct.
+pub use matchmaker_partial_macros::Columns;
+
 pub use nucleo;
 pub use ratatui::prelude::*;
 
@@ -111,3 +116,134 @@ impl<T: Display + SegmentableItem> Display for Segmented<T> {
         write!(f, "{}", self.inner)
     }
 }
+
+// ------------------------------------------------
+
+bitflags::bitflags! {
+    /// What an item refers to, as determined once by its injector.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+    pub struct ItemKind: u8 {
+        const FILE = 1 << 0;
+        const DIR = 1 << 1;
+        const SYMLINK = 1 << 2;
+        const EXECUTABLE = 1 << 3;
+        /// The item could not be classified, i.e. it is a broken link or does not exist.
+        const ERROR = 1 << 4;
+    }
+}
+
+impl ItemKind {
+    /// Stat a path. Symlinks are followed, and tagged as such.
+    pub fn of_path(path: &std::path::Path) -> Self {
+        let mut ret = Self::empty();
+        if path.symlink_metadata().is_ok_and(|m| m.is_symlink()) {
+            ret |= Self::SYMLINK;
+        }
+
+        let Ok(metadata) = path.metadata() else {
+            return ret | Self::ERROR;
+        };
+        if metadata.is_dir() {
+            ret |= Self::DIR;
+        } else if metadata.is_file() {
+            ret |= Self::FILE;
+            #[cfg(unix)]
+            if std::os::unix::fs::PermissionsExt::mode(&metadata.permissions()) & 0o111 != 0 {
+                ret |= Self::EXECUTABLE;
+            }
+        }
+        ret
+    }
+
+    /// The suffix used by `ls -F`.
+    pub fn indicator(self) -> &'static str {
+        if self.contains(Self::DIR) {
+            "/"
+        } else if self.contains(Self::SYMLINK) {
+            "@"
+        } else if self.contains(Self::EXECUTABLE) {
+            "*"
+        } else {
+            ""
+        }
+    }
+
+    /// Style applied by [`Kinded::as_text`], following the default `ls --color` palette.
+    pub fn style(self) -> Style {
+        if self.contains(Self::ERROR) {
+            Style::new().red()
+        } else if self.contains(Self::DIR) {
+            Style::new().blue().bold()
+        } else if self.contains(Self::SYMLINK) {
+            Style::new().cyan()
+        } else if self.contains(Self::EXECUTABLE) {
+            Style::new().green()
+        } else {
+            Style::new()
+        }
+    }
+}
+
+/// An item tagged with its [`ItemKind`], so that displaying, sorting or filtering the output
+/// does not need to classify it again.
+///
+/// [`Render`] displays the item styled and suffixed according to its kind, while its
+/// `as_str` (which is matched on and output) is that of the inner item. To rank by kind, i.e.
+/// directories first, pass a closure reading [`Kinded::kind`] to [`Worker::set_score_fn`].
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub struct Kinded<T> {
+    pub inner: T,
+    pub kind: ItemKind,
+}
+
+impl<T> Kinded<T> {
+    pub fn new(inner: T, kind: ItemKind) -> Self {
+        Self { inner, kind }
+    }
+
+    /// Tag a path-like item by statting it.
+    pub fn from_path(inner: T) -> Self
+    where
+        T: AsRef<std::path::Path>,
+    {
+        let kind = ItemKind::of_path(inner.as_ref());
+        Self { inner, kind }
+    }
+}
+
+impl<T> std::ops::Deref for Kinded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+impl<T: Render> Render for Kinded<T> {
+    fn as_str(&self) -> Cow<'_, str> {
+        self.inner.as_str()
+    }
+
+    fn as_text(&self) -> Text<'_> {
+        let mut text = self.inner.as_text();
+        if let Some(line) = text.lines.last_mut() {
+            line.push_span(self.kind.indicator());
+        }
+        text.patch_style(self.kind.style())
+    }
+}
+
+impl<T: SegmentableItem> SegmentableItem for Kinded<T> {
+    fn slice(&self, range: Range<usize>) -> Text<'_> {
+        self.inner.slice(range)
+    }
+    fn slice_str(&self, range: Range<usize>) -> Cow<'_, str> {
+        self.inner.slice_str(range)
+    }
+}
+
+impl<T: Display> Display for Kinded<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
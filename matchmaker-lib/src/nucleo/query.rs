@@ -86,6 +86,10 @@ impl PickerQuery {
         let mut in_field = false;
         let mut field = None;
         let mut text = String::new();
+        // A single term routed to a column with `name:`
+        let mut scoped: Option<(&Arc<str>, String)> = None;
+        // The byte index at which the current term starts
+        let mut term_start = 0;
 
         macro_rules! finish_field {
             () => {
@@ -106,6 +110,26 @@ impl PickerQuery {
             };
         }
 
+        macro_rules! finish_scoped {
+            ($idx:expr) => {
+                if let Some((key, pat)) = scoped.take() {
+                    if pat.is_empty() {
+                        // still being typed
+                    } else if let Some(pattern) = fields.get_mut(key) {
+                        pattern.push(' ');
+                        pattern.push_str(&pat);
+                    } else {
+                        fields.insert(key.clone(), pat);
+                    }
+                    // Return to the column of the surrounding text.
+                    if let Some((range, _)) = column_ranges.last_mut() {
+                        range.end = $idx;
+                    }
+                    column_ranges.push(($idx..usize::MAX, field.or(Some(primary_field)).cloned()));
+                }
+            };
+        }
+
         for (idx, ch) in input.char_indices() {
             match ch {
                 // Backslash escaping
@@ -113,6 +137,10 @@ impl PickerQuery {
                     // '%' is the only character that is special cased.
                     // You can escape it to prevent parsing the text that
                     // follows it as a field name.
+                    let text = match &mut scoped {
+                        Some((_, pat)) => pat,
+                        None => &mut text,
+                    };
                     if ch != '%' {
                         text.push('\\');
                     }
@@ -121,6 +149,7 @@ impl PickerQuery {
                 }
                 '\\' => escaped = !escaped,
                 '%' => {
+                    finish_scoped!(idx);
                     if !text.is_empty() {
                         finish_field!();
                     }
@@ -148,6 +177,7 @@ impl PickerQuery {
                         text.clear();
                     }
                     in_field = false;
+                    term_start = idx + 1;
                 }
                 _ if in_field => {
                     text.push(ch);
@@ -170,9 +200,35 @@ impl PickerQuery {
                         column_ranges.push((idx..usize::MAX, field.cloned()));
                     }
                 }
-                _ => text.push(ch),
+                ' ' if scoped.is_some() => {
+                    finish_scoped!(idx);
+                    term_start = idx + 1;
+                }
+                _ => match &mut scoped {
+                    Some((_, pat)) => pat.push(ch),
+                    None => {
+                        let key = (ch == ':')
+                            .then(|| &input[term_start..idx])
+                            .filter(|word| !word.is_empty() && text.ends_with(word))
+                            .and_then(|word| self.column_names.iter().find(|c| &***c == word));
+                        if let Some(key) = key {
+                            text.truncate(text.len() - key.len());
+                            scoped = Some((key, String::new()));
+                            if let Some((range, _)) = column_ranges.last_mut() {
+                                range.end = term_start;
+                            }
+                            column_ranges.push((term_start..usize::MAX, Some(key.clone())));
+                        } else {
+                            if ch == ' ' {
+                                term_start = idx + 1;
+                            }
+                            text.push(ch);
+                        }
+                    }
+                },
             }
         }
+        finish_scoped!(input.len());
 
         // Finish the last field if we're not in a field and there's leftover text
         if !in_field && !text.is_empty() {
@@ -245,3 +301,106 @@ impl PickerQuery {
         self
     }
 }
+
+/// The space-separated terms of a pattern. Spaces can be escaped with `\\`.
+fn terms(pattern: &str) -> impl Iterator<Item = &str> {
+    let mut start = 0;
+    let mut escaped = false;
+    pattern
+        .char_indices()
+        .chain([(pattern.len(), ' ')])
+        .filter_map(move |(idx, ch)| {
+            match ch {
+                _ if escaped && idx < pattern.len() => escaped = false,
+                '\\' => escaped = true,
+                ' ' => {
+                    let term = &pattern[start..idx];
+                    start = idx + 1;
+                    return (!term.is_empty()).then_some(term);
+                }
+                _ => {}
+            }
+            None
+        })
+}
+
+/// Split the `|` alternations out of the pattern of a column, as in fzf: `^src | ^lib .rs$`
+/// matches items starting with `src` or `lib` and ending with `.rs`.
+///
+/// Returns the remaining terms, which are left to nucleo along with the rest of the fzf syntax
+/// (`'exact`, `!negated`, `^prefix` and `suffix$`), and the alternatives of each alternation.
+pub fn split_alternations(pattern: &str) -> (String, Vec<Vec<&str>>) {
+    let mut alternations: Vec<Vec<&str>> = Vec::new();
+    let mut or = false;
+    for term in terms(pattern) {
+        if term == "|" {
+            or = !alternations.is_empty();
+        } else if or && let Some(last) = alternations.last_mut() {
+            last.push(term);
+            or = false;
+        } else {
+            alternations.push(vec![term]);
+        }
+    }
+
+    let (single, alternations): (Vec<_>, Vec<_>) =
+        alternations.into_iter().partition(|t| t.len() == 1);
+    let rest = single.into_iter().flatten().collect::<Vec<_>>().join(" ");
+    (rest, alternations)
+}
+
+/// Quote the fuzzy terms of a pattern with `'`, and unquote the quoted ones, as in `fzf --exact`.
+/// Anchored and negated terms are matched as substrings already.
+pub fn exact_terms(pattern: &str) -> String {
+    terms(pattern)
+        .map(|term| {
+            if let Some(fuzzy) = term.strip_prefix('\'') {
+                fuzzy.into()
+            } else if term.starts_with(['^', '!']) || term.ends_with('$') {
+                term.into()
+            } else {
+                format!("'{term}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scoped_terms() {
+        let names = ["name", "size"].map(Arc::<str>::from);
+        let mut query = PickerQuery::new(names.into_iter(), 0);
+        query.parse("foo size:>10 bar %name baz size:1\\ 2");
+        assert_eq!(query.get("size").map(|s| &**s), Some(">10 1\\ 2"));
+        assert_eq!(query.get("name").map(|s| &**s), Some("foo bar baz"));
+        assert_eq!(query.current_column(6).map(|s| &**s), Some("size"));
+        assert_eq!(query.current_column(14).map(|s| &**s), Some("name"));
+
+        // unknown columns are matched literally
+        query.parse("http://a");
+        assert_eq!(query.get("name").map(|s| &**s), Some("http://a"));
+    }
+
+    #[test]
+    fn test_exact_terms() {
+        assert_eq!(
+            exact_terms("foo 'bar ^baz a\\ b$ !c"),
+            "'foo bar ^baz a\\ b$ !c"
+        );
+    }
+
+    #[test]
+    fn test_split_alternations() {
+        let (rest, alternations) = split_alternations("^src | ^lib .rs$ a\\ | b |");
+        assert_eq!(rest, ".rs$ a\\ | b");
+        assert_eq!(alternations, [["^src", "^lib"]]);
+
+        let (rest, alternations) = split_alternations("| a | b | !c");
+        assert_eq!(rest, "");
+        assert_eq!(alternations, [["a", "b", "!c"]]);
+    }
+}
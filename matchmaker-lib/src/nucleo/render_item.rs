@@ -14,23 +14,30 @@ use crate::{
 
 /// Renders a single cell by applying match highlighting, wrapping, and hscroll clipping.
 ///
+/// With `keep_right`, lines which are too long and not positioned by autoscrolling are clipped
+/// on the left instead of the right, as for an unmatched line when autoscrolling to the end.
+///
+/// The best alternative of each of the `alternations` of the column is highlighted as well.
+///
 /// ### Mutations:
 /// - `matcher`: Mutated internally for calculating match sub-span indices.
 /// - `col_indices_buffer`: Mutated (cleared and refilled) as a reusable scratch vector to avoid allocations.
 ///
 /// ### Returns:
 /// - `(Text<'static>, usize)`: A tuple where the first element is the styled static `Text`, and the second is the calculated maximum visual width of the cell.
-pub fn render_cell<T: SSS>(
+pub fn render_cell<'a, T: SSS>(
     cell: Text<'_>,
     col_idx: usize,
     snapshot: &nucleo::Snapshot<T>,
     item: &nucleo::Item<T>,
+    alternations: impl IntoIterator<Item = &'a [nucleo::pattern::Pattern]>,
     matcher: &mut nucleo::Matcher,
     highlight_style: Style,
     wrap: bool,
     width_limit: u16,
     col_indices_buffer: &mut Vec<u32>,
     mut autoscroll: AutoscrollSettings,
+    keep_right: bool,
     hscroll_offset: i8,
 ) -> (Text<'static>, usize) {
     // Disable autoscrolling by default if text wrapping is enabled, as wrapping naturally pushes
@@ -46,11 +53,20 @@ pub fn render_cell<T: SSS>(
     // The indices tell us which character positions inside this column's text match the search query.
     let indices_buffer = col_indices_buffer;
     indices_buffer.clear();
-    snapshot.pattern().column_pattern(col_idx).indices(
-        item.matcher_columns[col_idx].slice(..),
-        matcher,
-        indices_buffer,
-    );
+    let haystack = item.matcher_columns[col_idx].slice(..);
+    snapshot
+        .pattern()
+        .column_pattern(col_idx)
+        .indices(haystack, matcher, indices_buffer);
+    for alternatives in alternations {
+        let best = alternatives
+            .iter()
+            .filter_map(|pattern| Some((pattern.score(haystack, matcher)?, pattern)))
+            .max_by_key(|(score, _)| *score);
+        if let Some((_, pattern)) = best {
+            pattern.indices(haystack, matcher, indices_buffer);
+        }
+    }
     // Sort and remove duplicates to guarantee match indices are processed sequentially from left to right.
     indices_buffer.sort_unstable();
     indices_buffer.dedup();
@@ -94,8 +110,12 @@ pub fn render_cell<T: SSS>(
 
         // Step 3: Calculate where to start rendering this line (HScroll calculation)
         let mut i; // start_idx of the rendered slice
+        if !autoscroll.enabled {
+            match_idx = None;
+        }
+        let anchor_right = keep_right && !wrap && match_idx.is_none();
 
-        if autoscroll.enabled && autoscroll.end {
+        if (autoscroll.enabled && autoscroll.end) || anchor_right {
             // Horizontal autoscrolling focused on the end of matches:
             // Shift the start index leftwards so the end match remains fully visible.
             i = match_idx.unwrap_or(line_graphemes.len().saturating_sub(1));
@@ -189,7 +209,7 @@ pub fn render_cell<T: SSS>(
         let mut current_width = 0;
 
         // If shifting occurred, prepend the initial preserved segment and hscroll indicator (...)
-        if i > 0 && autoscroll.enabled {
+        if i > 0 && (autoscroll.enabled || anchor_right) {
             for (g, s) in
                 line_graphemes.drain(..autoscroll.initial_preserved.min(line_graphemes.len()))
             {
@@ -367,6 +387,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             false,
@@ -376,6 +397,7 @@ mod tests {
                 enabled: false,
                 ..Default::default()
             },
+            false,
             0,
         );
 
@@ -384,6 +406,45 @@ mod tests {
         assert_eq!(width, 17);
     }
 
+    #[test]
+    fn test_alternations_are_highlighted() {
+        let (nucleo, mut matcher, mut buffer) = setup_nucleo_mocks("", "hello match world");
+        let snapshot = nucleo.snapshot();
+        let item = snapshot.get_item(0).unwrap();
+        let alternatives = ["nope", "world"].map(|alternative| {
+            nucleo::pattern::Pattern::parse(
+                alternative,
+                nucleo::pattern::CaseMatching::Ignore,
+                nucleo::pattern::Normalization::Smart,
+            )
+        });
+
+        let highlight = Style::default().fg(Color::Red);
+        let (result_text, _) = render_cell(
+            Text::from("hello match world"),
+            0,
+            &snapshot,
+            &item,
+            [alternatives.as_slice()],
+            &mut matcher,
+            highlight,
+            false,
+            u16::MAX,
+            &mut buffer,
+            AutoscrollSettings::default(),
+            false,
+            0,
+        );
+
+        let highlighted: String = result_text.lines[0]
+            .spans
+            .iter()
+            .filter(|span| span.style == highlight)
+            .map(|span| span.content.as_ref())
+            .collect();
+        assert_eq!(highlighted, "world");
+    }
+
     #[test]
     fn test_scroll_context_cuts_prefix_correctly() {
         let (nucleo, mut matcher, mut buffer) = setup_nucleo_mocks("match", "hello match world");
@@ -398,6 +459,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             false,
@@ -408,6 +470,7 @@ mod tests {
                 context: 2,
                 ..Default::default()
             },
+            false,
             0,
         );
 
@@ -443,6 +506,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             false,
@@ -453,6 +517,7 @@ mod tests {
                 context: 1,
                 ..Default::default()
             },
+            false,
             0,
         );
 
@@ -487,6 +552,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             false,
@@ -497,6 +563,7 @@ mod tests {
                 context: 1,
                 ..Default::default()
             },
+            false,
             0,
         );
 
@@ -519,6 +586,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             true,
@@ -529,6 +597,7 @@ mod tests {
                 context: 1,
                 ..Default::default()
             },
+            false,
             -2,
         );
 
@@ -551,6 +620,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             true,
@@ -560,6 +630,7 @@ mod tests {
                 enabled: false,
                 ..Default::default()
             },
+            false,
             0,
         );
 
@@ -583,6 +654,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             false,
@@ -593,6 +665,7 @@ mod tests {
                 context: 4,
                 ..Default::default()
             },
+            false,
             0,
         );
 
@@ -615,6 +688,7 @@ mod tests {
             0,
             &snapshot,
             &item,
+            [],
             &mut matcher,
             highlight,
             false,
@@ -625,6 +699,7 @@ mod tests {
                 context: 2,
                 ..Default::default()
             },
+            false,
             0,
         );
 
@@ -632,4 +707,37 @@ mod tests {
         assert_eq!(output_str, "…fghijmatc");
         assert_eq!(width, 10);
     }
+
+    #[test]
+    fn test_keep_right() {
+        let (nucleo, mut matcher, mut buffer) = setup_nucleo_mocks("", "abcdefghijklmno");
+        let snapshot = nucleo.snapshot();
+        let item = snapshot.get_item(0).unwrap();
+
+        let cell = Text::from("abcdefghijklmno");
+        let highlight = Style::default().fg(Color::Red);
+
+        let (result_text, width) = render_cell(
+            cell,
+            0,
+            &snapshot,
+            &item,
+            [],
+            &mut matcher,
+            highlight,
+            false,
+            10,
+            &mut buffer,
+            AutoscrollSettings {
+                enabled: false,
+                ..Default::default()
+            },
+            true,
+            0,
+        );
+
+        let output_str = result_text.to_string();
+        assert_eq!(output_str, "…ghijklmno");
+        assert_eq!(width, 10);
+    }
 }
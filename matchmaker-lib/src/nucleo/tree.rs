@@ -0,0 +1,218 @@
+use std::{collections::HashSet, sync::Arc};
+
+use super::worker::EditMap;
+
+/// The depth of an item in a tree whose items are read in depth-first order, such as the output
+/// of `tree` or `cargo tree`. See [`super::Worker::set_tree`].
+pub type DepthFn<T> = Arc<dyn Fn(&T) -> u16 + Send + Sync>;
+
+/// The hierarchy of the items in tree mode: the parent of an item is the closest item before it
+/// with a lower depth.
+pub(super) struct Tree<T> {
+    depth_fn: DepthFn<T>,
+    depths: Vec<u16>,
+    parents: Vec<Option<u32>>,
+    /// The ancestors of the next item to be read.
+    stack: Vec<u32>,
+    /// The number of removed items when the items were read.
+    removed: usize,
+    /// Whether each item is shown, only set during [`Self::layout`].
+    shown: Vec<bool>,
+    /// Nodes whose descendants are hidden.
+    pub(super) collapsed: HashSet<u32>,
+    /// The branch drawn before each row of the last layout.
+    pub(super) branches: Vec<String>,
+}
+
+impl<T> Tree<T> {
+    pub(super) fn new(depth_fn: DepthFn<T>) -> Self {
+        Self {
+            depth_fn,
+            depths: Vec::new(),
+            parents: Vec::new(),
+            stack: Vec::new(),
+            removed: 0,
+            shown: Vec::new(),
+            collapsed: HashSet::new(),
+            branches: Vec::new(),
+        }
+    }
+
+    /// Read the depths of the first `count` items, given by `item` in input order. Only the
+    /// items added since the last read are read, unless more items were removed since.
+    ///
+    /// Items in `removed` are not the parent of any item, so that their children are moved up to
+    /// their parent.
+    pub(super) fn read<'a>(
+        &mut self,
+        count: u32,
+        item: impl Fn(u32) -> &'a T,
+        removed: &HashSet<u32>,
+    ) where
+        T: 'a,
+    {
+        if removed.len() != self.removed || (count as usize) < self.depths.len() {
+            self.reset();
+            self.removed = removed.len();
+        }
+        for idx in self.depths.len() as u32..count {
+            let depth = (self.depth_fn)(item(idx));
+            while self
+                .stack
+                .last()
+                .is_some_and(|&top| self.depths[top as usize] >= depth)
+            {
+                self.stack.pop();
+            }
+            self.parents.push(self.stack.last().copied());
+            if !removed.contains(&idx) {
+                self.stack.push(idx);
+            }
+            self.depths.push(depth);
+        }
+    }
+
+    /// Forget the items read, e.g. when the worker is restarted.
+    pub(super) fn reset(&mut self) {
+        self.depths.clear();
+        self.parents.clear();
+        self.stack.clear();
+        self.removed = 0;
+    }
+
+    /// Move the collapsed nodes to their new indices, see [`super::Worker::edit`]. The items are
+    /// read again, as they were edited.
+    pub(super) fn remap(&mut self, map: &EditMap) {
+        self.reset();
+        self.collapsed = self
+            .collapsed
+            .iter()
+            .filter_map(|&idx| map.get(&idx).copied().unwrap_or(Some(idx)))
+            .collect();
+    }
+
+    pub(super) fn parent(&self, idx: u32) -> Option<u32> {
+        self.parents.get(idx as usize).copied().flatten()
+    }
+
+    pub(super) fn has_children(&self, idx: u32) -> bool {
+        let idx = idx as usize;
+        self.depths
+            .get(idx + 1)
+            .is_some_and(|&depth| depth > self.depths[idx])
+    }
+
+    /// The rows shown for the matched items, in input order: each is preceded by its ancestors,
+    /// and those below a collapsed node are hidden. Items in `skip` are left out.
+    pub(super) fn layout(
+        &mut self,
+        matched: impl IntoIterator<Item = u32>,
+        skip: &HashSet<u32>,
+    ) -> Vec<u32> {
+        // the matches and their ancestors, marked in `shown` so that each is visited once
+        self.shown.resize(self.depths.len(), false);
+        let mut marked = Vec::new();
+        for idx in matched {
+            let mut next = Some(idx);
+            while let Some(i) = next
+                && let Some(false) = self.shown.get(i as usize)
+            {
+                self.shown[i as usize] = true;
+                marked.push(i);
+                next = self.parent(i);
+            }
+        }
+        marked.sort_unstable();
+        for &idx in &marked {
+            self.shown[idx as usize] = false;
+        }
+
+        let mut rows = Vec::new();
+        let mut hidden_below = None;
+        for idx in marked {
+            let depth = self.depths[idx as usize];
+            if hidden_below.is_some_and(|d| depth > d) {
+                continue;
+            }
+            hidden_below = self.collapsed.contains(&idx).then_some(depth);
+            if !skip.contains(&idx) {
+                rows.push(idx);
+            }
+        }
+
+        self.branches = self.branches(&rows);
+        rows
+    }
+
+    /// The branch drawn before each row, connecting it to its parent.
+    fn branches(&self, rows: &[u32]) -> Vec<String> {
+        // whether each row is the last of its siblings, found from the end
+        let mut last = vec![false; rows.len()];
+        let mut later: Vec<u16> = Vec::new();
+        for (r, &idx) in rows.iter().enumerate().rev() {
+            let depth = self.depths[idx as usize];
+            while later.last().is_some_and(|&d| d > depth) {
+                later.pop();
+            }
+            last[r] = later.last() != Some(&depth);
+            if last[r] {
+                later.push(depth);
+            }
+        }
+
+        let mut ancestors: Vec<(u16, bool)> = Vec::new();
+        rows.iter()
+            .zip(last)
+            .map(|(&idx, is_last)| {
+                let depth = self.depths[idx as usize];
+                while ancestors.last().is_some_and(|&(d, _)| d >= depth) {
+                    ancestors.pop();
+                }
+                let folded = self.collapsed.contains(&idx) && self.has_children(idx);
+
+                let mut branch = String::new();
+                if ancestors.is_empty() {
+                    if folded {
+                        branch.push_str("▸ ");
+                    }
+                } else {
+                    // the roots have no branch to continue
+                    for &(_, ancestor_last) in &ancestors[1..] {
+                        branch.push_str(if ancestor_last { "   " } else { "│  " });
+                    }
+                    branch.push_str(if is_last { "└─" } else { "├─" });
+                    branch.push(if folded { '▸' } else { ' ' });
+                }
+                ancestors.push((depth, is_last));
+                branch
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layout() {
+        let items = [0, 1, 2, 1, 0];
+        let mut tree = Tree::new(Arc::new(|depth: &u16| *depth));
+        tree.read(3, |idx| &items[idx as usize], &HashSet::new());
+        // only the new items are read
+        tree.read(5, |idx| &items[idx as usize], &HashSet::new());
+        assert_eq!(tree.parent(2), Some(1));
+        assert_eq!(tree.parent(3), Some(0));
+
+        let rows = tree.layout(0..5, &HashSet::new());
+        assert_eq!(rows, [0, 1, 2, 3, 4]);
+        assert_eq!(tree.branches, ["", "├─ ", "│  └─ ", "└─ ", ""]);
+
+        // ancestors of matches are shown
+        assert_eq!(tree.layout([2], &HashSet::new()), [0, 1, 2]);
+
+        tree.collapsed.insert(1);
+        assert_eq!(tree.layout(0..5, &HashSet::new()), [0, 1, 3, 4]);
+        assert_eq!(tree.branches, ["", "├─▸", "└─ ", ""]);
+    }
+}
@@ -1,6 +1,6 @@
 use super::{
     Text, injector,
-    worker::{Column, Worker},
+    worker::{Column, EditMap, Worker},
 };
 use crate::{RenderFn, SSS};
 use std::{borrow::Cow, sync::Arc};
@@ -92,6 +92,51 @@ impl<T: SSS, D: 'static> Worker<T, D> {
     }
 }
 
+impl<T: SSS + Clone, D: 'static> Worker<T, D> {
+    /// Remove the item at the nucleo index (see [`nucleo::Match::idx`]).
+    ///
+    /// See [`Self::edit`] for how indices change.
+    pub fn remove(&mut self, idx: u32) -> Vec<Option<u32>> {
+        self.edit([(idx, None)])
+    }
+
+    /// Replace the item at the nucleo index (see [`nucleo::Match::idx`]).
+    ///
+    /// See [`Self::edit`] for how indices change.
+    pub fn update(&mut self, idx: u32, item: T) -> Vec<Option<u32>> {
+        self.edit([(idx, Some(item))])
+    }
+
+    /// Remove (`None`) or replace items by nucleo index.
+    ///
+    /// Nucleo can only append, so the edited items are tombstoned, which hides them from the
+    /// results, and replacements are pushed as new items. The returned map gives the new index
+    /// of each edited index, or `None` if the item was removed; the indices of the other items
+    /// don't change. Pinned items stay pinned and collapsed tree nodes stay collapsed unless
+    /// removed.
+    pub fn edit(&mut self, edits: impl IntoIterator<Item = (u32, Option<T>)>) -> EditMap {
+        let injector = self.nucleo.injector();
+        let count = injector.injected_items();
+        let mut map = EditMap::new();
+        for (idx, replacement) in edits {
+            if idx >= count || !self.removed.insert(idx) {
+                continue;
+            }
+            let new = replacement.and_then(|item| {
+                let d = (self.raw_preprocessor)(&item)?;
+                Some(injector::push_impl(&injector, &self.columns, item, &d))
+            });
+            map.insert(idx, new);
+        }
+
+        if !map.is_empty() {
+            self.remap(&map);
+            self.rank();
+        }
+        map
+    }
+}
+
 /// You must either impl as_str or as_text
 pub trait Render {
     fn as_str(&self) -> std::borrow::Cow<'_, str> {
@@ -207,3 +252,91 @@ where
         Self::new(columns, default_index, raw_preprocessor, text_preprocessor)
     }
 }
+
+/// Items whose columns have names, usually implemented with `#[derive(Columns)]`.
+///
+/// # Example
+/// ```rust
+/// use matchmaker::Matchmaker;
+/// use matchmaker::nucleo::{Columns, Worker, human_bytes};
+///
+/// #[derive(Clone, Columns)]
+/// pub struct File {
+///     name: String,
+///     #[column(name = "Size", format = "human_bytes")]
+///     size: u64,
+///     #[column(skip)]
+///     inode: u64,
+/// }
+///
+/// assert_eq!(File::COLUMN_NAMES, ["name", "Size"]);
+///
+/// pub fn make_mm(items: impl Iterator<Item = File>) -> Matchmaker<File, File> {
+///     let worker = Worker::new_columns(None);
+///     worker.append(items);
+///     Matchmaker::new_on_cloneable(worker)
+/// }
+/// ```
+pub trait Columns: ColumnIndexable {
+    /// The names of the columns, in order of their indices.
+    const COLUMN_NAMES: &'static [&'static str];
+}
+
+impl<T> Worker<T, ()>
+where
+    T: Columns + SSS,
+{
+    /// Create a new worker over items whose columns are named by [`Columns::COLUMN_NAMES`].
+    pub fn new_columns(default_column: Option<crate::config_types::StringOrInt>) -> Self {
+        Self::new_indexable(T::COLUMN_NAMES.iter().copied(), default_column)
+    }
+}
+
+/// Format a number of bytes with a binary unit, such as `1.5 KiB`.
+///
+/// ```rust
+/// use matchmaker::nucleo::human_bytes;
+///
+/// assert_eq!(human_bytes(&512u32), "512 B");
+/// assert_eq!(human_bytes(&1536u64), "1.5 KiB");
+/// ```
+pub fn human_bytes<N: Copy + TryInto<u64>>(n: &N) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    let bytes = (*n).try_into().unwrap_or(u64::MAX);
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edit() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["a", "b", "c", "d"].map(String::from));
+
+        let map = worker.edit([(1, None), (2, Some("C".to_string())), (1, None)]);
+        assert_eq!(map, EditMap::from([(1, None), (2, Some(4))]));
+        assert!(worker.edit([(2, None), (9, None)]).is_empty());
+
+        while worker.refresh().running {}
+        let results: Vec<_> = worker.matched_results().cloned().collect();
+        assert_eq!(results, ["a", "d", "C"]);
+        assert_eq!(worker.counts(), (3, 3));
+        assert_eq!(worker.get_by_idx(1), None);
+
+        worker.find("c");
+        while worker.refresh().running {}
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [4]);
+    }
+}
@@ -6,14 +6,51 @@ use bitflags::bitflags;
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
     sync::{
         Arc,
         atomic::{self, AtomicU32},
     },
 };
 
-use super::{injector::WorkerInjector, query::PickerQuery};
-use crate::{SSS, config::StringOrInt};
+use super::{
+    injector::WorkerInjector,
+    query::{PickerQuery, exact_terms, split_alternations},
+    tree::{DepthFn, Tree},
+};
+use crate::{
+    SSS,
+    config::{StringOrInt, Tiebreak},
+    utils::span,
+};
+
+/// Comparison passed to [`nucleo::Nucleo::sort_with`], returning whether the first item goes first.
+pub type SortFn<T> = Arc<dyn Fn((u32, &T), (u32, &T)) -> bool + Send + Sync>;
+
+/// A bonus for an item, ranking it ahead of results with the same score and a lower bonus.
+pub type BoostFn<T> = Arc<dyn Fn(&T) -> f64 + Send + Sync>;
+
+/// Adjusts the score of a matched item, given the score from nucleo. Results are ranked by the
+/// adjusted score, highest first.
+pub type ScoreFn<T> = Arc<dyn Fn(&T, u32) -> u32 + Send + Sync>;
+
+/// The name of the group of an item. See [`Worker::set_group_by`].
+pub type GroupFn<T> = Arc<dyn Fn(&T) -> String + Send + Sync>;
+
+/// The new index of each edited item, or `None` if it was removed. See [`Worker::edit`].
+pub type EditMap = HashMap<u32, Option<u32>>;
+
+/// A header row of the results, followed by the items of the group unless it is collapsed.
+#[derive(Debug, Clone)]
+pub struct Group {
+    pub name: Arc<str>,
+    /// The number of matched items in the group, including hidden ones.
+    pub len: u32,
+    pub collapsed: bool,
+    /// The row of the header.
+    pub row: u32,
+}
 
 type ColumnFormatFn<T, D> = Box<dyn for<'a> Fn(&'a T, &'a D) -> Text<'a> + Send + Sync>;
 type ColumnRawFn<T, D> = Box<dyn for<'a> Fn(&'a T, &'a D) -> Cow<'a, str> + Send + Sync>;
@@ -96,8 +133,52 @@ where
 
     // Background tasks which push to the injector check their version matches this or exit
     pub(super) version: Arc<AtomicU32>,
+    // Held by every injector, so that the worker can tell whether input may still arrive
+    pub(super) injectors: Arc<()>,
     // pub settings: WorkerSettings,
     column_options: Vec<ColumnOptions>,
+    // Passed to nucleo, ordering equal scores by the boost
+    sort: Option<SortFn<T>>,
+    // Whether nucleo's order was replaced by [`Self::sort_with`], which tiebreaks don't reorder
+    sorted: bool,
+    tiebreak_criteria: Vec<Tiebreak>,
+    // The tiebreak keys by item index, computed once per query when scores are equal
+    tiebreak_keys: HashMap<u32, TiebreakKey>,
+    boost: Option<BoostFn<T>>,
+    score_fn: Option<ScoreFn<T>>,
+    // The adjusted score by item index, and the score it was adjusted from
+    scores: HashMap<u32, (u32, u32)>,
+    // Item indices of the kept matches, in ranked order, and the number of matches they were
+    // taken from. The results are laid out from these without ranking them again.
+    ranking: Option<(Vec<u32>, usize)>,
+    // Item indices of the unpinned results, in the order they are shown, and the number of
+    // matches they were taken from. None if the matches are shown as nucleo sorts them.
+    order: Option<(Vec<u32>, usize)>,
+    // Indices of the items shown before the matches, see [`Self::pin`]
+    pinned: Vec<u32>,
+    // The same, for looking them up
+    pinned_set: HashSet<u32>,
+    // Indices of the items removed or replaced by [`Self::edit`], which are left out of the results
+    pub(super) removed: HashSet<u32>,
+    group_fn: Option<GroupFn<T>>,
+    // The groups of the ranked results, in the order they are shown
+    groups: Vec<Group>,
+    // Names of the collapsed groups, kept when the results are ranked again
+    collapsed: HashSet<Arc<str>>,
+    tree: Option<Tree<T>>,
+    // The `|` alternations of the query, by matcher column, of which the results must match one
+    // alternative each. Nucleo has no alternation, so they are applied when ranking.
+    alternations: Vec<(usize, Vec<nucleo::pattern::Pattern>)>,
+    // The summed score of the best alternative of each alternation by item index, or None if an
+    // alternation has no match. Items are only scored once until the alternations change.
+    alternation_scores: HashMap<u32, Option<u32>>,
+    // Scores the alternations and locates the matches for the tiebreak
+    matcher: nucleo::Matcher,
+    indices: Vec<u32>,
+    // Whether queries are ignored
+    disabled: bool,
+    case_sensitive: bool,
+    exact: bool,
 }
 
 // #[derive(Debug, Default)]
@@ -143,23 +224,122 @@ where
             raw_preprocessor,
             text_preprocessor,
             version: Arc::new(AtomicU32::new(0)),
+            injectors: Arc::new(()),
+            sort: None,
+            sorted: false,
+            tiebreak_criteria: Vec::new(),
+            tiebreak_keys: HashMap::new(),
+            boost: None,
+            score_fn: None,
+            scores: HashMap::new(),
+            ranking: None,
+            order: None,
+            pinned: Vec::new(),
+            pinned_set: HashSet::new(),
+            removed: HashSet::new(),
+            group_fn: None,
+            groups: Vec::new(),
+            collapsed: HashSet::new(),
+            tree: None,
+            alternations: Vec::new(),
+            alternation_scores: HashMap::new(),
+            matcher: nucleo::Matcher::new(nucleo::Config::DEFAULT),
+            indices: Vec::new(),
+            disabled: false,
+            case_sensitive: false,
+            exact: false,
         }
     }
 
     pub fn set_column_options(&mut self, index: usize, options: ColumnOptions) {
-        if options.contains(ColumnOptions::Optional) {
+        if options.contains(ColumnOptions::Optional)
+            && let Some(i) = self.matcher_index(index)
+        {
             self.nucleo
                 .pattern
-                .configure_column(index, nucleo::pattern::Variant::Optional)
+                .configure_column(i, nucleo::pattern::Variant::Optional)
         }
 
         self.column_options[index] = options
     }
 
+    /// Ignore the query passed to [`Worker::find`], so that every item matches.
+    pub fn disable_matching(&mut self, disabled: bool) {
+        self.disabled = disabled;
+    }
+
+    pub fn matching_disabled(&self) -> bool {
+        self.disabled
+    }
+
     pub fn reverse_items(&mut self, reverse_items: bool) {
         self.nucleo.reverse_items(reverse_items);
     }
 
+    /// Order results with equal scores by each criterion in turn, and then by index.
+    ///
+    /// As in fzf, `Begin` and `End` are the distances of the first and last matched characters
+    /// of the primary column from the start and end of its text.
+    pub fn set_tiebreak(&mut self, tiebreak: &[Tiebreak]) {
+        // index is the final criterion regardless
+        self.tiebreak_criteria = tiebreak
+            .iter()
+            .copied()
+            .take_while(|t| *t != Tiebreak::Index)
+            .collect();
+        self.rank();
+    }
+
+    /// Order results with equal scores by the given bonus first, highest first, followed by the
+    /// criteria of [`Self::set_tiebreak`].
+    pub fn set_boost(&mut self, boost: Option<BoostFn<T>>) {
+        self.boost = boost;
+        self.build_sort();
+    }
+
+    /// Rank results by an adjusted score, e.g. to boost recently used items or penalize hidden
+    /// files. Items with equal adjusted scores keep the order of [`Self::set_tiebreak`].
+    ///
+    /// Scores are adjusted once per item and nucleo score, so the function should only depend on
+    /// its arguments.
+    pub fn set_score_fn(&mut self, score_fn: Option<ScoreFn<T>>) {
+        self.score_fn = score_fn;
+        self.scores.clear();
+        self.rank();
+    }
+
+    fn build_sort(&mut self) {
+        self.sort = self.boost.clone().map(|boost| {
+            Arc::new(move |(ia, a): (u32, &T), (ib, b): (u32, &T)| {
+                match boost(b).total_cmp(&boost(a)) {
+                    Ordering::Equal => ia < ib,
+                    o => o.is_lt(),
+                }
+            }) as SortFn<T>
+        });
+
+        if !self.sorted {
+            self.restore_sort();
+        }
+    }
+
+    /// Order the results by a custom comparison instead of by score, until [`Self::restore_sort`].
+    pub fn sort_with(&mut self, sort: SortFn<T>) {
+        self.sorted = true;
+        self.nucleo.sort_with(Some(sort));
+        self.nucleo.resort();
+        self.rank();
+    }
+
+    /// Restore the ordering by score, [`Self::set_boost`] and [`Self::set_tiebreak`], i.e. after
+    /// [`Self::sort_with`].
+    pub fn restore_sort(&mut self) {
+        self.sorted = false;
+        self.nucleo.sort_with(self.sort.clone());
+        self.nucleo.resort();
+        self.rank();
+    }
+
     pub fn injector(&self) -> WorkerInjector<T, D> {
         WorkerInjector {
             inner: self.nucleo.injector(),
@@ -167,18 +347,39 @@ where
             raw_preprocessor: self.raw_preprocessor.clone(),
             version: self.version.load(atomic::Ordering::Relaxed),
             picker_version: self.version.clone(),
+            token: self.injectors.clone(),
         }
     }
 
+    /// Whether any injector is still alive, i.e. items are still being read.
+    pub fn reading(&self) -> bool {
+        Arc::strong_count(&self.injectors) > 1
+    }
+
     pub fn find(&mut self, line: &str) {
+        let line = if self.disabled { "" } else { line };
         let old_query = self.query.parse(line);
         if self.query == old_query {
             return;
         }
-        for (i, column) in self
+        self.reparse(Some(&old_query));
+    }
+
+    /// Pass the patterns of the query to nucleo. Only those which differ from `old_query` are
+    /// reparsed, or all of them if it is None.
+    fn reparse(&mut self, old_query: Option<&HashMap<Arc<str>, Arc<str>>>) {
+        let case_matching = if self.case_sensitive {
+            nucleo::pattern::CaseMatching::Respect
+        } else {
+            nucleo::pattern::CaseMatching::Smart
+        };
+        self.tiebreak_keys.clear();
+        let mut alternations_changed = false;
+        for (i, (j, column)) in self
             .columns
             .iter()
-            .filter(|column| column.filter)
+            .enumerate()
+            .filter(|(_, column)| column.filter)
             .enumerate()
         {
             let pattern = self
@@ -186,49 +387,137 @@ where
                 .get(&column.name)
                 .map(|s| &**s)
                 .unwrap_or_else(|| {
-                    self.column_options[i]
+                    self.column_options[j]
                         .contains(ColumnOptions::OrUseDefault)
                         .then(|| self.query.primary_column_query())
                         .flatten()
                         .unwrap_or_default()
                 });
 
-            let old_pattern = old_query
-                .get(&column.name)
-                .map(|s| &**s)
-                .unwrap_or_else(|| {
-                    self.column_options[i]
-                        .contains(ColumnOptions::OrUseDefault)
-                        .then(|| {
-                            let name = self.query.primary_column_name()?;
-                            old_query.get(name).map(|s| &**s)
-                        })
-                        .flatten()
-                        .unwrap_or_default()
-                });
+            let old_pattern = old_query.map(|old_query| {
+                old_query
+                    .get(&column.name)
+                    .map(|s| &**s)
+                    .unwrap_or_else(|| {
+                        self.column_options[j]
+                            .contains(ColumnOptions::OrUseDefault)
+                            .then(|| {
+                                let name = self.query.primary_column_name()?;
+                                old_query.get(name).map(|s| &**s)
+                            })
+                            .flatten()
+                            .unwrap_or_default()
+                    })
+            });
 
             // Fastlane: most columns will remain unchanged after each edit.
-            if pattern == old_pattern {
+            if old_pattern == Some(pattern) {
                 continue;
             }
 
-            let is_append = pattern.starts_with(old_pattern);
+            let (pattern, alternations) = split_alternations(pattern);
+            let is_append = old_pattern.is_some_and(|old_pattern| {
+                let (old_pattern, _) = split_alternations(old_pattern);
+                pattern.starts_with(&old_pattern)
+            });
 
+            let terms = self.exact_terms(&pattern);
             self.nucleo.pattern.reparse(
                 i,
-                pattern,
-                nucleo::pattern::CaseMatching::Smart,
+                &terms,
+                case_matching,
                 nucleo::pattern::Normalization::Smart,
                 is_append,
             );
+
+            let len = self.alternations.len();
+            self.alternations.retain(|(column, _)| *column != i);
+            alternations_changed |= self.alternations.len() != len || !alternations.is_empty();
+            let alternations: Vec<_> = alternations
+                .into_iter()
+                .map(|alternatives| {
+                    let alternatives = alternatives
+                        .into_iter()
+                        .map(|alternative| {
+                            nucleo::pattern::Pattern::parse(
+                                &self.exact_terms(alternative),
+                                case_matching,
+                                nucleo::pattern::Normalization::Smart,
+                            )
+                        })
+                        .collect();
+                    (i, alternatives)
+                })
+                .collect();
+            self.alternations.extend(alternations);
+        }
+
+        // the matches are the same if only the alternations changed
+        if alternations_changed {
+            self.alternation_scores.clear();
+            self.rank();
+        }
+    }
+
+    fn exact_terms<'a>(&self, pattern: &'a str) -> Cow<'a, str> {
+        if self.exact {
+            exact_terms(pattern).into()
+        } else {
+            pattern.into()
+        }
+    }
+
+    /// Match case-sensitively, rather than only when the query contains an uppercase letter.
+    ///
+    /// Prefer [`crate::ui::PickerUI::toggle_case`] while the picker is shown.
+    pub fn set_case_sensitive(&mut self, case_sensitive: bool) {
+        if self.case_sensitive != case_sensitive {
+            self.case_sensitive = case_sensitive;
+            self.reparse(None);
+        }
+    }
+
+    pub fn case_sensitive(&self) -> bool {
+        self.case_sensitive
+    }
+
+    /// Match the terms of the query as substrings, as if they were quoted with `'`. Quoted terms
+    /// are matched fuzzily instead.
+    pub fn set_exact(&mut self, exact: bool) {
+        if self.exact != exact {
+            self.exact = exact;
+            self.reparse(None);
         }
     }
 
+    pub fn exact(&self) -> bool {
+        self.exact
+    }
+
     // --------- UTILS
-    /// matched item count, total item count
+    /// The index of a column among those passed to nucleo, or None if it is not used for matching.
+    pub fn matcher_index(&self, column: usize) -> Option<usize> {
+        matcher_index(&self.columns, column)
+    }
+
+    /// The alternatives of each `|` alternation of the query in a matcher column.
+    pub fn alternations(
+        &self,
+        matcher_column: usize,
+    ) -> impl Iterator<Item = &[nucleo::pattern::Pattern]> + '_ {
+        self.alternations
+            .iter()
+            .filter(move |(column, _)| *column == matcher_column)
+            .map(|(_, alternatives)| alternatives.as_slice())
+    }
+
+    /// matched item count (including pinned items), total item count (excluding removed items)
     pub fn counts(&self) -> (u32, u32) {
-        let snapshot = self.nucleo.snapshot();
-        (snapshot.matched_item_count(), snapshot.item_count())
+        let count = self.nucleo.snapshot().item_count();
+        (
+            self.item_count(),
+            count.saturating_sub(self.removed.len() as u32),
+        )
     }
 
     pub fn set_stability(&mut self, threshold: u32) {
@@ -240,31 +529,460 @@ where
         self.nucleo.get_stability()
     }
 
+    /// Refresh the snapshot, ranking the matches by [`Self::set_score_fn`] if they changed.
+    ///
+    /// The matched count of the returned status includes the pinned items, and the row count
+    /// also includes the group headers.
+    pub fn refresh(&mut self) -> Status {
+        span!("worker_tick");
+        let (_, mut status) = new_snapshot(&mut self.nucleo);
+        if status.changed
+            || self
+                .order
+                .as_ref()
+                .is_some_and(|(_, len)| *len != status.matched_count as usize)
+        {
+            self.rank();
+        }
+        status.matched_count = self.item_count();
+        status.row_count = self.row_count();
+        status
+    }
+
+    /// Score and sort the matches, then lay them out. Only the items whose score changed are
+    /// scored again.
+    fn rank(&mut self) {
+        let tiebreak = !self.sorted && !self.tiebreak_criteria.is_empty();
+        if self.score_fn.is_none()
+            && !tiebreak
+            && self.pinned.is_empty()
+            && self.group_fn.is_none()
+            && self.tree.is_none()
+            && self.alternations.is_empty()
+            && self.removed.is_empty()
+        {
+            self.groups.clear();
+            self.ranking = None;
+            self.order = None;
+            return;
+        }
+        let snapshot = self.nucleo.snapshot();
+        let matches = snapshot.matches();
+
+        let mut kept: Vec<&nucleo::Match> = matches
+            .iter()
+            .filter(|m| !self.removed.contains(&m.idx))
+            .collect();
+        if !self.alternations.is_empty() {
+            let alternations = &self.alternations;
+            let matcher = &mut self.matcher;
+            kept.retain(|m| {
+                self.alternation_scores
+                    .entry(m.idx)
+                    .or_insert_with(|| {
+                        // SAFETY: `idx` is taken from a match in the current snapshot
+                        let item = unsafe { snapshot.get_item_unchecked(m.idx) };
+                        alternation_score(alternations, &item, matcher)
+                    })
+                    .is_some()
+            });
+        }
+
+        // the tree lays out the matches in input order
+        if self.tree.is_some() {
+            self.ranking = Some((kept.iter().map(|m| m.idx).collect(), matches.len()));
+            self.layout();
+            return;
+        }
+
+        let mut scored: Vec<(u32, u32)> = kept
+            .into_iter()
+            .map(|m| {
+                let bonus = self.alternation_scores.get(&m.idx).copied().flatten();
+                let score = m.score + bonus.unwrap_or(0);
+                match &self.score_fn {
+                    Some(score_fn) => match self.scores.get(&m.idx) {
+                        Some(&(base, adjusted)) if base == score => (adjusted, m.idx),
+                        _ => {
+                            // SAFETY: `idx` is taken from a match in the current snapshot
+                            let item = unsafe { snapshot.get_item_unchecked(m.idx) };
+                            let adjusted = score_fn(item.data, score);
+                            self.scores.insert(m.idx, (score, adjusted));
+                            (adjusted, m.idx)
+                        }
+                    },
+                    // nucleo's order already ranks by score
+                    None if bonus.is_none() && !tiebreak => (0, m.idx),
+                    None => (score, m.idx),
+                }
+            })
+            .collect();
+        // stable, so that equal scores keep nucleo's order
+        scored.sort_by(|a, b| b.0.cmp(&a.0));
+
+        if tiebreak {
+            let column = self.matcher_index(self.query.primary_column_index());
+            for run in scored.chunk_by_mut(|a, b| a.0 == b.0) {
+                if run.len() < 2 {
+                    continue;
+                }
+                for &(_, idx) in run.iter() {
+                    if self.tiebreak_keys.contains_key(&idx) {
+                        continue;
+                    }
+                    // SAFETY: `idx` is taken from a match in the current snapshot
+                    let item = unsafe { snapshot.get_item_unchecked(idx) };
+                    let key = column.and_then(|column| {
+                        TiebreakKey::new(
+                            &item,
+                            self.boost.as_ref(),
+                            column,
+                            &self.nucleo.pattern,
+                            &mut self.matcher,
+                            &mut self.indices,
+                        )
+                    });
+                    // keys of matches from before the query changed are computed again
+                    if let Some(key) = key {
+                        self.tiebreak_keys.insert(idx, key);
+                    }
+                }
+                let keys = &self.tiebreak_keys;
+                let criteria = &self.tiebreak_criteria;
+                run.sort_by(|&(_, a), &(_, b)| match (keys.get(&a), keys.get(&b)) {
+                    (Some(ka), Some(kb)) => ka.cmp(kb, criteria).then(a.cmp(&b)),
+                    _ => Ordering::Equal,
+                });
+            }
+        }
+
+        self.ranking = Some((
+            scored.into_iter().map(|(_, idx)| idx).collect(),
+            matches.len(),
+        ));
+        self.layout();
+    }
+
+    /// Lay out the ranked matches into the shown order, leaving out the pinned items, and
+    /// grouping or arranging them as a tree. The matches are ranked first if they changed.
+    fn layout(&mut self) {
+        let len = self.nucleo.snapshot().matches().len();
+        let Some((ranked, _)) = self.ranking.as_ref().filter(|(_, l)| *l == len) else {
+            return self.rank();
+        };
+        self.groups.clear();
+
+        if let Some(tree) = &mut self.tree {
+            let snapshot = self.nucleo.snapshot();
+            tree.read(
+                snapshot.item_count(),
+                // SAFETY: the indices are below the item count of the snapshot
+                |idx| unsafe { snapshot.get_item_unchecked(idx) }.data,
+                &self.removed,
+            );
+            let order = tree.layout(ranked.iter().copied(), &self.pinned_set);
+            self.order = Some((order, len));
+            return;
+        }
+
+        let mut order: Vec<u32> = ranked
+            .iter()
+            .copied()
+            .filter(|idx| !self.pinned_set.contains(idx))
+            .collect();
+
+        if let Some(group_fn) = &self.group_fn {
+            let snapshot = self.nucleo.snapshot();
+            // groups are shown in the order of their best result
+            let mut names: Vec<String> = Vec::new();
+            let mut index: HashMap<String, usize> = HashMap::new();
+            let mut grouped: Vec<(usize, u32)> = order
+                .iter()
+                .map(|&idx| {
+                    // SAFETY: `idx` is taken from a match in the current snapshot
+                    let item = unsafe { snapshot.get_item_unchecked(idx) };
+                    let name = group_fn(item.data);
+                    let group = *index.entry(name).or_insert_with_key(|name| {
+                        names.push(name.clone());
+                        names.len() - 1
+                    });
+                    (group, idx)
+                })
+                .collect();
+            grouped.sort_by_key(|(group, _)| *group);
+
+            order.clear();
+            let mut row = self.pinned.len() as u32;
+            let mut rest = grouped.as_slice();
+            for (i, name) in names.into_iter().enumerate() {
+                let len = rest.iter().take_while(|(group, _)| *group == i).count();
+                let (members, tail) = rest.split_at(len);
+                rest = tail;
+
+                let name: Arc<str> = name.into();
+                let collapsed = self.collapsed.contains(&name);
+                self.groups.push(Group {
+                    name,
+                    len: len as u32,
+                    collapsed,
+                    row,
+                });
+                row += 1;
+                if !collapsed {
+                    order.extend(members.iter().map(|(_, idx)| *idx));
+                    row += len as u32;
+                }
+            }
+        }
+        self.order = Some((order, len));
+    }
+
+    /// The item indices of the ranked results, if they are up to date.
+    fn ranked(&self) -> Option<&[u32]> {
+        let (order, len) = self.order.as_ref()?;
+        (*len == self.nucleo.snapshot().matches().len()).then_some(order.as_slice())
+    }
+
+    /// The number of results, including the pinned items but not the group headers.
+    fn item_count(&self) -> u32 {
+        let unpinned = match self.ranked() {
+            Some(order) => order.len(),
+            None => self.nucleo.snapshot().matches().len(),
+        };
+        (self.pinned.len() + unpinned) as u32
+    }
+
+    /// The number of rows, including the group headers.
+    fn row_count(&self) -> u32 {
+        self.item_count() + self.groups().len() as u32
+    }
+
+    /// The item index of the k-th result, not counting group headers.
+    fn item_idx(&self, k: u32) -> Option<u32> {
+        if let Some(&idx) = self.pinned.get(k as usize) {
+            return Some(idx);
+        }
+        let k = k - self.pinned.len() as u32;
+        match self.ranked() {
+            Some(order) => order.get(k as usize).copied(),
+            None => Some(self.nucleo.snapshot().matches().get(k as usize)?.idx),
+        }
+    }
+
+    /// The row of an item, or None if it isn't shown.
+    pub fn row_of(&self, idx: u32) -> Option<u32> {
+        let k = (0..self.item_count()).position(|k| self.item_idx(k) == Some(idx))? as u32;
+        // each header before the item is a row of its own
+        let headers = (0..)
+            .zip(self.groups())
+            .take_while(|(i, g)| g.row - i <= k)
+            .count();
+        Some(k + headers as u32)
+    }
+
+    /// The item index of the n-th row, or None if it is a group header.
+    fn nth_idx(&self, n: u32) -> Option<u32> {
+        let groups = self.groups();
+        let headers = groups.partition_point(|g| g.row < n);
+        if groups.get(headers).is_some_and(|g| g.row == n) {
+            return None;
+        }
+        self.item_idx(n - headers as u32)
+    }
+
+    /// Cluster the results under header rows, by the name returned for each item, e.g. "Recent"
+    /// and "All files". Groups are shown in the order of their best result.
+    ///
+    /// The header rows can't be selected, and are skipped by the cursor unless collapsed.
+    pub fn set_group_by(&mut self, group_fn: Option<GroupFn<T>>) {
+        self.group_fn = group_fn;
+        self.layout();
+    }
+
+    /// The groups of the results, in the order they are shown. Empty if they aren't grouped.
+    pub fn groups(&self) -> &[Group] {
+        if self.ranked().is_some() {
+            &self.groups
+        } else {
+            &[]
+        }
+    }
+
+    /// The group whose header is the n-th row.
+    pub fn group_at(&self, n: u32) -> Option<&Group> {
+        let groups = self.groups();
+        let i = groups.binary_search_by_key(&n, |g| g.row).ok()?;
+        Some(&groups[i])
+    }
+
+    /// The group of the n-th row, which is either its header or one of its items.
+    pub fn group_of(&self, n: u32) -> Option<&Group> {
+        let groups = self.groups();
+        let i = groups.partition_point(|g| g.row <= n).checked_sub(1)?;
+        Some(&groups[i])
+    }
+
+    /// Hide the items of a group, keeping its header, or show them again.
+    ///
+    /// Prefer [`crate::ui::PickerUI::toggle_group`] while the picker is shown.
+    pub fn set_collapsed(&mut self, name: &str, collapsed: bool) {
+        if collapsed {
+            self.collapsed.insert(name.into());
+        } else {
+            self.collapsed.remove(name);
+        }
+        self.layout();
+    }
+
+    /// Collapse or expand every group.
+    pub fn set_all_collapsed(&mut self, collapsed: bool) {
+        if collapsed {
+            let names: Vec<_> = self.groups.iter().map(|g| g.name.clone()).collect();
+            self.collapsed.extend(names);
+        } else {
+            self.collapsed.clear();
+        }
+        self.layout();
+    }
+
+    /// Show the items as a tree, given the depth of each: the parent of an item is the closest
+    /// item before it with a lower depth. Matches are shown in input order, after their
+    /// ancestors, and the branches are drawn in the gutter.
+    ///
+    /// Replaces the ranking of [`Self::set_score_fn`] and [`Self::set_group_by`].
+    pub fn set_tree(&mut self, depth_fn: Option<DepthFn<T>>) {
+        self.tree = depth_fn.map(Tree::new);
+        self.rank();
+    }
+
+    /// The branch drawn before the n-th row in tree mode.
+    pub fn tree_prefix(&self, n: u32) -> Option<&str> {
+        let tree = self.tree.as_ref()?;
+        self.ranked()?;
+        let k = n.checked_sub(self.pinned.len() as u32)?;
+        tree.branches.get(k as usize).map(String::as_str)
+    }
+
+    /// The parent of an item in tree mode.
+    pub fn tree_parent(&self, idx: u32) -> Option<u32> {
+        self.tree.as_ref()?.parent(idx)
+    }
+
+    /// Hide the descendants of an item in tree mode, or show them again.
+    ///
+    /// Returns whether the item has descendants and wasn't already in that state. Prefer
+    /// [`crate::ui::PickerUI::expand_node`] and [`crate::ui::PickerUI::collapse_node`] while the
+    /// picker is shown.
+    pub fn set_node_collapsed(&mut self, idx: u32, collapsed: bool) -> bool {
+        let Some(tree) = &mut self.tree else {
+            return false;
+        };
+        if !tree.has_children(idx) {
+            return false;
+        }
+        let changed = if collapsed {
+            tree.collapsed.insert(idx)
+        } else {
+            tree.collapsed.remove(&idx)
+        };
+        if changed {
+            self.layout();
+        }
+        changed
+    }
+
+    /// Move the pinned items and the collapsed tree nodes to their new indices.
+    pub(super) fn remap(&mut self, map: &EditMap) {
+        self.pinned = self
+            .pinned
+            .iter()
+            .filter_map(|&idx| map.get(&idx).copied().unwrap_or(Some(idx)))
+            .collect();
+        self.pinned_set = self.pinned.iter().copied().collect();
+        if let Some(tree) = &mut self.tree {
+            tree.remap(map);
+        }
+    }
+
+    /// Show an item before the matches, whether or not it matches the query, e.g. for a
+    /// "Create new" entry. Items are shown in the order they are pinned.
+    ///
+    /// Returns false if the item has not been read yet, or is already pinned. Pins are cleared
+    /// when the worker is restarted.
+    ///
+    /// Prefer [`crate::ui::PickerUI::pin`] while the picker is shown.
+    pub fn pin(&mut self, idx: u32) -> bool {
+        if idx >= self.nucleo.snapshot().item_count()
+            || self.removed.contains(&idx)
+            || !self.pinned_set.insert(idx)
+        {
+            return false;
+        }
+        self.pinned.push(idx);
+        self.layout();
+        true
+    }
+
+    /// Returns whether the item was pinned.
+    pub fn unpin(&mut self, idx: u32) -> bool {
+        if !self.pinned_set.remove(&idx) {
+            return false;
+        }
+        self.pinned.retain(|&p| p != idx);
+        self.layout();
+        true
+    }
+
+    /// The indices of the pinned items, in the order they are shown.
+    pub fn pinned(&self) -> &[u32] {
+        &self.pinned
+    }
+
     /// Prefer [`crate::ui::PickerUI::restart`]
     pub fn restart(&mut self, clear_snapshot: bool) {
         self.nucleo.restart(clear_snapshot);
+        self.pinned.clear();
+        self.pinned_set.clear();
+        self.removed.clear();
+        self.alternation_scores.clear();
+        self.tiebreak_keys.clear();
+        self.scores.clear();
+        if let Some(tree) = &mut self.tree {
+            tree.reset();
+            tree.collapsed.clear();
+        }
+        self.rank();
     }
 
     // ------------------------- GETTERS ---------------------
     pub fn get_nth(&self, n: u32) -> Option<&T> {
-        self.nucleo
-            .snapshot()
-            .get_matched_item(n)
-            .map(|item| item.data)
+        self.get_nth_indexed(n).map(|(_, item)| item)
     }
 
+    /// The item at a nucleo index, unless it was removed by [`Self::edit`].
     pub fn get_by_idx(&self, idx: u32) -> Option<&T> {
+        if self.removed.contains(&idx) {
+            return None;
+        }
         self.nucleo.snapshot().get_item(idx).map(|item| item.data)
     }
 
     pub fn matched_results(&self) -> impl ExactSizeIterator<Item = &T> + DoubleEndedIterator + '_ {
         let snapshot = self.nucleo.snapshot();
-        snapshot.matched_items(..).map(|item| item.data)
+        self.matched_indices().map(move |idx| {
+            // pinned items are checked against the snapshot when pinned, and are remapped or
+            // cleared with its items
+            snapshot
+                .get_item(idx)
+                .expect("results are in the snapshot")
+                .data
+        })
     }
 
+    /// The item indices of the results, starting with the pinned items. The items of collapsed
+    /// groups are left out.
     pub fn matched_indices(&self) -> impl ExactSizeIterator<Item = u32> + DoubleEndedIterator + '_ {
-        let snapshot = self.nucleo.snapshot();
-        snapshot.matches().iter().map(|m| m.idx)
+        (0..self.item_count()).map(move |k| self.item_idx(k).expect("k is below the item count"))
     }
 
     /// Return the nucleo index and a reference to the data of the n-th matched item, if any.
@@ -272,22 +990,13 @@ where
     /// The returned `u32` is the stable nucleo item index (see [`nucleo::Match::idx`]).
     /// Callers can use this as a key into [`crate::Selector`] or as a row-cache key.
     pub fn get_nth_indexed(&self, n: u32) -> Option<(u32, &T)> {
-        let snapshot = self.nucleo.snapshot();
-        let m = snapshot.matches().get(n as usize)?;
-        let idx = m.idx;
-        // SAFETY: `idx` is taken from a match in the snapshot we just took, so it
-        // points to an initialized item in that snapshot.
-        let item = unsafe { snapshot.get_item_unchecked(idx) };
-        Some((idx, item.data))
+        self.get_nth_indexed_item(n)
+            .map(|(idx, item)| (idx, item.data))
     }
 
     pub(crate) fn get_nth_indexed_item(&self, n: u32) -> Option<(u32, nucleo::Item<'_, T>)> {
-        let snapshot = self.nucleo.snapshot();
-        let m = snapshot.matches().get(n as usize)?;
-        let idx = m.idx;
-        // SAFETY: `idx` is taken from a match in the snapshot we just took, so it
-        // points to an initialized item in that snapshot.
-        let item = unsafe { snapshot.get_item_unchecked(idx) };
+        let idx = self.nth_idx(n)?;
+        let item = self.nucleo.snapshot().get_item(idx)?;
         Some((idx, item))
     }
 
@@ -333,10 +1042,94 @@ where
 pub struct Status {
     pub item_count: u32,
     pub matched_count: u32,
+    /// The number of rows of results, including group headers.
+    pub row_count: u32,
     pub running: bool,
     pub changed: bool,
 }
 
+/// The summed score of the best alternative of each alternation, or None if one has no match.
+fn alternation_score<T>(
+    alternations: &[(usize, Vec<nucleo::pattern::Pattern>)],
+    item: &nucleo::Item<'_, T>,
+    matcher: &mut nucleo::Matcher,
+) -> Option<u32> {
+    alternations
+        .iter()
+        .try_fold(0, |total, (column, alternatives)| {
+            let haystack = item.matcher_columns[*column].slice(..);
+            let best = alternatives
+                .iter()
+                .filter_map(|pattern| pattern.score(haystack, matcher))
+                .max()?;
+            Some(total + best)
+        })
+}
+
+/// The criteria of [`Tiebreak`] for a matched item, in characters of its primary column, after
+/// the bonus of [`Worker::set_boost`].
+#[derive(Debug, Clone, Copy)]
+struct TiebreakKey {
+    boost: f64,
+    length: u32,
+    // from the start to the first matched character
+    begin: u32,
+    // from the last matched character to the end
+    end: u32,
+}
+
+impl TiebreakKey {
+    /// None if the item doesn't match `pattern`.
+    fn new<T>(
+        item: &nucleo::Item<'_, T>,
+        boost: Option<&BoostFn<T>>,
+        column: usize,
+        pattern: &nucleo::pattern::MultiPattern,
+        matcher: &mut nucleo::Matcher,
+        indices: &mut Vec<u32>,
+    ) -> Option<Self> {
+        let haystack = item.matcher_columns[column].slice(..);
+        let length = haystack.len() as u32;
+        indices.clear();
+        pattern
+            .column_pattern(column)
+            .indices(haystack, matcher, indices)?;
+        let (begin, end) = match (indices.iter().min(), indices.iter().max()) {
+            (Some(&first), Some(&last)) => (first, length.saturating_sub(last + 1)),
+            // an empty pattern
+            _ => (0, 0),
+        };
+        let boost = boost.map_or(0.0, |boost| boost(item.data));
+        Some(Self {
+            boost,
+            length,
+            begin,
+            end,
+        })
+    }
+
+    fn cmp(&self, other: &Self, criteria: &[Tiebreak]) -> Ordering {
+        let boost = other.boost.total_cmp(&self.boost);
+        criteria
+            .iter()
+            .map(|t| match t {
+                Tiebreak::Length => self.length.cmp(&other.length),
+                Tiebreak::Begin => self.begin.cmp(&other.begin),
+                Tiebreak::End => self.end.cmp(&other.end),
+                Tiebreak::Index => Ordering::Equal,
+            })
+            .fold(boost, Ordering::then)
+    }
+}
+
+/// The index of `columns[column]` among the columns passed to nucleo.
+pub(crate) fn matcher_index<T, D>(columns: &[Column<T, D>], column: usize) -> Option<usize> {
+    columns
+        .get(column)?
+        .filter
+        .then(|| columns[..column].iter().filter(|c| c.filter).count())
+}
+
 /// Do not create new snapshots
 pub fn new_snapshot<T: Sync + Send + 'static>(
     nucleo: &mut nucleo::Nucleo<T>,
@@ -348,6 +1141,7 @@ pub fn new_snapshot<T: Sync + Send + 'static>(
         Status {
             item_count: snapshot.item_count(),
             matched_count: snapshot.matched_item_count(),
+            row_count: snapshot.matched_item_count(),
             running,
             changed,
         },
@@ -361,3 +1155,168 @@ pub enum WorkerError {
     #[error("{0}")]
     Custom(&'static str),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_pin() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["new", "ab", "b", "abc"].map(String::from));
+        while worker.refresh().running {}
+        assert!(worker.pin(0));
+        assert!(!worker.pin(0));
+        assert!(!worker.pin(4));
+
+        worker.find("ab");
+        while worker.refresh().running {}
+        let results: Vec<_> = worker.matched_results().cloned().collect();
+        assert_eq!(results, ["new", "ab", "abc"]);
+        assert_eq!(worker.counts(), (3, 4));
+
+        assert_eq!(worker.edit([(1, None)]), EditMap::from([(1, None)]));
+        assert_eq!(worker.pinned(), [0]);
+        assert!(worker.unpin(0));
+        while worker.refresh().running {}
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [3]);
+    }
+
+    #[test]
+    fn test_tiebreak() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["xx ab", "ab x", "x ab", "ab"].map(String::from));
+        worker.find("ab");
+
+        for (tiebreak, expected) in [
+            (Tiebreak::Length, ["ab", "ab x", "x ab", "xx ab"]),
+            (Tiebreak::Begin, ["ab x", "ab", "x ab", "xx ab"]),
+            (Tiebreak::End, ["xx ab", "x ab", "ab", "ab x"]),
+        ] {
+            worker.set_tiebreak(&[tiebreak]);
+            while worker.refresh().running {}
+            let results: Vec<_> = worker.matched_results().cloned().collect();
+            assert_eq!(results, expected, "{tiebreak:?}");
+        }
+    }
+
+    #[test]
+    fn test_score_fn() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["a", "bb", "ccc"].map(String::from));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        worker.set_score_fn(Some(Arc::new(move |item: &String, score| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            score + item.len() as u32
+        })));
+        while worker.refresh().running {}
+        let results: Vec<_> = worker.matched_results().cloned().collect();
+        assert_eq!(results, ["ccc", "bb", "a"]);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+        // the results are laid out again without scoring them again
+        worker.set_group_by(Some(Arc::new(|_: &String| "All".to_string())));
+        worker.set_collapsed("All", true);
+        assert!(worker.pin(0));
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn test_group_by() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["recent a", "b", "recent c", "d"].map(String::from));
+        worker.set_group_by(Some(Arc::new(|item: &String| {
+            if item.starts_with("recent") {
+                "Recent".to_string()
+            } else {
+                "All".to_string()
+            }
+        })));
+        let mut status = worker.refresh();
+        while status.running {
+            status = worker.refresh();
+        }
+        assert_eq!((status.matched_count, status.row_count), (4, 6));
+        assert_eq!(&*worker.group_at(0).unwrap().name, "Recent");
+        assert_eq!(worker.get_nth_indexed(0), None);
+        assert_eq!(worker.get_nth_indexed(2).map(|(idx, _)| idx), Some(2));
+        assert_eq!(&*worker.group_of(5).unwrap().name, "All");
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [0, 2, 1, 3]);
+
+        worker.set_collapsed("Recent", true);
+        assert!(worker.group_at(0).unwrap().collapsed);
+        assert_eq!(&*worker.group_at(1).unwrap().name, "All");
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [1, 3]);
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["src", "  main.rs", "  ui", "    mod.rs", "README.md"].map(String::from));
+        worker.set_tree(Some(Arc::new(|item: &String| {
+            (item.len() - item.trim_start().len()) as u16 / 2
+        })));
+        while worker.refresh().running {}
+        assert_eq!(worker.tree_prefix(3), Some("   └─ "));
+        assert_eq!(worker.tree_parent(3), Some(2));
+
+        // matches are shown under their ancestors
+        worker.find("mod");
+        while worker.refresh().running {}
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [0, 2, 3]);
+        assert_eq!(worker.row_of(3), Some(2));
+
+        worker.find("");
+        while worker.refresh().running {}
+        assert!(worker.set_node_collapsed(2, true));
+        assert!(!worker.set_node_collapsed(1, true));
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [0, 1, 2, 4]);
+        assert_eq!(worker.tree_prefix(2), Some("└─▸"));
+    }
+
+    #[test]
+    fn test_alternations() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["src/a.rs", "lib/b.rs", "src/c.md", "doc/d.rs"].map(String::from));
+        worker.find("^src | ^lib .rs$");
+        while worker.refresh().running {}
+        let mut matched: Vec<_> = worker.matched_indices().collect();
+        matched.sort();
+        assert_eq!(matched, [0, 1]);
+
+        // only the alternation changed
+        worker.find("^src | ^doc .rs$");
+        while worker.refresh().running {}
+        let mut matched: Vec<_> = worker.matched_indices().collect();
+        matched.sort();
+        assert_eq!(matched, [0, 3]);
+    }
+
+    #[test]
+    fn test_alternations_are_scored() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["x_a_x_b_x_c", "abc", "xyz"].map(String::from));
+        worker.find("zzz | abc");
+        while worker.refresh().running {}
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [1, 0]);
+    }
+
+    #[test]
+    fn test_matching_modes() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["abc", "a_b_c", "ABC"].map(String::from));
+        worker.find("abc");
+        while worker.refresh().running {}
+        assert_eq!(worker.counts(), (3, 3));
+
+        worker.set_exact(true);
+        while worker.refresh().running {}
+        assert_eq!(worker.counts(), (2, 3));
+
+        worker.set_case_sensitive(true);
+        while worker.refresh().running {}
+        assert_eq!(worker.matched_indices().collect::<Vec<_>>(), [0]);
+    }
+}
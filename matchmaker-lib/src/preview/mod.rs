@@ -1,7 +1,16 @@
 pub mod previewer;
+#[cfg(feature = "pty")]
+mod pty;
 mod view;
+#[cfg(feature = "pty")]
+pub use pty::PtyHandle;
 pub use view::Preview;
 
+/// Storage for an image output by a preview command.
+#[cfg(feature = "image")]
+pub(crate) type PreviewImage =
+    std::sync::Arc<std::sync::Mutex<Option<std::sync::Arc<image::DynamicImage>>>>;
+
 // -------------- APPENDONLY
 use arc_swap::ArcSwap;
 use std::sync::Arc;
@@ -2,8 +2,10 @@ use ansi_to_tui::IntoText;
 use cba::broc::{CommandExt, EnvVars};
 use futures::FutureExt;
 use log::{debug, error, warn};
-use ratatui::text::{Line, Text};
+use ratatui::style::{Modifier, Style};
+use ratatui::text::{Line, Span, Text};
 use std::io::BufReader;
+use std::path::PathBuf;
 use std::process::{Child, Command, Stdio};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -13,14 +15,22 @@ use tokio::sync::watch::{Receiver, Sender, channel};
 use tokio::task::JoinHandle;
 
 use super::AppendOnly;
+#[cfg(feature = "image")]
+use super::PreviewImage;
+#[cfg(feature = "pty")]
+use super::PtyHandle;
 use crate::config::PreviewerConfig;
 use crate::event::EventSender;
-use crate::message::Event;
+use crate::message::{Event, Notifier};
 use crate::preview::Preview;
+use crate::ui::ToastLevel;
+use crate::utils::hyperlink::{Hyperlink, extract_hyperlinks};
+use crate::utils::span;
 
 #[derive(Debug, Default, strum_macros::Display, Clone)]
 pub enum PreviewMessage {
-    Run(String, EnvVars),
+    /// Run a command with extra environment variables, optionally in the given directory.
+    Run(String, EnvVars, Option<PathBuf>),
     Set(Text<'static>),
     Unset,
     #[default]
@@ -35,23 +45,35 @@ pub struct Previewer {
     rx: Receiver<PreviewMessage>,
     /// storage for preview command output
     lines: AppendOnly<Line<'static>>,
+    /// OSC 8 hyperlinks in the command output, which are removed from `lines`
+    hyperlinks: AppendOnly<Hyperlink>,
     /// storage for preview string override
     string: Arc<Mutex<Option<Text<'static>>>>,
     /// Flag which is set to true whenever the state changes
     /// and which the viewer can toggle after receiving the current state
     changed: Arc<AtomicBool>,
+    /// storage for an image output by the preview command
+    #[cfg(feature = "image")]
+    image: PreviewImage,
+    /// the command running in a pseudo-terminal, see [`PreviewerConfig::pty`]
+    #[cfg(feature = "pty")]
+    pty: PtyHandle,
 
     paused: bool,
     /// Maintain a queue of child processes to improve cleanup reliability
     procs: Vec<Child>,
     /// The currently executing child process
     current: Option<(Child, JoinHandle<bool>)>,
+    /// When the current process was started, for [`PreviewerConfig::timeout_ms`]
+    started: Option<Instant>,
 
     pub config: PreviewerConfig,
     last: String,
     /// Event loop controller
     // We only use it to send [`ControlEvent::Event`]
     event_controller_tx: Option<EventSender>,
+    /// Reports failures to the user
+    notifier: Option<Notifier>,
 }
 
 impl Previewer {
@@ -61,26 +83,45 @@ impl Previewer {
         let new = Self {
             rx,
             lines: AppendOnly::new(),
+            hyperlinks: AppendOnly::new(),
             string: Default::default(),
             changed: Default::default(),
+            #[cfg(feature = "image")]
+            image: Default::default(),
+            #[cfg(feature = "pty")]
+            pty: Default::default(),
             paused: false,
 
             procs: Vec::new(),
             current: None,
+            started: None,
             config,
             last: Default::default(),
             event_controller_tx: None,
+            notifier: None,
         };
 
         (new, tx)
     }
 
     pub fn view(&self) -> Preview {
-        Preview::new(
+        let view = Preview::new(
             self.lines.clone(),
             self.string.clone(),
             self.changed.clone(),
         )
+        .with_hyperlinks(self.hyperlinks.clone());
+        #[cfg(feature = "image")]
+        let view = view.with_image(self.image.clone());
+        #[cfg(feature = "pty")]
+        let view = view.with_pty(self.pty.clone());
+        view
+    }
+
+    /// The handle which keys are forwarded to while the preview is focused.
+    #[cfg(feature = "pty")]
+    pub fn pty(&self) -> PtyHandle {
+        self.pty.clone()
     }
 
     fn signal_dirty(&self) {
@@ -110,18 +151,26 @@ impl Previewer {
     }
 
     pub async fn run(mut self) -> Result<(), Vec<Child>> {
-        while self.rx.changed().await.is_ok() {
+        while self.recv().await {
             let mut m = self.rx.borrow_and_update().clone();
 
+            if self.config.delay_ms > 0 && matches!(m, PreviewMessage::Run(..)) {
+                let delay = Duration::from_millis(self.config.delay_ms);
+                // run only the command the cursor rests on
+                while let Ok(Ok(())) = tokio::time::timeout(delay, self.rx.changed()).await {
+                    m = self.rx.borrow_and_update().clone();
+                }
+            }
+
             if self.config.trim_commands
-                && let PreviewMessage::Run(cmd, _) = &mut m
+                && let PreviewMessage::Run(cmd, ..) = &mut m
             {
                 *cmd = cmd.trim().to_string();
             }
 
             log::trace!("Previewer received: {m:?}");
 
-            if let PreviewMessage::Run(cmd, _) = &m {
+            if let PreviewMessage::Run(cmd, ..) = &m {
                 if !self.config.always_trigger && &self.last == cmd {
                     continue;
                 }
@@ -188,9 +237,18 @@ impl Previewer {
 
             self.dispatch_kill();
             self.clear_string();
+            #[cfg(feature = "image")]
+            if let Ok(mut guard) = self.image.lock() {
+                *guard = None;
+            }
 
             match m {
-                PreviewMessage::Run(cmd, variables) => {
+                #[cfg(feature = "pty")]
+                PreviewMessage::Run(cmd, variables, cwd) if self.config.pty => {
+                    self.last = cmd.clone();
+                    self.spawn_pty(&cmd, &variables, cwd);
+                }
+                PreviewMessage::Run(cmd, variables, cwd) => {
                     self.last = cmd.clone();
                     let mut cmd_builder = if let Some(s) = &self.config.shell
                         && !s.is_empty()
@@ -208,12 +266,16 @@ impl Previewer {
                         .stdout(Stdio::piped())
                         .stdin(Stdio::null())
                         .stderr(Stdio::null());
+                    if let Some(cwd) = cwd {
+                        cmd_builder.current_dir(cwd);
+                    }
 
                     #[cfg(not(target_os = "windows"))]
                     cmd_builder.detach();
 
                     if !self.config.delay_clear {
                         self.lines.clear();
+                        self.hyperlinks.clear();
                         self.changed.store(true, Ordering::Release);
                     }
                     // we could send it later to save some cpu but maybe this is more responsive
@@ -225,13 +287,19 @@ impl Previewer {
                     if let Some(mut child) = cmd_builder._spawn() {
                         if let Some(stdout) = child.stdout.take() {
                             let lines = self.lines.clone();
+                            let hyperlinks = self.hyperlinks.clone();
                             let mut guard = self.lines.read();
                             let changed = self.changed.clone();
                             let cmd_str = cmd.clone();
                             let event_tx = self.event_controller_tx.clone();
+                            #[cfg(feature = "image")]
+                            let image_slot = self.image.clone();
 
                             // false => needs refresh (i.e. invalid utf-8)
-                            let handle = tokio::spawn(async move {
+                            // the reads block until the command outputs more, which may be never
+                            // for commands which follow a file
+                            let handle = tokio::task::spawn_blocking(move || {
+                                span!("preview", cmd = %cmd_str);
                                 let mut reader = BufReader::new(stdout);
                                 let mut leftover = Vec::new();
                                 let mut buf = [0u8; 8192];
@@ -245,10 +313,69 @@ impl Previewer {
                                     if first {
                                         if self.config.delay_clear {
                                             lines.clear();
+                                            hyperlinks.clear();
                                             guard = lines.read(); // get new consistent snapshot
                                             changed.store(true, Ordering::Relaxed);
                                         }
                                         first = false;
+
+                                        #[cfg(feature = "image")]
+                                        if is_image(&buf[..n]) {
+                                            let mut bytes = buf[..n].to_vec();
+                                            // one more byte than allowed, to tell if it was cut off
+                                            let rest = IMAGE_BYTES + 1 - n as u64;
+                                            let _ = std::io::Read::read_to_end(
+                                                &mut std::io::Read::take(&mut reader, rest),
+                                                &mut bytes,
+                                            );
+                                            if lines.is_expired(&guard) {
+                                                return true;
+                                            }
+                                            if bytes.len() as u64 > IMAGE_BYTES {
+                                                warn!(
+                                                    "Image from {cmd_str} is larger than {IMAGE_BYTES} bytes"
+                                                );
+                                                guard.push(Line::from(format!(
+                                                    "[image larger than {} MiB]",
+                                                    IMAGE_BYTES >> 20
+                                                )));
+                                                break;
+                                            }
+                                            match image::load_from_memory(&bytes) {
+                                                Ok(decoded) => {
+                                                    if let Ok(mut image) = image_slot.lock() {
+                                                        *image = Some(Arc::new(decoded));
+                                                    }
+                                                    changed.store(true, Ordering::Relaxed);
+                                                }
+                                                Err(e) => {
+                                                    warn!(
+                                                        "Failed to decode image from {cmd_str}: {e}"
+                                                    );
+                                                    // display it as text instead
+                                                    leftover = bytes;
+                                                }
+                                            }
+                                            break;
+                                        }
+
+                                        let cap = self.config.hexdump_bytes;
+                                        if cap > 0 && is_binary(&buf[..n]) {
+                                            let mut bytes = buf[..n].to_vec();
+                                            let rest = cap.saturating_sub(n) as u64;
+                                            let _ = std::io::Read::read_to_end(
+                                                &mut std::io::Read::take(&mut reader, rest),
+                                                &mut bytes,
+                                            );
+                                            bytes.truncate(cap);
+                                            for line in hexdump(&bytes) {
+                                                if lines.is_expired(&guard) {
+                                                    return true;
+                                                }
+                                                guard.push(line);
+                                            }
+                                            break;
+                                        }
                                     }
 
                                     leftover.extend_from_slice(&buf[..n]);
@@ -265,7 +392,9 @@ impl Previewer {
                                         .unwrap_or(valid_up_to);
 
                                     let (valid_bytes, rest) = leftover.split_at(split_at);
+                                    let (valid_bytes, found) = extract_hyperlinks(valid_bytes);
 
+                                    let base = guard.count();
                                     match valid_bytes.into_text() {
                                         Ok(text) => {
                                             for line in text {
@@ -275,6 +404,12 @@ impl Previewer {
                                                 }
                                                 guard.push(line);
                                             }
+                                            found.into_iter().for_each(|l| {
+                                                hyperlinks.push(Hyperlink {
+                                                    line: base + l.line,
+                                                    ..l
+                                                })
+                                            });
                                         }
                                         Err(e) => {
                                             if self.config.try_lossy {
@@ -299,8 +434,11 @@ impl Previewer {
                                 // no lines read, clear
                                 if first && self.config.delay_clear {
                                     lines.clear();
+                                    hyperlinks.clear();
                                     changed.store(true, Ordering::Relaxed);
                                 } else if !leftover.is_empty() && !lines.is_expired(&guard) {
+                                    let (leftover, found) = extract_hyperlinks(&leftover);
+                                    let base = guard.count();
                                     match leftover.into_text() {
                                         Ok(text) => {
                                             for line in text {
@@ -309,6 +447,12 @@ impl Previewer {
                                                 }
                                                 guard.push(line);
                                             }
+                                            found.into_iter().for_each(|l| {
+                                                hyperlinks.push(Hyperlink {
+                                                    line: base + l.line,
+                                                    ..l
+                                                })
+                                            });
                                         }
                                         Err(e) => {
                                             if self.config.try_lossy {
@@ -333,14 +477,19 @@ impl Previewer {
 
                                 true
                             });
-                            self.current = Some((child, handle))
+                            self.current = Some((child, handle));
+                            self.started = Some(Instant::now());
                         } else {
-                            error!("Failed to get stdout of preview command: {cmd}")
+                            error!("Failed to get stdout of preview command: {cmd}");
+                            self.notify(ToastLevel::Error, "Failed to read preview output");
                         }
+                    } else {
+                        self.notify(ToastLevel::Error, format!("Failed to spawn preview: {cmd}"));
                     }
                 }
                 PreviewMessage::Stop => {
                     self.lines.clear();
+                    self.hyperlinks.clear();
                     if !self.last.is_empty() {
                         self.last.clear();
                         self.signal_dirty();
@@ -352,11 +501,82 @@ impl Previewer {
             self.prune_procs();
         }
 
+        self.dispatch_kill();
         let ret = self.cleanup_procs();
         if ret.is_empty() { Ok(()) } else { Err(ret) }
     }
 
+    /// Wait for the next message, returning false once the sender is dropped. Meanwhile, the
+    /// current command is killed if it runs longer than [`PreviewerConfig::timeout_ms`].
+    async fn recv(&mut self) -> bool {
+        loop {
+            let Some(deadline) = self
+                .started
+                .filter(|_| self.config.timeout_ms > 0)
+                .map(|t| t + Duration::from_millis(self.config.timeout_ms))
+            else {
+                return self.rx.changed().await.is_ok();
+            };
+
+            tokio::select! {
+                changed = self.rx.changed() => return changed.is_ok(),
+                _ = tokio::time::sleep_until(deadline.into()) => {}
+            }
+            self.started = None;
+
+            if let Some((child, _)) = &mut self.current
+                && matches!(child.try_wait(), Ok(None))
+            {
+                warn!("Preview timed out: {}", self.last);
+                self.dispatch_kill();
+                self.notify(
+                    ToastLevel::Warn,
+                    format!("Preview timed out after {}ms", self.config.timeout_ms),
+                );
+            }
+        }
+    }
+
+    /// Run `cmd` in a pseudo-terminal, displaying its screen in place of its output.
+    #[cfg(feature = "pty")]
+    fn spawn_pty(&mut self, cmd: &str, variables: &EnvVars, cwd: Option<PathBuf>) {
+        let argv = match &self.config.shell {
+            Some(s) if !s.is_empty() => s.iter().cloned().chain([cmd.into()]).collect(),
+            #[cfg(windows)]
+            _ => vec!["cmd".into(), "/C".into(), cmd.into()],
+            #[cfg(not(windows))]
+            _ => vec!["sh".into(), "-c".into(), cmd.into()],
+        };
+
+        self.lines.clear();
+        self.hyperlinks.clear();
+        if let Some(event_tx) = &self.event_controller_tx {
+            let _ = event_tx.send(Event::PreviewStarted);
+        }
+
+        let changed = self.changed.clone();
+        let event_tx = self.event_controller_tx.clone();
+        let on_output = move || {
+            changed.store(true, Ordering::Release);
+            if let Some(event_tx) = &event_tx {
+                let _ = event_tx.send(Event::PreviewFinished);
+            }
+        };
+
+        // interactive commands aren't subject to the timeout
+        if let Err(e) = self
+            .pty
+            .spawn(argv, variables, cwd, self.string.clone(), on_output)
+        {
+            error!("Failed to spawn {cmd} in a pseudo-terminal: {e}");
+            self.notify(ToastLevel::Error, format!("Failed to spawn preview: {cmd}"));
+        }
+    }
+
     fn dispatch_kill(&mut self) {
+        self.started = None;
+        #[cfg(feature = "pty")]
+        self.pty.kill();
         if let Some((mut child, old)) = self.current.take() {
             kill_child(&mut child);
             self.procs.push(child);
@@ -387,13 +607,24 @@ impl Previewer {
         self.event_controller_tx = Some(event_controller_tx)
     }
 
+    pub fn connect_notifier(&mut self, notifier: Notifier) {
+        self.notifier = Some(notifier)
+    }
+
+    fn notify(&self, level: ToastLevel, message: impl Into<String>) {
+        if let Some(ref notifier) = self.notifier {
+            notifier.notify(level, message);
+        }
+    }
+
     // todo: This would be cleaner with tokio::Child, but does that merit a conversion? I'm not sure if its worth it for the previewer to yield control while waiting for output cuz we are multithreaded anyways
     // also, maybe don't want this delaying exit?
     fn cleanup_procs(mut self) -> Vec<Child> {
         let total_timeout = Duration::from_secs(1);
         let start = Instant::now();
 
-        self.procs.retain_mut(|child| {
+        let mut procs = std::mem::take(&mut self.procs);
+        procs.retain_mut(|child| {
             loop {
                 match child.try_wait() {
                     Ok(Some(_)) => return false,
@@ -413,7 +644,7 @@ impl Previewer {
             }
         });
 
-        self.procs
+        procs
     }
 
     fn prune_procs(&mut self) {
@@ -431,6 +662,21 @@ impl Previewer {
     }
 }
 
+// If the previewer is dropped without finishing, such as when the runtime is shut down on exit,
+// its commands would otherwise outlive the picker, as they don't share its process group.
+impl Drop for Previewer {
+    fn drop(&mut self) {
+        #[cfg(feature = "pty")]
+        self.pty.kill();
+        if let Some((child, _)) = &mut self.current {
+            kill_child(child);
+        }
+        for child in &mut self.procs {
+            kill_child(child);
+        }
+    }
+}
+
 fn kill_child(child: &mut Child) {
     let pid = child.id();
 
@@ -563,3 +809,87 @@ fn kill_child(child: &mut Child) {
 //         Self::new(1)
 //     }
 // }
+
+/// The most bytes of image output which are decoded. Larger images are not shown.
+#[cfg(feature = "image")]
+const IMAGE_BYTES: u64 = 64 << 20;
+
+/// Whether `bytes` start like an image. Only formats with unambiguous signatures are detected,
+/// so that text is never mistaken for an image.
+#[cfg(feature = "image")]
+fn is_image(bytes: &[u8]) -> bool {
+    use image::ImageFormat;
+    matches!(
+        image::guess_format(bytes),
+        Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP)
+    )
+}
+
+/// Whether `bytes` are binary: they contain a NUL byte, or invalid UTF-8 other than a character
+/// cut off at the end.
+fn is_binary(bytes: &[u8]) -> bool {
+    bytes.contains(&0) || std::str::from_utf8(bytes).is_err_and(|e| e.error_len().is_some())
+}
+
+/// Format `bytes` like `hexdump -C`: the offset, 16 bytes in hex, and the same bytes as ASCII.
+fn hexdump(bytes: &[u8]) -> impl Iterator<Item = Line<'static>> + '_ {
+    bytes.chunks(16).enumerate().map(|(i, chunk)| {
+        let mut hex = String::with_capacity(50);
+        for j in 0..16 {
+            if j == 8 {
+                hex.push(' ');
+            }
+            match chunk.get(j) {
+                Some(b) => hex.push_str(&format!("{b:02x} ")),
+                None => hex.push_str("   "),
+            }
+        }
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+
+        Line::from(vec![
+            Span::styled(
+                format!("{:08x}  ", i * 16),
+                Style::default().add_modifier(Modifier::DIM),
+            ),
+            Span::raw(hex),
+            Span::raw(format!(" |{ascii}|")),
+        ])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_binary() {
+        assert!(!is_binary("plain text\n".as_bytes()));
+        assert!(!is_binary(&"é".as_bytes()[..1])); // cut off
+        assert!(is_binary(b"\x7fELF\x02\x01\x01\x00"));
+        assert!(is_binary(b"\xff\xfe text"));
+    }
+
+    #[test]
+    fn test_hexdump() {
+        let lines: Vec<String> =
+            hexdump(b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00hi")
+                .map(|l| l.to_string())
+                .collect();
+        assert_eq!(
+            lines,
+            [
+                "00000000  7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  |.ELF............|",
+                "00000010  68 69                                             |hi|",
+            ]
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Running preview commands in a pseudo-terminal, so that keys can be forwarded to them.
+
+use std::{
+    ffi::OsString,
+    fmt,
+    io::{Read, Write},
+    path::PathBuf,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU64, Ordering},
+    },
+    thread,
+};
+
+use cba::broc::EnvVars;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use log::{debug, warn};
+use portable_pty::{ChildKiller, CommandBuilder, MasterPty, PtySize, native_pty_system};
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+};
+
+/// The command running in a pseudo-terminal, shared between the [`super::previewer::Previewer`]
+/// which spawns it, the [`super::Preview`] which displays it, and the
+/// [`crate::event::EventLoop`] which forwards keys to it while it is focused.
+#[derive(Clone, Default)]
+pub struct PtyHandle(Arc<PtyState>);
+
+#[derive(Default)]
+struct PtyState {
+    session: Mutex<Option<Session>>,
+    focused: AtomicBool,
+    /// Incremented for each command, so that the output of the previous one is discarded
+    generation: AtomicU64,
+}
+
+struct Session {
+    child: Box<dyn portable_pty::Child + Send + Sync>,
+    writer: Box<dyn Write + Send>,
+    // the terminal is closed when dropped
+    master: Box<dyn MasterPty + Send>,
+    /// Shared with the thread reading the output, so that the screen is resized with the terminal
+    parser: Arc<Mutex<vt100::Parser>>,
+}
+
+impl PtyHandle {
+    /// Whether a command is running.
+    pub fn is_active(&self) -> bool {
+        self.0.session.lock().is_ok_and(|s| s.is_some())
+    }
+
+    /// Forward keys to the command, returning false if none is running.
+    pub fn focus(&self) -> bool {
+        let active = self.is_active();
+        self.0.focused.store(active, Ordering::Release);
+        active
+    }
+
+    pub fn unfocus(&self) {
+        self.0.focused.store(false, Ordering::Release);
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.0.focused.load(Ordering::Acquire)
+    }
+
+    /// Write `key` to the command, encoded as a terminal would.
+    pub fn send_key(&self, key: KeyEvent) {
+        let Some(bytes) = key_bytes(key) else {
+            debug!("Not forwarding {key:?} to the preview");
+            return;
+        };
+        if let Ok(mut guard) = self.0.session.lock()
+            && let Some(session) = guard.as_mut()
+            && let Err(e) = session
+                .writer
+                .write_all(&bytes)
+                .and_then(|_| session.writer.flush())
+        {
+            warn!("Failed to write to the preview: {e}");
+        }
+    }
+
+    /// Run `argv` in a terminal the size given by the `COLUMNS` and `LINES` of `envs`, replacing
+    /// the previous command. Its screen is stored into `screen`, after which `on_output` is
+    /// called.
+    pub(crate) fn spawn(
+        &self,
+        argv: Vec<OsString>,
+        envs: &EnvVars,
+        cwd: Option<PathBuf>,
+        screen: Arc<Mutex<Option<Text<'static>>>>,
+        on_output: impl Fn() + Send + 'static,
+    ) -> anyhow::Result<()> {
+        self.kill();
+
+        let dimension = |name: &str| {
+            envs.iter()
+                .find(|(k, _)| k == name)
+                .and_then(|(_, v)| v.parse::<u16>().ok())
+                .filter(|&n| n > 0)
+        };
+        let size = PtySize {
+            rows: dimension("LINES").unwrap_or(24),
+            cols: dimension("COLUMNS").unwrap_or(80),
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+
+        let pair = native_pty_system().openpty(size)?;
+        let mut cmd = CommandBuilder::from_argv(argv);
+        for (k, v) in envs.iter() {
+            cmd.env(k, v);
+        }
+        if let Some(cwd) = cwd {
+            cmd.cwd(cwd);
+        }
+        let child = pair.slave.spawn_command(cmd)?;
+        drop(pair.slave);
+
+        let mut reader = pair.master.try_clone_reader()?;
+        let writer = pair.master.take_writer()?;
+        let generation = self.0.generation.fetch_add(1, Ordering::AcqRel) + 1;
+        let parser = Arc::new(Mutex::new(vt100::Parser::new(size.rows, size.cols, 0)));
+        if let Ok(mut guard) = self.0.session.lock() {
+            *guard = Some(Session {
+                child,
+                writer,
+                master: pair.master,
+                parser: parser.clone(),
+            });
+        }
+
+        let state = self.0.clone();
+        thread::spawn(move || {
+            let current = || state.generation.load(Ordering::Acquire) == generation;
+            let mut buf = [0u8; 8192];
+
+            while let Ok(n) = reader.read(&mut buf)
+                && n > 0
+            {
+                if !current() {
+                    return;
+                }
+                let text = match parser.lock() {
+                    Ok(mut parser) => {
+                        parser.process(&buf[..n]);
+                        screen_text(parser.screen())
+                    }
+                    Err(_) => return,
+                };
+                if let Ok(mut guard) = screen.lock() {
+                    *guard = Some(text);
+                }
+                on_output();
+            }
+
+            // the command exited by itself
+            if current() {
+                state.focused.store(false, Ordering::Release);
+                if let Ok(mut guard) = state.session.lock()
+                    && let Some(mut session) = guard.take()
+                {
+                    let _ = session.child.wait();
+                }
+                on_output();
+            }
+        });
+
+        Ok(())
+    }
+
+    /// Resize the terminal of the running command, which is signalled to redraw for the new
+    /// size.
+    pub(crate) fn resize(&self, rows: u16, cols: u16) {
+        if rows == 0 || cols == 0 {
+            return;
+        }
+        let Ok(guard) = self.0.session.lock() else {
+            return;
+        };
+        let Some(session) = guard.as_ref() else {
+            return;
+        };
+        let size = PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        };
+        if let Err(e) = session.master.resize(size) {
+            warn!("Failed to resize the preview: {e}");
+            return;
+        }
+        if let Ok(mut parser) = session.parser.lock() {
+            parser.screen_mut().set_size(rows, cols);
+        }
+    }
+
+    /// Kill the running command, if any.
+    pub(crate) fn kill(&self) {
+        self.0.generation.fetch_add(1, Ordering::AcqRel);
+        self.0.focused.store(false, Ordering::Release);
+        let Some(mut session) = self.0.session.lock().ok().and_then(|mut s| s.take()) else {
+            return;
+        };
+        let _ = session.child.kill();
+        // reap it without blocking the previewer
+        thread::spawn(move || {
+            let _ = session.child.wait();
+        });
+    }
+}
+
+impl fmt::Debug for PtyHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PtyHandle")
+            .field("active", &self.is_active())
+            .field("focused", &self.is_focused())
+            .finish()
+    }
+}
+
+/// The visible screen of the terminal, with the cursor drawn reversed unless it is hidden.
+fn screen_text(screen: &vt100::Screen) -> Text<'static> {
+    let (rows, cols) = screen.size();
+    let cursor = (!screen.hide_cursor()).then(|| screen.cursor_position());
+
+    let lines = (0..rows).map(|row| {
+        let mut spans: Vec<Span<'static>> = Vec::new();
+        for col in 0..cols {
+            let Some(cell) = screen.cell(row, col) else {
+                continue;
+            };
+            if cell.is_wide_continuation() {
+                continue;
+            }
+            let mut style = cell_style(cell);
+            if cursor == Some((row, col)) {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            let contents = cell.contents();
+            let contents: &str = if contents.is_empty() { " " } else { &contents };
+
+            match spans.last_mut() {
+                Some(last) if last.style == style => last.content.to_mut().push_str(contents),
+                _ => spans.push(Span::styled(contents.to_string(), style)),
+            }
+        }
+        Line::from(spans)
+    });
+
+    Text::from_iter(lines)
+}
+
+fn cell_style(cell: &vt100::Cell) -> Style {
+    let color = |c| match c {
+        vt100::Color::Default => None,
+        vt100::Color::Idx(i) => Some(Color::Indexed(i)),
+        vt100::Color::Rgb(r, g, b) => Some(Color::Rgb(r, g, b)),
+    };
+
+    let mut style = Style::default();
+    if let Some(fg) = color(cell.fgcolor()) {
+        style = style.fg(fg);
+    }
+    if let Some(bg) = color(cell.bgcolor()) {
+        style = style.bg(bg);
+    }
+    for (set, modifier) in [
+        (cell.bold(), Modifier::BOLD),
+        (cell.italic(), Modifier::ITALIC),
+        (cell.underline(), Modifier::UNDERLINED),
+        (cell.inverse(), Modifier::REVERSED),
+    ] {
+        if set {
+            style = style.add_modifier(modifier);
+        }
+    }
+    style
+}
+
+/// The bytes a terminal sends for `key`, or None if it has no encoding.
+fn key_bytes(key: KeyEvent) -> Option<Vec<u8>> {
+    let KeyEvent {
+        code, modifiers, ..
+    } = key;
+
+    let mut bytes = match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+            match c.to_ascii_lowercase() {
+                c @ 'a'..='z' => vec![c as u8 - b'a' + 1],
+                ' ' | '@' => vec![0],
+                '[' => vec![0x1b],
+                '\\' => vec![0x1c],
+                ']' => vec![0x1d],
+                _ => return None,
+            }
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => b"\r".to_vec(),
+        KeyCode::Tab => b"\t".to_vec(),
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => b"\x7f".to_vec(),
+        KeyCode::Esc => b"\x1b".to_vec(),
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        KeyCode::Home => b"\x1b[H".to_vec(),
+        KeyCode::End => b"\x1b[F".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::F(n @ 1..=4) => vec![0x1b, b'O', b'P' + n - 1],
+        KeyCode::F(n @ 5..=12) => {
+            let code = [15, 17, 18, 19, 20, 21, 23, 24][n as usize - 5];
+            format!("\x1b[{code}~").into_bytes()
+        }
+        _ => return None,
+    };
+
+    if modifiers.contains(KeyModifiers::ALT) {
+        bytes.insert(0, 0x1b);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode, modifiers: KeyModifiers) -> Option<Vec<u8>> {
+        key_bytes(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn test_key_bytes() {
+        let none = KeyModifiers::NONE;
+        assert_eq!(key(KeyCode::Char('a'), none), Some(b"a".to_vec()));
+        assert_eq!(key(KeyCode::Char('é'), none), Some("é".as_bytes().to_vec()));
+        assert_eq!(
+            key(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            Some(vec![3])
+        );
+        assert_eq!(
+            key(KeyCode::Char('['), KeyModifiers::CONTROL),
+            Some(vec![0x1b])
+        );
+        assert_eq!(key(KeyCode::Char('1'), KeyModifiers::CONTROL), None);
+        assert_eq!(
+            key(KeyCode::Char('x'), KeyModifiers::ALT),
+            Some(b"\x1bx".to_vec())
+        );
+        assert_eq!(key(KeyCode::Enter, none), Some(b"\r".to_vec()));
+        assert_eq!(key(KeyCode::Up, none), Some(b"\x1b[A".to_vec()));
+        assert_eq!(key(KeyCode::F(1), none), Some(b"\x1bOP".to_vec()));
+        assert_eq!(key(KeyCode::F(12), none), Some(b"\x1b[24~".to_vec()));
+        assert_eq!(key(KeyCode::F(13), none), None);
+        assert_eq!(key(KeyCode::CapsLock, none), None);
+    }
+}
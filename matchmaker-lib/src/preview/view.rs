@@ -1,18 +1,29 @@
 use cba::bait::ResultExt;
 use cba::unwrap;
 use ratatui::text::{Line, Text};
+use std::ops::Range;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 use super::AppendOnly;
+#[cfg(feature = "image")]
+use super::PreviewImage;
+#[cfg(feature = "pty")]
+use super::PtyHandle;
+use crate::utils::hyperlink::Hyperlink;
 
-// Images?
 #[derive(Debug)]
 pub struct Preview {
     lines: AppendOnly<Line<'static>>,
-    string: Arc<Mutex<Option<Text<'static>>>>,
+    hyperlinks: AppendOnly<Hyperlink>,
     /// Overrides lines when present
+    string: Arc<Mutex<Option<Text<'static>>>>,
     changed: Arc<AtomicBool>,
+    /// An image output by the command, drawn in place of the lines when present
+    #[cfg(feature = "image")]
+    image: PreviewImage,
+    #[cfg(feature = "pty")]
+    pty: PtyHandle,
 }
 
 impl Preview {
@@ -28,12 +39,48 @@ impl Preview {
     pub fn len(&self) -> usize {
         if let Some(s) = unwrap!(self.string.lock().prefix("Previewer panicked")._elog()).as_ref() {
             s.height()
+        } else {
+            self.lines.len()
+        }
+    }
+
+    /// Clone the `idx`th line.
+    pub fn line(&self, idx: usize) -> Option<Line<'static>> {
+        if let Some(s) = unwrap!(self.string.lock().prefix("Previewer panicked")._elog()).as_ref() {
+            s.lines.get(idx).cloned()
+        } else {
+            self.lines.read().get(idx).cloned()
+        }
+    }
+
+    /// Clone the lines within `range`, which is clamped to the output.
+    /// Unlike [`Self::results`], the cost is independent of the size of the output.
+    pub fn lines(&self, range: Range<usize>) -> Vec<Line<'static>> {
+        if let Some(s) = unwrap!(self.string.lock().prefix("Previewer panicked")._elog()).as_ref() {
+            let end = range.end.min(s.lines.len());
+            s.lines
+                .get(range.start.min(end)..end)
+                .unwrap_or_default()
+                .to_vec()
         } else {
             let output = self.lines.read();
-            output.iter().count()
+            range.map_while(|i| output.get(i).cloned()).collect()
         }
     }
 
+    /// The range of lines, excluding empty lines at either end.
+    pub fn content_bounds(&self) -> Range<usize> {
+        let len = self.len();
+        let is_empty = |i: usize| self.line(i).is_none_or(|l| l.spans.is_empty());
+
+        let start = (0..len).find(|&i| !is_empty(i)).unwrap_or(len);
+        let end = (start..len)
+            .rev()
+            .find(|&i| !is_empty(i))
+            .map_or(start, |i| i + 1);
+        start..end
+    }
+
     pub fn is_empty(&self) -> bool {
         if let Some(s) = unwrap!(self.string.lock().prefix("Previewer panicked")._elog()).as_ref() {
             s.height() == 0
@@ -43,6 +90,43 @@ impl Preview {
         }
     }
 
+    /// The OSC 8 hyperlinks in the output of the preview command.
+    pub fn hyperlinks(&self) -> Vec<Hyperlink> {
+        if unwrap!(self.string.lock().prefix("Previewer panicked")._elog()).is_some() {
+            return Vec::new();
+        }
+        self.hyperlinks.map_to_vec(Clone::clone)
+    }
+
+    pub(crate) fn with_hyperlinks(mut self, hyperlinks: AppendOnly<Hyperlink>) -> Self {
+        self.hyperlinks = hyperlinks;
+        self
+    }
+
+    /// The image output by the preview command, if it output one.
+    #[cfg(feature = "image")]
+    pub fn image(&self) -> Option<Arc<image::DynamicImage>> {
+        unwrap!(self.image.lock().prefix("Previewer panicked")._elog()).clone()
+    }
+
+    #[cfg(feature = "image")]
+    pub(crate) fn with_image(mut self, image: PreviewImage) -> Self {
+        self.image = image;
+        self
+    }
+
+    /// The command running in a pseudo-terminal, see [`crate::config::PreviewerConfig::pty`].
+    #[cfg(feature = "pty")]
+    pub fn pty(&self) -> &PtyHandle {
+        &self.pty
+    }
+
+    #[cfg(feature = "pty")]
+    pub(crate) fn with_pty(mut self, pty: PtyHandle) -> Self {
+        self.pty = pty;
+        self
+    }
+
     pub fn changed(&self) -> bool {
         self.changed.swap(false, Ordering::Relaxed)
     }
@@ -54,8 +138,13 @@ impl Preview {
     ) -> Self {
         Self {
             lines,
+            hyperlinks: AppendOnly::new(),
             string,
             changed,
+            #[cfg(feature = "image")]
+            image: Default::default(),
+            #[cfg(feature = "pty")]
+            pty: Default::default(),
         }
     }
 }
@@ -1,29 +1,47 @@
 mod dynamic;
 mod state;
+#[cfg(feature = "time-travel")]
+mod trace;
+mod view;
 
 use cba::_info;
 use cba::bait::ResultExt;
 use crossterm::event::{MouseButton, MouseEventKind};
 pub use dynamic::*;
 pub use state::*;
+#[cfg(feature = "time-travel")]
+pub use trace::*;
+pub use view::*;
 // ------------------------------
 
-use std::io::Write;
+use std::sync::Arc;
+use std::time::Duration;
 
 use log::{debug, info, warn};
 use ratatui::Frame;
-use ratatui::layout::{Position, Rect};
-use tokio::sync::mpsc;
+use ratatui::layout::{Constraint, Position, Rect};
+use ratatui::style::Style;
+use ratatui::text::{Line, Span};
+use ratatui::widgets::Clear;
 
 #[cfg(feature = "bracketed-paste")]
 use crate::PasteHandler;
 use crate::action::{Action, ActionExt};
-use crate::config::{CursorSetting, ExitConfig, RowConnectionStyle};
-use crate::event::{BindSender, EventSender};
+use crate::config::{
+    CursorSetting, ExitConfig, RowClick, RowConnectionStyle, ScrollbarSetting, SelectionMode,
+    SpinnerPosition, StringOrInt,
+};
+use crate::event::{BindSender, EventSender, RenderReceiver, TickGate};
 use crate::message::{BindDirective, Event, Interrupt, RenderCommand};
-use crate::tui::Tui;
-use crate::ui::{DisplayUI, OverlayUI, PickerUI, PreviewUI, QueryUI, ResultsUI, StatusUI, UI};
-use crate::{AcceptHook, ActionAliaser, ActionExtHandler, Initializer, MatchError, SSS};
+use crate::tui::{Tui, TuiBackend};
+use crate::ui::utils::render_scrollbar;
+use crate::ui::{
+    ColumnLayout, DisplayUI, OverlayUI, PickerUI, PreviewUI, QueryUI, ResultsUI, StatusUI, UI,
+    render_toasts,
+};
+use crate::utils::span;
+use crate::utils::string::substitute_escaped;
+use crate::{ActionAliaser, ActionExtHandler, Initializer, MatchError, SSS, Selector};
 
 fn apply_aliases<T: SSS, D, A: ActionExt>(
     buffer: &mut Vec<RenderCommand<A>>,
@@ -47,22 +65,26 @@ fn apply_aliases<T: SSS, D, A: ActionExt>(
 }
 
 #[allow(clippy::too_many_arguments)]
-pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionExt>(
+pub(crate) async fn render_loop<'a, B: TuiBackend, T: SSS, D: 'static, S, A: ActionExt>(
     mut ui: UI,
-    mut picker_ui: PickerUI<'a, T, D>,
+    picker_ui: &mut PickerUI<'a, T, D>,
+    // the other pickers, see [`focus_pane`]
+    panes: &mut [PickerUI<'a, T, D>],
+    focus: &mut usize,
     mut footer_ui: DisplayUI,
-    mut preview_ui: Option<PreviewUI>,
-    mut tui: Tui<W>,
+    preview_ui: &mut Option<PreviewUI>,
+    tui: &mut Tui<B>,
 
     mut overlay_ui: Option<OverlayUI<A>>,
     mut exit_config: ExitConfig,
 
-    mut render_rx: mpsc::UnboundedReceiver<RenderCommand<A>>,
+    render_rx: &mut RenderReceiver<A>,
     controller_tx: EventSender,
     bind_tx: BindSender<A>,
+    ticks: Arc<TickGate>,
 
-    output: AcceptHook<T, D, S>,
-    mut dynamic_handlers: DynamicHandlers<T, D>,
+    output: &mut dyn FnMut(&mut MMState<'_, '_, T, D>) -> Vec<S>,
+    dynamic_handlers: &mut DynamicHandlers<T, D>,
     mut ext_handler: Option<ActionExtHandler<T, D, A>>,
     mut ext_aliaser: Option<ActionAliaser<T, D, A>>,
     initializer: Option<Initializer<T, D>>,
@@ -70,13 +92,15 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
     mut paste_handler: Option<PasteHandler<T, D>>,
 ) -> Result<Vec<S>, MatchError> {
     let mut state = State::new();
+    #[cfg(feature = "time-travel")]
+    let mut trace = Trace::default();
 
     if let Some(handler) = initializer {
         handler(&mut state.dispatcher(
             &mut ui,
-            &mut picker_ui,
+            picker_ui,
             &mut footer_ui,
-            &mut preview_ui,
+            preview_ui,
             &controller_tx,
         ));
     }
@@ -84,7 +108,7 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
     let mut click = Click::None;
 
     // place the initial command in the state where the preview listener can access
-    if let Some(ref p) = preview_ui {
+    if let Some(p) = preview_ui.as_ref() {
         state.update_preview_payload(p.get_initial_command());
     }
 
@@ -105,16 +129,20 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
             tui.exit(None);
             let mut dispatcher = state.dispatcher(
                 &mut ui,
-                &mut picker_ui,
+                picker_ui,
                 &mut footer_ui,
-                &mut preview_ui,
+                preview_ui,
                 &controller_tx,
             );
+            dispatcher.panes = &mut *panes;
+            dispatcher.focus = *focus;
             let ret = output(&mut dispatcher);
 
             return Ok(ret);
         }
 
+        let has_tick = buffer.iter().any(|c| matches!(c, RenderCommand::Tick));
+
         let (
             mut did_pause,
             mut did_reload,
@@ -130,9 +158,9 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                 aliaser,
                 &mut state.dispatcher(
                     &mut ui,
-                    &mut picker_ui,
+                    picker_ui,
                     &mut footer_ui,
-                    &mut preview_ui,
+                    preview_ui,
                     &controller_tx,
                 ),
             )
@@ -146,11 +174,13 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                 tui.exit(None);
                 let mut dispatcher = state.dispatcher(
                     &mut ui,
-                    &mut picker_ui,
+                    picker_ui,
                     &mut footer_ui,
-                    &mut preview_ui,
+                    preview_ui,
                     &controller_tx,
                 );
+                dispatcher.panes = &mut *panes;
+                dispatcher.focus = *focus;
                 let ret = output(&mut dispatcher);
                 return Ok(ret);
             } else if state.should_quit_nomatch {
@@ -160,6 +190,8 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
 
             if !matches!(event, RenderCommand::Tick) {
                 info!("Received {event:?}");
+                #[cfg(feature = "time-travel")]
+                trace.command(&event);
             } else {
                 did_tick = true;
                 // log::trace!("Recieved {event:?}");
@@ -174,9 +206,9 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                 content,
                                 &state.dispatcher(
                                     &mut ui,
-                                    &mut picker_ui,
+                                    picker_ui,
                                     &mut footer_ui,
-                                    &mut preview_ui,
+                                    preview_ui,
                                     &controller_tx,
                                 ),
                             )
@@ -210,9 +242,34 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                     picker_ui.results.set_dirty();
                     tui.redraw();
                 }
+                RenderCommand::Preedit(text) => {
+                    picker_ui.query.set_preedit(text);
+                }
                 RenderCommand::HeaderTable(columns) => {
                     picker_ui.header.header_table(columns);
                 }
+                RenderCommand::Unbound(key) => {
+                    let config = &picker_ui.status.status_config;
+                    if !config.unbound_hint.is_empty() {
+                        let hint = substitute_escaped(&config.unbound_hint, &[('k', key)]);
+                        let duration = Duration::from_millis(config.transient_ms);
+                        state.flash(hint, duration);
+                    }
+                }
+                RenderCommand::Key(key) => {
+                    state.key = Some(key);
+                }
+                RenderCommand::Notify(level, message) => {
+                    state
+                        .dispatcher(
+                            &mut ui,
+                            picker_ui,
+                            &mut footer_ui,
+                            preview_ui,
+                            &controller_tx,
+                        )
+                        .notify(level, message);
+                }
                 RenderCommand::Mouse(mouse) => {
                     use crate::config::Side;
                     // we could also impl this in the aliasing step
@@ -281,7 +338,12 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                 } else {
                                     let y = mouse.row - layout.results.top();
                                     debug!("Results clicked at: {y}");
-                                    click = Click::ResultPos(y);
+                                    let mouse_config = &picker_ui.results.config.mouse;
+                                    if mouse_config.drag_select && picker_ui.selector.is_multi() {
+                                        state.drag_selecting =
+                                            picker_ui.results.get_index_of_row(y);
+                                    }
+                                    click = Click::ResultPos(y, mouse_config.left);
                                 }
                             } else if layout.input.contains(pos) {
                                 // The X offset of the start of the visible text relative to the terminal
@@ -307,7 +369,19 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                 let rel_y = pos.y.saturating_sub(layout.header.y);
                                 debug!("Header clicked at x: {rel_x}, y: {rel_y}");
 
-                                if let Some(setting) =
+                                // the header is aligned to the result columns
+                                let column_x = rel_x
+                                    .checked_sub(picker_ui.results.column_layout().offset)
+                                    .map(|x| x + picker_ui.results.indentation() as u16);
+                                if let Some(idx) = column_x
+                                    .filter(|_| {
+                                        picker_ui.header.config.match_indent
+                                            && !picker_ui.header.is_single_column()
+                                    })
+                                    .and_then(|x| picker_ui.results.get_gutter_col_idx(x))
+                                {
+                                    state.dragging = Some(Ok((pos, idx)));
+                                } else if let Some(setting) =
                                     picker_ui.header.config.interactions.get(rel_y as usize)
                                     && let Some(action) = find_interaction(setting, rel_x)
                                 {
@@ -326,6 +400,19 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                 }
                             }
                         }
+                        MouseEventKind::Down(
+                            button @ (MouseButton::Right | MouseButton::Middle),
+                        ) if layout.results.contains(pos) => {
+                            let y = mouse.row - layout.results.top();
+                            debug!("Results clicked with {button:?} at: {y}");
+                            let mouse_config = &picker_ui.results.config.mouse;
+                            let row_click = if button == MouseButton::Right {
+                                mouse_config.right
+                            } else {
+                                mouse_config.middle
+                            };
+                            click = Click::ResultPos(y, row_click);
+                        }
                         MouseEventKind::ScrollDown | MouseEventKind::ScrollUp => {
                             if layout.preview.contains(pos) {
                                 if let Some(p) = preview_ui.as_mut() {
@@ -412,10 +499,28 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                     );
                                 }
                                 *start_pos = pos;
+                            } else if let Some(last) = state.drag_selecting
+                                && layout.results.contains(pos)
+                                && let Some(idx) = picker_ui
+                                    .results
+                                    .get_index_of_row(mouse.row - layout.results.top())
+                                && idx != last
+                            {
+                                // select every result between the last one and this one, in
+                                // case the pointer skipped over some
+                                for i in last.min(idx)..=last.max(idx) {
+                                    if let Some((id, _)) = picker_ui.worker.get_nth_indexed(i) {
+                                        picker_ui.selector.sel(id);
+                                    }
+                                }
+                                picker_ui.results.changed[0] = true;
+                                picker_ui.results.cursor_jump(idx);
+                                state.drag_selecting = Some(idx);
                             }
                         }
                         MouseEventKind::Up(MouseButton::Left) => {
                             state.dragging = None;
+                            state.drag_selecting = None;
                         }
                         _ => {}
                     }
@@ -427,6 +532,7 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                     return Ok(vec![]);
                 }
                 RenderCommand::Action(action) => {
+                    span!("action", ?action);
                     if let Some(x) = overlay_ui.as_mut()
                         && match action {
                             Action::Char(c) => x.handle_input(c),
@@ -441,12 +547,28 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                         worker,
                         selector,
                         ..
-                    } = &mut picker_ui;
+                    } = &mut *picker_ui;
                     match action {
+                        Action::Select
+                        | Action::Deselect
+                        | Action::ToggleSelection
+                        | Action::CycleSelections
+                            if !selector.is_multi() =>
+                        {
+                            state
+                                .dispatcher(
+                                    &mut ui,
+                                    picker_ui,
+                                    &mut footer_ui,
+                                    preview_ui,
+                                    &controller_tx,
+                                )
+                                .notify(crate::ui::ToastLevel::Warn, "Multi-select is disabled");
+                        }
                         Action::Select => {
                             if let Some((idx, _)) = worker.get_nth_indexed(results.index()) {
                                 results.changed[0] = true;
-                                selector.insert(idx);
+                                selector.sel(idx);
                             }
                         }
                         Action::Deselect => {
@@ -458,11 +580,7 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                         Action::ToggleSelection => {
                             if let Some((idx, _)) = worker.get_nth_indexed(results.index()) {
                                 results.changed[0] = true;
-                                if selector.contains(&idx) {
-                                    selector.shift_remove(&idx);
-                                } else {
-                                    selector.insert(idx);
-                                }
+                                selector.toggle(idx);
                             }
                         }
                         Action::CycleSelections => {
@@ -473,24 +591,42 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                             results.changed[0] = true;
                             selector.clear();
                         }
-                        Action::Accept => {
+                        Action::ToggleMulti => {
+                            results.changed[0] = true;
+                            selector.set_mode(match selector.mode() {
+                                SelectionMode::Single => SelectionMode::Multi,
+                                SelectionMode::Multi => SelectionMode::Single,
+                            });
+                        }
+                        Action::Accept | Action::AcceptWith(_) => {
                             if selector.is_empty()
                                 && worker.get_nth(results.index()).is_none()
                                 && !exit_config.allow_empty
                             {
                                 continue;
                             };
+                            if let Action::AcceptWith(key) = action {
+                                state.accept_key = Some(key);
+                            }
                             tui.exit(None);
                             let mut dispatcher = state.dispatcher(
                                 &mut ui,
-                                &mut picker_ui,
+                                picker_ui,
                                 &mut footer_ui,
-                                &mut preview_ui,
+                                preview_ui,
                                 &controller_tx,
                             );
+                            dispatcher.panes = &mut *panes;
+                            dispatcher.focus = *focus;
                             let ret = output(&mut dispatcher);
                             return Ok(ret);
                         }
+                        Action::AcceptNonExit => {
+                            if selector.is_empty() && worker.get_nth(results.index()).is_none() {
+                                continue;
+                            };
+                            state.set_interrupt(Interrupt::AcceptNonExit, String::new());
+                        }
                         Action::Quit(code) => {
                             return Err(MatchError::Abort(code));
                         }
@@ -502,6 +638,25 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                         Action::ToggleHeaderWrap => {
                             picker_ui.header.wrap(!picker_ui.header.is_wrap());
                         }
+                        Action::ToggleGroup => {
+                            picker_ui.toggle_group();
+                        }
+                        Action::CollapseGroups | Action::ExpandGroups => {
+                            picker_ui
+                                .set_groups_collapsed(matches!(action, Action::CollapseGroups));
+                        }
+                        Action::ToggleCase => {
+                            picker_ui.toggle_case();
+                        }
+                        Action::ToggleExact => {
+                            picker_ui.toggle_exact();
+                        }
+                        Action::ExpandNode => {
+                            picker_ui.expand_node();
+                        }
+                        Action::CollapseNode => {
+                            picker_ui.collapse_node();
+                        }
                         Action::Up(x) | Action::Down(x) => {
                             let next = matches!(action, Action::Down(_)) ^ results.reverse();
                             for _ in 0..x.into() {
@@ -516,7 +671,7 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                             let pos = if pos >= 0 {
                                 pos as u32
                             } else {
-                                results.status.matched_count.saturating_sub((-pos) as u32)
+                                results.status.row_count.saturating_sub((-pos) as u32)
                             };
                             results.cursor_jump(pos);
                         }
@@ -529,7 +684,7 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                             query.set(None, pos);
                         }
                         Action::HScroll(n) | Action::VScroll(n) => {
-                            if let Some(p) = &mut preview_ui
+                            if let Some(p) = preview_ui.as_mut()
                                 && !p.config.wrap
                                 && false
                             // track mouse location?
@@ -598,6 +753,30 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                 p.jump()
                             }
                         }
+                        Action::FocusPreview => {
+                            #[cfg(feature = "pty")]
+                            let focused = preview_ui
+                                .as_ref()
+                                .is_some_and(|p| p.visible() && p.view.pty().focus());
+                            #[cfg(not(feature = "pty"))]
+                            let focused = false;
+                            if !focused {
+                                let message = if cfg!(feature = "pty") {
+                                    "No interactive preview to focus"
+                                } else {
+                                    "FocusPreview requires the pty feature"
+                                };
+                                state
+                                    .dispatcher(
+                                        &mut ui,
+                                        picker_ui,
+                                        &mut footer_ui,
+                                        preview_ui,
+                                        &controller_tx,
+                                    )
+                                    .notify(crate::ui::ToastLevel::Warn, message);
+                            }
+                        }
 
                         // Preview
                         Action::NextPreview | Action::PrevPreview => {
@@ -668,6 +847,28 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                             state.set_interrupt(Interrupt::ExecuteSilent, payload.clone());
                             state.discriminant_payload = Some(if tui.config.osc52 { 3 } else { 2 });
                         }
+                        Action::CopyColumn(n) => {
+                            let col = StringOrInt::Int(n);
+                            let mut lines: Vec<_> = selector
+                                .iter()
+                                .filter_map(|&idx| worker.get_by_idx(idx))
+                                .filter_map(|item| worker.format_with(item, &col))
+                                .collect();
+                            if lines.is_empty()
+                                && let Some(item) = worker.get_nth(results.index())
+                                && let Some(text) = worker.format_with(item, &col)
+                            {
+                                lines.push(text);
+                            }
+                            if lines.is_empty() {
+                                continue;
+                            }
+
+                            if let Err(e) = crate::set_host_clipboard_universal(&lines.join("\n")) {
+                                warn!("Failed to set host clipboard: {e}");
+                            }
+                            tui.redraw();
+                        }
                         Action::ExecuteAsync(ref payload) | Action::ExecuteThen(ref payload) => {
                             let is_async = matches!(action, Action::ExecuteAsync(_));
                             let payload = payload.clone();
@@ -702,6 +903,23 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                         Action::Reload(payload) => {
                             state.set_interrupt(Interrupt::Reload, payload);
                         }
+                        Action::NextSource | Action::Source(_) => {
+                            let n = match action {
+                                Action::Source(n) => n,
+                                _ => (picker_ui.sources.active() + 1) % picker_ui.sources.len(),
+                            };
+                            let Some(source) = picker_ui.switch_source(n) else {
+                                continue;
+                            };
+                            if let Some(p) = preview_ui.as_ref() {
+                                let command = source.preview.as_deref().unwrap_or(p.command());
+                                state.update_preview_payload(command);
+                            }
+                            state.synced = [false; 3];
+                        }
+                        Action::SetCwd(payload) => {
+                            state.set_interrupt(Interrupt::SetCwd, payload);
+                        }
                         Action::Print(payload) => {
                             state.set_interrupt(Interrupt::Print, payload);
                         }
@@ -806,6 +1024,9 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                 results.resize_col(delta, v);
                             }
                         }
+                        Action::ResetColumnWidths => {
+                            results.reset_col_widths();
+                        }
 
                         // Edit
                         Action::SetQuery(context) => {
@@ -823,6 +1044,12 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                         Action::DeleteLineStart => query.delete_line_start(),
                         Action::DeleteLineEnd => query.delete_line_end(),
                         Action::ClearQuery => query.cancel(),
+                        Action::HistoryPrev => {
+                            query.history_prev();
+                        }
+                        Action::HistoryNext => {
+                            query.history_next();
+                        }
 
                         // Other
                         Action::Redraw => {
@@ -846,9 +1073,9 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                                     e,
                                     &mut state.dispatcher(
                                         &mut ui,
-                                        &mut picker_ui,
+                                        picker_ui,
                                         &mut footer_ui,
-                                        &mut preview_ui,
+                                        preview_ui,
                                         &controller_tx,
                                     ),
                                 );
@@ -856,10 +1083,47 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                         }
                         Action::Char(c) => picker_ui.query.push_char(c),
 
+                        Action::FocusNext => {
+                            if !panes.is_empty() {
+                                let next = (*focus + 1) % (panes.len() + 1);
+                                focus_pane(picker_ui, panes, *focus, next);
+                                *focus = next;
+                                picker_ui.results.set_dirty();
+                                state.insert(Event::CursorChange);
+                            }
+                        }
+
                         // unreachable
                         Action::PrintKey => {}
                         Action::Semantic(_) => {}
                         Action::Trace(_) => {}
+                        Action::DumpTrace => {
+                            #[cfg(feature = "time-travel")]
+                            let (level, message) = match trace.dump() {
+                                Ok(path) => (
+                                    crate::ui::ToastLevel::Info,
+                                    format!("Wrote trace to {}", path.display()),
+                                ),
+                                Err(e) => (
+                                    crate::ui::ToastLevel::Error,
+                                    format!("Failed to write trace: {e}"),
+                                ),
+                            };
+                            #[cfg(not(feature = "time-travel"))]
+                            let (level, message) = (
+                                crate::ui::ToastLevel::Warn,
+                                "DumpTrace requires the time-travel feature".to_string(),
+                            );
+                            state
+                                .dispatcher(
+                                    &mut ui,
+                                    picker_ui,
+                                    &mut footer_ui,
+                                    preview_ui,
+                                    &controller_tx,
+                                )
+                                .notify(level, message);
+                        }
                     }
                 }
                 _ => {}
@@ -897,9 +1161,9 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
             {
                 let mut dispatcher = state.dispatcher(
                     &mut ui,
-                    &mut picker_ui,
+                    picker_ui,
                     &mut footer_ui,
-                    &mut preview_ui,
+                    preview_ui,
                     &controller_tx,
                 );
                 for h in dynamic_handlers.1.get_mut(interrupt) {
@@ -910,6 +1174,10 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                     return Err(MatchError::Become(state.payload().clone()));
                 }
             }
+            if matches!(interrupt, Interrupt::AcceptNonExit) {
+                picker_ui.results.changed[0] = true;
+                picker_ui.selector.clear();
+            }
         }
 
         // debug!("{state:?}");
@@ -920,6 +1188,7 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
         } else {
             // nothing
         }
+        picker_ui.selector.apply_preselect(&picker_ui.worker);
         if did_cursor_wrap {
             log::trace!("cursor wrapped"); // todo: event handler?
         }
@@ -935,7 +1204,12 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
         let mut cursor_y_offset = 0;
 
         if did_tick {
-            tui.terminal
+            span!("frame", iteration = state.iteration);
+            let spinner = state.spinner_frame(picker_ui.worker.reading(), &ui.config.spinner);
+            let mut hyperlinks = Vec::new();
+
+            let completed = tui
+                .terminal
                 .draw(|frame| {
                     let mut area = frame.area();
 
@@ -980,6 +1254,22 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                         [Rect::default(), _area, footer]
                     };
 
+                    // the other pickers share the picker area in equal columns
+                    let picker_area = if panes.is_empty() {
+                        picker_area
+                    } else {
+                        let areas = ratatui::layout::Layout::horizontal(vec![
+                            Constraint::Fill(1);
+                            panes.len() + 1
+                        ])
+                        .split(picker_area);
+                        let others = (0..areas.len()).filter(|&i| i != *focus);
+                        for (pane, i) in panes.iter_mut().zip(others) {
+                            render_pane(frame, areas[i], pane, state.filtering, ui.area().width);
+                        }
+                        areas[*focus]
+                    };
+
                     let [input, status, header, results] = picker_ui.layout(picker_area);
 
                     // save dimensions and check if dimensions changed
@@ -1015,27 +1305,75 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
                     };
 
                     cursor_y_offset = render_input(frame, input, &mut picker_ui.query).y;
+                    render_results(frame, results, picker_ui, state.filtering);
+                    // the status aligns to the column layout computed by the results
                     render_status(
                         frame,
                         status,
                         &picker_ui.status,
                         &picker_ui.results,
+                        &picker_ui.selector,
+                        &StatusUI::matching_modes(
+                            picker_ui.worker.case_sensitive(),
+                            picker_ui.worker.exact(),
+                        ),
                         ui.area().width,
+                        state.transient(),
                     );
-                    render_results(frame, results, &mut picker_ui, state.filtering);
-                    render_display(frame, header, &mut picker_ui.header, &picker_ui.results);
-                    render_display(frame, footer, &mut footer_ui, &picker_ui.results);
+                    if let Some(c) = spinner {
+                        let area = match ui.config.spinner.position {
+                            SpinnerPosition::Status if picker_ui.status.status_config.show => {
+                                status
+                            }
+                            SpinnerPosition::Prompt => picker_ui.query.config.border.inner(input),
+                            _ => Rect::default(),
+                        };
+                        render_spinner(frame, area, c, ui.config.spinner.style.into());
+                    }
+                    let layout = picker_ui.results.column_layout();
+                    render_display(frame, header, &mut picker_ui.header, layout);
+                    render_display(frame, footer, &mut footer_ui, layout);
                     if let Some(preview_ui) = preview_ui.as_mut() {
                         state.update_preview_visible(preview_ui);
                         if preview_ui.visible() {
                             render_preview(frame, preview, preview_ui);
                         }
                     }
+                    if ui.config.toast.show {
+                        render_toasts(
+                            frame,
+                            picker_ui.results.config.border.inner(results),
+                            state.toasts(),
+                            &ui.config.toast,
+                        );
+                    }
                     if let Some(x) = overlay_ui_ref {
                         x.draw(frame);
                     }
+                    // after everything which could cover them
+                    if let Some(preview_ui) = preview_ui.as_ref()
+                        && preview_ui.visible()
+                    {
+                        hyperlinks = preview_ui.hyperlinks(frame.buffer_mut());
+                    }
                 })
                 .map_err(|e| MatchError::TUIError(e.to_string()))?;
+
+            if let Some(recorder) = &mut tui.recorder {
+                recorder.frame(completed.buffer)._elog();
+            }
+            if let Some(frames) = &tui.frames {
+                frames.push(completed.buffer);
+            }
+            for (url, cells) in hyperlinks {
+                tui.terminal
+                    .backend_mut()
+                    .draw_hyperlink(&url, &cells)
+                    ._elog();
+            }
+        }
+        if has_tick {
+            ticks.release();
         }
 
         if did_resize {
@@ -1050,15 +1388,23 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
 
         // note: the remainder could be scoped by a conditional on having run?
         // ====== Event handling ==========
-        state.update(&mut picker_ui, &overlay_ui);
+        if did_tick
+            && let Some(p) = preview_ui.as_mut()
+            && p.refresh_due()
+        {
+            state.insert(Event::PreviewChange);
+        }
+        state.update(picker_ui, &overlay_ui);
         let events = state.events();
+        #[cfg(feature = "time-travel")]
+        trace.record(&state, &picker_ui, events);
 
         // ---- Invoke handlers -------
         let mut dispatcher = state.dispatcher(
             &mut ui,
-            &mut picker_ui,
+            picker_ui,
             &mut footer_ui,
-            &mut preview_ui,
+            preview_ui,
             &controller_tx,
         );
         // if let Some((signal, handler)) = signal_handler &&
@@ -1098,10 +1444,36 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
         if did_reload {
             controller_tx.send(Event::Reloaded)._elog();
         }
+        // ticks stop when idle, so wake the event loop to draw the frame which clears each message
+        for remaining in state.schedule_expiries() {
+            let controller_tx = controller_tx.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(remaining).await;
+                let _ = controller_tx.send(Event::empty());
+            });
+        }
+
+        if let Some(remaining) = preview_ui.as_mut().and_then(|p| p.schedule_refresh()) {
+            let controller_tx = controller_tx.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(remaining).await;
+                let _ = controller_tx.send(Event::empty());
+            });
+        }
+
+        if let Some(interval) = state.schedule_spinner(&ui.config.spinner) {
+            let controller_tx = controller_tx.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(interval).await;
+                let _ = controller_tx.send(Event::empty());
+            });
+        }
 
         click.process(&mut picker_ui.results, &mut buffer, &bind_tx);
     }
 
+    #[cfg(feature = "time-travel")]
+    trace.dump()._elog();
     Err(MatchError::EventLoopClosed)
 }
 
@@ -1109,7 +1481,7 @@ pub(crate) async fn render_loop<'a, W: Write, T: SSS, D: 'static, S, A: ActionEx
 
 pub enum Click {
     None,
-    ResultPos(u16),
+    ResultPos(u16, RowClick),
     Semantic(String),
 }
 
@@ -1121,9 +1493,19 @@ impl Click {
         bind_tx: &BindSender<A>,
     ) {
         match self {
-            Click::ResultPos(y) => {
-                if let Some(idx) = results.get_index_of_row(*y) {
+            Click::ResultPos(y, row_click) => {
+                if *row_click != RowClick::None
+                    && let Some(idx) = results.get_index_of_row(*y)
+                {
                     results.cursor_jump(idx);
+                    let action = match row_click {
+                        RowClick::Toggle => Some(Action::ToggleSelection),
+                        RowClick::Accept => Some(Action::Accept),
+                        _ => None,
+                    };
+                    if let Some(action) = action {
+                        bind_tx.send(BindDirective::Action(action))._elog();
+                    }
                 }
             }
             Click::Semantic(s) => {
@@ -1156,8 +1538,27 @@ fn render_preview(frame: &mut Frame, area: Rect, ui: &mut PreviewUI) {
     //     frame.render_widget(widget, area);
     // }
     assert!(ui.visible()); // don't call if not visible.
+    #[cfg(feature = "image")]
+    if ui.render_image(frame, area) {
+        return;
+    }
     let widget = ui.make_preview();
     frame.render_widget(widget, area);
+
+    let inner = ui.border().inner(area);
+    let (total, position) = ui.scroll_position();
+    render_scrollbar(
+        frame,
+        inner,
+        &ui.config.scrollbar,
+        total,
+        ui.area.height as usize,
+        position,
+    );
+
+    if let Some(indicator) = ui.make_indicator() {
+        render_indicator(frame, inner, indicator, &ui.config.scrollbar);
+    }
 }
 
 fn render_results<T: SSS, D: 'static>(
@@ -1184,6 +1585,7 @@ fn render_results<T: SSS, D: 'static>(
         &picker_ui.selector,
         picker_ui.matcher,
     );
+    picker_ui.results.update_column_layout();
     let (table, width) = picker_ui.results.get_table();
 
     if cap {
@@ -1191,6 +1593,97 @@ fn render_results<T: SSS, D: 'static>(
     }
 
     frame.render_widget(table, area);
+
+    let results = &picker_ui.results;
+    let (total, position) = results.scroll_position();
+    render_scrollbar(
+        frame,
+        results.config.border.inner(area),
+        &results.config.scrollbar,
+        total,
+        results.height() as usize,
+        position,
+    );
+
+    if let Some(indicator) = results.make_indicator() {
+        let inner = results.config.border.inner(area);
+        render_indicator(frame, inner, indicator, &results.config.scrollbar);
+    }
+}
+
+/// Draw a picker which doesn't have the focus, before the focused picker so that the latter
+/// places the cursor.
+fn render_pane<T: SSS, D: 'static>(
+    frame: &mut Frame,
+    area: Rect,
+    pane: &mut PickerUI<T, D>,
+    filtering: bool,
+    full_width: u16,
+) {
+    let [input, status, header, results] = pane.layout(area);
+    let dimensions = (pane.results.width(), pane.results.height());
+    pane.results.update_dimensions(results);
+    if dimensions != (pane.results.width(), pane.results.height()) {
+        pane.query.update_width(input.width);
+        pane.header.update_width(header.width);
+    }
+
+    render_input(frame, input, &mut pane.query);
+    render_results(frame, results, pane, filtering);
+    render_status(
+        frame,
+        status,
+        &pane.status,
+        &pane.results,
+        &pane.selector,
+        &StatusUI::matching_modes(pane.worker.case_sensitive(), pane.worker.exact()),
+        full_width,
+        None,
+    );
+    let layout = pane.results.column_layout();
+    render_display(frame, header, &mut pane.header, layout);
+}
+
+/// Give the focus to the pane at position `next`, where `focused` is the pane at position
+/// `focus` and `others` are the other panes in order.
+pub(crate) fn focus_pane<P>(focused: &mut P, others: &mut [P], focus: usize, next: usize) {
+    if next == focus {
+        return;
+    }
+    let j = if next < focus { next } else { next - 1 };
+    std::mem::swap(focused, &mut others[j]);
+    // move the previously focused pane to its position among the others
+    let target = if focus < next { focus } else { focus - 1 };
+    if j < target {
+        others[j..=target].rotate_left(1);
+    } else {
+        others[target..=j].rotate_right(1);
+    }
+}
+
+/// Draw a position indicator in the top right corner of `inner`, clear of the `scrollbar`.
+fn render_indicator(
+    frame: &mut Frame,
+    inner: Rect,
+    indicator: Line<'_>,
+    scrollbar: &ScrollbarSetting,
+) {
+    let right = inner.right().saturating_sub(scrollbar.show as u16);
+    let width = (indicator.width() as u16).min(right.saturating_sub(inner.x));
+    if inner.height > 0 && width > 0 {
+        let rect = Rect::new(right - width, inner.y, width, 1);
+        frame.render_widget(Clear, rect);
+        frame.render_widget(indicator, rect);
+    }
+}
+
+/// Draw the spinner at the right end of `area`.
+fn render_spinner(frame: &mut Frame, area: Rect, c: char, style: Style) {
+    if area.is_empty() {
+        return;
+    }
+    let rect = Rect::new(area.right() - 1, area.y, 1, 1);
+    frame.render_widget(Span::styled(c.to_string(), style), rect);
 }
 
 /// Returns the offset of the cursor against the drawing area
@@ -1212,30 +1705,31 @@ fn render_status(
     area: Rect,
     ui: &StatusUI,
     results_ui: &ResultsUI,
+    selector: &Selector,
+    modes: &str,
     full_width: u16,
+    transient: Option<&Line<'static>>,
 ) {
-    if ui.status_config.show {
-        let widget = ui.make_status(results_ui, full_width);
-        frame.render_widget(widget, area);
+    if !ui.status_config.show {
+        return;
+    }
+    if let Some(message) = transient {
+        frame.render_widget(ui.make_transient(message, results_ui), area);
+    } else {
+        frame.render_widget(ui.make_status(results_ui, selector, modes, full_width), area);
     }
 }
 
-fn render_display(frame: &mut Frame, area: Rect, ui: &mut DisplayUI, results_ui: &ResultsUI) {
+fn render_display(frame: &mut Frame, area: Rect, ui: &mut DisplayUI, layout: &ColumnLayout) {
     if !ui.show {
         return;
     }
-    let widths = results_ui.width_limits().to_vec();
-
-    let widget = ui.make_display((
-        results_ui.indentation() as u16 + results_ui.config.border.left(),
-        results_ui.config.column_spacing.0,
-        widths,
-    ));
+    let widget = ui.make_display(layout);
 
     frame.render_widget(widget, area);
 
     if ui.is_single_column() {
-        let widget = ui.make_full_width_row(results_ui.indentation() as u16);
+        let widget = ui.make_full_width_row(layout);
         frame.render_widget(widget, area);
     }
 }
@@ -1278,25 +1772,35 @@ fn split(rect: &mut Rect, height: u16, cut_top: bool) -> Rect {
 
 // -----------------------------------------------------------------------------------
 
-/// Collects selected items in match order. Scans `snapshot.get_matched_item(n)`
-/// for each n and yields (nucleo_idx, &T) for matches present in the selector.
+/// Collects the selected items in the order of [`Selector::ordered`], as (nucleo_idx, &T),
+/// including those which don't match the query.
 pub fn get_selected<'a, T: SSS, D>(picker_ui: &'a PickerUI<'_, T, D>) -> Vec<(u32, &'a T)> {
-    let snapshot = picker_ui.worker.nucleo.snapshot();
-    let mc = snapshot.matched_item_count();
-    (0..mc)
-        .filter_map(|n| {
-            let item = snapshot.get_matched_item(n)?;
-            let idx = snapshot.matches().get(n as usize)?.idx;
-            picker_ui
-                .selector
-                .contains(&idx)
-                .then_some((idx, item.data))
-        })
+    let worker = &picker_ui.worker;
+    picker_ui
+        .selector
+        .ordered()
+        .into_iter()
+        .filter_map(|idx| Some((idx, worker.get_by_idx(idx)?)))
         .collect()
 }
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_focus_pane() {
+        let (mut focused, mut others) = (0, [1, 2]);
+        focus_pane(&mut focused, &mut others, 0, 1);
+        assert_eq!((focused, others), (1, [0, 2]));
+        focus_pane(&mut focused, &mut others, 1, 2);
+        assert_eq!((focused, others), (2, [0, 1]));
+        focus_pane(&mut focused, &mut others, 2, 0);
+        assert_eq!((focused, others), (0, [1, 2]));
+        focus_pane(&mut focused, &mut others, 0, 2);
+        assert_eq!((focused, others), (2, [0, 1]));
+    }
+}
 
 // #[cfg(test)]
 // async fn send_every_second(tx: mpsc::UnboundedSender<RenderCommand>) {
@@ -1,14 +1,16 @@
 use bitflags::Flags;
 use cba::{_info, bait::TransformExt, broc::EnvVars, env_vars, unwrap};
-use ratatui::text::Text;
+use ratatui::text::{Line, Text};
+use std::time::{Duration, Instant};
 
 use crate::{
     SSS, Selector,
     action::{ActionExt, Actions},
+    config::SpinnerConfig,
     event::{self, BindSender, EventSender},
     message::{BindDirective, Event, Interrupt},
     nucleo::{Status, injector::WorkerInjector},
-    ui::{DisplayUI, OverlayUI, PickerUI, PreviewUI, Rect, UI},
+    ui::{DisplayUI, OverlayUI, PickerUI, PreviewUI, Rect, Toast, ToastLevel, UI},
 };
 use ratatui::layout::Position;
 
@@ -26,6 +28,15 @@ pub struct Layout {
 /// In the "standard implementation", None represents unset, String: command, Text: display
 pub type PreviewSetPayload = Option<Result<String, Text<'static>>>;
 
+/// A message displayed in place of the status line until it expires.
+#[derive(Debug, Clone)]
+pub struct Transient {
+    pub message: Line<'static>,
+    pub expires: Instant,
+    // whether the render loop has arranged a frame for the expiry
+    scheduled: bool,
+}
+
 pub struct State {
     last_id: Option<u32>,
     interrupt: Interrupt,
@@ -37,8 +48,11 @@ pub struct State {
     pub(crate) preview_visible: bool,
     pub(crate) layout: Layout,
     pub(crate) dragging: Option<Result<(Position, usize), Position>>,
+    /// The last result dragged over while selecting with the mouse
+    pub(crate) drag_selecting: Option<u32>,
     pub(crate) overlay_index: Option<usize>,
     pub(crate) synced: [bool; 3], // ran, synced, not_stopped
+    pub(crate) reading: bool,
 
     pub(crate) events: Event,
 
@@ -49,14 +63,22 @@ pub struct State {
     pub envs: EnvVars,
     /// A place to stash the preview visibility when overriding it
     stashed_preview_visibility: Option<bool>,
+    transient: Option<Transient>,
+    toasts: Vec<Toast>,
+    /// When the spinner started, and whether a frame has been arranged for its next step
+    spinner: Option<(Instant, bool)>,
     /// Setting this to true finishes the picker with the contents of [`Selector`].
-    /// If [`Selector`] is disabled, the picker finishes with the current item.
+    /// If [`Selector`] is empty (as in [`crate::config::SelectionMode::Single`]), the picker finishes with the current item.
     /// If there are no items to finish with, the picker finishes with [`crate::errors::MatchError::Abort`]\(0).
     /// Note: this bypasses the accept hook.
     pub should_quit: bool,
     /// Setting this to true finishes the picker with [`crate::MatchError::NoMatch`].
     pub should_quit_nomatch: bool,
     pub filtering: bool,
+    /// The key name given by [`crate::action::Action::AcceptWith`], if that was how the picker was accepted.
+    pub(crate) accept_key: Option<String>,
+    /// The key which triggered the most recent key bind.
+    pub(crate) key: Option<String>,
 
     /// This field is never touched by the rendering loop and is reserved for
     /// callers to use to store values, such as distinguishing between multiple
@@ -109,18 +131,25 @@ impl State {
             preview_set_payload: None,
             preview_visible: false,
             stashed_preview_visibility: None,
+            transient: None,
+            toasts: Vec::new(),
+            spinner: None,
             layout: Layout::default(),
             dragging: None,
+            drag_selecting: None,
             overlay_index: None,
 
             input: String::new(),
             iteration: 0,
             synced: [false; 3],
+            reading: false,
 
             events: Event::empty(),
             should_quit: false,
             should_quit_nomatch: false,
             filtering: true,
+            accept_key: None,
+            key: None,
 
             discriminant_payload: None,
             async_actions: std::array::from_fn(|_| None),
@@ -167,6 +196,86 @@ impl State {
         self.stashed_preview_visibility
     }
 
+    // ------ transient messages -----------
+
+    /// Display a message in place of the status line for `duration`.
+    /// Replaces any message currently shown.
+    pub fn flash(&mut self, message: impl Into<Line<'static>>, duration: Duration) {
+        self.transient = Some(Transient {
+            message: message.into(),
+            expires: Instant::now() + duration,
+            scheduled: false,
+        });
+    }
+
+    /// The current transient message, if it has not expired.
+    pub fn transient(&self) -> Option<&Line<'static>> {
+        self.transient
+            .as_ref()
+            .filter(|t| t.expires > Instant::now())
+            .map(|t| &t.message)
+    }
+
+    pub fn clear_transient(&mut self) {
+        self.transient = None;
+    }
+
+    /// Push a toast, dropping the oldest beyond `max`.
+    pub fn toast(&mut self, toast: Toast, max: usize) {
+        self.toasts.push(toast);
+        let excess = self.toasts.len().saturating_sub(max);
+        self.toasts.drain(..excess);
+    }
+
+    /// The toasts which have not expired, oldest first.
+    pub fn toasts(&self) -> impl DoubleEndedIterator<Item = &Toast> {
+        self.toasts.iter().filter(|t| !t.expired())
+    }
+
+    pub fn clear_toasts(&mut self) {
+        self.toasts.clear();
+    }
+
+    /// Drops expired toasts, and returns the time remaining on each newly flashed message or toast, once.
+    pub(crate) fn schedule_expiries(&mut self) -> Vec<Duration> {
+        self.toasts.retain(|t| !t.expired());
+
+        let now = Instant::now();
+        let mut ret = Vec::new();
+        if let Some(t) = self.transient.as_mut().filter(|t| !t.scheduled) {
+            t.scheduled = true;
+            ret.push(t.expires.saturating_duration_since(now));
+        }
+        for t in self.toasts.iter_mut().filter(|t| !t.scheduled) {
+            t.scheduled = true;
+            ret.push(t.expires.saturating_duration_since(now));
+        }
+        ret
+    }
+
+    // ------ spinner -----------
+
+    /// The current frame of the spinner, which animates while `reading`.
+    pub(crate) fn spinner_frame(&mut self, reading: bool, config: &SpinnerConfig) -> Option<char> {
+        let len = config.frames.chars().count();
+        if !reading || len == 0 {
+            self.spinner = None;
+            return None;
+        }
+        let (start, scheduled) = self.spinner.get_or_insert_with(|| (Instant::now(), false));
+        *scheduled = false;
+
+        let step = start.elapsed().as_millis() / config.interval_ms.max(1) as u128;
+        config.frames.chars().nth(step as usize % len)
+    }
+
+    /// Returns the time until the next step of an animating spinner, once per frame.
+    pub(crate) fn schedule_spinner(&mut self, config: &SpinnerConfig) -> Option<Duration> {
+        let (_, scheduled) = self.spinner.as_mut().filter(|(_, s)| !s)?;
+        *scheduled = true;
+        Some(Duration::from_millis(config.interval_ms.max(1)))
+    }
+
     pub fn stash_actions<A: ActionExt + 'static>(
         &mut self,
         actions: Actions<A>,
@@ -244,11 +353,11 @@ impl State {
         changed
     }
 
-    /// Emit PreviewChange event on change to visible
+    /// Emit PreviewChange event on change to visibility, so that the preview is stopped when hidden
     pub(crate) fn update_preview_visible(&mut self, preview_ui: &PreviewUI) -> bool {
         let visible = preview_ui.visible();
         let changed = self.preview_visible.cmp_replace(visible);
-        if changed && visible {
+        if changed {
             self.insert(Event::PreviewChange);
         }
         changed
@@ -269,10 +378,16 @@ impl State {
         }
         self.iteration += 1;
 
+        // the input ending changes the status even if the matches don't
+        if self.reading.cmp_replace(picker_ui.worker.reading()) {
+            self.insert(Event::StatusChange);
+        }
+
         let status = &picker_ui.results.status;
         self.synced[1] |= status.running;
         if status.changed {
             _info!(status; self.synced);
+            self.insert(Event::StatusChange);
             // add a synced event when worker stops running
             if !status.running {
                 if !self.synced[0] {
@@ -322,6 +437,10 @@ impl State {
                 self.insert(Event::CursorLost);
             }
         }
+
+        if picker_ui.selector.take_changed() {
+            self.insert(Event::SelectionChange);
+        }
         // log::trace!("{self:?}");
     }
 
@@ -342,6 +461,8 @@ impl State {
             footer_ui,
             preview_ui,
             event_controller,
+            panes: &mut [],
+            focus: 0,
         }
     }
 
@@ -364,6 +485,11 @@ pub struct MMState<'a, 'b: 'a, T: SSS, D> {
     pub footer_ui: &'a mut DisplayUI,
     pub preview_ui: &'a mut Option<PreviewUI>,
     pub event_controller: &'a EventSender,
+    /// The other pickers added with [`crate::PickOptions::pane`], in the order they are shown,
+    /// leaving out the focused one, which is [`Self::picker_ui`]. Only set for the accept hook.
+    pub panes: &'a mut [PickerUI<'b, T, D>],
+    /// The position of [`Self::picker_ui`] among the panes.
+    pub focus: usize,
 }
 
 impl<'a, 'b: 'a, T: SSS, D: 'static> MMState<'a, 'b, T, D> {
@@ -381,6 +507,30 @@ impl<'a, 'b: 'a, T: SSS, D: 'static> MMState<'a, 'b, T, D> {
         })
     }
 
+    /// Display a toast, with the duration and limit given by [`crate::config::ToastConfig`].
+    pub fn notify(&mut self, level: ToastLevel, message: impl Into<String>) {
+        let config = &self.ui.config.toast;
+        if !config.show {
+            return;
+        }
+        let toast = Toast::new(level, message, Duration::from_millis(config.duration_ms));
+        let max = config.max;
+        self.state.toast(toast, max);
+    }
+
+    /// The key name recorded by [`crate::action::Action::AcceptWith`].
+    /// None if the picker was accepted by [`crate::action::Action::Accept`].
+    pub fn accept_key(&self) -> Option<&str> {
+        self.state.accept_key.as_deref()
+    }
+
+    /// The name of the key which triggered the actions being processed, for instance to
+    /// dispatch on it in an [`crate::action::ActionExt`] handler.
+    /// None if no key bind has fired yet.
+    pub fn key(&self) -> Option<&str> {
+        self.state.key.as_deref()
+    }
+
     pub fn tui_area(&self) -> Rect {
         self.ui.full_area()
     }
@@ -407,12 +557,13 @@ impl<'a, 'b: 'a, T: SSS, D: 'static> MMState<'a, 'b, T, D> {
     /// Pure selection only:
     /// - No fallback
     /// - Uses `worker.get_by_idx`
-    /// - Order follows `selector.iter()`
+    /// - Order follows [`Selector::ordered`]
     pub fn map_selections_to_vec<U>(&self, mut f: impl FnMut(u32, &T) -> U) -> Vec<U> {
         self.picker_ui
             .selector
-            .iter()
-            .filter_map(|&idx| {
+            .ordered()
+            .into_iter()
+            .filter_map(|idx| {
                 self.picker_ui
                     .worker
                     .get_by_idx(idx)
@@ -469,6 +620,52 @@ impl<'a, 'b: 'a, T: SSS, D: 'static> MMState<'a, 'b, T, D> {
         self.state.synced = [false; 3];
     }
 
+    /// Remove (`None`) or replace items by nucleo index, e.g. to drop exited processes.
+    pub fn edit_items(&mut self, edits: impl IntoIterator<Item = (u32, Option<T>)>)
+    where
+        T: Clone,
+    {
+        self.picker_ui.edit_items(edits);
+        self.state.synced = [false; 3];
+    }
+
+    // -------- dispatch
+    // Like the actions of the same name, the changes take effect when the picker is next drawn.
+
+    /// Replace the query, moving the cursor to its end.
+    pub fn set_query(&mut self, query: impl Into<String>) {
+        self.picker_ui.query.set(query.into(), u16::MAX);
+    }
+
+    /// Move the cursor to the n-th row, or the last if there are fewer.
+    pub fn jump(&mut self, n: u32) {
+        self.picker_ui.results.cursor_jump(n);
+    }
+
+    /// Replace the prompt. None restores the configured prompt.
+    pub fn set_prompt(&mut self, prompt: Option<Line<'static>>) {
+        self.picker_ui.query.set_prompt(prompt);
+    }
+
+    pub fn set_header(&mut self, header: impl Into<Text<'static>>) {
+        self.picker_ui.header.set(header);
+    }
+
+    /// Select an item by its nucleo index, returning whether it is selected afterwards. See
+    /// [`Selector::sel`].
+    pub fn select_index(&mut self, idx: u32) -> bool {
+        if self.picker_ui.worker.get_by_idx(idx).is_none() {
+            return false;
+        }
+        self.picker_ui.results.changed[0] = true;
+        self.picker_ui.selector.sel(idx)
+    }
+
+    /// Show or hide the preview, returning whether its visibility changed.
+    pub fn show_preview(&mut self, show: bool) -> bool {
+        self.preview_ui.as_mut().is_some_and(|p| p.show(show))
+    }
+
     pub fn make_env_vars(&self) -> EnvVars {
         let mut vars = env_vars! {
             "FZF_LINES" => self.tui_area().height.to_string(),
@@ -478,6 +675,7 @@ impl<'a, 'b: 'a, T: SSS, D: 'static> MMState<'a, 'b, T, D> {
             "FZF_SELECT_COUNT" => self.selections().len().to_string(),
             "FZF_POS" => self.picker_ui.current_indexed().map_or("".to_string(), |x| format!("{}", x.0)),
             "FZF_QUERY" => self.input.clone(),
+            "FZF_KEY" => self.state.key.clone().unwrap_or_default(),
             "FZF_MODE" => event::MODE
                 .lock()
                 .map(|m| m.iter().map(|s| s.as_ref()).collect::<Vec<_>>().join(","))
@@ -490,10 +688,12 @@ impl<'a, 'b: 'a, T: SSS, D: 'static> MMState<'a, 'b, T, D> {
             "MM_SELECT_COUNT" => self.selections().len().to_string(),
             "MM_POS" => self.picker_ui.current_indexed().map_or("".to_string(), |x| format!("{}", x.0)),
             "MM_QUERY" => self.input.clone(),
+            "MM_KEY" => self.state.key.clone().unwrap_or_default(),
             "MM_MODE" => event::MODE
                 .lock()
                 .map(|m| m.iter().map(|s| s.as_ref()).collect::<Vec<_>>().join(","))
                 .unwrap_or_default(),
+            "MM_SOURCE" => self.picker_ui.sources.active().to_string(),
         };
 
         vars.extend(self.envs.clone());
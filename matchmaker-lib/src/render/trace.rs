@@ -0,0 +1,118 @@
+//! Time-travel debugging (the `time-travel` feature).
+//!
+//! The render loop keeps the last [`TRACE_CAPACITY`] frames which did anything, each with the
+//! commands which produced it and a snapshot of the resulting state. The trace is written to a
+//! file by [`crate::action::Action::DumpTrace`], and when the render loop panics or loses its
+//! event loop, so that reports such as "the cursor jumped" can be analyzed without a reproduction.
+
+use std::{collections::VecDeque, fmt, io, path::PathBuf};
+
+use crate::{SSS, message::Event, ui::PickerUI};
+
+use super::State;
+
+/// Number of frames kept.
+pub const TRACE_CAPACITY: usize = 256;
+
+/// The state after a frame.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub iteration: u32,
+    /// The commands processed in this frame, excluding ticks.
+    pub commands: Vec<String>,
+    pub events: Event,
+    pub query: String,
+    /// Grapheme index of the query cursor.
+    pub query_cursor: u16,
+    /// Index of the cursor among the matches.
+    pub cursor: u32,
+    pub matched_count: u32,
+    pub item_count: u32,
+    pub selected_count: usize,
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "#{} query={:?}@{} cursor={} matched={}/{} selected={} events={}",
+            self.iteration,
+            self.query,
+            self.query_cursor,
+            self.cursor,
+            self.matched_count,
+            self.item_count,
+            self.selected_count,
+            self.events
+        )?;
+        for command in &self.commands {
+            writeln!(f, "  <- {command}")?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Trace {
+    snapshots: VecDeque<Snapshot>,
+    /// Commands of the current frame.
+    pending: Vec<String>,
+}
+
+impl Trace {
+    pub fn command(&mut self, command: &impl fmt::Debug) {
+        self.pending.push(format!("{command:?}"));
+    }
+
+    /// Snapshot the state at the end of a frame, unless nothing happened in it.
+    pub fn record<T: SSS, D>(
+        &mut self,
+        state: &State,
+        picker_ui: &PickerUI<'_, T, D>,
+        events: Event,
+    ) {
+        if self.pending.is_empty() && events.is_empty() {
+            return;
+        }
+        if self.snapshots.len() == TRACE_CAPACITY {
+            self.snapshots.pop_front();
+        }
+        let status = &picker_ui.results.status;
+        self.snapshots.push_back(Snapshot {
+            iteration: state.iteration,
+            commands: std::mem::take(&mut self.pending),
+            events,
+            query: picker_ui.query.input.clone(),
+            query_cursor: picker_ui.query.cursor(),
+            cursor: picker_ui.results.index(),
+            matched_count: status.matched_count,
+            item_count: status.item_count,
+            selected_count: picker_ui.selector.len(),
+        });
+    }
+
+    pub fn snapshots(&self) -> impl Iterator<Item = &Snapshot> {
+        self.snapshots.iter()
+    }
+
+    /// Write the trace, oldest frame first, to a file in the temporary directory.
+    pub fn dump(&self) -> io::Result<PathBuf> {
+        let path =
+            std::env::temp_dir().join(format!("matchmaker-trace-{}.log", std::process::id()));
+        let mut out = String::new();
+        for snapshot in &self.snapshots {
+            out.push_str(&snapshot.to_string());
+        }
+        std::fs::write(&path, out)?;
+        log::info!("Wrote render trace to {path:?}");
+        Ok(path)
+    }
+}
+
+impl Drop for Trace {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            let _ = self.dump();
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use crokey::KeyCombination;
+use crossterm::event::KeyEvent;
+use ratatui::Frame;
+use ratatui::layout::{Constraint, Layout, Rect};
+
+use super::{get_selected, render_display, render_input, render_results, render_status};
+use crate::{
+    Action, SSS, Selector,
+    binds::{BindMap, BindMapExt, ResolvedBindMap, TriggerKind},
+    config::RenderConfig,
+    event::key_code_as_letter,
+    nucleo::Worker,
+    ui::{PickerUI, StatusUI},
+};
+
+/// How a [`PickerView`] was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ViewOutcome {
+    /// The selections, or the current item if none, are given by [`PickerView::selected`].
+    Accept,
+    /// With the exit code of [`Action::Quit`].
+    Quit(i32),
+}
+
+/// A picker drawn into an area of a frame owned by the caller, for applications which already
+/// run a ratatui terminal.
+///
+/// Unlike [`crate::Matchmaker::pick`], the view doesn't touch the terminal: the caller passes
+/// it key events with [`Self::handle_key`], and draws it with [`Self::render`]. Only the
+/// query, status, header and results are shown, and the actions which concern them are
+/// handled, i.e. there is no preview, footer, overlay or event handler.
+///
+/// ```no_run
+/// # use matchmaker::{Selector, config::RenderConfig, nucleo::{Worker, nucleo}, render::PickerView};
+/// let mut matcher = nucleo::Matcher::new(nucleo::Config::DEFAULT);
+/// let worker = Worker::<String>::new_single_column();
+/// worker.append(["a".to_string(), "b".to_string()]);
+/// let view = PickerView::new(RenderConfig::default(), &mut matcher, worker, Selector::new());
+/// // in the host's draw callback: view.render(frame, area);
+/// // in the host's event loop: view.handle_key(key);
+/// ```
+pub struct PickerView<'a, T: SSS, D = ()> {
+    pub picker: PickerUI<'a, T, D>,
+    binds: ResolvedBindMap,
+    // The last layout and query, to update the dimensions and results when they change
+    layout: [Rect; 4],
+    input: String,
+}
+
+impl<'a, T: SSS, D: 'static> PickerView<'a, T, D> {
+    pub fn new(
+        mut config: RenderConfig,
+        matcher: &'a mut nucleo::Matcher,
+        worker: Worker<T, D>,
+        selector: Selector,
+    ) -> Self {
+        assert!(!worker.columns.is_empty());
+        config.results.reverse.get_or_insert(false);
+        let picker = PickerUI::new(
+            config.results,
+            config.status,
+            config.query,
+            config.header,
+            matcher,
+            worker,
+            selector,
+        );
+
+        Self {
+            picker,
+            binds: BindMap::default_binds().resolve_semantics(&[]),
+            layout: Default::default(),
+            input: String::new(),
+        }
+    }
+
+    /// Replace the default binds.
+    pub fn with_binds(mut self, binds: BindMap) -> Self {
+        self.binds = binds.resolve_semantics(&[]);
+        self
+    }
+
+    /// Handle a key event from the host's terminal, returning the outcome if it closes the
+    /// picker.
+    ///
+    /// Keys without a bind are typed into the query if they are characters.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ViewOutcome> {
+        self.key_actions(key)
+            .into_iter()
+            .find_map(|action| self.handle_action(action))
+    }
+
+    fn key_actions(&self, key: KeyEvent) -> Vec<Action> {
+        let key = KeyCombination::normalized(key.into());
+        if let Some(actions) = self.binds.get(&TriggerKind::Key(key)) {
+            actions.0.clone().into_iter().collect()
+        } else {
+            key_code_as_letter(key)
+                .map(Action::Char)
+                .into_iter()
+                .collect()
+        }
+    }
+
+    /// Handle an action, returning the outcome if it closes the picker. Actions which concern
+    /// the parts of the picker not shown by the view are ignored.
+    pub fn handle_action(&mut self, action: Action) -> Option<ViewOutcome> {
+        let PickerUI {
+            query,
+            results,
+            worker,
+            selector,
+            ..
+        } = &mut self.picker;
+        match action {
+            Action::Accept => {
+                if !selector.is_empty() || worker.get_nth(results.index()).is_some() {
+                    return Some(ViewOutcome::Accept);
+                }
+            }
+            Action::Quit(code) => return Some(ViewOutcome::Quit(code)),
+
+            // Results
+            Action::Up(x) | Action::Down(x) => {
+                let next = matches!(action, Action::Down(_)) ^ results.reverse();
+                for _ in 0..x {
+                    if next {
+                        results.cursor_next();
+                    } else {
+                        results.cursor_prev();
+                    }
+                }
+            }
+            Action::Pos(pos) => {
+                let pos = if pos >= 0 {
+                    pos as u32
+                } else {
+                    results.status.row_count.saturating_sub((-pos) as u32)
+                };
+                results.cursor_jump(pos);
+            }
+            Action::Select | Action::Deselect | Action::ToggleSelection => {
+                if let Some((idx, _)) = worker.get_nth_indexed(results.index()) {
+                    results.changed[0] = true;
+                    match action {
+                        Action::Select => {
+                            selector.sel(idx);
+                        }
+                        Action::Deselect => {
+                            selector.shift_remove(&idx);
+                        }
+                        _ => {
+                            selector.toggle(idx);
+                        }
+                    }
+                }
+            }
+            Action::ClearSelections => {
+                results.changed[0] = true;
+                selector.clear();
+            }
+            Action::ToggleWrap => {
+                results.wrap(!results.is_wrap());
+            }
+            Action::ToggleGroup => self.picker.toggle_group(),
+            Action::ExpandNode => self.picker.expand_node(),
+            Action::CollapseNode => self.picker.collapse_node(),
+
+            // Query
+            Action::Char(c) => query.push_char(c),
+            Action::SetQuery(context) => query.set(context, u16::MAX),
+            Action::InsertQuery(context) => query.insert_str(&context),
+            Action::ForwardChar => query.forward_char(),
+            Action::BackwardChar => query.backward_char(),
+            Action::ForwardWord => query.forward_word(),
+            Action::BackwardWord => query.backward_word(),
+            Action::DeleteChar => query.delete(),
+            Action::DeleteWord => query.delete_word(),
+            Action::DeleteLineStart => query.delete_line_start(),
+            Action::DeleteLineEnd => query.delete_line_end(),
+            Action::ClearQuery => query.cancel(),
+            Action::ToggleCase => self.picker.toggle_case(),
+            Action::ToggleExact => self.picker.toggle_exact(),
+            _ => {}
+        }
+        None
+    }
+
+    /// Draw the picker into `area`, matching the query against the items read so far.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let picker = &mut self.picker;
+        if picker.query.input != self.input {
+            self.input.clone_from(&picker.query.input);
+            picker.results.set_dirty();
+        }
+        picker.update();
+
+        let layout = picker.layout(area);
+        let [input, status, header, results] = layout;
+        if layout != self.layout {
+            self.layout = layout;
+            picker.results.update_dimensions(results);
+            picker.query.update_width(input.width);
+            picker.header.update_width(header.width);
+        }
+
+        render_input(frame, input, &mut picker.query);
+        render_results(frame, results, picker, true);
+        render_status(
+            frame,
+            status,
+            &picker.status,
+            &picker.results,
+            &picker.selector,
+            &StatusUI::matching_modes(picker.worker.case_sensitive(), picker.worker.exact()),
+            area.width,
+            None,
+        );
+        let layout = picker.results.column_layout();
+        render_display(frame, header, &mut picker.header, layout);
+    }
+
+    /// The selected items in the order of [`crate::config::SelectorConfig::order`], whether or
+    /// not they match the query, or the current item if none are selected.
+    pub fn selected(&self) -> Vec<(u32, &T)> {
+        let mut selected = get_selected(&self.picker);
+        if selected.is_empty()
+            && let Some(current) = self.picker.current_indexed()
+        {
+            selected.push(current);
+        }
+        selected
+    }
+}
+
+/// Several [`PickerView`]s side-by-side in one area, e.g. to pick a source and a destination.
+///
+/// Keys are handled by the focused pane, using its binds, except for [`Action::FocusNext`],
+/// which moves the focus to the next pane.
+pub struct PickerPanes<'a, T: SSS, D = ()> {
+    pub panes: Vec<PickerView<'a, T, D>>,
+    focus: usize,
+}
+
+impl<'a, T: SSS, D: 'static> PickerPanes<'a, T, D> {
+    pub fn new(panes: Vec<PickerView<'a, T, D>>) -> Self {
+        assert!(!panes.is_empty());
+        Self { panes, focus: 0 }
+    }
+
+    /// The index of the focused pane.
+    pub fn focus(&self) -> usize {
+        self.focus
+    }
+
+    pub fn set_focus(&mut self, n: usize) {
+        self.focus = n.min(self.panes.len() - 1);
+    }
+
+    pub fn focused(&mut self) -> &mut PickerView<'a, T, D> {
+        &mut self.panes[self.focus]
+    }
+
+    /// Handle a key event with the binds of the focused pane, returning the index of the pane
+    /// and its outcome if it was closed.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<(usize, ViewOutcome)> {
+        self.panes[self.focus]
+            .key_actions(key)
+            .into_iter()
+            .find_map(|action| self.handle_action(action))
+    }
+
+    /// Handle an action in the focused pane, returning the index of the pane and its outcome
+    /// if it was closed.
+    pub fn handle_action(&mut self, action: Action) -> Option<(usize, ViewOutcome)> {
+        if let Action::FocusNext = action {
+            self.focus = (self.focus + 1) % self.panes.len();
+            return None;
+        }
+        let focus = self.focus;
+        self.panes[focus]
+            .handle_action(action)
+            .map(|outcome| (focus, outcome))
+    }
+
+    /// Draw the panes into equal columns of `area`.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let areas = Layout::horizontal(vec![Constraint::Fill(1); self.panes.len()]).split(area);
+        // the focused pane is drawn last so that its cursor is shown
+        let n = self.panes.len();
+        for i in (self.focus + 1..n).chain(0..=self.focus) {
+            self.panes[i].render(frame, areas[i]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selected() {
+        let mut matcher = nucleo::Matcher::new(nucleo::Config::DEFAULT);
+        let worker = Worker::new_single_column();
+        worker.append(["apple", "banana", "cherry"].map(String::from));
+        let mut view = PickerView::new(
+            RenderConfig::default(),
+            &mut matcher,
+            worker,
+            Selector::new(),
+        );
+        view.picker.selector.sel(2);
+        view.picker.selector.sel(0);
+
+        // "apple" is hidden by the query, but stays selected
+        view.picker.worker.find("ch");
+        while view.picker.worker.refresh().running {}
+        assert_eq!(
+            view.selected(),
+            [(2, &"cherry".to_string()), (0, &"apple".to_string())]
+        );
+
+        view.picker.selector.clear();
+        assert_eq!(view.selected(), [(2, &"cherry".to_string())]);
+    }
+}
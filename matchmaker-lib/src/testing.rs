@@ -0,0 +1,402 @@
+//! Driving a picker without a terminal, for deterministic tests.
+
+pub mod fixtures;
+
+use std::{
+    path::Path,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+};
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    layout::Rect,
+    style::{Color, Modifier},
+};
+use tokio::sync::Notify;
+
+use crate::{
+    PickOptions, SSS,
+    action::{ActionExt, NullActionExt},
+    event::RenderSender,
+    message::RenderCommand,
+};
+
+/// The frames drawn by a headless [`crate::tui::Tui`].
+#[derive(Debug, Default)]
+pub(crate) struct Frames {
+    buffers: Mutex<Vec<Buffer>>,
+    drawn: Notify,
+    closed: AtomicBool,
+}
+
+impl Frames {
+    pub(crate) fn push(&self, buffer: &Buffer) {
+        self.buffers.lock().unwrap().push(buffer.clone());
+        self.drawn.notify_waiters();
+    }
+
+    pub(crate) fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.drawn.notify_waiters();
+    }
+}
+
+/// Runs a picker against an in-memory buffer instead of the terminal.
+///
+/// The picker is driven only by the commands sent with the harness: no keys are read and no
+/// ticks are sent, so a frame is drawn only when asked for with [`Self::draw`]. Dropping the
+/// harness closes the picker if it is still open.
+///
+/// ```no_run
+/// # use matchmaker::{Action, Matchmaker, PickOptions, nucleo::Worker, testing::TestHarness};
+/// # async fn test() {
+/// let worker = Worker::new_single_column();
+/// worker.append(["a".to_string(), "b".to_string()]);
+/// let mm = Matchmaker::new_on_cloneable(worker);
+///
+/// let mut options: PickOptions<String, ()> = PickOptions::new();
+/// let harness = TestHarness::new(&mut options, 40, 10);
+/// let (result, _) = tokio::join!(mm.pick(options), async move {
+///     harness.draw().await;
+///     assert!(harness.text().contains('a'));
+///     harness.send(Action::Char('b'));
+///     harness.draw().await;
+///     harness.send(Action::Accept);
+/// });
+/// assert_eq!(result.unwrap(), ["b"]);
+/// # }
+/// ```
+pub struct TestHarness<A: ActionExt = NullActionExt> {
+    tx: RenderSender<A>,
+    frames: Arc<Frames>,
+}
+
+impl<A: ActionExt> TestHarness<A> {
+    /// Make the picker started with `options` draw into a buffer of `width` x `height`.
+    pub fn new<T: SSS, D>(options: &mut PickOptions<'_, T, D, A>, width: u16, height: u16) -> Self {
+        let frames = Arc::new(Frames::default());
+        options.headless = Some((Rect::new(0, 0, width, height), frames.clone()));
+        Self {
+            tx: options.render_tx(),
+            frames,
+        }
+    }
+
+    /// Queue a command, such as an [`crate::Action`], which is handled before the next frame.
+    pub fn send(&self, command: impl Into<RenderCommand<A>>) {
+        let _ = self.tx.send(command.into());
+    }
+
+    /// Draw a frame after handling the commands sent so far, returning it, or `None` if the
+    /// picker was closed.
+    pub async fn draw(&self) -> Option<Buffer> {
+        let drawn = self.frames.drawn.notified();
+        if self.frames.closed.load(Ordering::Acquire) {
+            return None;
+        }
+        self.send(RenderCommand::Tick);
+        drawn.await;
+        if self.frames.closed.load(Ordering::Acquire) {
+            None
+        } else {
+            self.buffer()
+        }
+    }
+
+    /// The last frame drawn.
+    pub fn buffer(&self) -> Option<Buffer> {
+        self.frames.buffers.lock().unwrap().last().cloned()
+    }
+
+    /// Every frame drawn so far, oldest first.
+    pub fn frames(&self) -> Vec<Buffer> {
+        self.frames.buffers.lock().unwrap().clone()
+    }
+
+    /// The text of the last frame, one line per row without trailing spaces.
+    pub fn text(&self) -> String {
+        self.buffer().map(|b| buffer_text(&b)).unwrap_or_default()
+    }
+}
+
+impl<A: ActionExt> Drop for TestHarness<A> {
+    fn drop(&mut self) {
+        let _ = self.tx.send(RenderCommand::quit());
+    }
+}
+
+/// The text of `buffer`, one line per row without trailing spaces.
+pub fn buffer_text(buffer: &Buffer) -> String {
+    buffer_lines(buffer, false)
+}
+
+/// Like [`buffer_text`], but each run of cells with the same style is preceded by an annotation
+/// of the style, such as `{fg=Red bg=Indexed(8) +BOLD}`, or `{}` for the default style.
+pub fn buffer_styled_text(buffer: &Buffer) -> String {
+    buffer_lines(buffer, true)
+}
+
+fn buffer_lines(buffer: &Buffer, styled: bool) -> String {
+    let width = buffer.area.width as usize;
+    let mut text = String::new();
+    for row in buffer.content.chunks(width.max(1)) {
+        let end = row
+            .iter()
+            .rposition(|cell| cell.symbol() != " " || (styled && !is_plain(cell)))
+            .map_or(0, |i| i + 1);
+
+        let mut last = None;
+        for cell in &row[..end] {
+            let style = (cell.fg, cell.bg, cell.modifier);
+            if styled && last.unwrap_or((Color::Reset, Color::Reset, Modifier::empty())) != style {
+                text.push_str(&style_annotation(cell));
+            }
+            last = Some(style);
+            text.push_str(cell.symbol());
+        }
+        text.push('\n');
+    }
+    text
+}
+
+fn is_plain(cell: &Cell) -> bool {
+    cell.fg == Color::Reset && cell.bg == Color::Reset && cell.modifier.is_empty()
+}
+
+fn style_annotation(cell: &Cell) -> String {
+    let mut parts = vec![];
+    if cell.fg != Color::Reset {
+        parts.push(format!("fg={:?}", cell.fg));
+    }
+    if cell.bg != Color::Reset {
+        parts.push(format!("bg={:?}", cell.bg));
+    }
+    for (name, _) in cell.modifier.iter_names() {
+        parts.push(format!("+{name}"));
+    }
+    format!("{{{}}}", parts.join(" "))
+}
+
+/// Compare `actual` with the golden file at `path`, panicking with both if they differ.
+///
+/// If `MM_UPDATE_SNAPSHOTS` is set, a missing or differing golden file is written from `actual`
+/// instead, so that snapshots are reviewed as part of the diff.
+#[track_caller]
+pub fn assert_snapshot(path: impl AsRef<Path>, actual: &str) {
+    let path = path.as_ref();
+    let update = std::env::var_os("MM_UPDATE_SNAPSHOTS").is_some();
+    match std::fs::read_to_string(path) {
+        Ok(expected) if expected == actual => {}
+        Ok(expected) if !update => {
+            panic!(
+                "snapshot {} differs\n--- expected\n{expected}--- actual\n{actual}\nRerun with MM_UPDATE_SNAPSHOTS=1 to accept it.",
+                path.display()
+            );
+        }
+        Err(_) if !update => {
+            panic!(
+                "snapshot {} is missing\n--- actual\n{actual}\nRerun with MM_UPDATE_SNAPSHOTS=1 to write it.",
+                path.display()
+            );
+        }
+        _ => {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).unwrap();
+            }
+            std::fs::write(path, actual).unwrap();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        style::Style,
+        text::{Line, Text},
+        widgets::{Borders, Paragraph, Widget},
+    };
+
+    use super::*;
+    use crate::{
+        Action, MatchError, Matchmaker,
+        config::{
+            BorderSetting, PreviewConfig, PreviewSetting, RenderConfig, ResultsConfig, StatusConfig,
+        },
+        nucleo::Worker,
+        preview::{AppendOnly, Preview},
+    };
+
+    #[tokio::test]
+    async fn test_harness() {
+        let worker = Worker::new_single_column();
+        worker.append(["apple", "banana", "cherry"].map(String::from));
+        let mm = Matchmaker::new_on_cloneable(worker);
+
+        let mut options: PickOptions<String, ()> = PickOptions::new();
+        let harness = TestHarness::new(&mut options, 40, 10);
+        let (result, _) = tokio::join!(mm.pick(options), async move {
+            let buffer = harness.draw().await.unwrap();
+            assert!(buffer_text(&buffer).contains("cherry"));
+
+            harness.send(Action::Char('b'));
+            harness.draw().await;
+            assert!(harness.text().contains("banana"));
+            assert!(!harness.text().contains("cherry"));
+            assert_eq!(harness.frames().len(), 2);
+
+            harness.send(Action::Accept);
+            assert!(harness.draw().await.is_none());
+        });
+        assert_eq!(result.unwrap(), ["banana"]);
+    }
+
+    #[tokio::test]
+    async fn test_panes() {
+        let worker = Worker::new_single_column();
+        worker.append(["apple"].map(String::from));
+        let mm = Matchmaker::new_on_cloneable(worker);
+        let pane = Worker::new_single_column();
+        pane.append(["banana"].map(String::from));
+
+        let mut options: PickOptions<String, ()> = PickOptions::new().pane(pane);
+        let harness = TestHarness::new(&mut options, 40, 10);
+        let (result, _) = tokio::join!(mm.pick(options), async move {
+            let text = buffer_text(&harness.draw().await.unwrap());
+            assert!(text.contains("apple") && text.contains("banana"));
+
+            // the query goes to the focused picker, which "apple" doesn't match
+            harness.send(Action::FocusNext);
+            harness.send(Action::Char('n'));
+            harness.draw().await;
+            assert!(harness.text().contains("apple") && harness.text().contains("banana"));
+
+            harness.send(Action::Accept);
+            assert!(harness.draw().await.is_none());
+        });
+        assert_eq!(result.unwrap(), ["banana"]);
+    }
+
+    #[tokio::test]
+    async fn test_pick_blocking_in_runtime() {
+        let worker = Worker::new_single_column();
+        worker.append(["apple"].map(String::from));
+        let mm = Matchmaker::new_on_cloneable(worker);
+
+        let mut options: PickOptions<String, ()> = PickOptions::new();
+        let harness = TestHarness::new(&mut options, 40, 10);
+        harness.send(Action::Quit(3));
+        let result = mm.pick_blocking(options);
+        assert!(matches!(result, Err(MatchError::Abort(3))));
+    }
+
+    #[test]
+    fn test_styled_text() {
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 6, 2));
+        let red = Style::new().fg(Color::Red).add_modifier(Modifier::BOLD);
+        buffer.set_string(0, 0, "ab", red);
+        buffer.set_string(2, 0, "c", Style::new());
+        buffer.set_string(0, 1, "d", Style::new().bg(Color::Indexed(8)));
+
+        assert_eq!(buffer_text(&buffer), "abc\nd\n");
+        assert_eq!(
+            buffer_styled_text(&buffer),
+            "{fg=Red +BOLD}ab{}c\n{bg=Indexed(8)}d\n"
+        );
+    }
+
+    #[test]
+    fn test_border_snapshot() {
+        let border = BorderSetting {
+            sides: Some(Borders::ALL),
+            title: "mm".into(),
+            ..Default::default()
+        };
+        let area = Rect::new(0, 0, 8, 3);
+        let mut buffer = Buffer::empty(area);
+        border.as_block().render(area, &mut buffer);
+        Paragraph::new("ab").render(border.inner(area), &mut buffer);
+
+        assert_snapshot(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/border.txt"),
+            &buffer_text(&buffer),
+        );
+    }
+
+    /// The text of the frame drawn for `mm` once the column widths have settled.
+    async fn settled_frame(
+        mm: Matchmaker<String, String>,
+        mut options: PickOptions<'_, String, ()>,
+        width: u16,
+        height: u16,
+    ) -> String {
+        let harness = TestHarness::new(&mut options, width, height);
+        let (_, text) = tokio::join!(mm.pick(options), async move {
+            // the widths are measured on the first frame and applied on the next
+            for _ in 0..3 {
+                harness.draw().await;
+            }
+            let text = harness.text();
+            harness.send(Action::Accept);
+            text
+        });
+        text
+    }
+
+    #[tokio::test]
+    async fn test_results_wrap_snapshot() {
+        let worker = Worker::new_single_column();
+        worker.append(["abcdefghijklmnopqrstuvwxyz".to_string()]);
+        let mut mm = Matchmaker::new_on_cloneable(worker);
+        mm.config_render(RenderConfig {
+            results: ResultsConfig {
+                wrap: true,
+                ..Default::default()
+            },
+            status: StatusConfig {
+                show: false,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let text = settled_frame(mm, PickOptions::new(), 20, 4).await;
+        assert_snapshot(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/results_wrap.txt"),
+            &text,
+        );
+    }
+
+    #[tokio::test]
+    async fn test_preview_layout_snapshot() {
+        let worker = Worker::new_single_column();
+        worker.append(["apple", "mango"].map(String::from));
+        let mut mm = Matchmaker::new_on_cloneable(worker);
+        mm.config_render(RenderConfig {
+            status: StatusConfig {
+                show: false,
+                ..Default::default()
+            },
+            preview: PreviewConfig {
+                layout: vec![PreviewSetting::default()],
+                show: true.into(),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        let text = Text::from(vec![Line::from("line one"), Line::from("line two")]);
+        let preview = Preview::new(
+            AppendOnly::new(),
+            Arc::new(Mutex::new(Some(text))),
+            Default::default(),
+        );
+
+        let text = settled_frame(mm, PickOptions::new().preview(preview), 40, 4).await;
+        assert_snapshot(
+            concat!(env!("CARGO_MANIFEST_DIR"), "/snapshots/preview_layout.txt"),
+            &text,
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Synthetic inputs for tests and benchmarks.
+//!
+//! The lines are generated from a fixed seed, so every call with the same arguments returns the
+//! same lines.
+
+const DIRS: &[&str] = &[
+    "src", "lib", "tests", "benches", "docs", "assets", "config", "nucleo", "render", "ui",
+    "preview", "utils", "target", "debug", "release", "examples",
+];
+const STEMS: &[&str] = &[
+    "main", "mod", "worker", "injector", "query", "results", "status", "input", "display",
+    "overlay", "matcher", "config", "binds", "action", "event", "state", "layout", "view",
+];
+const EXTENSIONS: &[&str] = &["rs", "toml", "md", "json", "txt", "sh", "lock", "yaml"];
+const COLORS: &[u8] = &[31, 32, 33, 34, 35, 36, 90, 91, 94];
+
+/// A xorshift generator, which is plenty for picking words.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn pick<'a>(&mut self, words: &[&'a str]) -> &'a str {
+        words[self.next_u64() as usize % words.len()]
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next_u64() % n
+    }
+}
+
+fn path(rng: &mut Rng) -> String {
+    let depth = 1 + rng.below(5);
+    let mut path = String::new();
+    for _ in 0..depth {
+        path.push_str(rng.pick(DIRS));
+        path.push('/');
+    }
+    path.push_str(rng.pick(STEMS));
+    if rng.below(4) == 0 {
+        path.push_str(&format!("_{}", rng.below(1000)));
+    }
+    path.push('.');
+    path.push_str(rng.pick(EXTENSIONS));
+    path
+}
+
+/// `n` file paths, such as `src/ui/results_12.rs`.
+pub fn paths(n: usize) -> Vec<String> {
+    let mut rng = Rng::new(0x5eed);
+    (0..n).map(|_| path(&mut rng)).collect()
+}
+
+/// `n` lines of whitespace separated columns, like the output of `ls -l`: permissions, size,
+/// date and path.
+pub fn columns(n: usize) -> Vec<String> {
+    let mut rng = Rng::new(0xc01);
+    (0..n)
+        .map(|_| {
+            let mode = if rng.below(5) == 0 {
+                "drwxr-xr-x"
+            } else {
+                "-rw-r--r--"
+            };
+            let size = rng.below(1 << 20);
+            let day = 1 + rng.below(28);
+            format!("{mode} {size:>8} 2025-03-{day:02} {}", path(&mut rng))
+        })
+        .collect()
+}
+
+/// `n` lines colored with ANSI escapes, like the output of `rg --color=always`: a colored path,
+/// line number, and a match highlighted within the line.
+pub fn ansi_lines(n: usize) -> Vec<String> {
+    let mut rng = Rng::new(0xa751);
+    (0..n)
+        .map(|_| {
+            let color = COLORS[rng.below(COLORS.len() as u64) as usize];
+            let line = 1 + rng.below(2000);
+            format!(
+                "\x1b[{color}m{}\x1b[0m:\x1b[32m{line}\x1b[0m:    let {} = \x1b[1;31m{}\x1b[0m(&{});",
+                path(&mut rng),
+                rng.pick(STEMS),
+                rng.pick(STEMS),
+                rng.pick(STEMS),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fixtures() {
+        assert_eq!(paths(3), paths(3));
+        assert_eq!(columns(10).len(), 10);
+        assert_eq!(columns(1)[0].split_whitespace().count(), 4);
+        assert!(ansi_lines(1)[0].starts_with("\x1b["));
+    }
+}
@@ -1,47 +1,55 @@
 use crate::config::TerminalConfig;
 use anyhow::Result;
 use cba::{_info, bait::ResultExt};
-use crossterm::{
-    event::{
-        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
-        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
-    },
-    execute,
-    terminal::{ClearType, EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode},
-};
 use log::{debug, error};
-use ratatui::{Terminal, TerminalOptions, Viewport, layout::Rect, prelude::CrosstermBackend};
+use ratatui::{
+    Terminal, TerminalOptions, Viewport,
+    backend::{ClearType, CrosstermBackend},
+    layout::Rect,
+};
 use serde::{Deserialize, Serialize};
 use std::{
     io::{self, Write},
+    sync::Arc,
     thread::sleep,
     time::Duration,
 };
-pub struct Tui<W>
-where
-    W: Write,
-{
-    pub terminal: ratatui::Terminal<CrosstermBackend<W>>,
+
+use crate::testing::Frames;
+
+mod backend;
+mod record;
+pub use backend::{AnyBackend, BackendKind, TuiBackend};
+pub use record::Recorder;
+
+pub struct Tui<B: TuiBackend = AnyBackend> {
+    pub terminal: ratatui::Terminal<B>,
     pub area: Rect,
     pub config: TerminalConfig,
+    /// Receives each drawn frame when [`TerminalConfig::record`] is set.
+    pub recorder: Option<Recorder>,
+    /// Receives each drawn frame when headless, in which case the terminal is never set up.
+    pub(crate) frames: Option<Arc<Frames>>,
+    /// Set while held by a [`crate::SharedTerminal`], so that the pickers drawn on it leave the
+    /// screen as it is when they exit.
+    pub(crate) shared: bool,
 
     in_execute: bool,
 }
 
-impl<W> Tui<W>
-where
-    W: Write,
-{
+impl<B: TuiBackend> Tui<B> {
     // waiting on https://github.com/ratatui/ratatui/issues/984 to implement growable inline, currently just tries to request max
     // if max > than remainder, then scrolls up a bit
-    pub fn new_with_writer(writer: W, config: TerminalConfig) -> Result<Self> {
-        let mut backend = CrosstermBackend::new(writer);
+    pub fn new_with_backend(mut backend: B, mut config: TerminalConfig) -> Result<Self> {
         let mut options = TerminalOptions::default();
 
         // important for getting cursor
-        crossterm::terminal::enable_raw_mode()?;
+        backend.set_raw_mode(true)?;
+        if !backend.supports_inline() {
+            config.layout = None;
+        }
 
-        let (width, height) = Self::full_size().unwrap_or_default();
+        let (width, height) = Self::full_size(&backend).unwrap_or_default();
         let area = if let Some(ref layout) = config.layout {
             _info!(layout);
 
@@ -49,7 +57,8 @@ where
                 .percentage
                 .compute_clamped(height, layout.min, layout.max);
 
-            let cursor_y = Self::get_cursor_y(Duration::from_millis(config.sleep_ms))
+            let cursor_y = backend
+                .cursor_row(Duration::from_millis(config.sleep_ms))
                 .unwrap_or_else(|e| {
                     error!("Failed to read cursor: {e}");
                     height - 1 // overestimate
@@ -61,7 +70,7 @@ where
             debug!("TUI dimensions: {width}, {height}. Cursor_y: {cursor_y}.",);
 
             // ensure available by scrolling
-            let cursor_y = match Self::scroll_up(&mut backend, scroll)._elog() {
+            let cursor_y = match backend.scroll_up(scroll)._elog() {
                 Some(_) => {
                     cursor_y.saturating_sub(scroll) // the requested cursor doesn't seem updated so we assume it succeeded
                     // todo: highpri: scroll doesn't actually seem happening tho, erasing buffer
@@ -97,36 +106,68 @@ where
 
         debug!("TUI area: {area}");
 
+        let recorder = config
+            .record
+            .as_deref()
+            .filter(|p| !p.as_os_str().is_empty())
+            .and_then(|p| Recorder::new(p, area)._elog());
+
         let terminal = Terminal::with_options(backend, options)?;
         Ok(Self {
             terminal,
             config,
             area,
+            recorder,
+            frames: None,
+            shared: false,
             in_execute: false,
         })
     }
 
+    /// A terminal of the fixed size `area` which is drawn only into `frames`. Nothing is
+    /// written to the real terminal, and the writer only receives the drawn output.
+    pub(crate) fn headless(
+        backend: B,
+        config: TerminalConfig,
+        area: Rect,
+        frames: Arc<Frames>,
+    ) -> Self {
+        let options = TerminalOptions {
+            viewport: Viewport::Fixed(area),
+        };
+        let terminal = Terminal::with_options(backend, options)
+            .expect("a fixed viewport doesn't query the terminal");
+        Self {
+            terminal,
+            config,
+            area,
+            recorder: None,
+            frames: Some(frames),
+            shared: false,
+            in_execute: false,
+        }
+    }
+
     pub fn enter(&mut self) -> Result<()> {
+        if self.frames.is_some() {
+            return Ok(());
+        }
         let fullscreen = self.is_fullscreen();
 
-        crossterm::terminal::enable_raw_mode()?;
+        self.terminal.backend_mut().set_raw_mode(true)?;
         if fullscreen {
             self.enter_alternate_screen(true)?;
         }
 
         let backend = self.terminal.backend_mut();
-        execute!(backend, EnableMouseCapture)._elog();
+        backend.set_mouse_capture(true)._elog();
         #[cfg(feature = "bracketed-paste")]
         {
-            execute!(backend, crossterm::event::EnableBracketedPaste)._elog();
+            backend.set_bracketed_paste(true)._elog();
         }
 
         if self.config.extended_keys {
-            execute!(
-                backend,
-                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
-            )
-            ._elog();
+            backend.set_extended_keys(true)._elog();
             log::trace!("keyboard enhancement set");
         }
 
@@ -136,10 +177,10 @@ where
     // call iff self.is_fullscreen
     pub fn enter_alternate_screen(&mut self, clear: bool) -> Result<()> {
         let backend = self.terminal.backend_mut();
-        execute!(backend, EnterAlternateScreen)?;
+        backend.set_alternate_screen(true)?;
 
         if clear {
-            execute!(backend, crossterm::terminal::Clear(ClearType::All))?;
+            backend.clear_region(ClearType::All)?;
             self.terminal.clear()?;
         }
 
@@ -152,15 +193,22 @@ where
     }
 
     pub fn enter_execute(&mut self) {
+        if self.frames.is_some() {
+            self.in_execute = true;
+            return;
+        }
         self.exit(None);
         sleep(self.sleep()); // necessary to give resize some time
-        debug!("state: {:?}", crossterm::terminal::is_raw_mode_enabled());
         self.in_execute = true;
 
         // do we ever need to scroll up?
     }
 
     pub fn return_execute(&mut self, clear: bool) -> Result<()> {
+        if self.frames.is_some() {
+            self.in_execute = false;
+            return Ok(());
+        }
         if self.config.restore_fullscreen {
             self.config.layout = None;
         }
@@ -179,16 +227,15 @@ where
             sleep(self.sleep());
             log::trace!("During return, slept {}", self.sleep().as_millis());
 
-            execute!(
-                self.terminal.backend_mut(),
-                crossterm::terminal::Clear(ClearType::All)
-            )
-            ._wlog();
+            self.terminal
+                .backend_mut()
+                .clear_region(ClearType::All)
+                ._wlog();
         }
 
         // resize
         if self.is_fullscreen() {
-            if let Some((width, height)) = Self::full_size() {
+            if let Some((width, height)) = Self::full_size(self.terminal.backend()) {
                 self.resize(Rect::new(0, 0, width, height));
             } else {
                 error!("Failed to get terminal size");
@@ -210,12 +257,16 @@ where
             log::debug!("Skipped teardown after already having left");
             return;
         }
+        if self.frames.is_some() || self.shared {
+            return;
+        }
         let backend = self.terminal.backend_mut();
 
-        execute!(backend, LeaveAlternateScreen, DisableMouseCapture)._wlog();
+        backend.set_alternate_screen(false)._wlog();
+        backend.set_mouse_capture(false)._wlog();
 
         if self.config.extended_keys {
-            execute!(backend, PopKeyboardEnhancementFlags)._elog();
+            backend.set_extended_keys(false)._elog();
         }
 
         if clear.is_none() {
@@ -230,43 +281,34 @@ where
 
         match clear {
             Some(true) => {
-                execute!(
-                    backend,
-                    crossterm::cursor::MoveToRow(self.area.y),
-                    crossterm::cursor::MoveToColumn(0),
-                    crossterm::terminal::Clear(ClearType::FromCursorDown)
-                )
-                ._elog();
+                backend.clear_below(Some(self.area.y))._elog();
             }
             None => {
-                execute!(
-                    backend,
-                    crossterm::cursor::MoveUp(0), // todo
-                    crossterm::cursor::MoveToColumn(0),
-                    crossterm::terminal::Clear(ClearType::FromCursorDown)
-                )
-                ._elog();
+                backend.clear_below(None)._elog();
             }
             _ => {}
         }
 
         self.terminal.show_cursor()._wlog();
 
-        disable_raw_mode()._wlog();
+        self.terminal.backend_mut().set_raw_mode(false)._wlog();
 
         debug!("Terminal exited");
     }
 
     pub fn exit_lite(&mut self) {
+        if self.frames.is_some() || self.shared {
+            return;
+        }
         let backend = self.terminal.backend_mut();
 
         // execute!(backend, LeaveAlternateScreen, DisableMouseCapture)._wlog();
 
         if self.config.extended_keys {
-            execute!(backend, PopKeyboardEnhancementFlags)._elog();
+            backend.set_extended_keys(false)._elog();
         }
 
-        disable_raw_mode()._wlog();
+        backend.set_raw_mode(false)._wlog();
 
         debug!("Terminal exited (lite)");
     }
@@ -280,34 +322,13 @@ where
         self.terminal.resize(self.area)._elog();
     }
 
-    // note: do not start before event stream
-    pub fn get_cursor_y(timeout: Duration) -> io::Result<u16> {
-        // crossterm uses stdout to determine cursor position
-        // todo: workarounds?
-        // #[cfg(not(target_os = "windows"))]
-        Ok(if !atty::is(atty::Stream::Stdout) {
-            utils::query_cursor_position(timeout)
-                .map_err(io::Error::other)?
-                .1
-        } else {
-            crossterm::cursor::position()?.1
-        })
-    }
-
-    pub fn scroll_up(backend: &mut CrosstermBackend<W>, lines: u16) -> io::Result<u16> {
-        execute!(backend, crossterm::terminal::ScrollUp(lines))?;
-        Ok(0) // not used
-        // Self::get_cursor_y() // note: do we want to skip this for speed
-    }
-    pub fn size() -> io::Result<(u16, u16)> {
-        crossterm::terminal::size()
-    }
-    pub fn full_size() -> Option<(u16, u16)> {
-        if let Ok((width, height)) = Self::size() {
-            Some((width, height))
-        } else {
-            error!("Failed to read terminal size");
-            None
+    pub fn full_size(backend: &B) -> Option<(u16, u16)> {
+        match backend.size() {
+            Ok(size) => Some((size.width, size.height)),
+            Err(_) => {
+                error!("Failed to read terminal size");
+                None
+            }
         }
     }
     pub fn is_fullscreen(&self) -> bool {
@@ -318,20 +339,26 @@ where
     }
 }
 
-impl Tui<Box<dyn Write + Send>> {
+impl<W: Write> Tui<CrosstermBackend<W>> {
+    pub fn new_with_writer(writer: W, config: TerminalConfig) -> Result<Self> {
+        Self::new_with_backend(CrosstermBackend::new(writer), config)
+    }
+}
+
+impl Tui {
+    /// Set up the backend chosen by [`TerminalConfig::backend`].
     pub fn new(config: TerminalConfig) -> Result<Self> {
-        let writer = config.stream.to_stream();
-        let tui = Self::new_with_writer(writer, config)?;
-        Ok(tui)
+        let backend = AnyBackend::new(config.backend, &config.stream)?;
+        Self::new_with_backend(backend, config)
     }
 }
 
-impl<W> Drop for Tui<W>
-where
-    W: Write,
-{
+impl<B: TuiBackend> Drop for Tui<B> {
     fn drop(&mut self) {
         self.exit(None);
+        if let Some(frames) = &self.frames {
+            frames.close();
+        }
     }
 }
 
@@ -352,85 +379,3 @@ impl IoStream {
         }
     }
 }
-
-// ------------------------------------------------------------
-
-#[cfg(unix)]
-mod utils {
-    use anyhow::{Context, Result, bail};
-    use std::{
-        fs::OpenOptions,
-        io::{Read, Write},
-        time::Duration,
-    };
-
-    /// Query the terminal for the current cursor position (col, row)
-    /// Needed because crossterm implementation fails when stdout is not connected.
-    /// Requires raw mode
-    pub fn query_cursor_position(timeout: Duration) -> Result<(u16, u16)> {
-        use nix::sys::{
-            select::{FdSet, select},
-            time::{TimeVal, TimeValLike},
-        };
-        use std::os::fd::AsFd;
-
-        let mut tty = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .open("/dev/tty")
-            .context("Failed to open /dev/tty")?;
-
-        // Send the ANSI cursor position report query
-        tty.write_all(b"\x1b[6n")?;
-        tty.flush()?;
-
-        // Wait for input using select()
-        let fd = tty.as_fd();
-        let mut fds = FdSet::new();
-        fds.insert(fd);
-
-        let mut timeout = TimeVal::milliseconds(timeout.as_millis() as i64);
-
-        let ready =
-            select(None, &mut fds, None, None, Some(&mut timeout)).context("select() failed")?;
-
-        if ready == 0 {
-            bail!("Timed out waiting for cursor position response: {timeout:?}");
-        }
-
-        // Read the response
-        let mut buf = [0u8; 64];
-        let n = tty.read(&mut buf)?;
-        let s = String::from_utf8_lossy(&buf[..n]);
-
-        parse_cursor_response(&s).context(format!("Failed to parse terminal response: {s}"))
-    }
-
-    /// Parse the terminal response with format ESC [ row ; col R
-    /// and return (col, row) as 0-based coordinates.
-    fn parse_cursor_response(s: &str) -> Result<(u16, u16)> {
-        let coords = s
-            .strip_prefix("\x1b[")
-            .context("Missing ESC]")?
-            .strip_suffix('R')
-            .context("Missing R")?;
-
-        let mut parts = coords.split(';');
-
-        let row: u16 = parts.next().context("Missing row")?.parse()?;
-
-        let col: u16 = parts.next().context("Missing column")?.parse()?;
-
-        Ok((col - 1, row - 1)) // convert to 0-based
-    }
-}
-
-#[cfg(windows)]
-mod utils {
-    use anyhow::Result;
-    use std::time::Duration;
-    pub fn query_cursor_position(timeout: Duration) -> Result<(u16, u16)> {
-        let ret = crossterm::cursor::position()?;
-        Ok(ret)
-    }
-}
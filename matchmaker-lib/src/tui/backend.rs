@@ -0,0 +1,454 @@
+use std::{io, time::Duration};
+
+use crossterm::{
+    event::{
+        DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+        PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+    },
+    execute, queue,
+    style::Print,
+    terminal::{ClearType, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::{
+    backend::{Backend, ClearType as RatatuiClearType, CrosstermBackend, WindowSize},
+    buffer::Cell,
+    layout::{Position, Size},
+};
+use serde::{Deserialize, Serialize};
+
+use super::IoStream;
+
+/// The terminal operations of a [`super::Tui`] besides drawing.
+///
+/// Implemented for [`CrosstermBackend`], and for `TermwizBackend` with the `termwiz` feature.
+/// Events are read with crossterm whichever backend draws the picker.
+pub trait TuiBackend: Backend<Error = io::Error> {
+    fn set_raw_mode(&mut self, enable: bool) -> io::Result<()>;
+    fn set_alternate_screen(&mut self, enable: bool) -> io::Result<()>;
+    fn set_mouse_capture(&mut self, enable: bool) -> io::Result<()>;
+    fn set_bracketed_paste(&mut self, enable: bool) -> io::Result<()>;
+    /// Report keys unambiguously, such as distinguishing `Esc` from `Alt`.
+    fn set_extended_keys(&mut self, enable: bool) -> io::Result<()>;
+    fn scroll_up(&mut self, lines: u16) -> io::Result<()>;
+    /// Clear from the start of `row` to the end of the screen, or from the start of the cursor's
+    /// row if `None`.
+    fn clear_below(&mut self, row: Option<u16>) -> io::Result<()>;
+
+    /// The row of the cursor, waiting at most `timeout` for the terminal to report it.
+    fn cursor_row(&mut self, _timeout: Duration) -> io::Result<u16> {
+        self.get_cursor_position().map(|p| p.y)
+    }
+
+    /// Whether the picker can be drawn below the cursor rather than fullscreen.
+    fn supports_inline(&self) -> bool {
+        true
+    }
+
+    /// Draw `cells` again as an OSC 8 hyperlink to `url`, leaving the cursor where it was.
+    /// Does nothing by default.
+    fn draw_hyperlink(&mut self, _url: &str, _cells: &[(u16, u16, Cell)]) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<W: io::Write> TuiBackend for CrosstermBackend<W> {
+    fn set_raw_mode(&mut self, enable: bool) -> io::Result<()> {
+        if enable {
+            crossterm::terminal::enable_raw_mode()
+        } else {
+            crossterm::terminal::disable_raw_mode()
+        }
+    }
+
+    fn set_alternate_screen(&mut self, enable: bool) -> io::Result<()> {
+        if enable {
+            execute!(self, EnterAlternateScreen)
+        } else {
+            execute!(self, LeaveAlternateScreen)
+        }
+    }
+
+    fn set_mouse_capture(&mut self, enable: bool) -> io::Result<()> {
+        if enable {
+            execute!(self, EnableMouseCapture)
+        } else {
+            execute!(self, DisableMouseCapture)
+        }
+    }
+
+    #[cfg(feature = "bracketed-paste")]
+    fn set_bracketed_paste(&mut self, enable: bool) -> io::Result<()> {
+        if enable {
+            execute!(self, crossterm::event::EnableBracketedPaste)
+        } else {
+            execute!(self, crossterm::event::DisableBracketedPaste)
+        }
+    }
+
+    #[cfg(not(feature = "bracketed-paste"))]
+    fn set_bracketed_paste(&mut self, _enable: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn set_extended_keys(&mut self, enable: bool) -> io::Result<()> {
+        if enable {
+            execute!(
+                self,
+                PushKeyboardEnhancementFlags(KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES)
+            )
+        } else {
+            execute!(self, PopKeyboardEnhancementFlags)
+        }
+    }
+
+    fn scroll_up(&mut self, lines: u16) -> io::Result<()> {
+        execute!(self, crossterm::terminal::ScrollUp(lines))
+    }
+
+    fn clear_below(&mut self, row: Option<u16>) -> io::Result<()> {
+        match row {
+            Some(row) => execute!(
+                self,
+                crossterm::cursor::MoveToRow(row),
+                crossterm::cursor::MoveToColumn(0),
+                crossterm::terminal::Clear(ClearType::FromCursorDown)
+            ),
+            None => execute!(
+                self,
+                crossterm::cursor::MoveUp(0), // todo
+                crossterm::cursor::MoveToColumn(0),
+                crossterm::terminal::Clear(ClearType::FromCursorDown)
+            ),
+        }
+    }
+
+    // note: do not start before event stream
+    fn cursor_row(&mut self, timeout: Duration) -> io::Result<u16> {
+        // crossterm uses stdout to determine cursor position
+        // todo: workarounds?
+        // #[cfg(not(target_os = "windows"))]
+        Ok(if !atty::is(atty::Stream::Stdout) {
+            utils::query_cursor_position(timeout)
+                .map_err(io::Error::other)?
+                .1
+        } else {
+            crossterm::cursor::position()?.1
+        })
+    }
+
+    fn draw_hyperlink(&mut self, url: &str, cells: &[(u16, u16, Cell)]) -> io::Result<()> {
+        queue!(
+            self,
+            crossterm::cursor::SavePosition,
+            Print(format!("\x1b]8;;{url}\x1b\\"))
+        )?;
+        Backend::draw(self, cells.iter().map(|(x, y, cell)| (*x, *y, cell)))?;
+        execute!(
+            self,
+            Print("\x1b]8;;\x1b\\"),
+            crossterm::cursor::RestorePosition
+        )
+    }
+}
+
+#[cfg(feature = "termwiz")]
+mod termwiz_backend {
+    use std::io;
+
+    use ratatui::{
+        backend::TermwizBackend,
+        termwiz::{
+            color::ColorAttribute,
+            surface::{Change, Position},
+            terminal::Terminal,
+        },
+    };
+
+    use super::TuiBackend;
+
+    impl TuiBackend for TermwizBackend {
+        fn set_raw_mode(&mut self, enable: bool) -> io::Result<()> {
+            let terminal = self.buffered_terminal_mut().terminal();
+            if enable {
+                terminal.set_raw_mode()
+            } else {
+                terminal.set_cooked_mode()
+            }
+            .map_err(io::Error::other)
+        }
+
+        fn set_alternate_screen(&mut self, enable: bool) -> io::Result<()> {
+            let terminal = self.buffered_terminal_mut().terminal();
+            if enable {
+                terminal.enter_alternate_screen()
+            } else {
+                terminal.exit_alternate_screen()
+            }
+            .map_err(io::Error::other)
+        }
+
+        // termwiz enables mouse reporting and bracketed paste along with raw mode
+        fn set_mouse_capture(&mut self, _enable: bool) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn set_bracketed_paste(&mut self, _enable: bool) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn set_extended_keys(&mut self, _enable: bool) -> io::Result<()> {
+            Ok(())
+        }
+
+        // only needed inline
+        fn scroll_up(&mut self, _lines: u16) -> io::Result<()> {
+            Ok(())
+        }
+
+        fn clear_below(&mut self, row: Option<u16>) -> io::Result<()> {
+            let y = match row {
+                Some(row) => Position::Absolute(row as usize),
+                None => Position::Relative(0),
+            };
+            self.buffered_terminal_mut()
+                .terminal()
+                .render(&[
+                    Change::CursorPosition {
+                        x: Position::Absolute(0),
+                        y,
+                    },
+                    Change::ClearToEndOfScreen(ColorAttribute::Default),
+                ])
+                .map_err(io::Error::other)
+        }
+
+        // the backend always draws to the alternate screen
+        fn supports_inline(&self) -> bool {
+            false
+        }
+    }
+}
+
+// ---------- Selection ---------------
+
+/// The backends which can be chosen with [`crate::config::TerminalConfig::backend`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    #[default]
+    Crossterm,
+    /// Requires the `termwiz` feature. Always fullscreen, and writes to the terminal rather than
+    /// [`crate::config::TerminalConfig::stream`].
+    Termwiz,
+}
+
+/// The backend chosen by [`BackendKind`].
+pub enum AnyBackend {
+    Crossterm(CrosstermBackend<Box<dyn io::Write + Send>>),
+    #[cfg(feature = "termwiz")]
+    Termwiz(Box<ratatui::backend::TermwizBackend>),
+}
+
+impl AnyBackend {
+    pub fn new(kind: BackendKind, stream: &IoStream) -> io::Result<Self> {
+        match kind {
+            BackendKind::Crossterm => {
+                Ok(Self::Crossterm(CrosstermBackend::new(stream.to_stream())))
+            }
+            #[cfg(feature = "termwiz")]
+            BackendKind::Termwiz => ratatui::backend::TermwizBackend::new()
+                .map(|b| Self::Termwiz(Box::new(b)))
+                .map_err(|e| io::Error::other(e.to_string())),
+            #[cfg(not(feature = "termwiz"))]
+            BackendKind::Termwiz => Err(io::Error::other(
+                "the termwiz backend requires the termwiz feature",
+            )),
+        }
+    }
+}
+
+macro_rules! dispatch {
+    ($self:ident, $b:ident => $e:expr) => {
+        match $self {
+            AnyBackend::Crossterm($b) => $e,
+            #[cfg(feature = "termwiz")]
+            AnyBackend::Termwiz($b) => $e,
+        }
+    };
+}
+
+impl Backend for AnyBackend {
+    type Error = io::Error;
+
+    fn draw<'a, I>(&mut self, content: I) -> io::Result<()>
+    where
+        I: Iterator<Item = (u16, u16, &'a Cell)>,
+    {
+        dispatch!(self, b => b.draw(content))
+    }
+
+    fn append_lines(&mut self, n: u16) -> io::Result<()> {
+        dispatch!(self, b => b.append_lines(n))
+    }
+
+    fn hide_cursor(&mut self) -> io::Result<()> {
+        dispatch!(self, b => b.hide_cursor())
+    }
+
+    fn show_cursor(&mut self) -> io::Result<()> {
+        dispatch!(self, b => b.show_cursor())
+    }
+
+    fn get_cursor_position(&mut self) -> io::Result<Position> {
+        dispatch!(self, b => b.get_cursor_position())
+    }
+
+    fn set_cursor_position<P: Into<Position>>(&mut self, position: P) -> io::Result<()> {
+        let position = position.into();
+        dispatch!(self, b => b.set_cursor_position(position))
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        dispatch!(self, b => b.clear())
+    }
+
+    fn clear_region(&mut self, clear_type: RatatuiClearType) -> io::Result<()> {
+        dispatch!(self, b => b.clear_region(clear_type))
+    }
+
+    fn size(&self) -> io::Result<Size> {
+        dispatch!(self, b => b.size())
+    }
+
+    fn window_size(&mut self) -> io::Result<WindowSize> {
+        dispatch!(self, b => b.window_size())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        dispatch!(self, b => b.flush())
+    }
+}
+
+impl TuiBackend for AnyBackend {
+    fn set_raw_mode(&mut self, enable: bool) -> io::Result<()> {
+        dispatch!(self, b => b.set_raw_mode(enable))
+    }
+
+    fn set_alternate_screen(&mut self, enable: bool) -> io::Result<()> {
+        dispatch!(self, b => b.set_alternate_screen(enable))
+    }
+
+    fn set_mouse_capture(&mut self, enable: bool) -> io::Result<()> {
+        dispatch!(self, b => b.set_mouse_capture(enable))
+    }
+
+    fn set_bracketed_paste(&mut self, enable: bool) -> io::Result<()> {
+        dispatch!(self, b => b.set_bracketed_paste(enable))
+    }
+
+    fn set_extended_keys(&mut self, enable: bool) -> io::Result<()> {
+        dispatch!(self, b => b.set_extended_keys(enable))
+    }
+
+    fn scroll_up(&mut self, lines: u16) -> io::Result<()> {
+        dispatch!(self, b => b.scroll_up(lines))
+    }
+
+    fn clear_below(&mut self, row: Option<u16>) -> io::Result<()> {
+        dispatch!(self, b => b.clear_below(row))
+    }
+
+    fn cursor_row(&mut self, timeout: Duration) -> io::Result<u16> {
+        dispatch!(self, b => b.cursor_row(timeout))
+    }
+
+    fn supports_inline(&self) -> bool {
+        dispatch!(self, b => b.supports_inline())
+    }
+
+    fn draw_hyperlink(&mut self, url: &str, cells: &[(u16, u16, Cell)]) -> io::Result<()> {
+        dispatch!(self, b => b.draw_hyperlink(url, cells))
+    }
+}
+
+// ------------------------------------------------------------
+
+#[cfg(unix)]
+mod utils {
+    use anyhow::{Context, Result, bail};
+    use std::{
+        fs::OpenOptions,
+        io::{Read, Write},
+        time::Duration,
+    };
+
+    /// Query the terminal for the current cursor position (col, row)
+    /// Needed because crossterm implementation fails when stdout is not connected.
+    /// Requires raw mode
+    pub fn query_cursor_position(timeout: Duration) -> Result<(u16, u16)> {
+        use nix::sys::{
+            select::{FdSet, select},
+            time::{TimeVal, TimeValLike},
+        };
+        use std::os::fd::AsFd;
+
+        let mut tty = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .context("Failed to open /dev/tty")?;
+
+        // Send the ANSI cursor position report query
+        tty.write_all(b"\x1b[6n")?;
+        tty.flush()?;
+
+        // Wait for input using select()
+        let fd = tty.as_fd();
+        let mut fds = FdSet::new();
+        fds.insert(fd);
+
+        let mut timeout = TimeVal::milliseconds(timeout.as_millis() as i64);
+
+        let ready =
+            select(None, &mut fds, None, None, Some(&mut timeout)).context("select() failed")?;
+
+        if ready == 0 {
+            bail!("Timed out waiting for cursor position response: {timeout:?}");
+        }
+
+        // Read the response
+        let mut buf = [0u8; 64];
+        let n = tty.read(&mut buf)?;
+        let s = String::from_utf8_lossy(&buf[..n]);
+
+        parse_cursor_response(&s).context(format!("Failed to parse terminal response: {s}"))
+    }
+
+    /// Parse the terminal response with format ESC [ row ; col R
+    /// and return (col, row) as 0-based coordinates.
+    fn parse_cursor_response(s: &str) -> Result<(u16, u16)> {
+        let coords = s
+            .strip_prefix("\x1b[")
+            .context("Missing ESC]")?
+            .strip_suffix('R')
+            .context("Missing R")?;
+
+        let mut parts = coords.split(';');
+
+        let row: u16 = parts.next().context("Missing row")?.parse()?;
+
+        let col: u16 = parts.next().context("Missing column")?.parse()?;
+
+        Ok((col - 1, row - 1)) // convert to 0-based
+    }
+}
+
+#[cfg(windows)]
+mod utils {
+    use anyhow::Result;
+    use std::time::Duration;
+    pub fn query_cursor_position(timeout: Duration) -> Result<(u16, u16)> {
+        let ret = crossterm::cursor::position()?;
+        Ok(ret)
+    }
+}
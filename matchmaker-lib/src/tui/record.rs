@@ -0,0 +1,173 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    layout::Rect,
+    style::{Color, Modifier},
+};
+use unicode_width::UnicodeWidthStr;
+
+/// Writes drawn frames to an [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) file.
+///
+/// Each frame is written in full, rather than as a diff against the previous one, so the
+/// recording can be cut or seeked at any frame.
+#[derive(Debug)]
+pub struct Recorder {
+    out: BufWriter<File>,
+    start: Instant,
+    size: (u16, u16),
+    frames: usize,
+}
+
+impl Recorder {
+    pub fn new(path: &Path, area: Rect) -> io::Result<Self> {
+        let mut out = BufWriter::new(File::create(path)?);
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        writeln!(
+            out,
+            r#"{{"version": 2, "width": {}, "height": {}, "timestamp": {timestamp}}}"#,
+            area.width, area.height
+        )?;
+
+        Ok(Self {
+            out,
+            start: Instant::now(),
+            size: (area.width, area.height),
+            frames: 0,
+        })
+    }
+
+    pub fn frames(&self) -> usize {
+        self.frames
+    }
+
+    /// Append a frame, preceded by a resize event if the buffer has changed size.
+    pub fn frame(&mut self, buffer: &Buffer) -> io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+        let area = buffer.area;
+
+        if (area.width, area.height) != self.size {
+            self.size = (area.width, area.height);
+            writeln!(
+                self.out,
+                r#"[{time:.6}, "r", "{}x{}"]"#,
+                area.width, area.height
+            )?;
+        }
+
+        let data = encode(buffer);
+        let mut escaped = String::with_capacity(data.len());
+        escape_json(&data, &mut escaped);
+        writeln!(self.out, r#"[{time:.6}, "o", "{escaped}"]"#)?;
+
+        self.frames += 1;
+        self.out.flush()
+    }
+}
+
+/// Render the buffer as escape sequences, positioned relative to its own area.
+fn encode(buffer: &Buffer) -> String {
+    let area = buffer.area;
+    let mut ret = String::from("\x1b[0m\x1b[H\x1b[2J");
+    let mut last: Option<&Cell> = None;
+
+    for y in 0..area.height {
+        let _ = write!(ret, "\x1b[{};1H", y + 1);
+        let mut skip = 0;
+
+        for x in 0..area.width {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            let cell = &buffer[(area.x + x, area.y + y)];
+            if last
+                .is_none_or(|l| l.fg != cell.fg || l.bg != cell.bg || l.modifier != cell.modifier)
+            {
+                push_sgr(cell, &mut ret);
+                last = Some(cell);
+            }
+            let symbol = cell.symbol();
+            ret.push_str(symbol);
+            skip = symbol.width().saturating_sub(1);
+        }
+    }
+    ret.push_str("\x1b[0m");
+    ret
+}
+
+fn push_sgr(cell: &Cell, out: &mut String) {
+    out.push_str("\x1b[0");
+
+    const MODIFIERS: [(Modifier, u8); 9] = [
+        (Modifier::BOLD, 1),
+        (Modifier::DIM, 2),
+        (Modifier::ITALIC, 3),
+        (Modifier::UNDERLINED, 4),
+        (Modifier::SLOW_BLINK, 5),
+        (Modifier::RAPID_BLINK, 6),
+        (Modifier::REVERSED, 7),
+        (Modifier::HIDDEN, 8),
+        (Modifier::CROSSED_OUT, 9),
+    ];
+    for (modifier, code) in MODIFIERS {
+        if cell.modifier.contains(modifier) {
+            let _ = write!(out, ";{code}");
+        }
+    }
+
+    push_color(cell.fg, 30, out);
+    push_color(cell.bg, 40, out);
+    out.push('m');
+}
+
+/// `base` is 30 for the foreground and 40 for the background.
+fn push_color(color: Color, base: u8, out: &mut String) {
+    let _ = match color {
+        Color::Reset => Ok(()),
+        Color::Black => write!(out, ";{base}"),
+        Color::Red => write!(out, ";{}", base + 1),
+        Color::Green => write!(out, ";{}", base + 2),
+        Color::Yellow => write!(out, ";{}", base + 3),
+        Color::Blue => write!(out, ";{}", base + 4),
+        Color::Magenta => write!(out, ";{}", base + 5),
+        Color::Cyan => write!(out, ";{}", base + 6),
+        Color::Gray => write!(out, ";{}", base + 7),
+        Color::DarkGray => write!(out, ";{}", base + 60),
+        Color::LightRed => write!(out, ";{}", base + 61),
+        Color::LightGreen => write!(out, ";{}", base + 62),
+        Color::LightYellow => write!(out, ";{}", base + 63),
+        Color::LightBlue => write!(out, ";{}", base + 64),
+        Color::LightMagenta => write!(out, ";{}", base + 65),
+        Color::LightCyan => write!(out, ";{}", base + 66),
+        Color::White => write!(out, ";{}", base + 67),
+        Color::Indexed(i) => write!(out, ";{};5;{i}", base + 8),
+        Color::Rgb(r, g, b) => write!(out, ";{};2;{r};{g};{b}", base + 8),
+    };
+}
+
+fn escape_json(s: &str, out: &mut String) {
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+}
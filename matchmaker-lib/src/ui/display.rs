@@ -1,19 +1,24 @@
 use cba::bait::TransformExt;
 use ratatui::{
     layout::Constraint,
+    style::Style,
     text::{Line, Text},
     widgets::{Cell, Paragraph, Row, Table},
 };
 
+use unicode_width::UnicodeWidthStr;
+
 use crate::{
-    config::{DisplayConfig, RowConnectionStyle},
+    action::ActionExt,
+    binds::{ResolvedBindMap, TriggerKind},
+    config::{DisplayConfig, InteractionRegionSetting, RowConnectionStyle},
+    ui::ColumnLayout,
     utils::{
         serde::StringOrVec,
         text::{wrap_line, wrap_text, wrapping_indicator},
     },
 };
 pub type HeaderTable = Vec<Vec<Line<'static>>>;
-pub type ResultWidths = (u16, u16, Vec<u16>);
 
 #[derive(Debug, Default)]
 pub struct DisplayUI {
@@ -25,7 +30,9 @@ pub struct DisplayUI {
     pub config: DisplayConfig,
     dirty: bool,
     table: Table<'static>,
-    cached_result_widths: ResultWidths,
+    cached_layout: ColumnLayout,
+    /// The line of [`DisplayConfig::buttons`] and its interactions.
+    button_bar: Option<(String, InteractionRegionSetting)>,
 }
 
 impl DisplayUI {
@@ -41,19 +48,24 @@ impl DisplayUI {
 
     /// Refresh content and interactions from config.
     pub fn init(&mut self) {
-        let (text, height) = match &self.config.content {
-            Some(StringOrVec::String(s)) => {
-                let text = Text::from(s.clone());
-                let height = text.height() as u16;
-                (vec![text], height)
+        let mut text = match &self.config.content {
+            Some(StringOrVec::String(s)) => vec![Text::from(s.clone())],
+            Some(StringOrVec::Vec(s)) => s.iter().map(|s| Text::from(s.clone())).collect(),
+            _ => vec![],
+        };
+
+        if let Some((line, regions)) = &self.button_bar {
+            if text.is_empty() {
+                text.push(Text::default());
             }
-            Some(StringOrVec::Vec(s)) => {
-                let text: Vec<_> = s.iter().map(|s| Text::from(s.clone())).collect();
-                let height = text.iter().map(|t| t.height()).max().unwrap_or_default() as u16;
-                (text, height)
+            let row = text[0].height();
+            text[0].push_line(line.clone());
+            if self.config.interactions.len() <= row {
+                self.config.interactions.resize(row + 1, Vec::new());
             }
-            _ => (vec![], 0),
-        };
+            self.config.interactions[row] = regions.clone();
+        }
+        let height = text.iter().map(|t| t.height()).max().unwrap_or_default() as u16;
 
         for line in &mut self.config.interactions {
             line.sort_by_key(|(i, _)| *i);
@@ -61,10 +73,40 @@ impl DisplayUI {
 
         self.text = text;
         self.height = height;
-        self.show = self.config.content.is_some() || self.config.header_lines > 0;
+        self.show = self.config.content.is_some()
+            || self.config.header_lines > 0
+            || self.button_bar.is_some();
         self.dirty = true;
     }
 
+    /// Label the [`DisplayConfig::buttons`] with the descriptions of their binds, falling back
+    /// to their names.
+    pub fn set_buttons<A: ActionExt>(&mut self, binds: &ResolvedBindMap<A>) {
+        if self.config.buttons.is_empty() {
+            return;
+        }
+
+        let mut line = String::new();
+        let mut regions = Vec::new();
+        let offset = |line: &String| line.width().min(u8::MAX as usize) as u8;
+        for name in &self.config.buttons {
+            let label = binds
+                .get(&TriggerKind::Semantic(name.clone()))
+                .and_then(|actions| actions.desc())
+                .unwrap_or(name.as_str());
+            if !line.is_empty() {
+                regions.push((offset(&line), String::new()));
+                line.push(' ');
+            }
+            regions.push((offset(&line), name.clone()));
+            line.push_str(&format!("[{label}]"));
+        }
+        regions.push((offset(&line), String::new()));
+
+        self.button_bar = Some((line, regions));
+        self.init();
+    }
+
     pub fn update_width(&mut self, width: u16) {
         let border_w = self.config.border.width();
         let new_w = width.saturating_sub(border_w);
@@ -131,15 +173,14 @@ impl DisplayUI {
         self.dirty = true;
     }
 
-    pub fn make_display(&mut self, result_widths: ResultWidths) -> &Table<'static> {
-        if result_widths.2.is_empty() || (!self.dirty && self.cached_result_widths == result_widths)
-        {
+    pub fn make_display(&mut self, layout: &ColumnLayout) -> &Table<'static> {
+        if layout.widths.is_empty() || (!self.dirty && self.cached_layout == *layout) {
             return &self.table;
         }
 
-        self.cached_result_widths = result_widths.clone();
+        self.cached_layout.clone_from(layout);
         self.dirty = false;
-        let (result_indentation, col_spacing, widths) = result_widths;
+        let widths = &layout.widths;
 
         if self.text.is_empty() && self.lines.is_empty() {
             self.table = Table::default();
@@ -151,7 +192,7 @@ impl DisplayUI {
             if self.config.match_indent {
                 let mut padding = self.config.border.padding;
 
-                padding.left = result_indentation.saturating_sub(self.config.border.left());
+                padding.left = layout.offset.saturating_sub(self.config.border.left());
                 b.padding(padding.0)
             } else {
                 b
@@ -234,12 +275,11 @@ impl DisplayUI {
 
                 header_height += row_height;
 
-                let mut ret = Row::new(cells).height(row_height);
-
+                let mut style = self.config.header_lines_style.into_style_no_submodifiers();
                 if matches!(self.config.row_connection, RowConnectionStyle::Disjoint) {
-                    ret = ret.style(self.config.style);
+                    style = Style::from(self.config.style).patch(style);
                 }
-                ret
+                Row::new(cells).height(row_height).style(style)
             }));
 
             self.height += header_height;
@@ -254,14 +294,14 @@ impl DisplayUI {
             //     *s = s.saturating_sub(surplus);
             // }
             widths
-                .into_iter()
-                .filter_map(|x| (x > 0).then_some(Constraint::Length(x)))
+                .iter()
+                .filter_map(|&x| (x > 0).then_some(Constraint::Length(x)))
                 .collect()
         };
 
         self.table = Table::new(rows, widths)
             .block(block)
-            .column_spacing(col_spacing)
+            .column_spacing(layout.spacing)
             .transform_if(
                 !matches!(self.config.row_connection, RowConnectionStyle::Disjoint),
                 |t| t.style(self.config.style),
@@ -271,10 +311,10 @@ impl DisplayUI {
     }
 
     /// Draw in the same area as display when self.single() to produce a full width row over the table area
-    pub fn make_full_width_row(&self, result_indentation: u16) -> Paragraph<'_> {
+    pub fn make_full_width_row(&self, layout: &ColumnLayout) -> Paragraph<'_> {
         // Compute padding
         let left = if self.config.match_indent {
-            result_indentation.saturating_sub(self.config.border.left())
+            layout.offset.saturating_sub(self.config.border.left())
         } else {
             self.config.border.left()
         };
@@ -1,8 +1,11 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    collections::VecDeque,
+    ops::{Deref, DerefMut},
+};
 
 use ratatui::{
     layout::{Position, Rect},
-    style::Style,
+    style::{Modifier, Style},
     text::{Line, Span},
     widgets::Paragraph,
 };
@@ -19,6 +22,99 @@ pub struct InputUI {
     pub graphemes: Vec<(usize, u16)>,
     pub before: usize, // index into graphemes of the first visible grapheme
     pub width: u16,    // only relevant to cursor scrolling
+    pub history: History,
+    /// Text being composed by an input method, displayed at the cursor but not yet part of the input.
+    pub preedit: String,
+}
+
+/// Previously accepted queries, oldest first.
+#[derive(Debug, Default, Clone)]
+pub struct History {
+    entries: VecDeque<String>,
+    /// Maximum number of entries kept.
+    pub size: usize,
+    // position while cycling
+    index: Option<usize>,
+    // the input from before cycling began, restored after cycling past the newest entry
+    stash: String,
+}
+
+impl History {
+    pub fn new(size: usize) -> Self {
+        Self {
+            size,
+            ..Default::default()
+        }
+    }
+
+    /// Record an entry, skipping empty entries and repeats of the newest entry.
+    pub fn push(&mut self, entry: String) {
+        self.reset();
+        if entry.is_empty() || self.entries.back() == Some(&entry) {
+            return;
+        }
+        self.entries.push_back(entry);
+        while self.entries.len() > self.size {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn extend(&mut self, entries: impl IntoIterator<Item = String>) {
+        for e in entries {
+            self.push(e);
+        }
+    }
+
+    pub fn entries(&self) -> impl DoubleEndedIterator<Item = &String> + ExactSizeIterator {
+        self.entries.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Step back to an older entry.
+    /// `current` is the input being displayed: if it was edited since the last step, cycling restarts from it.
+    pub fn prev(&mut self, current: &str) -> Option<&str> {
+        if self
+            .index
+            .is_some_and(|i| self.entries.get(i).is_none_or(|e| e != current))
+        {
+            self.reset();
+        }
+
+        let index = match self.index {
+            None => {
+                self.stash = current.to_string();
+                self.entries.len().checked_sub(1)?
+            }
+            Some(i) => i.checked_sub(1)?,
+        };
+        self.index = Some(index);
+        self.entries.get(index).map(String::as_str)
+    }
+
+    /// Step forward to a newer entry, or back to the stashed input after the newest.
+    pub fn next(&mut self) -> Option<&str> {
+        let index = self.index? + 1;
+        if index < self.entries.len() {
+            self.index = Some(index);
+            self.entries.get(index).map(String::as_str)
+        } else {
+            self.index = None;
+            Some(&self.stash)
+        }
+    }
+
+    /// Stop cycling.
+    pub fn reset(&mut self) {
+        self.index = None;
+        self.stash.clear();
+    }
 }
 
 impl InputUI {
@@ -60,6 +156,10 @@ impl InputUI {
         self.cursor as u16
     }
 
+    pub fn preedit_width(&self) -> u16 {
+        self.preedit.width() as u16
+    }
+
     // ------------ SETTERS ---------------
     pub fn set(&mut self, input: impl Into<Option<String>>, cursor: u16) {
         if let Some(input) = input.into() {
@@ -84,6 +184,17 @@ impl InputUI {
         self.cursor += added_graphemes;
     }
 
+    /// Set the composition text of an input method. Empty text ends the composition.
+    pub fn set_preedit(&mut self, preedit: impl Into<String>) {
+        self.preedit = preedit.into();
+    }
+
+    /// Insert the composition text at the cursor, ending the composition.
+    pub fn commit_preedit(&mut self) {
+        let preedit = std::mem::take(&mut self.preedit);
+        self.insert_str(&preedit);
+    }
+
     pub fn push_str(&mut self, content: &str) {
         self.input.push_str(content);
         self.recompute_graphemes();
@@ -108,9 +219,10 @@ impl InputUI {
                 ..=(self.cursor + padding.min(1)).min(self.graphemes.len().saturating_sub(1))]
                 .iter()
                 .map(|(_, w)| *w)
-                .sum();
+                .sum::<u16>()
+                + self.preedit_width();
 
-            // ensures visual_start..=cursor is displayed
+            // ensures visual_start..=cursor is displayed, followed by the preedit.
             // Padding ensures the following element after cursor if present is displayed.
             if visual_dist < self.width {
                 break;
@@ -125,11 +237,30 @@ impl InputUI {
         }
     }
 
+    /// Replace the input with an older history entry. Returns false if there is none.
+    pub fn history_prev(&mut self) -> bool {
+        let Some(entry) = self.history.prev(&self.input).map(str::to_string) else {
+            return false;
+        };
+        self.set(entry, u16::MAX);
+        true
+    }
+
+    /// Replace the input with a newer history entry. Returns false if not cycling.
+    pub fn history_next(&mut self) -> bool {
+        let Some(entry) = self.history.next().map(str::to_string) else {
+            return false;
+        };
+        self.set(entry, u16::MAX);
+        true
+    }
+
     pub fn cancel(&mut self) {
         self.input.clear();
         self.graphemes.clear();
         self.cursor = 0;
         self.before = 0;
+        self.preedit.clear();
     }
 
     // Doesn't recompute graphemes or update cursor
@@ -274,30 +405,46 @@ impl InputUI {
     // remember to call scroll_to_cursor beforehand
 
     pub fn render(&self) -> &str {
+        let end_idx = self.visible_end(self.before, self.width);
+        &self.input[self.byte_index(self.before)..self.byte_index(end_idx)]
+    }
+
+    /// The visible input before and after the cursor, leaving room for the preedit between them.
+    pub fn render_parts(&self) -> (&str, &str) {
+        let cursor_byte = self.byte_index(self.cursor);
+        let before = &self.input[self.byte_index(self.before)..cursor_byte];
+
+        let used = self.cursor_rel_offset();
+        if self.width != 0 && used >= self.width {
+            return (before, "");
+        }
+        let end_idx = self.visible_end(self.cursor, self.width.saturating_sub(used));
+        (before, &self.input[cursor_byte..self.byte_index(end_idx)])
+    }
+
+    /// The grapheme index following the graphemes from `start` which fit in `width` (0 for unlimited).
+    fn visible_end(&self, start: usize, width: u16) -> usize {
         let mut visible_width = 0;
-        let mut end_idx = self.before;
+        let mut end_idx = start;
 
         while end_idx < self.graphemes.len() {
             let g_width = self.graphemes[end_idx].1;
-            if self.width != 0 && visible_width + g_width > self.width {
+            if self.width != 0 && visible_width + g_width > width {
                 break;
             }
             visible_width += g_width;
             end_idx += 1;
         }
-
-        let start_byte = self.byte_index(self.before);
-        let end_byte = self.byte_index(end_idx);
-        
-
-        (&self.input[start_byte..end_byte]) as _
+        end_idx
     }
 
+    /// The visual offset of the cursor from the start of the visible input, after the preedit.
     pub fn cursor_rel_offset(&self) -> u16 {
-        self.graphemes[self.before..self.cursor]
+        let offset: u16 = self.graphemes[self.before..self.cursor]
             .iter()
             .map(|(_, w)| *w)
-            .sum()
+            .sum();
+        offset + self.preedit_width()
     }
 }
 
@@ -371,10 +518,18 @@ impl QueryUI {
 
     pub fn make_input(&self) -> Paragraph<'_> {
         let mut line = self.prompt.clone();
-        line.push_span(Span::styled(
-            self.state.render(),
-            self.config.style.r#override(Style::reset()),
-        ));
+        let style = self.config.style.r#override(Style::reset());
+        if self.preedit.is_empty() {
+            line.push_span(Span::styled(self.state.render(), style));
+        } else {
+            let (before, after) = self.state.render_parts();
+            line.push_span(Span::styled(before, style));
+            line.push_span(Span::styled(
+                self.preedit.as_str(),
+                style.add_modifier(Modifier::UNDERLINED),
+            ));
+            line.push_span(Span::styled(after, style));
+        }
 
         Paragraph::new(line).block(self.config.border.as_block())
     }
@@ -402,3 +557,50 @@ impl QueryUI {
         self.prompt = prompt;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preedit() {
+        let mut input = InputUI::new();
+        input.width = 8;
+        input.set("abcdefgh".to_string(), 2);
+        input.set_preedit("日本");
+
+        input.scroll_to_cursor(0);
+        assert_eq!(input.before, 0);
+        assert_eq!(input.cursor_rel_offset(), 6);
+        assert_eq!(input.render_parts(), ("ab", "cd"));
+
+        input.commit_preedit();
+        assert!(input.preedit.is_empty());
+        assert_eq!(input.input, "ab日本cdefgh");
+        assert_eq!(input.cursor, 4);
+
+        input.set_preedit("語");
+        input.set(None, u16::MAX);
+        input.scroll_to_cursor(0);
+        assert!(input.cursor_rel_offset() < input.width);
+        assert_eq!(input.render_parts().1, "");
+    }
+
+    #[test]
+    fn test_preedit_scroll() {
+        let mut input = InputUI::new();
+        input.width = 8;
+        input.set("abcdefgh".to_string(), 6);
+        input.set_preedit("日本");
+
+        // the graphemes up to the cursor and the preedit must fit before the last column
+        input.scroll_to_cursor(0);
+        assert_eq!(input.before, 4);
+        assert_eq!(input.cursor_rel_offset(), 6);
+        assert_eq!(input.render_parts(), ("ef", "gh"));
+
+        input.set_preedit("");
+        assert_eq!(input.cursor_rel_offset(), 2);
+        assert_eq!(input.render(), "efgh");
+    }
+}
@@ -3,7 +3,9 @@ mod input;
 mod overlay;
 mod preview;
 mod results;
+mod sources;
 mod status;
+mod toast;
 pub mod utils;
 pub use display::*;
 pub use input::*;
@@ -16,7 +18,9 @@ pub use ratatui::{
     widgets::Table,
 };
 pub use results::*;
+pub use sources::*;
 pub use status::*; // reexport for convenience
+pub use toast::*;
 
 use crate::{
     SSS, Selector,
@@ -26,7 +30,7 @@ use crate::{
     },
     nucleo::Worker,
     preview::Preview,
-    tui::Tui,
+    tui::{Tui, TuiBackend},
 };
 // UI
 pub struct UI {
@@ -37,13 +41,13 @@ pub struct UI {
 
 // requires columns > 1
 impl UI {
-    pub fn new<'a, T: SSS, D: 'static, W: std::io::Write>(
+    pub fn new<'a, T: SSS, D: 'static, B: TuiBackend>(
         mut config: RenderConfig,
         matcher: &'a mut nucleo::Matcher,
         worker: Worker<T, D>,
         selector: Selector,
         view: Option<Preview>,
-        tui: &mut Tui<W>,
+        tui: &mut Tui<B>,
         hidden_columns: impl IntoIterator<Item = usize>,
     ) -> (Self, PickerUI<'a, T, D>, DisplayUI, Option<PreviewUI>) {
         assert!(!worker.columns.is_empty());
@@ -142,6 +146,8 @@ pub struct PickerUI<'a, T: SSS, D> {
     pub matcher: &'a mut nucleo::Matcher,
     pub selector: Selector,
     pub worker: Worker<T, D>,
+    /// The inactive workers, which [`Self::switch_source`] swaps with the active one.
+    pub sources: SourceSet<T, D>,
 }
 
 impl<'a, T: SSS, D: 'static> PickerUI<'a, T, D> {
@@ -168,6 +174,7 @@ impl<'a, T: SSS, D: 'static> PickerUI<'a, T, D> {
             matcher,
             selector,
             worker,
+            sources: SourceSet::default(),
         }
     }
 
@@ -176,6 +183,117 @@ impl<'a, T: SSS, D: 'static> PickerUI<'a, T, D> {
         self.worker.restart(false);
         self.results.set_dirty();
         self.selector.clear();
+        self.selector.reset_preselect();
+    }
+
+    /// Remove or replace items, keeping the selections of the rest. See [`Worker::edit`].
+    ///
+    /// Prefer [`crate::render::MMState::edit_items`]
+    pub fn edit_items(&mut self, edits: impl IntoIterator<Item = (u32, Option<T>)>)
+    where
+        T: Clone,
+    {
+        let map = self.worker.edit(edits);
+        self.selector.remap(&map);
+        self.results.set_dirty();
+    }
+
+    /// Show an item at the top of the results, whatever the query. See [`Worker::pin`].
+    pub fn pin(&mut self, idx: u32) -> bool {
+        self.results.set_dirty();
+        self.worker.pin(idx)
+    }
+
+    pub fn unpin(&mut self, idx: u32) -> bool {
+        self.results.set_dirty();
+        self.worker.unpin(idx)
+    }
+
+    /// Collapse the group of the current result, moving the cursor onto its header, or expand
+    /// it if collapsed. See [`Worker::set_group_by`].
+    pub fn toggle_group(&mut self) {
+        let Some(group) = self.worker.group_of(self.results.index()) else {
+            return;
+        };
+        let (name, row, collapsed) = (group.name.clone(), group.row, !group.collapsed);
+        self.worker.set_collapsed(&name, collapsed);
+        if collapsed {
+            self.results.cursor_jump(row);
+        }
+        self.results.set_dirty();
+    }
+
+    /// Collapse or expand every group.
+    pub fn set_groups_collapsed(&mut self, collapsed: bool) {
+        let current = self
+            .worker
+            .group_of(self.results.index())
+            .map(|g| g.name.clone());
+        self.worker.set_all_collapsed(collapsed);
+        // keep the cursor within the same group
+        if let Some(name) = current
+            && let Some(group) = self.worker.groups().iter().find(|g| g.name == name)
+        {
+            self.results.cursor_jump(group.row);
+        }
+        self.results.set_dirty();
+    }
+
+    /// Switch between smart case and case-sensitive matching. See [`Worker::set_case_sensitive`].
+    pub fn toggle_case(&mut self) {
+        let case_sensitive = !self.worker.case_sensitive();
+        self.worker.set_case_sensitive(case_sensitive);
+        self.results.set_dirty();
+    }
+
+    /// Switch between fuzzy and exact matching. See [`Worker::set_exact`].
+    pub fn toggle_exact(&mut self) {
+        let exact = !self.worker.exact();
+        self.worker.set_exact(exact);
+        self.results.set_dirty();
+    }
+
+    /// Show the children of the current node. See [`Worker::set_tree`].
+    pub fn expand_node(&mut self) {
+        if let Some(idx) = self
+            .worker
+            .get_nth_indexed(self.results.index())
+            .map(|(idx, _)| idx)
+            && self.worker.set_node_collapsed(idx, false)
+        {
+            self.results.set_dirty();
+        }
+    }
+
+    /// Hide the children of the current node, or move the cursor to its parent if there are
+    /// none or they are already hidden.
+    pub fn collapse_node(&mut self) {
+        let Some((idx, _)) = self.worker.get_nth_indexed(self.results.index()) else {
+            return;
+        };
+        if !self.worker.set_node_collapsed(idx, true)
+            && let Some(row) = self
+                .worker
+                .tree_parent(idx)
+                .and_then(|parent| self.worker.row_of(parent))
+        {
+            self.results.cursor_jump(row);
+        }
+        self.results.set_dirty();
+    }
+
+    /// Switch to the n-th source, returning it if it wasn't already active.
+    pub fn switch_source(&mut self, n: usize) -> Option<&Source<T, D>> {
+        let source = self
+            .sources
+            .switch(n, &mut self.worker, &mut self.selector)?;
+        if self.results.hidden_columns.mask_len() != self.worker.columns.len() {
+            self.results.init(&self.worker.columns);
+        }
+        self.results.set_dirty();
+        self.results.cursor_jump(0);
+        self.query.set_prompt(source.prompt.clone().map(Into::into));
+        Some(source)
     }
 
     pub fn active_column_index(&self) -> usize {
@@ -1,17 +1,30 @@
+use std::time::{Duration, Instant};
+
 use cba::_info;
 use log::error;
 use ratatui::{
+    buffer::Buffer,
     layout::Rect,
-    text::Line,
-    widgets::{Paragraph, Wrap},
+    style::Style,
+    text::{Line, Span},
+    widgets::{Block, Paragraph},
 };
 
+#[cfg(feature = "image")]
+mod graphics;
+#[cfg(feature = "image")]
+pub(crate) use graphics::query_picker;
+
 use crate::{
     config::{
         BorderSetting, PreviewConfig, PreviewInitialSetting, PreviewSetting, ShowCondition, Side,
     },
     preview::Preview,
-    utils::text::{trim_text_lines, wrapped_line_height},
+    utils::{
+        hyperlink::{HyperlinkCells, RowOrigin, find_hyperlinks},
+        string::substitute_escaped,
+        text::{word_wrap_line_origins, wrapped_line_height},
+    },
 };
 
 #[derive(Debug)]
@@ -32,9 +45,26 @@ pub struct PreviewUI {
     pub scroll: [u16; 2],
     offset: usize,
     target: Option<usize>,
+    /// The line given by the index, counted from 1, which is highlighted however the offset
+    /// moves the target.
+    highlight: Option<usize>,
     attained_target: bool,
     pub jump: (bool, usize), // end, initial
     pub last_count: usize,
+    /// Whether the scroll is kept at the bottom, see [`PreviewConfig::follow`]
+    following: bool,
+    /// The border title with its placeholders expanded for the current item
+    title: Option<String>,
+    /// When the command was last run, and whether a wake-up is scheduled to rerun it, see
+    /// [`PreviewConfig::refresh_ms`]
+    refreshed: Option<(Instant, bool)>,
+    /// Whether the command is being rerun, so that the scroll position is kept
+    refreshing: bool,
+    /// Where each row of the last drawn preview comes from, to find the hyperlinks on it
+    row_origins: Vec<RowOrigin>,
+
+    #[cfg(feature = "image")]
+    image: graphics::ImageState,
 }
 
 impl PreviewUI {
@@ -90,6 +120,7 @@ impl PreviewUI {
         }
 
         let idx = config.initial_layout;
+        let following = config.follow;
 
         let mut ret = Self {
             view,
@@ -101,19 +132,53 @@ impl PreviewUI {
             offset: 0,
             area: Rect::default(),
             target: None,
+            highlight: None,
             attained_target: false,
             last_count: 0,
             jump: Default::default(),
             show,
             current_dimension: None,
+            following,
+            title: None,
+            refreshed: None,
+            refreshing: false,
+            row_origins: Vec::new(),
+            #[cfg(feature = "image")]
+            image: Default::default(),
         };
         ret.set_layout(idx);
 
         ret
     }
 
+    /// Draw images with the graphics protocol detected by `picker`, instead of describing them.
+    #[cfg(feature = "image")]
+    pub(crate) fn set_image_picker(&mut self, picker: ratatui_image::picker::Picker) {
+        self.image.set_picker(picker);
+    }
+
+    /// Draw the image output by the preview command into `area`, returning false if there is
+    /// none. Without a graphics protocol, the image is described instead.
+    #[cfg(feature = "image")]
+    pub fn render_image(&mut self, frame: &mut ratatui::Frame, area: Rect) -> bool {
+        let Some(image) = self.view.image() else {
+            return false;
+        };
+        let block = self.block();
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let (width, height) = (image.width(), image.height());
+        if !self.image.render(frame, inner, image) {
+            frame.render_widget(Paragraph::new(format!("[image {width}x{height}]")), inner);
+        }
+        true
+    }
+
     pub fn update_dimensions(&mut self, area: &Rect) {
         self.area = self.border().inner(*area);
+        #[cfg(feature = "pty")]
+        self.view.pty().resize(self.area.height, self.area.width);
         if self.config.reevaluate_show_on_resize {
             self.reevaluate_show_condition([area.width, area.height], false);
         }
@@ -184,6 +249,17 @@ impl PreviewUI {
             .unwrap_or(&self.config.border)
     }
 
+    /// Display `title` in place of the configured border title.
+    /// None restores the title defined in the config.
+    pub fn set_title(&mut self, title: Option<String>) {
+        self.title = title;
+    }
+
+    fn block(&self) -> Block<'_> {
+        let border = self.border();
+        border.as_titled_block(self.title.as_deref().unwrap_or(&border.title))
+    }
+
     pub fn get_initial_command(&self) -> &str {
         let x = self.command();
         if !x.is_empty() {
@@ -219,6 +295,9 @@ impl PreviewUI {
             }
         }
     }
+    pub fn layout_index(&self) -> u8 {
+        self.layout_idx as u8
+    }
     pub fn set_layout(&mut self, idx: u8) -> bool {
         let idx = idx as usize;
         if idx < self.config.layout.len() {
@@ -270,9 +349,36 @@ impl PreviewUI {
     pub fn target_line(&self) -> Option<usize> {
         self.target
     }
+    /// The number of lines in the preview, and the first line displayed below the header.
+    pub fn scroll_position(&self) -> (usize, usize) {
+        (self.view.len(), self.offset())
+    }
+
+    /// The position indicator configured by [`PreviewConfig::indicator`], if any.
+    pub fn make_indicator(&self) -> Option<Line<'static>> {
+        if self.config.indicator.is_empty() {
+            return None;
+        }
+        let (total, position) = self.scroll_position();
+        let bottom = (position + self.area.height as usize).min(total);
+        let percent = if total == 0 {
+            100
+        } else {
+            bottom * 100 / total
+        };
+
+        let replacements = [
+            ('c', (position + 1).min(total).to_string()),
+            ('t', total.to_string()),
+            ('p', percent.to_string()),
+        ];
+        let content = substitute_escaped(&self.config.indicator, &replacements);
+        Some(Line::styled(content, self.config.indicator_style))
+    }
 
     // ----- actions --------
     pub fn up(&mut self, n: u16) {
+        self.following = false;
         let total_lines = self.view.len();
         let n = n as usize;
 
@@ -297,6 +403,9 @@ impl PreviewUI {
         } else {
             self.offset += n;
         }
+        if self.config.follow && self.offset >= self.end_offset() {
+            self.following = true;
+        }
     }
 
     pub fn scroll(&mut self, horizontal: bool, val: i8) {
@@ -312,41 +421,92 @@ impl PreviewUI {
 
     pub fn set_target(&mut self, target: Option<isize>) {
         _info!(target);
+        if self.refreshing {
+            return;
+        }
+        self.refreshed = Some((Instant::now(), false));
+        self.following = self.config.follow;
 
         if self.initial().tail {
             return;
         }
 
-        let results = self.view.results().lines;
-        let line_count = results.len();
+        let line_count = self.view.len();
 
-        let Some(mut target) = target else {
+        let Some(index) = target else {
             self.target = None;
+            self.highlight = None;
             self.offset = 0;
             return;
         };
 
-        target += self.initial().offset.unwrap_or(-1);
-
-        self.target = Some(if target < 0 {
-            line_count.saturating_sub(target.unsigned_abs())
-        } else {
-            target as usize
-        });
+        let resolve = |i: isize| {
+            if i < 0 {
+                line_count.saturating_sub(i.unsigned_abs())
+            } else {
+                i as usize
+            }
+        };
+        self.highlight = Some(resolve(index - 1));
+        self.target = Some(resolve(index + self.initial().offset.unwrap_or(-1)));
 
         let index = self.target.unwrap();
 
-        self.offset = if index >= results.len() {
+        self.offset = if index >= line_count {
             self.attained_target = false;
-            results.len().saturating_sub(self.area.height as usize / 2)
+            line_count.saturating_sub(self.area.height as usize / 2)
         } else {
             self.attained_target = true;
-            self.target_to_offset(index, &results)
+            self.target_to_offset(index, 0)
         };
 
         _info!("Preview initial offset": self.offset; "index" : index);
     }
 
+    /// Whether the command is due to be rerun, see [`PreviewConfig::refresh_ms`]. Called on ticks.
+    pub(crate) fn refresh_due(&mut self) -> bool {
+        let interval = Duration::from_millis(self.config.refresh_ms);
+        if interval.is_zero() || !self.visible() {
+            return false;
+        }
+        let due = self
+            .refreshed
+            .is_some_and(|(last, _)| last.elapsed() >= interval);
+        if due {
+            self.refreshed = Some((Instant::now(), false));
+            self.refreshing = true;
+        }
+        due
+    }
+
+    /// The cells of the content area which show the text of an OSC 8 hyperlink in the output.
+    pub(crate) fn hyperlinks(&self, buf: &Buffer) -> Vec<HyperlinkCells> {
+        let links = self.view.hyperlinks();
+        if links.is_empty() {
+            return Vec::new();
+        }
+        // the paragraph is only scrolled when wrapped, see make_preview
+        let scroll = if self.config.wrap {
+            (self.scroll[0], self.scroll[1])
+        } else {
+            (0, 0)
+        };
+        find_hyperlinks(buf, self.area, &self.row_origins, scroll, &links)
+    }
+
+    /// Returns the time until the command is due to be rerun, once per run.
+    /// Ticks stop when idle, so the caller should wake the event loop after it.
+    pub(crate) fn schedule_refresh(&mut self) -> Option<Duration> {
+        self.refreshing = false;
+        let interval = Duration::from_millis(self.config.refresh_ms);
+        if interval.is_zero() || !self.visible() {
+            return None;
+        }
+        let (last, scheduled) = self.refreshed.as_mut().filter(|(_, s)| !s)?;
+        *scheduled = true;
+        Some(interval.saturating_sub(last.elapsed()))
+    }
+
     pub fn jump(&mut self) {
         if self.initial().tail {
             if self.offset > 0 {
@@ -387,19 +547,25 @@ impl PreviewUI {
     pub fn reset_scroll(&mut self) {
         self.offset = 0;
         self.attained_target = false;
+        self.following = false;
     }
     pub fn scroll_end(&mut self) {
-        let results = self.view.results();
-        let rl = results.lines.len();
+        self.offset = self.end_offset();
+        self.following = self.config.follow;
+    }
+    /// The offset at which the last line is at the bottom of the preview.
+    fn end_offset(&self) -> usize {
+        let rl = self.view.len();
         let height = self.area.height as usize;
 
         let header_count = self.initial().header_lines.min(height);
         let remaining_lines = rl.saturating_sub(header_count);
 
-        self.offset = remaining_lines.saturating_sub(height);
+        remaining_lines.saturating_sub(height)
     }
 
-    fn target_to_offset(&self, mut target: usize, results: &Vec<Line>) -> usize {
+    /// `start`: the index of the first displayed line of the output
+    fn target_to_offset(&self, mut target: usize, start: usize) -> usize {
         // decrement the index to put the target lower on the page.
         // The resulting height up to the top of target should >= p% of height.
         let mut lines_above =
@@ -411,9 +577,10 @@ impl PreviewUI {
 
         // shoddy approximation to how Paragraph wraps lines
         while target > 0 && lines_above > 0 {
-            let prev = results
-                .get(target)
-                .map(|x| wrapped_line_height(x, self.area.width))
+            let prev = self
+                .view
+                .line(start + target)
+                .map(|x| wrapped_line_height(&x, self.area.width))
                 .unwrap_or(1);
             if prev > lines_above {
                 break;
@@ -538,12 +705,14 @@ impl PreviewUI {
     }
 
     pub fn make_preview(&mut self) -> Paragraph<'_> {
-        let mut results = self.view.results();
-        if self.config.trim_ends {
-            trim_text_lines(&mut results)
-        }
+        // only the displayed lines are materialized, so that large outputs scroll as fast as small ones
+        let bounds = if self.config.trim_ends {
+            self.view.content_bounds()
+        } else {
+            0..self.view.len()
+        };
 
-        let rl = results.lines.len();
+        let rl = bounds.len();
         let height = self.area.height as usize;
         let mut offset = self.offset;
 
@@ -551,11 +720,16 @@ impl PreviewUI {
         if rl < self.last_count {
             self.offset = 0;
             self.attained_target = false;
-            self.jump = (false, 0)
+            self.jump = (false, 0);
+            self.following = self.config.follow;
         }
         self.last_count = rl;
 
-        if self.initial().tail && !self.attained_target {
+        if self.following {
+            // keep up with the output
+            self.offset = self.end_offset();
+            offset = self.offset;
+        } else if self.initial().tail && !self.attained_target {
             let header_count = self.initial().header_lines.min(height);
             let remaining_lines = rl.saturating_sub(header_count);
             let remaining_space = height.saturating_sub(header_count);
@@ -583,41 +757,97 @@ impl PreviewUI {
             && !self.attained_target
             && target < rl
         {
-            self.offset = self.target_to_offset(target, &results.lines);
+            self.offset = self.target_to_offset(target, bounds.start);
             self.attained_target = true;
         };
 
-        let mut results = results.into_iter();
-
         if height == 0 {
             return Paragraph::new(Vec::new());
         }
 
-        let mut lines = Vec::with_capacity(height);
+        let numbers = self.config.line_numbers.then(|| {
+            let width = rl.max(1).ilog10() as usize + 1;
+            (width, Style::from(self.config.line_number_style))
+        });
 
-        for _ in 0..self.initial().header_lines.min(height) {
-            if let Some(line) = results.next() {
-                lines.push(line);
-            } else {
-                break;
+        let header_lines = self.initial().header_lines;
+        // highlight the line given by the index, which is counted from after the header
+        let target = self
+            .highlight
+            .filter(|_| !self.initial().tail)
+            .map(|t| t + header_lines);
+        let target_style = Style::from(self.config.target_style);
+        // wrapped at word boundaries, leaving room for the line numbers
+        let wrap_width = self.config.wrap.then(|| {
+            let numbers_width = numbers.map_or(0, |(width, _)| width as u16 + 1);
+            self.area.width.saturating_sub(numbers_width)
+        });
+        let numbers_width = numbers.map_or(0, |(width, _)| width + 1);
+        let mark = |line: Line<'static>, idx: usize| {
+            let rows = match wrap_width {
+                Some(width) => word_wrap_line_origins(line, width),
+                None => vec![(0, 0, line)],
             };
-        }
-
-        let mut results = results.skip(offset);
+            rows.into_iter()
+                .enumerate()
+                .map(move |(row, (col, indent, line))| {
+                    let line = number_line(line, (row == 0).then_some(idx), numbers);
+                    let line = if target == Some(idx) {
+                        line.patch_style(target_style)
+                    } else {
+                        line
+                    };
+                    let origin = RowOrigin {
+                        line: bounds.start + idx,
+                        col,
+                        prefix: numbers_width + indent,
+                    };
+                    (origin, line)
+                })
+        };
 
-        for _ in self.initial().header_lines..height {
-            if let Some(line) = results.next() {
-                lines.push(line);
-            }
+        let header_count = header_lines.min(height).min(rl);
+        let mut rows: Vec<_> = self
+            .view
+            .lines(bounds.start..bounds.start + header_count)
+            .into_iter()
+            .enumerate()
+            .flat_map(|(i, line)| mark(line, i))
+            .collect();
+
+        // the body is empty if the header was not filled
+        let body_start = header_count + offset;
+        let body_end = (body_start + height.saturating_sub(header_lines)).min(rl);
+        if header_count == header_lines && body_start < body_end {
+            rows.extend(
+                self.view
+                    .lines(bounds.start + body_start..bounds.start + body_end)
+                    .into_iter()
+                    .enumerate()
+                    .flat_map(|(i, line)| mark(line, body_start + i)),
+            );
         }
+        let (origins, lines): (Vec<_>, Vec<_>) = rows.into_iter().unzip();
+        self.row_origins = origins;
 
         let mut preview = Paragraph::new(lines);
-        preview = preview.block(self.border().as_block());
+        preview = preview.block(self.block());
         if self.config.wrap {
-            preview = preview
-                .wrap(Wrap { trim: false })
-                .scroll(self.scroll.into());
+            preview = preview.scroll(self.scroll.into());
         }
         preview
     }
 }
+
+/// Prefix the `idx`th line of the preview with its (1-based) number, or with blanks for the
+/// continuation of a wrapped line.
+fn number_line(line: Line<'_>, idx: Option<usize>, numbers: Option<(usize, Style)>) -> Line<'_> {
+    let Some((width, style)) = numbers else {
+        return line;
+    };
+    let mut spans = Vec::with_capacity(line.spans.len() + 1);
+    let number = idx.map_or(String::new(), |idx| (idx + 1).to_string());
+    spans.push(Span::styled(format!("{number:>width$} "), style));
+    spans.extend(line.spans);
+    Line { spans, ..line }
+}
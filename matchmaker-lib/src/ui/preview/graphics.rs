@@ -0,0 +1,100 @@
+//! Drawing preview images with the terminal's graphics protocol.
+
+use std::{fmt, sync::Arc};
+
+use image::DynamicImage;
+use ratatui::{Frame, layout::Rect};
+use ratatui_image::{
+    StatefulImage,
+    picker::{Picker, ProtocolType},
+    protocol::StatefulProtocol,
+};
+
+/// Query the terminal for its graphics protocol and font size.
+///
+/// The query reads the reply from stdin, so it is only sent when stdin and stdout are both the
+/// terminal. Otherwise, the protocol is guessed from the environment, falling back to drawing
+/// with colored half blocks. Must be called in raw mode, before the event loop starts reading.
+pub(crate) fn query_picker() -> Picker {
+    if atty::is(atty::Stream::Stdin) && atty::is(atty::Stream::Stdout) {
+        match Picker::from_query_stdio() {
+            Ok(picker) => return picker,
+            Err(e) => log::debug!("Failed to query the graphics protocol: {e}"),
+        }
+    }
+
+    let font_size = crossterm::terminal::window_size()
+        .ok()
+        .filter(|w| w.columns > 0 && w.rows > 0 && w.width > 0 && w.height > 0)
+        .map_or((8, 16), |w| (w.width / w.columns, w.height / w.rows));
+    let mut picker = Picker::from_fontsize(font_size);
+    if let Some(protocol) = protocol_from_env() {
+        picker.set_protocol_type(protocol);
+    }
+    picker
+}
+
+fn protocol_from_env() -> Option<ProtocolType> {
+    let var = |name| std::env::var(name).unwrap_or_default();
+    let term = var("TERM");
+    let program = var("TERM_PROGRAM");
+
+    if std::env::var_os("KITTY_WINDOW_ID").is_some()
+        || term == "xterm-kitty"
+        || term == "xterm-ghostty"
+    {
+        Some(ProtocolType::Kitty)
+    } else if matches!(program.as_str(), "iTerm.app" | "WezTerm") {
+        Some(ProtocolType::Iterm2)
+    } else if term == "foot" || term.starts_with("mlterm") {
+        Some(ProtocolType::Sixel)
+    } else {
+        None
+    }
+}
+
+/// The image shown in the preview, encoded for the terminal.
+#[derive(Default)]
+pub(crate) struct ImageState {
+    picker: Option<Picker>,
+    current: Option<(Arc<DynamicImage>, StatefulProtocol)>,
+}
+
+impl ImageState {
+    pub fn set_picker(&mut self, picker: Picker) {
+        self.picker = Some(picker);
+        self.current = None;
+    }
+
+    /// Draw `image` scaled to fit `area`, re-encoding it only when it changes or is resized.
+    /// Returns false if no graphics protocol was detected.
+    pub fn render(&mut self, frame: &mut Frame, area: Rect, image: Arc<DynamicImage>) -> bool {
+        let Some(picker) = &self.picker else {
+            return false;
+        };
+        if self
+            .current
+            .as_ref()
+            .is_none_or(|(current, _)| !Arc::ptr_eq(current, &image))
+        {
+            let protocol = picker.new_resize_protocol((*image).clone());
+            self.current = Some((image, protocol));
+        }
+        if let Some((_, protocol)) = &mut self.current {
+            frame.render_stateful_widget(StatefulImage::default(), area, protocol);
+        }
+        true
+    }
+}
+
+impl fmt::Debug for ImageState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ImageState")
+            .field("picker", &self.picker.is_some())
+            .field(
+                "image",
+                &self.current.as_ref().map(|(i, _)| (i.width(), i.height())),
+            )
+            .finish()
+    }
+}
@@ -1,13 +1,13 @@
 use ratatui::{
     layout::Rect,
-    text::Text,
+    text::Line,
     widgets::{Row, Table},
 };
 use unicode_width::UnicodeWidthStr;
 
 use crate::{
     collections::HiddenColumns,
-    config::{HorizontalSeparator, ResultsConfig, RowConnectionStyle},
+    config::{ColumnStyle, HorizontalSeparator, ResultsConfig, RowConnectionStyle},
     nucleo::{Column, Status},
     utils::{
         string::{fit_width, substitute_escaped},
@@ -15,10 +15,28 @@ use crate::{
     },
 };
 
+mod cache;
+mod gutter;
 mod render;
 mod update;
 mod widths;
 
+use cache::RowCache;
+
+/// The column geometry of the results table, shared with the components which align to it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ColumnLayout {
+    /// The width of the prefix column.
+    pub indentation: u16,
+    /// The offset of the first column from the left edge of the results area.
+    pub offset: u16,
+    pub spacing: u16,
+    /// The width limit of each column, 0 if hidden.
+    pub widths: Vec<u16>,
+    /// The content width of the results.
+    pub width: u16,
+}
+
 #[derive(Debug)]
 pub struct ResultsUI {
     cursor: u16,
@@ -27,6 +45,10 @@ pub struct ResultsUI {
     pub vscroll: u8,
     cursor_disabled: bool,
     cursor_moved: Option<bool>,
+    /// Whether the cursor last moved down, i.e. which way group headers are skipped.
+    cursor_forward: bool,
+    /// Rows of the headers of the expanded groups, which the cursor skips.
+    headers: Vec<u32>,
 
     /// available height
     height: u16,
@@ -35,15 +57,22 @@ pub struct ResultsUI {
     // actual column widths.
     // Note that the first width include the indentation.
     widths: Vec<u16>,
+    /// Sum of the widths of the gutter elements.
+    gutter_width: u16,
 
     // either empty or same len as hidden_columns
     width_limits: Vec<u16>,
     pub(crate) hidden_columns: HiddenColumns,
     column_name_widths: Vec<u16>,
+    /// Per-column cell styles, indexed by column.
+    column_styles: Vec<ColumnStyle>,
 
     // used to compute width_limits
     // valid after calling update_preferred_widths
     preferred_widths: Vec<u16>,
+    /// The width overrides of the config, before any resizing
+    configured_width_overrides: Vec<u16>,
+    column_layout: ColumnLayout,
     // transient buffer for use within compute functions
     widths_buffer: Vec<u16>,
     col_indices_buffer: Vec<u32>,
@@ -51,7 +80,7 @@ pub struct ResultsUI {
     pub config: ResultsConfig,
     pub status: Status,
 
-    row_cache: [Vec<(u32, Vec<Text<'static>>, Vec<u16>)>; 2],
+    row_cache: RowCache,
     pub(crate) changed: [bool; 2], // need redraw, need recompute
     /// Visual-order row metadata from the most recent successful build.
     /// Each entry is `(item_idx, height)`; `u32::MAX` marks separator rows.
@@ -72,11 +101,15 @@ impl ResultsUI {
             height: 0, // uninitialized, so be sure to call update_dimensions
             width: 0,
             widths: Vec::new(),
+            gutter_width: 0,
             hidden_columns: Default::default(),
             column_name_widths: Default::default(),
+            column_styles: Vec::new(),
 
             width_limits: Vec::new(),
+            column_layout: ColumnLayout::default(),
             preferred_widths: Vec::new(),
+            configured_width_overrides: config.width_overrides.clone(),
             widths_buffer: Vec::new(),
             col_indices_buffer: Vec::new(),
 
@@ -85,11 +118,14 @@ impl ResultsUI {
 
             cursor_disabled: false,
             cursor_moved: None,
+            cursor_forward: true,
+            headers: Vec::new(),
             changed: Default::default(),
-            row_cache: [Vec::new(), Vec::new()],
+            row_cache: RowCache::default(),
             row_data: Vec::new(),
             table: ratatui::widgets::Table::default(),
         };
+        ret.gutter_width = ret.compute_gutter_width();
         ret.init(cols);
         ret
     }
@@ -129,6 +165,11 @@ impl ResultsUI {
         }
     }
 
+    pub fn set_column_styles(&mut self, column_styles: Vec<ColumnStyle>) {
+        self.column_styles = column_styles;
+        self.changed[0] = true;
+    }
+
     pub fn update_dimensions(&mut self, area: Rect) {
         let new = self.config.border.inner(area);
         if self.width != new.width || self.height != new.height {
@@ -167,7 +208,7 @@ impl ResultsUI {
         self.config.scroll_padding.min(self.height / 2)
     }
     pub fn end(&self) -> u32 {
-        self.status.matched_count.saturating_sub(1)
+        self.status.row_count.saturating_sub(1)
     }
 
     /// Index in worker snapshot of current item.
@@ -181,6 +222,52 @@ impl ResultsUI {
         }
     }
 
+    /// The first visible row and the cursor offset from it, to be restored with
+    /// [`Self::set_position`].
+    pub(crate) fn position(&self) -> (u32, u16) {
+        (self.bottom, self.cursor)
+    }
+
+    /// Restore a [`Self::position`]. It is clamped to the matches when the table is next updated.
+    pub(crate) fn set_position(&mut self, (bottom, cursor): (u32, u16)) {
+        self.bottom = bottom;
+        self.cursor = cursor;
+        self.set_cursor_changed(true);
+    }
+
+    /// The number of rows, and the position of the top visible row counted from the top of the table.
+    pub fn scroll_position(&self) -> (usize, usize) {
+        let total = self.status.row_count as usize;
+        let position = if self.reverse() {
+            total.saturating_sub(self.bottom as usize + self.height as usize)
+        } else {
+            self.bottom as usize
+        };
+        (total, position)
+    }
+
+    /// The position indicator configured by [`ResultsConfig::indicator`], if any rows are hidden.
+    pub fn make_indicator(&self) -> Option<Line<'static>> {
+        let (total, position) = self.scroll_position();
+        if self.config.indicator.is_empty() || total <= self.height as usize {
+            return None;
+        }
+        let bottom = (position + self.height as usize).min(total);
+        let current = if self.cursor_disabled {
+            0
+        } else {
+            (self.index() as usize + 1).min(total)
+        };
+
+        let replacements = [
+            ('c', current.to_string()),
+            ('t', total.to_string()),
+            ('p', (bottom * 100 / total).to_string()),
+        ];
+        let content = substitute_escaped(&self.config.indicator, &replacements);
+        Some(Line::styled(content, self.config.indicator_style))
+    }
+
     /// Map a visual y-offset (in the rendered results table) back to the
     /// absolute nucleo item index, or `None` if `y` falls outside the
     /// populated range (e.g. in a padding/spacer row).
@@ -211,6 +298,7 @@ impl ResultsUI {
         self.reset_current_scroll();
         self.set_cursor_changed(false);
 
+        self.cursor_forward = false;
         if self.cursor > 0 {
             self.cursor -= 1;
         } else if self.bottom > 0 {
@@ -228,6 +316,7 @@ impl ResultsUI {
         self.cursor_disabled = false;
         self.set_cursor_changed(false);
 
+        self.cursor_forward = true;
         if self.index() < self.end() {
             self.cursor = self.cursor.saturating_add(1);
         } else if self.config.scroll_wrap {
@@ -242,6 +331,7 @@ impl ResultsUI {
         self.reset_current_scroll();
         self.cursor_disabled = false;
         self.set_cursor_changed(true);
+        self.cursor_forward = true;
 
         let end = self.end();
         let index = index.min(end);
@@ -255,6 +345,38 @@ impl ResultsUI {
         log::debug!("cursor jumped to {}: {index}, end: {end}", self.cursor);
     }
 
+    /// Move the cursor off the header of an expanded group, the way it last moved, or the other
+    /// way if there are only headers that way.
+    fn skip_headers(&mut self) {
+        if self.cursor_disabled || self.headers.binary_search(&self.index()).is_err() {
+            return;
+        }
+        let index = self.index();
+        let is_item = |n: &u32| self.headers.binary_search(n).is_err();
+        let next = (index..=self.end()).find(is_item);
+        let prev = (0..index).rev().find(is_item);
+
+        let target = match (next, prev) {
+            (Some(next), _) if self.cursor_forward => next,
+            (_, Some(prev)) => prev,
+            (Some(next), None) => next,
+            (None, None) => return,
+        };
+        self.set_cursor_changed(false);
+        if target > index {
+            self.cursor += (target - index) as u16;
+        } else {
+            // like cursor_prev, so that the scroll position is kept
+            for _ in target..index {
+                if self.cursor > 0 {
+                    self.cursor -= 1;
+                } else {
+                    self.bottom = self.bottom.saturating_sub(1);
+                }
+            }
+        }
+    }
+
     pub fn current_scroll(&mut self, x: i8, horizontal: bool) {
         if horizontal {
             self.hscroll = if x == 0 {
@@ -273,14 +395,6 @@ impl ResultsUI {
             };
 
             self.set_dirty();
-
-            // if !self.config.vscroll_current_only {
-            //     self.set_dirty();
-            // } else {
-            //     let cursor_idx = self.bottom + self.cursor as u32;
-            //     self.row_cache[0].retain(|(i, _, _)| *i != cursor_idx);
-            //     self.row_cache[1].retain(|(i, _, _)| *i != cursor_idx);
-            // }
         }
     }
 
@@ -292,8 +406,9 @@ impl ResultsUI {
     }
 
     // ------- RENDERING GET/SET ------------
+    /// The width of the gutter.
     pub fn indentation(&self) -> usize {
-        self.config.multi_prefix.width()
+        self.gutter_width as usize
     }
 
     /// Table column widths.
@@ -305,6 +420,24 @@ impl ResultsUI {
         &self.width_limits
     }
 
+    /// The layout as of the last call to [`Self::update_column_layout`].
+    pub fn column_layout(&self) -> &ColumnLayout {
+        &self.column_layout
+    }
+
+    /// Called once per frame after the table is updated.
+    pub fn update_column_layout(&mut self) {
+        let indentation = self.indentation() as u16;
+        let layout = &mut self.column_layout;
+        layout.indentation = indentation;
+        layout.offset = indentation + self.config.border.left();
+        layout.spacing = self.config.column_spacing.0;
+        layout.width = self.width;
+        if layout.widths != self.width_limits {
+            layout.widths.clone_from(&self.width_limits);
+        }
+    }
+
     pub fn available_width(&self) -> u16 {
         self.width
             .saturating_sub(self.indentation() as u16)
@@ -327,8 +460,7 @@ impl ResultsUI {
     /// Mark row cache as stale
     pub fn set_dirty(&mut self) {
         log::trace!("cache cleared");
-        self.row_cache[0].clear();
-        // self.row_cache[1].clear();
+        self.row_cache.invalidate();
     }
 
     // ------- RENDERING ----------
@@ -0,0 +1,159 @@
+//! Caching rendered rows across table updates.
+
+use std::collections::HashMap;
+
+use ratatui::text::Text;
+
+/// How many table updates a row is kept for after it was last displayed, so that scrolling back
+/// doesn't render it again.
+const KEEP_UPDATES: u64 = 8;
+
+/// Identifies a rendered item: its id and the horizontal and vertical scroll it was rendered with.
+pub(super) type RowKey = (u32, i8, usize);
+
+/// The cells of a rendered item, and the unwrapped widths of its visible columns.
+#[derive(Debug, Clone, Default)]
+pub(super) struct CachedRow {
+    pub texts: Vec<Text<'static>>,
+    pub widths: Vec<u16>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    row: CachedRow,
+    generation: u64,
+    /// The last update which displayed the row
+    used: u64,
+}
+
+/// The rows rendered by [`super::render::render_row`], reused across table updates so that only
+/// the rows scrolled into view are rendered.
+///
+/// Invalidating only bumps a generation, so that it is cheap however many rows are cached. Rows
+/// of older generations, and rows not displayed for [`KEEP_UPDATES`] updates, are evicted after
+/// each update.
+#[derive(Debug, Default)]
+pub(super) struct RowCache {
+    rows: HashMap<RowKey, Entry>,
+    generation: u64,
+    /// The generation of the last update
+    built: Option<u64>,
+    updates: u64,
+    /// The rows displayed by the current update, in order
+    current: Vec<RowKey>,
+    /// The rows displayed by the last update
+    last: Vec<RowKey>,
+}
+
+impl RowCache {
+    /// Discard every row, such as after the query or the column widths change.
+    pub fn invalidate(&mut self) {
+        self.generation += 1;
+        self.last.clear();
+    }
+
+    /// Whether the cache was invalidated since the last update.
+    pub fn is_stale(&self) -> bool {
+        self.built != Some(self.generation)
+    }
+
+    /// A copy of the row, marking it as displayed.
+    pub fn get(&mut self, key: RowKey) -> Option<CachedRow> {
+        let entry = self
+            .rows
+            .get_mut(&key)
+            .filter(|e| e.generation == self.generation)?;
+        entry.used = self.updates;
+        if !self.current.contains(&key) {
+            self.current.push(key);
+        }
+        Some(entry.row.clone())
+    }
+
+    /// Store a newly rendered row, marking it as displayed.
+    pub fn insert(&mut self, key: RowKey, row: CachedRow) {
+        self.rows.insert(
+            key,
+            Entry {
+                row,
+                generation: self.generation,
+                used: self.updates,
+            },
+        );
+        if !self.current.contains(&key) {
+            self.current.push(key);
+        }
+    }
+
+    /// End the current update.
+    pub fn finish(&mut self) {
+        self.last = std::mem::take(&mut self.current);
+        self.built = Some(self.generation);
+
+        let (generation, updates) = (self.generation, self.updates);
+        self.rows
+            .retain(|_, e| e.generation == generation && e.used + KEEP_UPDATES > updates);
+        self.updates += 1;
+    }
+
+    /// Whether the last update displayed any rows.
+    pub fn is_empty(&self) -> bool {
+        self.last.is_empty()
+    }
+
+    /// The column widths of each row displayed by the last update.
+    pub fn widths(&self) -> impl Iterator<Item = &[u16]> {
+        self.last
+            .iter()
+            .filter_map(|key| self.rows.get(key))
+            .map(|e| e.row.widths.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(width: u16) -> CachedRow {
+        CachedRow {
+            texts: Vec::new(),
+            widths: vec![width],
+        }
+    }
+
+    #[test]
+    fn test_row_cache() {
+        let mut cache = RowCache::default();
+        assert!(cache.is_stale());
+
+        cache.insert((0, 0, 0), row(1));
+        cache.insert((1, 0, 0), row(2));
+        cache.finish();
+        assert!(!cache.is_stale());
+        assert_eq!(cache.widths().collect::<Vec<_>>(), [[1], [2]]);
+
+        // scrolled: only the displayed rows are counted
+        assert!(cache.get((1, 0, 0)).is_some());
+        assert!(cache.get((1, 1, 0)).is_none());
+        cache.finish();
+        assert_eq!(cache.widths().collect::<Vec<_>>(), [[2]]);
+        // but the others are kept for a while
+        assert!(cache.get((0, 0, 0)).is_some());
+
+        cache.invalidate();
+        assert!(cache.is_stale());
+        assert!(cache.is_empty());
+        assert!(cache.get((1, 0, 0)).is_none());
+    }
+
+    #[test]
+    fn test_row_cache_eviction() {
+        let mut cache = RowCache::default();
+        cache.insert((0, 0, 0), row(1));
+        cache.finish();
+        for _ in 0..KEEP_UPDATES {
+            cache.finish();
+        }
+        assert!(cache.get((0, 0, 0)).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+//! The gutter to the left of each result, made of the elements in
+//! [`crate::config::ResultsConfig::gutter`].
+//!
+//! Each element has a width which is the same on every row, and the sum of them is the
+//! indentation of the results. When it changes (i.e. when the number of matches gains a digit),
+//! the column widths are recomputed.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::{config::GutterElement, ui::ResultsUI, utils::string::fit_width};
+
+impl ResultsUI {
+    /// The width of an element, which its content is padded or truncated to.
+    fn gutter_element_width(&self, element: GutterElement) -> usize {
+        match element {
+            GutterElement::Marker => self.config.multi_prefix.width(),
+            GutterElement::Pointer => self.config.current_prefix.width(),
+            // digits and a space
+            GutterElement::Number => self.status.row_count.max(1).ilog10() as usize + 2,
+        }
+    }
+
+    pub(super) fn compute_gutter_width(&self) -> u16 {
+        self.config
+            .gutter
+            .iter()
+            .map(|e| self.gutter_element_width(*e))
+            .sum::<usize>() as u16
+    }
+
+    /// Recompute the gutter width, invalidating the column widths if it changed.
+    pub(super) fn update_gutter_width(&mut self) {
+        let width = self.compute_gutter_width();
+        if width != self.gutter_width {
+            self.gutter_width = width;
+            self.width_limits.clear();
+            self.set_dirty();
+        }
+    }
+
+    /// The gutter of the `idx`th match, given its position in the selections if selected.
+    pub(super) fn gutter(
+        &self,
+        idx: u32,
+        id: u32,
+        selected: Option<usize>,
+        is_current: bool,
+    ) -> String {
+        let mut ret = String::new();
+        for &element in &self.config.gutter {
+            let width = self.gutter_element_width(element);
+            match element {
+                GutterElement::Marker => {
+                    if let Some(position) = selected {
+                        if self.config.show_selection_order {
+                            ret.push_str(&fit_width(&(position + 1).to_string(), width));
+                        } else {
+                            ret.push_str(&self.config.multi_prefix);
+                        }
+                    } else {
+                        ret.push_str(&self.default_prefix((idx - self.bottom) as usize, id));
+                    }
+                }
+                GutterElement::Pointer => {
+                    let pointer = if is_current {
+                        self.config.current_prefix.as_str()
+                    } else {
+                        ""
+                    };
+                    ret.push_str(&fit_width(pointer, width));
+                }
+                GutterElement::Number => {
+                    ret.push_str(&format!("{:>1$} ", idx + 1, width - 1));
+                }
+            }
+        }
+        ret
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::ResultsConfig, nucleo::Column};
+
+    #[test]
+    fn test_gutter() {
+        let config = ResultsConfig {
+            gutter: vec![
+                GutterElement::Pointer,
+                GutterElement::Number,
+                GutterElement::Marker,
+            ],
+            current_prefix: "> ".into(),
+            multi_prefix: "* ".into(),
+            ..Default::default()
+        };
+        let cols: Vec<Column<(), ()>> = Vec::new();
+        let mut results = ResultsUI::new(config, &cols);
+        results.status.row_count = 120;
+        results.update_gutter_width();
+
+        assert_eq!(results.indentation(), 2 + 4 + 2);
+        assert_eq!(results.gutter(0, 0, None, true), ">   1   ");
+        assert_eq!(results.gutter(119, 0, Some(0), false), "  120 * ");
+
+        results.config.show_selection_order = true;
+        assert_eq!(results.gutter(119, 0, Some(2), false), "  120 3 ");
+    }
+}
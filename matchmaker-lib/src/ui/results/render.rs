@@ -1,11 +1,11 @@
 use cba::_info;
 
-use super::*;
+use super::{cache::CachedRow, *};
 use crate::{
     SSS, Selector,
     collections::HiddenColumns,
     config::AutoscrollSettings,
-    nucleo::{Style, Text, Worker, render_item::render_cell},
+    nucleo::{Group, Style, Text, Worker, render_item::render_cell},
     ui::ResultsUI,
     utils::text::{to_static, truncation_indicator, wrap_text_static},
 };
@@ -33,6 +33,7 @@ use crate::{
 /// - `wrap`: Whether text exceeding a width limit should wrap.
 /// - `highlight_style`: Style applied to matched query characters.
 /// - `autoscroll`: Horizontal autoscroll configuration.
+/// - `keep_right`: Clip long lines which aren't autoscrolled on the left.
 /// - `hscroll_offset`: Manual horizontal scroll offset.
 /// - `vscroll_offset`: Number of rendered lines to skip from the top.
 ///
@@ -83,6 +84,7 @@ pub fn render_row<T: SSS, D>(
     wrap: bool,
     highlight_style: Style,
     autoscroll: AutoscrollSettings,
+    keep_right: bool,
     hscroll_offset: i8,
     vscroll_offset: usize,
 
@@ -187,22 +189,22 @@ pub fn render_row<T: SSS, D>(
         .into_iter()
         .zip(visible_cols.iter())
         .map(|(cell, &(col_idx, width_limit))| {
-            let column = &columns[col_idx];
-
             // Apply rendering based on column type and settings
-            let cell = if column.filter() {
+            let cell = if let Some(matcher_idx) = worker.matcher_index(col_idx) {
                 // Filterable columns get match highlighting
                 let (t, _) = render_cell(
                     cell,
-                    col_idx,
+                    matcher_idx,
                     snapshot,
                     item,
+                    worker.alternations(matcher_idx),
                     matcher,
                     highlight_style,
                     wrap,
                     width_limit,
                     col_indices_buffer,
                     autoscroll,
+                    keep_right,
                     hscroll_offset,
                 );
                 t
@@ -254,28 +256,26 @@ impl ResultsUI {
         rows: &mut Vec<Row<'static>>,
         row_data: Option<&mut Vec<(u32, u16)>>,
     ) -> Option<u16> {
+        if let Some(group) = worker.group_at(idx) {
+            return Some(self.header_row(idx, group, is_current, rows, row_data));
+        }
         let vscroll_offset = self.vscroll_to_skip(is_current);
+        let hscroll_offset = self.hscroll_to_skip(is_current);
         let stacked = self.config.stacked_columns;
         let (id, item) = worker.get_nth_indexed_item(idx)?;
 
         let mut row_widths = vec![0u16; self.hidden_columns.visible_count()];
 
         // check cache
+        let key = (id, hscroll_offset, vscroll_offset);
         let cached = if id == u32::MAX {
             None
         } else {
-            self.row_cache[0]
-                .iter()
-                .find(|(idx, _, _)| *idx == id)
-                .cloned()
+            self.row_cache.get(key)
         };
 
-        let (id, texts) = if let Some(cached) = cached {
-            if !self.row_cache[1].iter().any(|(x, _, _)| *x == id) {
-                self.row_cache[1].push(cached.clone());
-            }
-
-            (cached.0, cached.1)
+        let texts = if let Some(cached) = cached {
+            cached.texts
         } else {
             let mut non_hidden_idx = 0;
             let width_callback = |_: usize, w: usize| {
@@ -294,7 +294,8 @@ impl ResultsUI {
                 self.config.wrap,
                 self.config.match_style.into(),
                 self.config.autoscroll,
-                self.hscroll,
+                self.config.keep_right,
+                hscroll_offset,
                 vscroll_offset,
                 matcher,
                 &mut self.col_indices_buffer,
@@ -319,11 +320,17 @@ impl ResultsUI {
                 }
             }
 
-            if id != u32::MAX && !self.row_cache[1].iter().any(|(x, _, _)| *x == id) {
-                self.row_cache[1].push((id, texts.clone(), row_widths));
+            if id != u32::MAX {
+                self.row_cache.insert(
+                    key,
+                    CachedRow {
+                        texts: texts.clone(),
+                        widths: row_widths,
+                    },
+                );
             }
 
-            (id, texts)
+            texts
         };
 
         if self.width_limits.is_empty() {
@@ -341,12 +348,10 @@ impl ResultsUI {
             return Some(height);
         }
 
-        let is_selected = selector.contains(&id);
-        let prefix = if is_selected {
-            self.config.multi_prefix.clone()
-        } else {
-            self.default_prefix((idx - self.bottom) as usize, id)
-        };
+        let mut prefix = self.gutter(idx, id, selector.position(id), is_current);
+        if let Some(branch) = worker.tree_prefix(idx) {
+            prefix.push_str(branch);
+        }
         let mut row_texts = vec![];
 
         for (i, (col_idx, mut col)) in self
@@ -366,12 +371,24 @@ impl ResultsUI {
                 );
             }
 
+            if let Some(column_style) = self.column_styles.get(col_idx) {
+                col = col.patch_style(column_style.style());
+                if let Some(align) = column_style.align {
+                    col.alignment = Some(align.into());
+                }
+            }
+
             let col = style_text(col, active_column == col_idx, is_current, &self.config);
             row_texts.push(col);
         }
 
-        if !stacked && self.config.right_align_last && row_texts.len() > 1 {
-            row_texts.last_mut().unwrap().alignment = Some(ratatui::layout::Alignment::Right);
+        if !stacked
+            && self.config.right_align_last
+            && row_texts.len() > 1
+            && let Some(last) = row_texts.last_mut()
+            && last.alignment.is_none()
+        {
+            last.alignment = Some(ratatui::layout::Alignment::Right);
         }
 
         // Apply truncation if max_height is specified
@@ -431,6 +448,45 @@ impl ResultsUI {
 
 // helpers
 impl ResultsUI {
+    /// Render the header of a group, which spans a single line.
+    fn header_row(
+        &mut self,
+        idx: u32,
+        group: &Group,
+        is_current: bool,
+        rows: &mut Vec<Row<'static>>,
+        row_data: Option<&mut Vec<(u32, u16)>>,
+    ) -> u16 {
+        if self.width_limits.is_empty() {
+            return 1;
+        }
+        let marker = if group.collapsed { '▸' } else { '▾' };
+        let line = format!(
+            "{}{marker} {} ({})",
+            " ".repeat(self.indentation()),
+            group.name,
+            group.len
+        );
+        let mut style: Style = self.config.group_header_style.into();
+        if is_current {
+            style = style.patch(self.config.current_style);
+        }
+
+        let cells = if !self.config.stacked_columns && self.widths.len() > 1 {
+            self.widths.len()
+        } else {
+            1
+        };
+        let mut texts = vec![Text::default(); cells];
+        texts[0] = Text::styled(line, style);
+        rows.push(Row::new(texts).height(1));
+        match row_data {
+            Some(rd) => rd.push((idx, 1)),
+            None => self.row_data.push((idx, 1)),
+        }
+        1
+    }
+
     pub(super) fn default_prefix(&self, i: usize, id: u32) -> String {
         let substituted = substitute_escaped(
             &self.config.default_prefix,
@@ -441,7 +497,7 @@ impl ResultsUI {
             ],
         );
 
-        fit_width(&substituted, self.indentation())
+        fit_width(&substituted, self.config.multi_prefix.width())
     }
 
     // todo: multicolumn needs to overlay onto empty row instead of inject row
@@ -473,6 +529,14 @@ impl ResultsUI {
             0
         }
     }
+
+    pub(super) fn hscroll_to_skip(&self, is_current: bool) -> i8 {
+        if !self.config.hscroll_current_only || is_current {
+            self.hscroll
+        } else {
+            0
+        }
+    }
 }
 fn style_text<'a>(
     mut t: ratatui::text::Text<'a>,
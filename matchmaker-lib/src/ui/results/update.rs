@@ -2,10 +2,7 @@ use crate::{config::RowConnectionStyle, ui::ResultsUI};
 use cba::_info;
 use ratatui::widgets::{Row, Table};
 
-use crate::{
-    SSS, Selector,
-    nucleo::{Worker, new_snapshot},
-};
+use crate::{SSS, Selector, nucleo::Worker};
 
 impl ResultsUI {
     // this is supposed to cover all invalidations
@@ -14,7 +11,7 @@ impl ResultsUI {
         let dirty = self.changed.iter().any(|x| *x)
             || self.cursor_moved.is_some()
             || self.width_limits.is_empty()
-            || self.row_cache.is_empty();
+            || self.row_cache.is_stale();
 
         let update_preferred = self.changed[1]
             || self.cursor_moved.is_some()
@@ -38,14 +35,23 @@ impl ResultsUI {
             !worker.columns.is_empty() && (self.hidden_columns.mask_len() == worker.columns.len())
         );
         // Step 0: Refresh the nucleo snapshot and status before rendering
-        let (_snapshot, status) = new_snapshot(&mut worker.nucleo);
+        let status = worker.refresh();
 
-        let mc = status.matched_count;
-        if mc != self.status.matched_count {
+        let mc = status.row_count;
+        if mc != self.status.row_count {
             self.changed[1] = true;
             // query change will emit dirty signal independently: this prevents unnecessary redraws while running
         }
         self.status = status;
+        self.update_gutter_width();
+        self.headers.clear();
+        self.headers.extend(
+            worker
+                .groups()
+                .iter()
+                .filter(|g| !g.collapsed)
+                .map(|g| g.row),
+        );
 
         // Section 1: Boundaries alignment, update width limits, early returns
         // Ensure cursor is within matched bounds, and update scroll position if bounds changed.
@@ -59,6 +65,7 @@ impl ResultsUI {
         } else {
             self.cursor = self.cursor.min(mc.saturating_sub(1) as u16);
         }
+        self.skip_headers();
 
         let (dirty, update_preferred) = self.is_clean();
         if !dirty {
@@ -303,8 +310,7 @@ impl ResultsUI {
         // Section 5.5: Compute preferred widths for next pass from collected data
 
         // if we needed redraw table, its because row changed
-        self.row_cache.swap(0, 1);
-        self.row_cache[1].clear();
+        self.row_cache.finish();
 
         // Recompute preferred widths when the row layout is known to have
         // changed (cursor moved, fresh table) or when we don't have valid
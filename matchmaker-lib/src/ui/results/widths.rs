@@ -6,7 +6,7 @@ impl ResultsUI {
     /// Every nonempty column is assigned a nonzero width.
     /// Noop if row_cache is empty or stacked_columns
     pub(super) fn update_preferred_widths(&mut self) -> bool {
-        if self.row_cache[0].is_empty() || self.config.stacked_columns {
+        if self.row_cache.is_empty() || self.config.stacked_columns {
             return false;
         }
 
@@ -17,7 +17,7 @@ impl ResultsUI {
 
         // Compute max_widths on the fly for the adjustment phase
         let mut max_widths = vec![0u16; v_cols];
-        for (_, _, row_widths) in &self.row_cache[0] {
+        for row_widths in self.row_cache.widths() {
             for (i, &w) in row_widths.iter().enumerate() {
                 if i < v_cols {
                     max_widths[i] = max_widths[i].max(w);
@@ -26,9 +26,10 @@ impl ResultsUI {
         }
 
         for col_idx in 0..v_cols {
-            let mut v: Vec<u16> = self.row_cache[0]
-                .iter()
-                .map(|(_, _, row_widths)| row_widths.get(col_idx).copied().unwrap_or(0))
+            let mut v: Vec<u16> = self
+                .row_cache
+                .widths()
+                .map(|row_widths| row_widths.get(col_idx).copied().unwrap_or(0))
                 .collect();
 
             let median = if !v.is_empty() {
@@ -187,7 +188,7 @@ impl ResultsUI {
     /// - Non-hidden columns have width >= min_width (when feasible)
     /// - User overrides are respected when feasible
     fn update_width_limits_into_width_buffer(&mut self) {
-        if self.row_cache[0].is_empty() || self.preferred_widths.is_empty() {
+        if self.row_cache.is_empty() || self.preferred_widths.is_empty() {
             _info!(
                 "skipped width_limits update: either row cache or preferred":
                 self.preferred_widths
@@ -198,7 +199,7 @@ impl ResultsUI {
 
         let v_cols = self.preferred_widths.len();
         let mut max_widths = vec![0u16; v_cols];
-        for (_, _, row_widths) in &self.row_cache[0] {
+        for row_widths in self.row_cache.widths() {
             for (i, &w) in row_widths.iter().enumerate() {
                 if i < v_cols {
                     max_widths[i] = max_widths[i].max(w);
@@ -399,6 +400,14 @@ impl ResultsUI {
         self.width_limits.clear();
     }
 
+    /// Restore the width overrides from the config, undoing [`Self::resize_col`].
+    pub fn reset_col_widths(&mut self) {
+        self.config
+            .width_overrides
+            .clone_from(&self.configured_width_overrides);
+        self.width_limits.clear();
+    }
+
     /// Width_overrides and other arrays only index into the visible cols of self.hidden_cols, while self.width_limits maps to the all the columns. This converts the first to the second.
     pub fn expand_idx(&self, idx: usize) -> usize {
         self.hidden_columns.nth_gap(idx)
@@ -441,7 +450,7 @@ impl ResultsUI {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{config::ResultsConfig, nucleo::Column};
+    use crate::{config::ResultsConfig, nucleo::Column, ui::results::cache::CachedRow};
 
     fn make_cols(n: usize) -> Vec<Column<(), ()>> {
         (0..n)
@@ -514,7 +523,14 @@ mod tests {
         let mut results = ResultsUI::new(config, &make_cols(3));
         results.width = 100;
         results.preferred_widths = vec![8, 12, 6];
-        results.row_cache[0] = vec![(0, vec![], vec![8, 12, 6])];
+        results.row_cache.insert(
+            (0, 0, 0),
+            CachedRow {
+                texts: vec![],
+                widths: vec![8, 12, 6],
+            },
+        );
+        results.row_cache.finish();
 
         results.update_width_limits();
 
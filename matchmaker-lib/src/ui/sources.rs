@@ -0,0 +1,143 @@
+use crate::{SSS, Selector, nucleo::Worker};
+
+/// A worker which the picker can switch to, with a prompt and preview command of its own.
+///
+/// Added with [`crate::Matchmaker::add_source`], and switched to with [`crate::Action::Source`]
+/// and [`crate::Action::NextSource`].
+pub struct Source<T: SSS, D = ()> {
+    pub name: String,
+    /// Replaces the configured prompt while the source is active.
+    pub prompt: Option<String>,
+    /// Replaces the preview command while the source is active.
+    pub preview: Option<String>,
+    /// Moved into the [`super::PickerUI`] while the source is active.
+    worker: Option<Worker<T, D>>,
+    /// Kept between switches, and configured like those of the first source.
+    selector: Option<Selector>,
+}
+
+impl<T: SSS, D> Source<T, D> {
+    pub fn new(name: impl Into<String>, worker: Worker<T, D>) -> Self {
+        Self {
+            name: name.into(),
+            prompt: None,
+            preview: None,
+            worker: Some(worker),
+            selector: None,
+        }
+    }
+
+    pub fn with_prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    pub fn with_preview(mut self, command: impl Into<String>) -> Self {
+        self.preview = Some(command.into());
+        self
+    }
+}
+
+/// The sources of a picker. The first is the worker the picker was created with, which uses the
+/// configured prompt and preview command.
+pub struct SourceSet<T: SSS, D = ()> {
+    sources: Vec<Source<T, D>>,
+    active: usize,
+}
+
+impl<T: SSS, D> Default for SourceSet<T, D> {
+    fn default() -> Self {
+        Self {
+            sources: vec![Source {
+                name: String::new(),
+                prompt: None,
+                preview: None,
+                worker: None,
+                selector: None,
+            }],
+            active: 0,
+        }
+    }
+}
+
+impl<T: SSS, D> SourceSet<T, D> {
+    pub fn push(&mut self, source: Source<T, D>) {
+        self.sources.push(source);
+    }
+
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    /// Always false, as the first source is the picker's own worker.
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// The index of the active source.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    pub fn get(&self, n: usize) -> Option<&Source<T, D>> {
+        self.sources.get(n)
+    }
+
+    pub fn get_mut(&mut self, n: usize) -> Option<&mut Source<T, D>> {
+        self.sources.get_mut(n)
+    }
+
+    /// Swap the active worker and selections with those of the n-th source.
+    ///
+    /// Returns the source switched to, or None if it doesn't exist or is already active.
+    pub(crate) fn switch(
+        &mut self,
+        n: usize,
+        worker: &mut Worker<T, D>,
+        selector: &mut Selector,
+    ) -> Option<&Source<T, D>> {
+        if n == self.active {
+            return None;
+        }
+        let source = self.sources.get_mut(n)?;
+        let next_worker = source.worker.take()?;
+        let next_selector = source.selector.take().unwrap_or_else(|| {
+            let mut empty = selector.clone();
+            empty.clear();
+            empty
+        });
+
+        let active = &mut self.sources[self.active];
+        active.worker = Some(std::mem::replace(worker, next_worker));
+        active.selector = Some(std::mem::replace(selector, next_selector));
+        selector.set_changed();
+        self.active = n;
+        Some(&self.sources[n])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switch() {
+        let mut worker = Worker::<String>::new_single_column();
+        worker.append(["a".to_string()]);
+        let mut selector = Selector::new();
+        selector.insert(0);
+
+        let mut sources = SourceSet::default();
+        sources.push(Source::new("b", Worker::new_single_column()).with_prompt("b> "));
+
+        let source = sources.switch(1, &mut worker, &mut selector).unwrap();
+        assert_eq!(source.prompt.as_deref(), Some("b> "));
+        assert!(selector.is_empty());
+        assert!(sources.switch(1, &mut worker, &mut selector).is_none());
+
+        sources.switch(0, &mut worker, &mut selector).unwrap();
+        assert_eq!(worker.append(["c".to_string()]), 1);
+        assert_eq!(selector.iter().copied().collect::<Vec<_>>(), [0]);
+        assert!(sources.switch(2, &mut worker, &mut selector).is_none());
+    }
+}
@@ -6,6 +6,7 @@ use ratatui::{
 };
 
 use crate::{
+    Selector,
     config::{RowConnectionStyle, StatusConfig},
     ui::ResultsUI,
     utils::{string::substitute_escaped, text::expand_indents},
@@ -32,19 +33,32 @@ impl StatusUI {
         self.status_config.interactions.sort_by_key(|(i, _)| *i);
     }
 
-    pub fn make_status(&self, results_ui: &ResultsUI, full_width: u16) -> Paragraph<'_> {
+    pub fn make_status(
+        &self,
+        results_ui: &ResultsUI,
+        selector: &Selector,
+        modes: &str,
+        full_width: u16,
+    ) -> Paragraph<'_> {
         let status_config = &self.status_config;
+        let selected = match selector.max() {
+            Some(max) => format!("{}/{max}", selector.len()),
+            None => selector.len().to_string(),
+        };
         let replacements = [
             ('r', results_ui.index().to_string()),
             ('m', results_ui.status.matched_count.to_string()),
             ('t', results_ui.status.item_count.to_string()),
+            ('p', selected),
+            ('o', modes.to_string()),
         ];
 
         // sub replacements into line
         let mut new_spans = Vec::new();
 
+        let layout = results_ui.column_layout();
         if status_config.match_indent {
-            new_spans.push(Span::raw(" ".repeat(results_ui.indentation())));
+            new_spans.push(Span::raw(" ".repeat(layout.indentation as usize)));
         }
 
         for span in &self.status_template {
@@ -57,7 +71,7 @@ impl StatusUI {
         // sub whitespace expansions
         let effective_width = match self.status_config.row_connection {
             RowConnectionStyle::Full => full_width,
-            _ => results_ui.width(),
+            _ => layout.width,
         } as usize;
 
         let mut style = Style::from(status_config.style);
@@ -74,6 +88,30 @@ impl StatusUI {
         Paragraph::new(expanded)
     }
 
+    /// Render a transient message in place of the status, with the same indentation and style.
+    pub fn make_transient(&self, message: &Line<'static>, results_ui: &ResultsUI) -> Paragraph<'_> {
+        let mut spans = Vec::new();
+        if self.status_config.match_indent {
+            let indentation = results_ui.column_layout().indentation as usize;
+            spans.push(Span::raw(" ".repeat(indentation)));
+        }
+        spans.extend(message.spans.iter().cloned());
+
+        Paragraph::new(Line::from(spans).style(message.style))
+            .style(Style::from(self.status_config.style))
+    }
+
+    /// The matching modes toggled at runtime, shown by `\o`: `[case]`, `[exact]`, `[case,exact]`,
+    /// or nothing.
+    pub fn matching_modes(case_sensitive: bool, exact: bool) -> String {
+        match (case_sensitive, exact) {
+            (false, false) => String::new(),
+            (true, false) => "[case]".into(),
+            (false, true) => "[exact]".into(),
+            (true, true) => "[case,exact]".into(),
+        }
+    }
+
     /// The style from the config overrides the Line style (but not the span styles).
     /// None restores the prompt defined in the config.
     pub fn set(&mut self, template: Option<Line<'static>>) {
@@ -0,0 +1,83 @@
+use std::time::{Duration, Instant};
+
+use ratatui::{
+    layout::Rect,
+    style::Style,
+    text::Line,
+    widgets::{Clear, Paragraph},
+};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{config::ToastConfig, ui::Frame};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, strum_macros::Display)]
+pub enum ToastLevel {
+    #[default]
+    Info,
+    Warn,
+    Error,
+}
+
+/// A timed notification drawn over the corner of the results.
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub message: String,
+    pub expires: Instant,
+    // whether the render loop has arranged a frame for the expiry
+    pub(crate) scheduled: bool,
+}
+
+impl Toast {
+    pub fn new(level: ToastLevel, message: impl Into<String>, duration: Duration) -> Self {
+        Self {
+            level,
+            message: message.into(),
+            expires: Instant::now() + duration,
+            scheduled: false,
+        }
+    }
+
+    pub fn expired(&self) -> bool {
+        self.expires <= Instant::now()
+    }
+}
+
+/// Stack toasts upwards from the bottom right corner of `area`, newest at the bottom.
+pub fn render_toasts<'a>(
+    frame: &mut Frame,
+    area: Rect,
+    toasts: impl DoubleEndedIterator<Item = &'a Toast>,
+    config: &ToastConfig,
+) {
+    let mut bottom = area.bottom();
+
+    for toast in toasts.rev() {
+        if bottom <= area.top() {
+            break;
+        }
+        let message = toast.message.lines().next().unwrap_or_default();
+        let width = (message.width() as u16 + 2).min(area.width);
+        bottom -= 1;
+
+        let rect = Rect {
+            x: area.right().saturating_sub(width),
+            y: bottom,
+            width,
+            height: 1,
+        };
+
+        let style: Style = match toast.level {
+            ToastLevel::Info => config.info_style,
+            ToastLevel::Warn => config.warn_style,
+            ToastLevel::Error => config.error_style,
+        }
+        .into();
+
+        frame.render_widget(Clear, rect);
+        frame.render_widget(
+            Paragraph::new(Line::from(format!(" {message} "))).style(style),
+            rect,
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use ratatui::style::{Color, Style};
-use ratatui::widgets::Block;
+use ratatui::widgets::{Block, Scrollbar, ScrollbarOrientation, ScrollbarState};
 
-use crate::config::OverlayLayoutSettings;
+use crate::config::{OverlayLayoutSettings, ScrollbarSetting};
 use crate::ui::{Frame, Rect, SizeHint};
 use crate::utils::Percentage;
 
@@ -125,3 +125,38 @@ pub fn update_area(area: &mut Rect, w: Option<u16>, h: Option<u16>) {
     area.x = center_x.saturating_sub(area.width / 2);
     area.y = center_y.saturating_sub(area.height / 2);
 }
+
+/// Render a vertical scrollbar along the right edge of `area`.
+///
+/// `total` and `viewport` are measured in rows, `position` is the first visible row.
+pub fn render_scrollbar(
+    frame: &mut Frame,
+    area: Rect,
+    setting: &ScrollbarSetting,
+    total: usize,
+    viewport: usize,
+    position: usize,
+) {
+    if !setting.show || area.width == 0 || area.height == 0 {
+        return;
+    }
+    if setting.auto_hide && total <= viewport {
+        return;
+    }
+
+    // ratatui counts scroll positions rather than rows
+    let scrollable = total.saturating_sub(viewport);
+    let mut state = ScrollbarState::new(scrollable + 1)
+        .position(position.min(scrollable))
+        .viewport_content_length(viewport);
+
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .thumb_symbol(&setting.thumb_symbol)
+        .track_symbol((!setting.track_symbol.is_empty()).then_some(setting.track_symbol.as_str()))
+        .track_style(Style::from(setting.style))
+        .thumb_style(Style::from(setting.thumb_style));
+
+    frame.render_stateful_widget(scrollbar, area, &mut state);
+}
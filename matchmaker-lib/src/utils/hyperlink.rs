@@ -0,0 +1,285 @@
+//! OSC 8 hyperlinks.
+//!
+//! Ratatui can't draw them, so they are removed from the text before it is parsed, remembering
+//! where the text of each link is. Once the frame is drawn, the cells which show it are drawn
+//! again as a link, see [`crate::tui::TuiBackend::draw_hyperlink`].
+
+use std::borrow::Cow;
+
+use ratatui::{
+    buffer::{Buffer, Cell},
+    layout::Rect,
+};
+use unicode_width::UnicodeWidthStr;
+
+const OSC8: &[u8] = b"\x1b]8;";
+
+/// The text of a link to `url`, on a single line of the output.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyperlink {
+    pub url: String,
+    /// The line the text is on
+    pub line: usize,
+    /// The column the text starts at
+    pub col: usize,
+    /// The width of the text
+    pub width: usize,
+}
+
+/// The cells showing the text of a link, with their positions.
+pub type HyperlinkCells = (String, Vec<(u16, u16, Cell)>);
+
+/// Where a row drawn in the preview comes from, so that the links on it can be found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowOrigin {
+    /// The line of the output
+    pub line: usize,
+    /// The column of the line the row starts at
+    pub col: usize,
+    /// The width of what is drawn before it, i.e. the line number and the indent of a wrapped row
+    pub prefix: usize,
+}
+
+/// Remove the OSC 8 sequences from `bytes`, returning the links they made, counting lines from
+/// the start of `bytes`. A link which spans lines is split into one per line.
+///
+/// Links to URLs containing control characters are removed without being returned, so that
+/// the output can't write escape sequences to the terminal through them.
+pub fn extract_hyperlinks(bytes: &[u8]) -> (Cow<'_, [u8]>, Vec<Hyperlink>) {
+    if !bytes.windows(OSC8.len()).any(|w| w == OSC8) {
+        return (Cow::Borrowed(bytes), Vec::new());
+    }
+
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut links = Vec::new();
+    // the url of the open link, and where its text starts in `out`
+    let mut open: Option<(String, usize)> = None;
+    let mut close = |open: Option<(String, usize)>, out: &[u8]| {
+        let Some((url, start)) = open else {
+            return;
+        };
+        let line_start = out[..start]
+            .iter()
+            .rposition(|&b| b == b'\n')
+            .map_or(0, |i| i + 1);
+        let mut line = out[..start].iter().filter(|&&b| b == b'\n').count();
+        let mut col = visible_text(&out[line_start..start]).width();
+
+        for text in visible_text(&out[start..]).split('\n') {
+            let width = text.trim_end_matches('\r').width();
+            if width > 0 {
+                links.push(Hyperlink {
+                    url: url.clone(),
+                    line,
+                    col,
+                    width,
+                });
+            }
+            line += 1;
+            col = 0;
+        }
+    };
+
+    let mut i = 0;
+    while i < bytes.len() {
+        if !bytes[i..].starts_with(OSC8) {
+            out.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+
+        // ESC ] 8 ; params ; url, terminated by BEL or ESC \
+        let body = &bytes[i + OSC8.len()..];
+        let Some((end, terminator)) = body.iter().enumerate().find_map(|(j, &b)| match b {
+            0x07 => Some((j, 1)),
+            0x1b if body.get(j + 1) == Some(&b'\\') => Some((j, 2)),
+            _ => None,
+        }) else {
+            // unterminated: keep it as is
+            out.extend_from_slice(&bytes[i..]);
+            break;
+        };
+        let url = body[..end]
+            .splitn(2, |&b| b == b';')
+            .nth(1)
+            .map(String::from_utf8_lossy)
+            .unwrap_or_default();
+
+        close(open.take(), &out);
+        if !url.is_empty() && !url.chars().any(char::is_control) {
+            open = Some((url.into_owned(), out.len()));
+        }
+        i += OSC8.len() + end + terminator;
+    }
+    close(open, &out);
+
+    (Cow::Owned(out), links)
+}
+
+/// `s` without its OSC 8 sequences, leaving the text of the links.
+pub fn strip_hyperlinks(s: &str) -> Cow<'_, [u8]> {
+    extract_hyperlinks(s.as_bytes()).0
+}
+
+/// `bytes` without escape sequences.
+fn visible_text(bytes: &[u8]) -> String {
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            // CSI: parameters up to a final byte
+            (0x1b, Some(b'[')) => {
+                i += 2;
+                while i < bytes.len() && !(0x40..=0x7e).contains(&bytes[i]) {
+                    i += 1;
+                }
+                i += 1;
+            }
+            (0x1b, Some(_)) => i += 2,
+            (b, _) => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Find the cells of `area` which show the text of each of `links`, given the origin of each
+/// row drawn in it, and the rows and columns scrolled past.
+pub fn find_hyperlinks<'a>(
+    buf: &Buffer,
+    area: Rect,
+    rows: &[RowOrigin],
+    (dy, dx): (u16, u16),
+    links: impl IntoIterator<Item = &'a Hyperlink>,
+) -> Vec<HyperlinkCells> {
+    let area = area.intersection(buf.area);
+    let mut found = Vec::new();
+
+    for link in links {
+        let mut cells = Vec::new();
+        for col in link.col..link.col + link.width {
+            // the last row of the line which starts at or before the column
+            let Some((r, row)) = rows
+                .iter()
+                .enumerate()
+                .filter(|(_, row)| row.line == link.line && row.col <= col)
+                .last()
+            else {
+                continue;
+            };
+            let (Some(y), Some(x)) = (
+                r.checked_sub(dy as usize),
+                (row.prefix + col - row.col).checked_sub(dx as usize),
+            ) else {
+                continue;
+            };
+            if x < area.width as usize && y < area.height as usize {
+                let (x, y) = (area.x + x as u16, area.y + y as u16);
+                cells.push((x, y, buf[(x, y)].clone()));
+            }
+        }
+        if !cells.is_empty() {
+            found.push((link.url.clone(), cells));
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_hyperlinks() {
+        let input = b"see \x1b]8;;https://example.com\x07\x1b[34mexample\x1b[0m\x1b]8;;\x07 and\n\x1b]8;id=1;file:///tmp\x1b\\tmp\x1b]8;;\x1b\\.";
+        let (text, links) = extract_hyperlinks(input);
+
+        assert_eq!(&*text, b"see \x1b[34mexample\x1b[0m and\ntmp.");
+        assert_eq!(
+            links,
+            [
+                Hyperlink {
+                    url: "https://example.com".into(),
+                    line: 0,
+                    col: 4,
+                    width: 7,
+                },
+                Hyperlink {
+                    url: "file:///tmp".into(),
+                    line: 1,
+                    col: 0,
+                    width: 3,
+                },
+            ]
+        );
+
+        // control characters in the url
+        let (text, links) = extract_hyperlinks(b"\x1b]8;;http://x\x1b[2J\x07x\x1b]8;;\x07");
+        assert!(links.is_empty());
+        assert_eq!(&*text, b"x");
+    }
+
+    #[test]
+    fn test_find_hyperlinks() {
+        let area = Rect::new(0, 0, 10, 3);
+        let mut buf = Buffer::empty(area);
+        // a link on "link", the other occurrence isn't one
+        buf.set_string(0, 0, "1 a link, ", Default::default());
+        buf.set_string(0, 1, "  not link", Default::default());
+        buf.set_string(0, 2, "2 linked", Default::default());
+        let rows = [
+            RowOrigin {
+                line: 0,
+                col: 0,
+                prefix: 2,
+            },
+            RowOrigin {
+                line: 0,
+                col: 8,
+                prefix: 2,
+            },
+            RowOrigin {
+                line: 1,
+                col: 0,
+                prefix: 2,
+            },
+        ];
+        let links = [
+            Hyperlink {
+                url: "https://example.com".into(),
+                line: 0,
+                col: 2,
+                width: 4,
+            },
+            Hyperlink {
+                url: "https://example.org".into(),
+                line: 1,
+                col: 0,
+                width: 6,
+            },
+        ];
+
+        let positions = |found: Vec<HyperlinkCells>| -> Vec<Vec<(u16, u16)>> {
+            found
+                .iter()
+                .map(|(_, cells)| cells.iter().map(|(x, y, _)| (*x, *y)).collect())
+                .collect()
+        };
+        assert_eq!(
+            positions(find_hyperlinks(&buf, area, &rows, (0, 0), &links)),
+            [
+                vec![(4, 0), (5, 0), (6, 0), (7, 0)],
+                vec![(2, 2), (3, 2), (4, 2), (5, 2), (6, 2), (7, 2)],
+            ]
+        );
+
+        // scrolled by a row and a column
+        assert_eq!(
+            positions(find_hyperlinks(&buf, area, &rows, (1, 1), &links)),
+            [vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1), (6, 1)]]
+        );
+    }
+}
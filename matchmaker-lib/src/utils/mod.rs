@@ -5,4 +5,18 @@ pub use percentage::Percentage;
 pub mod serde;
 pub mod text;
 
+pub mod hyperlink;
+
 pub mod string;
+
+/// Enter a [`tracing`](https://docs.rs/tracing) span until the end of the enclosing block, taking the
+/// same arguments as `tracing::info_span!`. Expands to nothing without the `tracing` feature.
+///
+/// The span guard is not [`Send`], so the block must not contain an `.await`.
+macro_rules! span {
+    ($($args:tt)*) => {
+        #[cfg(feature = "tracing")]
+        let _span = tracing::info_span!($($args)*).entered();
+    };
+}
+pub(crate) use span;
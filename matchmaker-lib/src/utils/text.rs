@@ -214,6 +214,116 @@ pub fn wrap_line<'a>(line: Line<'a>, max_width: u16, indicator: &Span<'a>) -> Ve
     wrapped_lines
 }
 
+/// Wrap a line to `max_width` at word boundaries, indenting the continuation lines to match its
+/// leading whitespace unless that takes more than half the width.
+/// Words wider than `max_width` are broken between graphemes.
+pub fn word_wrap_line(line: Line<'static>, max_width: u16) -> Vec<Line<'static>> {
+    word_wrap_line_origins(line, max_width)
+        .into_iter()
+        .map(|(_, _, row)| row)
+        .collect()
+}
+
+/// [`word_wrap_line`], also returning the column of the line each row starts at, and the width
+/// of its indent.
+pub fn word_wrap_line_origins(
+    line: Line<'static>,
+    max_width: u16,
+) -> Vec<(usize, usize, Line<'static>)> {
+    let max_width = max_width as usize;
+    if max_width == 0 || line.width() <= max_width {
+        return vec![(0, 0, line)];
+    }
+
+    let is_space = |g: &str| g.chars().all(char::is_whitespace);
+    let graphemes: Vec<(&str, Style)> = line
+        .spans
+        .iter()
+        .flat_map(|s| s.content.graphemes(true).map(move |g| (g, s.style)))
+        .collect();
+    // the column each grapheme starts at
+    let cols: Vec<usize> = graphemes
+        .iter()
+        .scan(0, |col, (g, _)| {
+            let start = *col;
+            *col += g.width();
+            Some(start)
+        })
+        .collect();
+    let indent: usize = graphemes
+        .iter()
+        .take_while(|(g, _)| is_space(g))
+        .map(|(g, _)| g.width())
+        .sum();
+    let indent = if indent * 2 <= max_width { indent } else { 0 };
+
+    let mut rows: Vec<Vec<(&str, Style, usize)>> = vec![Vec::new()];
+    let mut width = 0;
+    let mut row_start = 0;
+    // the index after the last space which follows text on the current row
+    let mut breakable = None;
+    let mut has_text = false;
+
+    for (&(g, style), &col) in graphemes.iter().zip(&cols) {
+        let g_width = g.width();
+        let space = is_space(g);
+
+        if width + g_width > max_width && width > row_start {
+            let row = rows.last_mut().unwrap();
+            let carried = match breakable {
+                Some(i) if !space => row.split_off(i),
+                _ => Vec::new(),
+            };
+            row_start = indent;
+            width = indent + carried.iter().map(|(g, ..)| g.width()).sum::<usize>();
+            has_text = !carried.is_empty();
+            breakable = None;
+            rows.push(carried);
+            // the space the line is broken at is dropped
+            if space {
+                continue;
+            }
+        }
+
+        let row = rows.last_mut().unwrap();
+        row.push((g, style, col));
+        width += g_width;
+        if space {
+            if has_text {
+                breakable = Some(row.len());
+            }
+        } else {
+            has_text = true;
+        }
+    }
+
+    let mut next_col = 0;
+    rows.into_iter()
+        .enumerate()
+        .map(|(i, row)| {
+            let col = row.first().map_or(next_col, |&(_, _, col)| col);
+            let prefix = if i > 0 { indent } else { 0 };
+            let mut spans: Vec<Span<'static>> = Vec::new();
+            if prefix > 0 {
+                spans.push(Span::raw(" ".repeat(prefix)));
+            }
+            for (g, style, col) in row {
+                next_col = col + g.width();
+                match spans.last_mut() {
+                    Some(last) if last.style == style => last.content.to_mut().push_str(g),
+                    _ => spans.push(Span::styled(g.to_string(), style)),
+                }
+            }
+            let row = Line {
+                spans,
+                style: line.style,
+                alignment: line.alignment,
+            };
+            (col, prefix, row)
+        })
+        .collect()
+}
+
 /// Convenience wrapper around line wrapper
 pub fn wrap_text<'a>(text: Text<'a>, max_width: u16) -> (Text<'a>, bool) {
     let wrapping_span = wrapping_indicator();
@@ -361,27 +471,6 @@ pub fn is_empty(text: &Text<'_>) -> bool {
     text.lines.iter().all(|l| l.spans.is_empty())
 }
 
-pub fn trim_text_lines(text: &mut Text) {
-    let lines = &text.lines;
-
-    // 1. Find indices
-    let start = lines.iter().position(|l| !l.spans.is_empty()).unwrap_or(0);
-    let end = lines
-        .iter()
-        .rposition(|l| !l.spans.is_empty())
-        .map(|i| i + 1)
-        .unwrap_or(0);
-
-    // 2. Modify the Vec in place
-    if start < end {
-        // Only keep the slice if there is actual content
-        text.lines = text.lines[start..end].to_vec();
-    } else {
-        // Everything was empty or whitespace
-        text.lines.clear();
-    }
-}
-
 /// Expand `placeholder` inside a Line and distribute spaces to reach `target_width`.
 pub fn expand_indents<'a>(
     input: Line<'a>,
@@ -589,6 +678,43 @@ mod tests {
         assert!(wrapped_text.lines.len() > 1);
     }
 
+    #[test]
+    fn test_word_wrap_line() {
+        let rows = |line: Line<'static>, width| -> Vec<String> {
+            word_wrap_line(line, width)
+                .iter()
+                .map(|l| l.to_string())
+                .collect()
+        };
+
+        assert_eq!(rows(Line::from("hello world"), 20), ["hello world"]);
+        assert_eq!(
+            rows(Line::from("the quick brown fox"), 10),
+            ["the quick ", "brown fox"]
+        );
+        // continuation lines keep the indent
+        assert_eq!(
+            rows(Line::from("  - one two three"), 10),
+            ["  - one ", "  two ", "  three"]
+        );
+        let origins: Vec<_> = word_wrap_line_origins(Line::from("  - one two three"), 10)
+            .into_iter()
+            .map(|(col, prefix, _)| (col, prefix))
+            .collect();
+        assert_eq!(origins, [(0, 0), (8, 2), (12, 2)]);
+        // long words are broken
+        assert_eq!(rows(Line::from("abcdefghij"), 4), ["abcd", "efgh", "ij"]);
+        // styles are kept across the break
+        let line = Line::from(vec![
+            Span::raw("aa "),
+            Span::styled("bb cc", Style::default().fg(Color::Red)),
+        ]);
+        let wrapped = word_wrap_line(line, 5);
+        assert_eq!(wrapped.len(), 2);
+        assert_eq!(wrapped[1].spans[0].content, "cc");
+        assert_eq!(wrapped[1].spans[0].style.fg, Some(Color::Red));
+    }
+
     use ratatui::style::{Color, Style};
     use ratatui::text::{Line, Span, Text};
 
@@ -0,0 +1,102 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Data, DeriveInput, LitStr, Path, Type, spanned::Spanned};
+
+/// A field displayed as a column.
+struct ColumnField {
+    name: String,
+    /// How to access the field on `self`.
+    member: TokenStream,
+    ty: Type,
+    format: Option<Path>,
+}
+
+pub fn derive(input: DeriveInput) -> syn::Result<TokenStream> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "Columns can only be derived for structs",
+        ));
+    };
+
+    let mut columns = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let (member, default_name) = match &field.ident {
+            Some(ident) => (quote!(#ident), ident.to_string()),
+            None => {
+                let index = syn::Index::from(i);
+                (quote!(#index), i.to_string())
+            }
+        };
+
+        let mut column = ColumnField {
+            name: default_name,
+            member,
+            ty: field.ty.clone(),
+            format: None,
+        };
+        let mut skip = false;
+
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("column")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("name") {
+                    column.name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("format") {
+                    column.format = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else {
+                    return Err(meta.error("unknown column attribute"));
+                }
+                Ok(())
+            })?;
+        }
+
+        if !skip {
+            columns.push(column);
+        }
+    }
+
+    if columns.is_empty() {
+        return Err(syn::Error::new(
+            input.span(),
+            "Columns requires at least one column",
+        ));
+    }
+
+    let names = columns.iter().map(|c| &c.name);
+    let arms = columns.iter().enumerate().map(|(i, c)| {
+        let member = &c.member;
+        let value = match &c.format {
+            Some(format) => quote!(::std::borrow::Cow::from(#format(&self.#member))),
+            None if is_string(&c.ty) => quote!(::std::borrow::Cow::Borrowed(self.#member.as_str())),
+            None => quote!(::std::borrow::Cow::Owned(::std::string::ToString::to_string(
+                &self.#member
+            ))),
+        };
+        quote!(#i => #value,)
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::matchmaker::nucleo::ColumnIndexable for #name #ty_generics #where_clause {
+            fn get_str(&self, i: usize) -> ::std::borrow::Cow<'_, str> {
+                match i {
+                    #(#arms)*
+                    _ => ::std::borrow::Cow::Borrowed(""),
+                }
+            }
+        }
+
+        impl #impl_generics ::matchmaker::nucleo::Columns for #name #ty_generics #where_clause {
+            const COLUMN_NAMES: &'static [&'static str] = &[#(#names),*];
+        }
+    })
+}
+
+/// Whether the type is `String`, which is displayed without allocating.
+fn is_string(ty: &Type) -> bool {
+    matches!(ty, Type::Path(tp) if tp.qself.is_none() && tp.path.is_ident("String"))
+}
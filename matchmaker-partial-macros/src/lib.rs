@@ -1,11 +1,29 @@
+mod columns;
+
 use proc_macro::TokenStream;
 use quote::{ToTokens, format_ident, quote};
 use std::collections::HashSet;
 use syn::{
-    Fields, GenericArgument, ItemStruct, LitStr, Meta, Path, PathArguments, Token, Type,
-    parse::Parse, parse_macro_input, spanned::Spanned,
+    DeriveInput, Fields, GenericArgument, ItemStruct, LitStr, Meta, Path, PathArguments, Token,
+    Type, parse::Parse, parse_macro_input, spanned::Spanned,
 };
 
+/// Implement `matchmaker::nucleo::ColumnIndexable` and `matchmaker::nucleo::Columns` for a
+/// struct, with a column for each field.
+///
+/// Fields are displayed by their `Display` representation, and can be annotated with:
+/// - `#[column(name = "Size")]`: the name of the column, which defaults to the field name.
+/// - `#[column(format = "path::to::fn")]`: a function from a reference to the field to a
+///   `String` or `&str`, used instead of `Display`.
+/// - `#[column(skip)]`: don't display the field.
+#[proc_macro_derive(Columns, attributes(column))]
+pub fn derive_columns(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+    columns::derive(input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
 #[proc_macro_attribute]
 pub fn partial(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut input = parse_macro_input!(item as ItemStruct);
@@ -141,6 +159,7 @@ pub fn partial(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut merge_field_stmts = Vec::new();
     let mut clear_field_stmts = Vec::new();
     let mut set_field_arms = Vec::new();
+    let mut path_stmts = Vec::new();
     let mut flattened_field_targets = Vec::new();
     let mut used_idents = HashSet::new();
 
@@ -622,9 +641,11 @@ pub fn partial(attr: TokenStream, item: TokenStream) -> TokenStream {
                         Ok(())
                     }
                 });
+                path_stmts.push(quote! { paths.push(#field_name_str.to_string()); });
             }
         } else {
             // Leaf field handling
+            let mut recursive_p_ty = None;
             current_field_ty = if should_recurse {
                 is_recursive_field = true;
                 let p_ty = if let Some(Some(ref overridden)) = recurse_override {
@@ -640,6 +661,7 @@ pub fn partial(attr: TokenStream, item: TokenStream) -> TokenStream {
                 } else {
                     quote! { #inner_ty }
                 };
+                recursive_p_ty = Some(p_ty.clone());
 
                 if field_unwrap {
                     p_ty
@@ -705,9 +727,19 @@ pub fn partial(attr: TokenStream, item: TokenStream) -> TokenStream {
                         quote! { &mut self.#field_ident }
                     };
 
+                    let p_ty = &recursive_p_ty;
                     if is_flattened {
                         flattened_field_targets.push(set_target);
+                        path_stmts.push(quote! {
+                            paths.extend(<#p_ty as matchmaker_partial::Set>::paths());
+                        });
                     } else {
+                        path_stmts.push(quote! {
+                            paths.push(#field_name_str.to_string());
+                            for p in <#p_ty as matchmaker_partial::Set>::paths() {
+                                paths.push(format!("{}.{}", #field_name_str, p));
+                            }
+                        });
                         set_field_arms.push(quote! {
                             #field_name_str #(| #field_aliases)* => {
                                 if tail.is_empty() {
@@ -777,6 +809,7 @@ pub fn partial(attr: TokenStream, item: TokenStream) -> TokenStream {
                         }
                     };
 
+                    path_stmts.push(quote! { paths.push(#field_name_str.to_string()); });
                     set_field_arms.push(quote! {
                         #field_name_str #(| #field_aliases)* => {
                             if !tail.is_empty() {
@@ -831,6 +864,12 @@ pub fn partial(attr: TokenStream, item: TokenStream) -> TokenStream {
                         }
                     }
                 }
+
+                fn paths() -> Vec<String> {
+                    let mut paths = Vec::new();
+                    #(#path_stmts)*
+                    paths
+                }
             }
         }
     } else {
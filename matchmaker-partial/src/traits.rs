@@ -2,6 +2,14 @@ use crate::PartialSetError;
 
 pub trait Set {
     fn set(&mut self, path: &[String], val: &[String]) -> Result<(), PartialSetError>;
+
+    /// The dot-separated paths accepted by [`Set::set`], excluding aliases and the keys of maps.
+    fn paths() -> Vec<String>
+    where
+        Self: Sized,
+    {
+        Vec::new()
+    }
 }
 
 pub trait Merge {
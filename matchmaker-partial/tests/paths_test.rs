@@ -0,0 +1,45 @@
+use matchmaker_partial::*;
+use matchmaker_partial_macros::partial;
+use serde::{Deserialize, Serialize};
+
+#[partial(path)]
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Leaf {
+    pub x: i32,
+    #[partial(alias = "y")]
+    pub why: Option<String>,
+}
+
+#[partial(path)]
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Flat {
+    pub f: bool,
+}
+
+#[partial(path)]
+#[derive(Default, Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Root {
+    pub n: u8,
+    #[partial(recurse)]
+    pub leaf: Leaf,
+    #[partial(recurse)]
+    #[serde(flatten)]
+    pub flat: Flat,
+    pub list: Vec<String>,
+}
+
+#[test]
+fn test_paths() {
+    assert_eq!(
+        PartialRoot::paths(),
+        ["n", "leaf", "leaf.x", "leaf.why", "f", "list"]
+    );
+
+    // every path is settable
+    let mut p = PartialRoot::default();
+    for path in PartialRoot::paths().into_iter().filter(|p| p != "leaf") {
+        let path: Vec<String> = path.split('.').map(String::from).collect();
+        let val = if path[0] == "f" { "true" } else { "1" };
+        p.set(&path, &[val.to_string()]).unwrap();
+    }
+}